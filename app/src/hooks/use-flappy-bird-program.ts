@@ -183,14 +183,14 @@ export function useFlappyBirdProgram() {
         return await sdkCreateSession(new PublicKey(IDL.address));
     }, [sdkCreateSession]);
 
-    // Derive PDA from wallet public key - using game_v2 seed to get fresh PDA
+    // Derive PDA from wallet public key - using game_v10 seed to get fresh PDA
     const derivePDA = useCallback((authority: PublicKey) => {
-        const GAME_SEED = Buffer.from("game_v2");
+        const GAME_SEED = Buffer.from("game_v10");
         const [pda] = PublicKey.findProgramAddressSync(
-            [GAME_SEED, authority.toBuffer()],
+            [GAME_SEED, authority.toBuffer(), Buffer.from([0])],
             new PublicKey(IDL.address)
         );
-        console.log("[PDA] Derived with game_v2 seed:", pda.toString());
+        console.log("[PDA] Derived with game_v10 seed:", pda.toString());
         return pda;
     }, []);
 
@@ -216,7 +216,7 @@ export function useFlappyBirdProgram() {
             const account = await program.account.gameState.fetch(gamePubkey);
             // Parse pipes array
             const pipes = (account.pipes || []).map((p: any) => ({
-                x: p.x,
+                x: p.x / 1000,
                 gapY: p.gapY,
                 passed: p.passed,
                 active: p.active,
@@ -277,7 +277,7 @@ export function useFlappyBirdProgram() {
                     try {
                         const account = await erProgram.account.gameState.fetch(gamePubkey);
                         const pipes = (account.pipes || []).map((p: any) => ({
-                            x: p.x,
+                            x: p.x / 1000,
                             gapY: p.gapY,
                             passed: p.passed,
                             active: p.active,
@@ -321,7 +321,7 @@ export function useFlappyBirdProgram() {
                 try {
                     const decoded = program.coder.accounts.decode("gameState", accountInfo.data);
                     const pipes = (decoded.pipes || []).map((p: any) => ({
-                        x: p.x,
+                        x: p.x / 1000,
                         gapY: p.gapY,
                         passed: p.passed,
                         active: p.active,
@@ -362,7 +362,7 @@ export function useFlappyBirdProgram() {
             try {
                 const account = await erProgram.account.gameState.fetch(gamePubkey);
                 const pipes = (account.pipes || []).map((p: any) => ({
-                    x: p.x,
+                    x: p.x / 1000,
                     gapY: p.gapY,
                     passed: p.passed,
                     active: p.active,
@@ -393,7 +393,7 @@ export function useFlappyBirdProgram() {
                 try {
                     const decoded = erProgram.coder.accounts.decode("gameState", accountInfo.data);
                     const pipes = (decoded.pipes || []).map((p: any) => ({
-                        x: p.x,
+                        x: p.x / 1000,
                         gapY: p.gapY,
                         passed: p.passed,
                         active: p.active,
@@ -432,7 +432,7 @@ export function useFlappyBirdProgram() {
 
         try {
             const tx = await program.methods
-                .initialize()
+                .initialize(0)
                 .accounts({
                     authority: wallet.publicKey,
                 })
@@ -670,7 +670,7 @@ export function useFlappyBirdProgram() {
                 try {
                     const account = await erProgram.account.gameState.fetch(gamePubkey);
                     const pipes = (account.pipes || []).map((p: any) => ({
-                        x: p.x,
+                        x: p.x / 1000,
                         gapY: p.gapY,
                         passed: p.passed,
                         active: p.active,
@@ -791,9 +791,10 @@ export function useFlappyBirdProgram() {
             // Build the delegate instruction for devnet
             // IMPORTANT: Must pass the validator identity in remainingAccounts
             const tx = await program.methods
-                .delegate()
+                .delegate(0, { validator: null, commitFrequencyMs: null, timeLimit: null })
                 .accounts({
                     payer: wallet.publicKey,
+                    authority: wallet.publicKey,
                 })
                 .remainingAccounts([
                     {
@@ -841,9 +842,10 @@ export function useFlappyBirdProgram() {
 
             // Build transaction using base program
             let tx = await program.methods
-                .commit()
+                .commit(0)
                 .accounts({
                     payer: wallet.publicKey,
+                    authority: wallet.publicKey,
                 })
                 .transaction();
 
@@ -891,9 +893,10 @@ export function useFlappyBirdProgram() {
 
             // Build transaction using base program
             let tx = await program.methods
-                .undelegate()
+                .undelegate(0, true)
                 .accounts({
                     payer: wallet.publicKey,
+                    authority: wallet.publicKey,
                 })
                 .transaction();
 
@@ -239,7 +239,7 @@ export function useOnChainFlappyBird() {
     const parseGameAccount = useCallback((data: any): GameAccount => {
         const pipesData = data.pipes || [];
         const pipes: Pipe[] = pipesData.map((p: any) => ({
-            x: Number(p.x),
+            x: Number(p.x) / 1000,
             gapY: Number(p.gapY ?? p.gap_y),
             passed: Boolean(p.passed),
             active: Boolean(p.active),
@@ -579,7 +579,7 @@ export function useOnChainFlappyBird() {
 
         try {
             const tx = await program.methods
-                .initialize()
+                .initialize(0)
                 .accounts({
                     authority: wallet.publicKey,
                 })
@@ -739,9 +739,10 @@ export function useOnChainFlappyBird() {
             // Delegate to a specific public ER validator (required when multiple validators exist)
             const validator = erValidatorIdentity ?? DEFAULT_ER_VALIDATOR.identity;
             const tx = await program.methods
-                .delegate()
+                .delegate(0, { validator: null, commitFrequencyMs: null, timeLimit: null })
                 .accounts({
                     payer: wallet.publicKey,
+                    authority: wallet.publicKey,
                 })
                 .remainingAccounts([
                     {
@@ -779,9 +780,10 @@ export function useOnChainFlappyBird() {
 
         try {
             let tx = await program.methods
-                .commit()
+                .commit(0)
                 .accounts({
                     payer: wallet.publicKey,
+                    authority: wallet.publicKey,
                 })
                 .transaction();
 
@@ -824,9 +826,10 @@ export function useOnChainFlappyBird() {
 
         try {
             let tx = await program.methods
-                .undelegate()
+                .undelegate(0, true)
                 .accounts({
                     payer: wallet.publicKey,
+                    authority: wallet.publicKey,
                 })
                 .transaction();
 
@@ -259,13 +259,13 @@ export const GameStateProvider = ({ children }: { children: React.ReactNode }) =
         return await sessionWallet.createSession(new PublicKey(IDL.address));
     }, [sessionWallet]);
 
-    // Game seed must match Rust: pub const GAME_SEED: &[u8] = b"game_v2";
-    const GAME_SEED = Buffer.from("game_v2");
+    // Game seed must match Rust: pub const GAME_SEED: &[u8] = b"game_v10";
+    const GAME_SEED = Buffer.from("game_v10");
 
     // Derive PDA from wallet public key
     const derivePDA = useCallback((authority: PublicKey) => {
         const [pda] = PublicKey.findProgramAddressSync(
-            [GAME_SEED, authority.toBuffer()],
+            [GAME_SEED, authority.toBuffer(), Buffer.from([0])],
             new PublicKey(IDL.address)
         );
         return pda;
@@ -275,7 +275,7 @@ export const GameStateProvider = ({ children }: { children: React.ReactNode }) =
     const parseGameAccount = useCallback((data: any): GameAccount => {
         const pipesData = data.pipes || [];
         const pipes: Pipe[] = pipesData.map((p: any) => ({
-            x: Number(p.x),
+            x: Number(p.x) / 1000,
             gapY: Number(p.gapY ?? p.gap_y),
             passed: Boolean(p.passed),
             active: Boolean(p.active),
@@ -553,7 +553,7 @@ export const GameStateProvider = ({ children }: { children: React.ReactNode }) =
 
         try {
             const tx = await program.methods
-                .initialize()
+                .initialize(0)
                 .accounts({
                     authority: wallet.publicKey,
                 })
@@ -863,9 +863,10 @@ export const GameStateProvider = ({ children }: { children: React.ReactNode }) =
 
         try {
             const tx = await program.methods
-                .delegate()
+                .delegate(0, { validator: null, commitFrequencyMs: null, timeLimit: null })
                 .accounts({
                     payer: wallet.publicKey,
+                    authority: wallet.publicKey,
                 })
                 .remainingAccounts([
                     {
@@ -900,9 +901,10 @@ export const GameStateProvider = ({ children }: { children: React.ReactNode }) =
 
         try {
             let tx = await program.methods
-                .commit()
+                .commit(0)
                 .accounts({
                     payer: wallet.publicKey,
+                    authority: wallet.publicKey,
                 })
                 .transaction();
 
@@ -945,9 +947,10 @@ export const GameStateProvider = ({ children }: { children: React.ReactNode }) =
 
         try {
             let tx = await program.methods
-                .undelegate()
+                .undelegate(0, true)
                 .accounts({
                     payer: wallet.publicKey,
+                    authority: wallet.publicKey,
                 })
                 .transaction();
 
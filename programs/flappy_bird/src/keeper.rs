@@ -0,0 +1,101 @@
+//! Keeper tip accounting: what `crank_tick` and `crank_finalize` owe the
+//! keepers who run them, kept free of accounts so the anti-farming cap can
+//! be tested on its own.
+//!
+//! A keeper's ledger remembers the games it was tipped for, per unix day,
+//! so one game yields it at most KEEPER_DAILY_CAP_LAMPORTS a day however
+//! often it's cranked. Slots from earlier days are reused; with every slot
+//! taken today, further cranks accrue nothing until tomorrow.
+
+use anchor_lang::prelude::*;
+
+use crate::GameAccrual;
+
+/// Games a keeper's ledger tracks at once
+pub const KEEPER_LEDGER_GAMES: usize = 16;
+/// Accrued for a `crank_tick` that advanced a run
+pub const KEEPER_TIP_LAMPORTS: u64 = 2_000;
+/// Accrued for a `crank_finalize` that ended one
+pub const KEEPER_FINALIZE_TIP_LAMPORTS: u64 = 10_000;
+/// Most one game earns a keeper in one unix day
+pub const KEEPER_DAILY_CAP_LAMPORTS: u64 = 20_000;
+
+/// Record up to `tip` for cranking `game` on `day`, within the daily cap and
+/// `available`, the vault's unowed balance. Returns the amount accrued.
+pub fn accrue(games: &mut [GameAccrual], game: &Pubkey, day: u32, tip: u64, available: u64) -> u64 {
+    let slot = match games.iter().position(|g| g.game == *game && g.day == day) {
+        Some(slot) => slot,
+        // A slot that is empty or from an earlier day starts over for this game
+        None => match games.iter().position(|g| g.amount == 0 || g.day != day) {
+            Some(slot) => {
+                games[slot] = GameAccrual { game: *game, day, amount: 0 };
+                slot
+            }
+            None => return 0,
+        },
+    };
+    let entry = &mut games[slot];
+    let amount = tip
+        .min(KEEPER_DAILY_CAP_LAMPORTS.saturating_sub(entry.amount))
+        .min(available);
+    entry.amount += amount;
+    amount
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(n: u8) -> Pubkey {
+        Pubkey::new_from_array([n; 32])
+    }
+
+    #[test]
+    fn micro_cranking_one_game_stops_at_the_daily_cap() {
+        let mut games = [GameAccrual::default(); 4];
+        let mut total = 0;
+        for _ in 0..100 {
+            total += accrue(&mut games, &game(1), 7, KEEPER_TIP_LAMPORTS, u64::MAX);
+        }
+        assert_eq!(total, KEEPER_DAILY_CAP_LAMPORTS);
+        assert_eq!(accrue(&mut games, &game(1), 7, KEEPER_FINALIZE_TIP_LAMPORTS, u64::MAX), 0);
+
+        // The last tip under the cap is cut short rather than dropped
+        let mut games = [GameAccrual::default(); 4];
+        for _ in 0..(KEEPER_DAILY_CAP_LAMPORTS / KEEPER_FINALIZE_TIP_LAMPORTS) {
+            accrue(&mut games, &game(1), 7, KEEPER_FINALIZE_TIP_LAMPORTS - 1, u64::MAX);
+        }
+        let left = KEEPER_DAILY_CAP_LAMPORTS - games[0].amount;
+        assert_eq!(accrue(&mut games, &game(1), 7, KEEPER_FINALIZE_TIP_LAMPORTS, u64::MAX), left);
+    }
+
+    #[test]
+    fn the_cap_is_per_game_and_per_day() {
+        let mut games = [GameAccrual::default(); 4];
+        assert_eq!(accrue(&mut games, &game(1), 7, KEEPER_DAILY_CAP_LAMPORTS, u64::MAX), KEEPER_DAILY_CAP_LAMPORTS);
+        assert_eq!(accrue(&mut games, &game(2), 7, KEEPER_TIP_LAMPORTS, u64::MAX), KEEPER_TIP_LAMPORTS);
+        // The next day the first game's slot has room again
+        assert_eq!(accrue(&mut games, &game(1), 8, KEEPER_TIP_LAMPORTS, u64::MAX), KEEPER_TIP_LAMPORTS);
+        assert_eq!(games.iter().filter(|g| g.game == game(1)).count(), 1);
+    }
+
+    #[test]
+    fn a_full_ledger_accrues_nothing_until_a_slot_frees_up() {
+        let mut games = [GameAccrual::default(); 2];
+        assert_eq!(accrue(&mut games, &game(1), 7, 1, u64::MAX), 1);
+        assert_eq!(accrue(&mut games, &game(2), 7, 1, u64::MAX), 1);
+        assert_eq!(accrue(&mut games, &game(3), 7, 1, u64::MAX), 0);
+        // Games already tracked today still accrue
+        assert_eq!(accrue(&mut games, &game(2), 7, 1, u64::MAX), 1);
+        // A new day frees every slot
+        assert_eq!(accrue(&mut games, &game(3), 8, 1, u64::MAX), 1);
+    }
+
+    #[test]
+    fn accruals_never_exceed_what_the_vault_can_pay() {
+        let mut games = [GameAccrual::default(); 4];
+        assert_eq!(accrue(&mut games, &game(1), 7, KEEPER_TIP_LAMPORTS, 500), 500);
+        assert_eq!(accrue(&mut games, &game(1), 7, KEEPER_TIP_LAMPORTS, 0), 0);
+        assert_eq!(games[0].amount, 500);
+    }
+}
@@ -0,0 +1,236 @@
+//! Sorted, fixed-size leaderboards: the array manipulation behind every
+//! board (global, distance, season, daily), kept free of accounts so it can
+//! be tested exhaustively.
+//!
+//! Boards are `entries[..count]` sorted best first: higher score, then on
+//! equal scores the earlier timestamp. Each player holds at most one entry.
+
+use anchor_lang::prelude::*;
+
+use crate::LeaderboardEntry;
+
+/// Outcome of offering an entry to a sorted leaderboard
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Submission {
+    /// Entry stored at `rank` (0-based); `count` is the new number of entries
+    Inserted { rank: usize, count: usize },
+    /// Board is full and the entry doesn't rank ahead of the last place
+    DoesNotQualify,
+    /// The player already holds an equal or better score
+    NotImproved,
+}
+
+/// Whether `a` ranks ahead of `b`
+fn ranks_ahead(a: &LeaderboardEntry, b: &LeaderboardEntry) -> bool {
+    a.score > b.score || (a.score == b.score && a.timestamp < b.timestamp)
+}
+
+/// Index of `player`'s entry in `entries[..count]`
+pub fn position_of(entries: &[LeaderboardEntry], count: usize, player: &Pubkey) -> Option<usize> {
+    entries[..count.min(entries.len())].iter().position(|e| e.player == *player)
+}
+
+/// Whether `insert_entry` would take `entry`: it beats the player's own
+/// score, or they have none and the board has room or it ranks ahead of the
+/// last place
+pub fn would_qualify(entries: &[LeaderboardEntry], count: usize, entry: &LeaderboardEntry) -> bool {
+    let capacity = entries.len();
+    let count = count.min(capacity);
+    match position_of(entries, count, &entry.player) {
+        Some(existing) => entry.score > entries[existing].score,
+        None => capacity > 0 && (count < capacity || ranks_ahead(entry, &entries[capacity - 1])),
+    }
+}
+
+/// Offer `entry` to `entries[..count]`. A player already on the board moves
+/// up only if the new score is higher; anyone else is inserted in sorted
+/// position, evicting the last entry when the board is full.
+pub fn insert_entry(entries: &mut [LeaderboardEntry], count: usize, entry: LeaderboardEntry) -> Submission {
+    let capacity = entries.len();
+    let mut count = count.min(capacity);
+
+    match position_of(entries, count, &entry.player) {
+        Some(existing) if entry.score <= entries[existing].score => return Submission::NotImproved,
+        // Take the old entry out; the improved one goes back in below
+        Some(existing) => {
+            entries.copy_within(existing + 1..count, existing);
+            count -= 1;
+        }
+        None if !would_qualify(entries, count, &entry) => return Submission::DoesNotQualify,
+        None => {}
+    }
+
+    let rank = entries[..count]
+        .iter()
+        .position(|e| ranks_ahead(&entry, e))
+        .unwrap_or(count);
+    let end = count.min(capacity - 1);
+    entries.copy_within(rank..end, rank + 1);
+    entries[rank] = entry;
+
+    Submission::Inserted {
+        rank,
+        count: (count + 1).min(capacity),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(player: u8, score: u64, timestamp: i64) -> LeaderboardEntry {
+        LeaderboardEntry {
+            player: Pubkey::new_from_array([player; 32]),
+            score,
+            timestamp,
+            ..Default::default()
+        }
+    }
+
+    fn scores(entries: &[LeaderboardEntry], count: usize) -> Vec<u64> {
+        entries[..count].iter().map(|e| e.score).collect()
+    }
+
+    fn players(entries: &[LeaderboardEntry], count: usize) -> Vec<u8> {
+        entries[..count].iter().map(|e| e.player.to_bytes()[0]).collect()
+    }
+
+    #[test]
+    fn empty_board_takes_any_entry() {
+        let mut board = [LeaderboardEntry::default(); 3];
+        assert_eq!(position_of(&board, 0, &entry(1, 0, 0).player), None);
+        assert!(would_qualify(&board, 0, &entry(1, 0, 0)));
+        assert_eq!(insert_entry(&mut board, 0, entry(1, 0, 0)), Submission::Inserted { rank: 0, count: 1 });
+        assert_eq!(position_of(&board, 1, &entry(1, 0, 0).player), Some(0));
+
+        // A zero-capacity board takes nothing
+        let mut none: [LeaderboardEntry; 0] = [];
+        assert!(!would_qualify(&none, 0, &entry(1, 5, 0)));
+        assert_eq!(insert_entry(&mut none, 0, entry(1, 5, 0)), Submission::DoesNotQualify);
+    }
+
+    #[test]
+    fn leaderboard_inserts_sorted() {
+        let mut board = [LeaderboardEntry::default(); 4];
+        let mut count = 0;
+        for (player, score) in [(1, 10), (2, 30), (3, 20)] {
+            match insert_entry(&mut board, count, entry(player, score, 0)) {
+                Submission::Inserted { count: c, .. } => count = c,
+                other => panic!("{:?}", other),
+            }
+        }
+        assert_eq!(scores(&board, count), vec![30, 20, 10]);
+    }
+
+    #[test]
+    fn leaderboard_evicts_last_when_full_and_rejects_low_scores() {
+        let mut board = [entry(1, 40, 0), entry(2, 30, 0), entry(3, 20, 0), entry(4, 10, 0)];
+        assert_eq!(insert_entry(&mut board, 4, entry(5, 10, 0)), Submission::DoesNotQualify);
+        assert_eq!(insert_entry(&mut board, 4, entry(5, 5, 0)), Submission::DoesNotQualify);
+        assert_eq!(
+            insert_entry(&mut board, 4, entry(5, 25, 0)),
+            Submission::Inserted { rank: 2, count: 4 }
+        );
+        assert_eq!(scores(&board, 4), vec![40, 30, 25, 20]);
+        assert_eq!(
+            insert_entry(&mut board, 4, entry(6, 50, 0)),
+            Submission::Inserted { rank: 0, count: 4 }
+        );
+        assert_eq!(scores(&board, 4), vec![50, 40, 30, 25]);
+    }
+
+    #[test]
+    fn exactly_full_board_evicts_only_the_last_entry() {
+        // One short of full, then full, then one over
+        let mut board = [LeaderboardEntry::default(); 3];
+        assert_eq!(insert_entry(&mut board, 0, entry(1, 30, 0)), Submission::Inserted { rank: 0, count: 1 });
+        assert_eq!(insert_entry(&mut board, 1, entry(2, 10, 0)), Submission::Inserted { rank: 1, count: 2 });
+        assert!(would_qualify(&board, 2, &entry(3, 1, 0)));
+        assert_eq!(insert_entry(&mut board, 2, entry(3, 1, 0)), Submission::Inserted { rank: 2, count: 3 });
+        assert_eq!(players(&board, 3), vec![1, 2, 3]);
+
+        assert!(!would_qualify(&board, 3, &entry(4, 1, 0)));
+        assert!(would_qualify(&board, 3, &entry(4, 2, 0)));
+        assert_eq!(insert_entry(&mut board, 3, entry(4, 20, 0)), Submission::Inserted { rank: 1, count: 3 });
+        assert_eq!(players(&board, 3), vec![1, 4, 2]);
+        assert_eq!(position_of(&board, 3, &entry(3, 0, 0).player), None);
+
+        // A count past the capacity is clamped to it
+        assert_eq!(insert_entry(&mut board, 7, entry(5, 100, 0)), Submission::Inserted { rank: 0, count: 3 });
+        assert_eq!(players(&board, 3), vec![5, 1, 4]);
+    }
+
+    #[test]
+    fn leaderboard_keeps_one_best_entry_per_player() {
+        let mut board = [entry(1, 40, 0), entry(2, 30, 0), entry(3, 20, 0), LeaderboardEntry::default()];
+        assert_eq!(insert_entry(&mut board, 3, entry(3, 15, 0)), Submission::NotImproved);
+        assert_eq!(insert_entry(&mut board, 3, entry(3, 20, 0)), Submission::NotImproved);
+        assert_eq!(
+            insert_entry(&mut board, 3, entry(3, 45, 0)),
+            Submission::Inserted { rank: 0, count: 3 }
+        );
+        assert_eq!(scores(&board, 3), vec![45, 40, 30]);
+        assert_eq!(board[0].player, entry(3, 0, 0).player);
+
+        // Improving on a full board moves the entry instead of evicting anyone
+        let mut board = [entry(1, 40, 0), entry(2, 30, 0), entry(3, 20, 0)];
+        assert_eq!(
+            insert_entry(&mut board, 3, entry(3, 35, 0)),
+            Submission::Inserted { rank: 1, count: 3 }
+        );
+        assert_eq!(scores(&board, 3), vec![40, 35, 30]);
+    }
+
+    #[test]
+    fn player_improving_in_place_keeps_their_rank() {
+        let mut board = [entry(1, 40, 0), entry(2, 30, 0), entry(3, 20, 0)];
+        assert!(would_qualify(&board, 3, &entry(2, 31, 9)));
+        assert_eq!(insert_entry(&mut board, 3, entry(2, 31, 9)), Submission::Inserted { rank: 1, count: 3 });
+        assert_eq!(players(&board, 3), vec![1, 2, 3]);
+        assert_eq!((board[1].score, board[1].timestamp), (31, 9));
+
+        // The last place improving doesn't evict itself
+        assert_eq!(insert_entry(&mut board, 3, entry(3, 50, 9)), Submission::Inserted { rank: 0, count: 3 });
+        assert_eq!(players(&board, 3), vec![3, 1, 2]);
+    }
+
+    #[test]
+    fn player_submitting_a_worse_score_changes_nothing() {
+        let mut board = [entry(1, 40, 0), entry(2, 30, 0), entry(3, 20, 0)];
+        let before = board;
+        for worse in [entry(1, 39, 5), entry(1, 40, 5), entry(3, 0, 5), entry(3, 20, 5)] {
+            assert!(!would_qualify(&board, 3, &worse));
+            assert_eq!(insert_entry(&mut board, 3, worse), Submission::NotImproved);
+        }
+        assert_eq!(board, before);
+
+        // ... even as last place on a full board, where it isn't mistaken for
+        // a newcomer that doesn't qualify
+        assert_eq!(insert_entry(&mut board, 3, entry(3, 19, 5)), Submission::NotImproved);
+    }
+
+    #[test]
+    fn leaderboard_ties_keep_earlier_entry_first() {
+        let mut board = [entry(1, 30, 0), entry(2, 20, 0), LeaderboardEntry::default()];
+        assert_eq!(
+            insert_entry(&mut board, 2, entry(3, 30, 5)),
+            Submission::Inserted { rank: 1, count: 3 }
+        );
+        assert_eq!(board[0].player, entry(1, 0, 0).player);
+    }
+
+    #[test]
+    fn ties_are_ordered_by_timestamp() {
+        let mut board = [entry(1, 30, 10), entry(2, 30, 20), entry(3, 10, 0)];
+        // An equal score with an earlier timestamp goes ahead of later ones
+        assert_eq!(insert_entry(&mut board, 3, entry(4, 30, 15)), Submission::Inserted { rank: 1, count: 3 });
+        assert_eq!(players(&board, 3), vec![1, 4, 2]);
+
+        // On a full board a tie with the last place qualifies only if earlier
+        assert!(!would_qualify(&board, 3, &entry(5, 30, 20)));
+        assert_eq!(insert_entry(&mut board, 3, entry(5, 30, 20)), Submission::DoesNotQualify);
+        assert!(would_qualify(&board, 3, &entry(5, 30, 19)));
+        assert_eq!(insert_entry(&mut board, 3, entry(5, 30, 19)), Submission::Inserted { rank: 2, count: 3 });
+        assert_eq!(players(&board, 3), vec![1, 4, 5]);
+    }
+}
@@ -0,0 +1,520 @@
+//! Frozen layouts of earlier account versions, read by `migrate_game`.
+//! These describe bytes already on chain: never edit them, add a new version.
+
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+use crate::{FlappyError, GameState};
+
+/// Seed salt of the game PDAs holding a `GameStateV6`
+pub const GAME_SEED_V6: &[u8] = b"game_v6";
+/// Seed salt of the game PDAs holding a `GameStateV7`
+pub const GAME_SEED_V7: &[u8] = b"game_v7";
+/// Seed salt of the game PDAs holding a `GameStateV8`
+pub const GAME_SEED_V8: &[u8] = b"game_v8";
+/// Seed salt of the game PDAs holding a `GameStateV9`
+pub const GAME_SEED_V9: &[u8] = b"game_v9";
+
+/// Seed salt of the game PDAs of an older `version`
+pub fn game_seed(version: u8) -> Result<&'static [u8]> {
+    match version {
+        6 => Ok(GAME_SEED_V6),
+        7 => Ok(GAME_SEED_V7),
+        8 => Ok(GAME_SEED_V8),
+        9 => Ok(GAME_SEED_V9),
+        _ => err!(FlappyError::UnsupportedGameVersion),
+    }
+}
+
+/// Copy what outlives a run (high scores, best run, reward progress and
+/// coins) from a legacy layout onto `game`; the fields are named alike in all
+macro_rules! carry_over_fields {
+    ($old:expr, $game:expr) => {{
+        let (old, game) = ($old, $game);
+        game.high_score = old.high_score;
+        game.high_scores = old.high_scores;
+        game.distance_high_score = old.distance_high_score;
+        game.mode_high_scores = old.mode_high_scores;
+        game.best_run_inputs.bits = old.best_run_inputs;
+        game.best_run_frames = old.best_run_frames;
+        game.best_run_seed = old.best_run_seed;
+        game.rewarded_score = old.rewarded_score;
+        game.coins = old.coins;
+    }};
+}
+
+/// `Pipe` as stored in a `GameStateV6` or `GameStateV7`
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Default)]
+pub struct PipeV6 {
+    pub x: i32,
+    pub gap_y: i32,
+    pub passed: bool,
+    pub active: bool,
+    pub pipe_type: u8,
+    pub phase: u16,
+    pub amplitude: i32,
+    pub shield: bool,
+    pub coin: bool,
+    pub gap_y2: i32,
+}
+
+/// `GameState` as last written under GAME_SEED_V6, before the pipe array
+/// grew to 8. Enums are kept as their borsh variant index and nested structs
+/// in their wire form, so later changes to those types can't shift it.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub struct GameStateV6 {
+    pub authority: Pubkey,
+    pub score: u64,
+    pub high_score: u64,
+    pub game_status: u8,
+    pub bird_y: i32,
+    pub bird_velocity: i32,
+    pub frame_count: u64,
+    pub last_update: i64,
+    pub pipes: [PipeV6; 5],
+    pub next_pipe_spawn_x: i32,
+    pub seed: u64,
+    pub bump: u8,
+    pub last_tick_slot: u64,
+    pub pending_flap: bool,
+    pub difficulty: u8,
+    pub gravity: i32,
+    pub pipe_gap: i32,
+    pub pipe_speed: i32,
+    pub has_shield: bool,
+    pub high_scores: [u64; 3],
+    pub coins: u64,
+    pub commitment: [u8; 32],
+    pub commitment_slot: u64,
+    pub tick_second: i64,
+    pub ticks_this_second: u16,
+    pub game_slot: u8,
+    /// gravity, jump_velocity, pipe_speed, pipe_gap, pipe_spawn_distance,
+    /// near_miss_threshold
+    pub physics: [i32; 6],
+    pub run_seed: u64,
+    pub input_log: [u64; 64],
+    pub best_run_inputs: [u64; 64],
+    pub best_run_frames: u32,
+    pub best_run_seed: u64,
+    pub rewarded_score: u64,
+    pub daily: bool,
+    pub daily_day: u32,
+    pub last_update_slot: u64,
+    pub scoring: u8,
+    pub pipes_passed: u64,
+    pub distance: u64,
+    pub distance_high_score: u64,
+    pub horizontal_control: bool,
+    pub bird_x: i32,
+    pub bird_x_velocity: i32,
+    pub wind_enabled: bool,
+    pub wind: i8,
+    pub game_mode: u8,
+    pub mode_high_scores: [u64; 3],
+    pub practice: bool,
+    pub lives: u8,
+    pub extra_lives: bool,
+    pub invulnerable_until: u64,
+    pub revived: bool,
+    pub flaps_since_last_pass: u16,
+    pub combo: u8,
+}
+
+/// `GameState` as last written under GAME_SEED_V7, the borsh layout from
+/// before it went zero-copy; the same as V6 with 8 pipe slots
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub struct GameStateV7 {
+    pub authority: Pubkey,
+    pub score: u64,
+    pub high_score: u64,
+    pub game_status: u8,
+    pub bird_y: i32,
+    pub bird_velocity: i32,
+    pub frame_count: u64,
+    pub last_update: i64,
+    pub pipes: [PipeV6; 8],
+    pub next_pipe_spawn_x: i32,
+    pub seed: u64,
+    pub bump: u8,
+    pub last_tick_slot: u64,
+    pub pending_flap: bool,
+    pub difficulty: u8,
+    pub gravity: i32,
+    pub pipe_gap: i32,
+    pub pipe_speed: i32,
+    pub has_shield: bool,
+    pub high_scores: [u64; 3],
+    pub coins: u64,
+    pub commitment: [u8; 32],
+    pub commitment_slot: u64,
+    pub tick_second: i64,
+    pub ticks_this_second: u16,
+    pub game_slot: u8,
+    /// gravity, jump_velocity, pipe_speed, pipe_gap, pipe_spawn_distance,
+    /// near_miss_threshold
+    pub physics: [i32; 6],
+    pub run_seed: u64,
+    pub input_log: [u64; 64],
+    pub best_run_inputs: [u64; 64],
+    pub best_run_frames: u32,
+    pub best_run_seed: u64,
+    pub rewarded_score: u64,
+    pub daily: bool,
+    pub daily_day: u32,
+    pub last_update_slot: u64,
+    pub scoring: u8,
+    pub pipes_passed: u64,
+    pub distance: u64,
+    pub distance_high_score: u64,
+    pub horizontal_control: bool,
+    pub bird_x: i32,
+    pub bird_x_velocity: i32,
+    pub wind_enabled: bool,
+    pub wind: i8,
+    pub game_mode: u8,
+    pub mode_high_scores: [u64; 3],
+    pub practice: bool,
+    pub lives: u8,
+    pub extra_lives: bool,
+    pub invulnerable_until: u64,
+    pub revived: bool,
+    pub flaps_since_last_pass: u16,
+    pub combo: u8,
+}
+
+impl From<GameStateV6> for GameStateV7 {
+    fn from(old: GameStateV6) -> Self {
+        let GameStateV6 {
+            pipes: old_pipes,
+            authority,
+            score,
+            high_score,
+            game_status,
+            bird_y,
+            bird_velocity,
+            frame_count,
+            last_update,
+            next_pipe_spawn_x,
+            seed,
+            bump,
+            last_tick_slot,
+            pending_flap,
+            difficulty,
+            gravity,
+            pipe_gap,
+            pipe_speed,
+            has_shield,
+            high_scores,
+            coins,
+            commitment,
+            commitment_slot,
+            tick_second,
+            ticks_this_second,
+            game_slot,
+            physics,
+            run_seed,
+            input_log,
+            best_run_inputs,
+            best_run_frames,
+            best_run_seed,
+            rewarded_score,
+            daily,
+            daily_day,
+            last_update_slot,
+            scoring,
+            pipes_passed,
+            distance,
+            distance_high_score,
+            horizontal_control,
+            bird_x,
+            bird_x_velocity,
+            wind_enabled,
+            wind,
+            game_mode,
+            mode_high_scores,
+            practice,
+            lives,
+            extra_lives,
+            invulnerable_until,
+            revived,
+            flaps_since_last_pass,
+            combo,
+        } = old;
+        let mut pipes = [PipeV6::default(); 8];
+        pipes[..5].copy_from_slice(&old_pipes);
+        Self {
+            authority,
+            score,
+            high_score,
+            game_status,
+            bird_y,
+            bird_velocity,
+            frame_count,
+            last_update,
+            next_pipe_spawn_x,
+            seed,
+            bump,
+            last_tick_slot,
+            pending_flap,
+            difficulty,
+            gravity,
+            pipe_gap,
+            pipe_speed,
+            has_shield,
+            high_scores,
+            coins,
+            commitment,
+            commitment_slot,
+            tick_second,
+            ticks_this_second,
+            game_slot,
+            physics,
+            run_seed,
+            input_log,
+            best_run_inputs,
+            best_run_frames,
+            best_run_seed,
+            rewarded_score,
+            daily,
+            daily_day,
+            last_update_slot,
+            scoring,
+            pipes_passed,
+            distance,
+            distance_high_score,
+            horizontal_control,
+            bird_x,
+            bird_x_velocity,
+            wind_enabled,
+            wind,
+            game_mode,
+            mode_high_scores,
+            practice,
+            lives,
+            extra_lives,
+            invulnerable_until,
+            revived,
+            flaps_since_last_pass,
+            combo,
+            pipes,
+        }
+    }
+}
+
+impl GameStateV7 {
+    /// Parse the data of a game account of `version`'s layout, discriminator
+    /// included, bringing a V6 one up to V7
+    pub fn try_from_account_data(version: u8, data: &[u8]) -> Result<Self> {
+        let mut body = data
+            .strip_prefix(GameState::DISCRIMINATOR)
+            .ok_or(FlappyError::InvalidLegacyGame)?;
+        let parsed = match version {
+            6 => GameStateV6::deserialize(&mut body).map(Self::from),
+            7 => Self::deserialize(&mut body),
+            _ => return err!(FlappyError::UnsupportedGameVersion),
+        };
+        parsed.map_err(|_| error!(FlappyError::InvalidLegacyGame))
+    }
+
+    /// Copy what outlives a run (high scores, best run, reward progress and
+    /// coins) onto `game`; the run itself and its settings are left behind
+    pub fn carry_over(&self, game: &mut GameState) {
+        carry_over_fields!(self, game);
+    }
+}
+
+/// `Pipe` as stored in a `GameStateV8`
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy)]
+pub struct PipeV8 {
+    pub x: i32,
+    pub gap_y: i32,
+    pub gap_y2: i32,
+    pub amplitude: i32,
+    pub phase: u16,
+    pub pipe_type: u8,
+    pub passed: u8,
+    pub active: u8,
+    pub shield: u8,
+    pub coin: u8,
+    pub padding: u8,
+}
+
+/// `GameState` as last written under GAME_SEED_V8, the first zero-copy
+/// layout, before the delegation parameters. Zero-copy `GameState` has no
+/// padding, so its bytes are this borsh encoding.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub struct GameStateV8 {
+    pub authority: Pubkey,
+    pub score: u64,
+    pub high_score: u64,
+    pub high_scores: [u64; 3],
+    pub mode_high_scores: [u64; 3],
+    pub distance_high_score: u64,
+    pub coins: u64,
+    pub rewarded_score: u64,
+    pub frame_count: u64,
+    pub last_update: i64,
+    pub last_update_slot: u64,
+    pub last_tick_slot: u64,
+    pub tick_second: i64,
+    pub seed: u64,
+    pub run_seed: u64,
+    pub commitment: [u8; 32],
+    pub commitment_slot: u64,
+    pub input_log: [u64; 64],
+    pub best_run_inputs: [u64; 64],
+    pub best_run_seed: u64,
+    pub pipes_passed: u64,
+    pub distance: u64,
+    pub invulnerable_until: u64,
+    pub physics: [i32; 6],
+    pub pipes: [PipeV8; 8],
+    pub bird_y: i32,
+    pub bird_velocity: i32,
+    pub bird_x: i32,
+    pub bird_x_velocity: i32,
+    pub gravity: i32,
+    pub pipe_gap: i32,
+    pub pipe_speed: i32,
+    pub next_pipe_spawn_x: i32,
+    pub best_run_frames: u32,
+    pub daily_day: u32,
+    pub ticks_this_second: u16,
+    pub flaps_since_last_pass: u16,
+    pub game_status: u8,
+    pub bump: u8,
+    pub game_slot: u8,
+    pub difficulty: u8,
+    pub scoring: u8,
+    pub game_mode: u8,
+    pub pending_flap: u8,
+    pub has_shield: u8,
+    pub daily: u8,
+    pub horizontal_control: u8,
+    pub wind_enabled: u8,
+    pub wind: i8,
+    pub practice: u8,
+    pub lives: u8,
+    pub extra_lives: u8,
+    pub revived: u8,
+    pub combo: u8,
+    pub is_delegated: u8,
+    pub commit_interval: u16,
+}
+
+impl GameStateV8 {
+    /// Parse the data of a V8 game account, discriminator included
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self> {
+        let mut body = data
+            .strip_prefix(GameState::DISCRIMINATOR)
+            .ok_or(FlappyError::InvalidLegacyGame)?;
+        Self::deserialize(&mut body).map_err(|_| error!(FlappyError::InvalidLegacyGame))
+    }
+
+    /// As `GameStateV7::carry_over`
+    pub fn carry_over(&self, game: &mut GameState) {
+        carry_over_fields!(self, game);
+    }
+}
+
+/// `GameState` as last written under GAME_SEED_V9: V8 with the delegation
+/// parameters after `physics`
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub struct GameStateV9 {
+    pub authority: Pubkey,
+    pub score: u64,
+    pub high_score: u64,
+    pub high_scores: [u64; 3],
+    pub mode_high_scores: [u64; 3],
+    pub distance_high_score: u64,
+    pub coins: u64,
+    pub rewarded_score: u64,
+    pub frame_count: u64,
+    pub last_update: i64,
+    pub last_update_slot: u64,
+    pub last_tick_slot: u64,
+    pub tick_second: i64,
+    pub seed: u64,
+    pub run_seed: u64,
+    pub commitment: [u8; 32],
+    pub commitment_slot: u64,
+    pub input_log: [u64; 64],
+    pub best_run_inputs: [u64; 64],
+    pub best_run_seed: u64,
+    pub pipes_passed: u64,
+    pub distance: u64,
+    pub invulnerable_until: u64,
+    pub physics: [i32; 6],
+    /// validator, time_limit, commit_frequency_ms, padding
+    pub delegation: [u8; 48],
+    pub pipes: [PipeV8; 8],
+    pub bird_y: i32,
+    pub bird_velocity: i32,
+    pub bird_x: i32,
+    pub bird_x_velocity: i32,
+    pub gravity: i32,
+    pub pipe_gap: i32,
+    pub pipe_speed: i32,
+    pub next_pipe_spawn_x: i32,
+    pub best_run_frames: u32,
+    pub daily_day: u32,
+    pub ticks_this_second: u16,
+    pub flaps_since_last_pass: u16,
+    pub game_status: u8,
+    pub bump: u8,
+    pub game_slot: u8,
+    pub difficulty: u8,
+    pub scoring: u8,
+    pub game_mode: u8,
+    pub pending_flap: u8,
+    pub has_shield: u8,
+    pub daily: u8,
+    pub horizontal_control: u8,
+    pub wind_enabled: u8,
+    pub wind: i8,
+    pub practice: u8,
+    pub lives: u8,
+    pub extra_lives: u8,
+    pub revived: u8,
+    pub combo: u8,
+    pub is_delegated: u8,
+    pub commit_interval: u16,
+}
+
+impl GameStateV9 {
+    /// Parse the data of a V9 game account, discriminator included
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self> {
+        let mut body = data
+            .strip_prefix(GameState::DISCRIMINATOR)
+            .ok_or(FlappyError::InvalidLegacyGame)?;
+        Self::deserialize(&mut body).map_err(|_| error!(FlappyError::InvalidLegacyGame))
+    }
+
+    /// As `GameStateV7::carry_over`
+    pub fn carry_over(&self, game: &mut GameState) {
+        carry_over_fields!(self, game);
+    }
+}
+
+/// Copy what outlives a run from the data of a game account of an older
+/// `version` onto `game`, returning the old game's authority
+pub fn carry_over(version: u8, data: &[u8], game: &mut GameState) -> Result<Pubkey> {
+    match version {
+        8 => {
+            let old = GameStateV8::try_from_account_data(data)?;
+            old.carry_over(game);
+            Ok(old.authority)
+        }
+        9 => {
+            let old = GameStateV9::try_from_account_data(data)?;
+            old.carry_over(game);
+            Ok(old.authority)
+        }
+        _ => {
+            let old = GameStateV7::try_from_account_data(version, data)?;
+            old.carry_over(game);
+            Ok(old.authority)
+        }
+    }
+}
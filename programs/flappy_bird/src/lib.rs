@@ -2,7 +2,15 @@ use anchor_lang::prelude::*;
 use ephemeral_rollups_sdk::anchor::{commit, delegate, ephemeral};
 use ephemeral_rollups_sdk::cpi::DelegateConfig;
 use ephemeral_rollups_sdk::ephem::{commit_accounts, commit_and_undelegate_accounts};
-use session_keys::{session_auth_or, Session, SessionError, SessionToken};
+use session_keys::{Session, SessionToken};
+
+use keeper::{KEEPER_FINALIZE_TIP_LAMPORTS, KEEPER_TIP_LAMPORTS};
+use leaderboard::{insert_entry, Submission};
+
+pub mod keeper;
+pub mod leaderboard;
+pub mod legacy;
+pub mod rng;
 
 declare_id!("DfJtsSqWNSetyRr3Fj4ZxM44oSBuZzAh4MTFMHQJSWvj");
 
@@ -11,8 +19,19 @@ declare_id!("DfJtsSqWNSetyRr3Fj4ZxM44oSBuZzAh4MTFMHQJSWvj");
 // ========================================
 pub const GAME_WIDTH: i32 = 600;
 pub const GAME_HEIGHT: i32 = 400;
+// Bounds on either side of a playfield set by `initialize_with_params`
+pub const MIN_PLAYFIELD_SIZE: u16 = 300;
+pub const MAX_PLAYFIELD_SIZE: u16 = 1200;
 pub const BIRD_SIZE: i32 = 30;
 pub const BIRD_X: i32 = 50; // Fixed X position
+// Horizontal-control runs: each move_horizontal adds DASH_IMPULSE to the
+// bird's X velocity, which then loses 1/HORIZONTAL_DRAG of itself per frame
+pub const DASH_IMPULSE: i32 = 2000;
+pub const MAX_HORIZONTAL_VELOCITY: i32 = 4000;
+pub const HORIZONTAL_DRAG: i32 = 4;
+pub const BIRD_X_MIN: i32 = 10;
+// On the default playfield; half the width on others
+pub const BIRD_X_MAX: i32 = GAME_WIDTH / 2;
 
 // Physics (scaled by 1000 for fixed-point)
 // OPTIMIZED: Lower values = smoother movement with fewer ticks
@@ -20,12 +39,226 @@ pub const GRAVITY: i32 = 400;         // 0.4 per tick - gentler fall
 pub const JUMP_VELOCITY: i32 = -6000; // -6.0 per tick - responsive jump
 pub const MAX_VELOCITY: i32 = 10000;  // 10.0 max - prevents crazy speeds
 
+// Ranked and daily flaps must be this many frames apart, unless the
+// GameConfig says otherwise
+pub const MIN_FLAP_INTERVAL_FRAMES: u8 = 3;
+
+// Pixels the collision box sits in from each side of the bird sprite, whose
+// corners are transparent, unless the GameConfig says otherwise
+pub const HITBOX_INSET: u8 = 4;
+pub const MAX_HITBOX_INSET: u8 = 10;
+
+// Frames at the start of a run that pipes can't kill in, so a client slow to
+// render the first frames doesn't lose the run to them, unless the
+// GameConfig says otherwise
+pub const GRACE_FRAMES: u8 = 20;
+pub const MAX_GRACE_FRAMES: u8 = 100;
+
+// Score (in pipes) a run must reach to extend the player's win streak,
+// unless the GameConfig says otherwise
+pub const STREAK_THRESHOLD: u16 = 10;
+
+// How long after a run ends submit_score still takes it, unless the
+// GameConfig says otherwise
+pub const SUBMIT_WINDOW_SECS: u32 = 24 * 60 * 60;
+
+// Tick cadence - ER slots are ~50ms, so one slot per frame approximates 20Hz
+pub const MIN_SLOTS_PER_TICK: u64 = 1;
+pub const MAX_TICKS_PER_BATCH: u8 = 30; // Upper bound for tick_multiple
+// tick_n runs another frame only while this much compute is left for it,
+// a frame with every hook and event at its most expensive, plus the
+// instruction's own finish; see the benchmark in tests/flappy_base_layer.ts
+pub const TICK_N_FRAME_CU: u64 = 12_000;
+pub const TICK_N_FINISH_CU: u64 = 25_000;
+pub const MAX_ADVANCE_FRAMES: u8 = 64; // One per bit of advance's inputs
+// Wall-clock cap: 20Hz plus headroom for a delayed transaction to catch up
+pub const MAX_FRAMES_PER_SECOND: u16 = 25;
+// catch_up replays wall time at the nominal rate, at most this many frames,
+// of a run no instruction touched for more than CATCH_UP_STALE_SECS
+pub const NOMINAL_FRAMES_PER_SECOND: u64 = 20;
+pub const MAX_CATCH_UP_FRAMES: u64 = 100;
+pub const CATCH_UP_STALE_SECS: i64 = 1;
+// crank_tick takes a Playing run no instruction touched for more than this,
+// for at most MAX_CRANK_FRAMES no-input frames, tipping the cranker
+// CRANK_TIP_LAMPORTS from the game's lamports above rent
+pub const CRANK_STALE_SECS: i64 = 10;
+pub const MAX_CRANK_FRAMES: u16 = 60;
+pub const CRANK_TIP_LAMPORTS: u64 = 5_000;
+// crank_finalize ends a run no instruction touched for more than this
+pub const CRANK_FINALIZE_SECS: i64 = 600;
+// A run paused for longer than this ends when resumed
+pub const MAX_PAUSE_SECONDS: i64 = 600;
+
+// Input log - one bit per frame for the first INPUT_LOG_FRAMES frames of a
+// run (~3.4 minutes at 20Hz), 512 bytes on GameState
+pub const INPUT_LOG_FRAMES: u64 = 4096;
+pub const INPUT_LOG_WORDS: usize = (INPUT_LOG_FRAMES / 64) as usize;
+
+// Independent game accounts per player (e.g. ranked, casual, testing)
+pub const MAX_GAME_SLOTS: u8 = 4;
+
+// Achievement ids: bit `id` of PlayerProfile::achievements. Clients render
+// badges from these, so never renumber them.
+#[constant]
+pub const ACHIEVEMENT_FIRST_PIPE: u8 = 0;
+#[constant]
+pub const ACHIEVEMENT_SCORE_10: u8 = 1;
+#[constant]
+pub const ACHIEVEMENT_SCORE_25: u8 = 2;
+#[constant]
+pub const ACHIEVEMENT_SCORE_50: u8 = 3;
+#[constant]
+pub const ACHIEVEMENT_SCORE_100: u8 = 4;
+/// FLAPLESS_FRAMES frames in a row without a flap
+#[constant]
+pub const ACHIEVEMENT_NO_FLAPS: u8 = 5;
+/// SURVIVOR_FRAMES frames survived over all runs
+#[constant]
+pub const ACHIEVEMENT_SURVIVOR: u8 = 6;
+/// STREAK_ACHIEVEMENT_RUNS runs in a row reaching the streak threshold
+#[constant]
+pub const ACHIEVEMENT_STREAK_5: u8 = 7;
+pub const FLAPLESS_FRAMES: u64 = 100;
+pub const SURVIVOR_FRAMES: u64 = 1000;
+pub const STREAK_ACHIEVEMENT_RUNS: u32 = 5;
+/// LONG_HAUL_PIXELS flown over all runs
+#[constant]
+pub const ACHIEVEMENT_LONG_HAUL: u8 = 8;
+/// MARATHON_PIXELS flown over all runs
+#[constant]
+pub const ACHIEVEMENT_MARATHON: u8 = 9;
+pub const LONG_HAUL_PIXELS: u64 = 100_000;
+pub const MARATHON_PIXELS: u64 = 1_000_000;
+
+// XP - a run earns XP_PER_POINT a point of score and one every
+// FRAMES_PER_XP frames survived; level n takes LEVEL_XP_BASE * n^2 XP
+pub const XP_PER_POINT: u64 = 10;
+pub const FRAMES_PER_XP: u64 = 20;
+pub const LEVEL_XP_BASE: u64 = 100;
+
+// Missions - slots rotate daily, then weekly; `rotation_mission` picks each
+// slot's kind and a difficulty tier from the day or week number
+pub const MISSION_SLOTS: usize = 6;
+pub const DAILY_MISSION_SLOTS: usize = 4;
+pub const MISSION_TIERS: usize = 3;
+/// Coins a completed mission credits, by tier
+pub const DAILY_MISSION_REWARD_COINS: [u32; MISSION_TIERS] = [10, 20, 40];
+pub const WEEKLY_MISSION_REWARD_COINS: [u32; MISSION_TIERS] = [60, 120, 200];
+
+// Where a run's seed came from, in GameState::seed_source
+/// `start_game` with the newest slot hash
+#[constant]
+pub const SEED_SOURCE_SLOT_HASH: u8 = 0;
+/// `start_game` without one, where the runtime keeps no slot hashes (an
+/// ephemeral rollup may not): slot and timestamp only
+#[constant]
+pub const SEED_SOURCE_CLOCK: u8 = 1;
+/// A seed fixed before the run: the daily seed, or a match's or challenge's
+#[constant]
+pub const SEED_SOURCE_FIXED: u8 = 2;
+
+// Daily challenge seeds: sha256(DAILY_SEED_SALT, unix day)
+pub const SECONDS_PER_DAY: i64 = 86_400;
+pub const DAILY_SEED_SALT: &[u8] = b"flappy_daily_v1";
+pub const SECONDS_PER_WEEK: i64 = 7 * SECONDS_PER_DAY;
+
+// Only this key may create or change the GameConfig physics overrides
+pub const CONFIG_ADMIN: Pubkey = pubkey!("anshxnbjGiUpsZpnx3c6LrK2vt8zt54vLMvY3C7Locm");
+
+// Delegation parameters accepted by `delegate`; time limits are in seconds
+pub const MIN_COMMIT_FREQUENCY_MS: u32 = 1_000;
+pub const MAX_DELEGATION_TIME_LIMIT: u64 = 7 * SECONDS_PER_DAY as u64;
+
+// SPL Token programs, for the reward mint (called directly, without anchor-spl)
+// and skin NFTs (either program)
+pub const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey = pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+// One reward token per point of a personal-best improvement
+pub const REWARD_DECIMALS: u8 = 0;
+
 // Pipe constants - OPTIMIZED for network latency
 pub const PIPE_WIDTH: i32 = 60;
 pub const PIPE_GAP: i32 = 160;        // Slightly larger gap for easier play
-pub const PIPE_SPEED: i32 = 4;        // Slower = less jitter visible
+pub const PIPE_SPEED: i32 = 4000;     // 4.0 px per tick (fixed-point) - slower = less jitter visible
 pub const PIPE_SPAWN_DISTANCE: i32 = 250; // More space between pipes
-pub const MAX_PIPES: usize = 4;       // Fewer pipes = less data
+// Passing a pipe within NEAR_MISS_THRESHOLD px of a gap edge scores
+// NEAR_MISS_BONUS extra points
+pub const NEAR_MISS_THRESHOLD: i32 = 10;
+pub const NEAR_MISS_BONUS: u64 = 1;
+// Each pipe passed without flapping since the previous one scores one more
+// bonus point than the last, up to MAX_COMBO_BONUS
+pub const MAX_COMBO_BONUS: u8 = 5;
+// Pipe slots on GameState; a run only uses as many as its spawn distance
+// can fill (see `pipe_slots`)
+pub const MAX_PIPES: usize = 8;
+
+// Progressive difficulty - every RAMP_PIPES_PER_LEVEL points the pipes speed
+// up and the gap narrows, within these caps
+pub const RAMP_PIPES_PER_LEVEL: u64 = 10;
+pub const RAMP_SPEED_STEP: i32 = 1000;
+pub const RAMP_SPEED_MAX: i32 = 20000;
+pub const RAMP_GAP_STEP: i32 = 5;
+pub const RAMP_GAP_MIN: i32 = 100;
+
+// Wind - one gust of WIND_GUST_FRAMES per WIND_PERIOD frames (after the
+// first) scales gravity by WIND_MIN_PERCENT..=WIND_MAX_PERCENT either way
+pub const WIND_PERIOD: u64 = 200;
+pub const WIND_GUST_FRAMES: u64 = 40;
+pub const WIND_MIN_PERCENT: u64 = 10;
+pub const WIND_MAX_PERCENT: u64 = 30;
+
+// Lives - start_game may grant up to MAX_LIVES; after losing one the bird
+// is immune to pipes for RESPAWN_INVULNERABLE_FRAMES frames
+pub const MAX_LIVES: u8 = 3;
+pub const RESPAWN_INVULNERABLE_FRAMES: u64 = 30;
+
+// Pipe types - oscillating pipes move their gap up and down over time;
+// double pipes have two gaps split by a strip of pipe
+pub const PIPE_TYPE_STATIC: u8 = 0;
+pub const PIPE_TYPE_OSCILLATING: u8 = 1;
+pub const PIPE_TYPE_DOUBLE: u8 = 2;
+pub const OSCILLATION_MIN_SCORE: u64 = 10;      // Moving pipes appear once score exceeds this
+pub const OSCILLATION_CHANCE_PERCENT: u64 = 30;
+pub const OSCILLATION_PERIOD: u64 = 80;         // Frames per full up-and-down cycle
+pub const OSCILLATION_AMPLITUDE_MIN: i32 = 20;
+pub const OSCILLATION_AMPLITUDE_MAX: i32 = 60;
+pub const DOUBLE_GAP_MIN_SCORE: u64 = 15;       // Double pipes appear once score exceeds this
+pub const DOUBLE_GAP_CHANCE_PERCENT: u64 = 20;
+pub const DOUBLE_GAP_STRIP_MIN: i32 = 20;       // Pipe between the two gaps
+pub const DOUBLE_GAP_EDGE_MIN: i32 = 20;        // Pipe above the upper gap and below the lower one
+
+// Shield power-up - floats in some pipe gaps and absorbs one pipe collision
+pub const SHIELD_SIZE: i32 = 20;
+pub const SHIELD_CHANCE_PERCENT: u64 = 10;
+
+// Timed pickups - the other power-ups, floating in some pipe gaps that hold
+// no coin or shield; ranked and daily runs only get the kinds the GameConfig
+// allows them
+pub const PICKUP_SIZE: i32 = 20;
+pub const PICKUP_CHANCE_PERCENT: u64 = 5;
+pub const FLIP_FRAMES: u8 = 100;
+pub const SLOW_FRAMES: u8 = 80;
+pub const SHRINK_FRAMES: u8 = 150;
+pub const MAGNET_FRAMES: u8 = 200;
+// Simulation speed, fixed-point: pipes and the bird move this share of a
+// frame's worth each frame
+pub const TIME_SCALE_NORMAL: u16 = 1000;
+pub const SLOW_TIME_SCALE: u16 = 500;
+/// Pixels from the bird's center to a coin's that a magnet pulls it in from
+pub const MAGNET_RADIUS: i32 = 60;
+/// Every timed pickup, as `GameState::pickups` bits
+pub const PICKUP_KINDS: u8 = 1 << Powerup::GravityFlip.repr()
+    | 1 << Powerup::SlowMotion.repr()
+    | 1 << Powerup::Shrink.repr()
+    | 1 << Powerup::Magnet.repr();
+
+// Coins - collectible in some pipe gaps, banked on GameState across runs
+pub const COIN_SIZE: i32 = 16;
+pub const COIN_CHANCE_PERCENT: u64 = 35;
+/// Coins `revive_with_coins` costs unless the GameConfig sets otherwise
+pub const REVIVE_COIN_COST: u64 = 50;
 
 // Random seed for pipe generation
 pub const PIPE_HEIGHT_MIN: i32 = 80;
@@ -37,436 +270,12582 @@ pub mod flappy_bird {
     use super::*;
 
     /// Initialize a new game account
-    /// Uses PDA derivation with player's public key
-    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
-        let game = &mut ctx.accounts.game;
-        game.authority = ctx.accounts.authority.key();
-        game.score = 0;
-        game.high_score = 0;
-        game.game_status = GameStatus::NotStarted;
-        game.bird_y = GAME_HEIGHT / 2 * 1000; // Center, scaled
-        game.bird_velocity = 0;
-        game.frame_count = 0;
-        game.last_update = Clock::get()?.unix_timestamp;
-        
-        // Initialize empty pipes
-        for i in 0..MAX_PIPES {
-            game.pipes[i] = Pipe {
-                x: -100, // Off-screen
-                gap_y: GAME_HEIGHT / 2,
-                passed: false,
-                active: false,
-            };
-        }
-        game.next_pipe_spawn_x = GAME_WIDTH + PIPE_SPAWN_DISTANCE;
-        game.seed = Clock::get()?.unix_timestamp as u64;
+    /// Uses PDA derivation with player's public key and `slot`
+    /// (0..MAX_GAME_SLOTS), so a player can keep several games side by side
+    /// Idempotent, as clients call it on every load: an existing game only has
+    /// its run reset, as by `reset_game`, keeping its high scores and coins.
+    pub fn initialize(ctx: Context<Initialize>, slot: u8) -> Result<()> {
+        init_game_account(ctx, slot, None)
+    }
+
+    /// `initialize` with the client's own playfield size, each side
+    /// MIN_PLAYFIELD_SIZE..=MAX_PLAYFIELD_SIZE pixels, in place of
+    /// GAME_WIDTH x GAME_HEIGHT. Kept for every run after; on an existing
+    /// game it replaces the size along with resetting the run.
+    pub fn initialize_with_params(ctx: Context<Initialize>, slot: u8, width: u16, height: u16) -> Result<()> {
+        require!(
+            [width, height].iter().all(|side| (MIN_PLAYFIELD_SIZE..=MAX_PLAYFIELD_SIZE).contains(side)),
+            FlappyError::InvalidPlayfield
+        );
+        init_game_account(ctx, slot, Some((width, height)))
+    }
+
+    /// Commit to the secret that will seed the next run: `client_commitment`
+    /// is sha256 of the reveal's 8 little-endian bytes
+    /// Note: On ER, any signer can play (session/burner wallet support)
+    pub fn request_game(ctx: Context<SimpleGameAction>, client_commitment: [u8; 32]) -> Result<()> {
+        let game = &mut *ctx.accounts.game.load_mut()?;
+        require!(
+            !game.game_status().in_run(),
+            FlappyError::GameAlreadyStarted
+        );
+        
+        game.commitment = client_commitment;
+        game.commitment_slot = Clock::get()?.slot;
         
-        msg!("Game initialized for player {}", game.authority);
+        msg!("Run requested at slot {}", game.commitment_slot);
         Ok(())
     }
 
     /// Start a new game - resets bird position and score
+    /// `options` picks the run's settings (difficulty) and carries the reveal for
+    /// the commitment stored by `request_game`, in a later slot. The seed mixes
+    /// the reveal with the player's key, the slot, the timestamp and the most
+    /// recent slot hash, so the pipe layout can't be known when committing;
+    /// where there are no slot hashes it goes without, and `seed_source`
+    /// records which it was. With a countdown in the
+    /// GameConfig the run starts in `Countdown`, which ticks run down before
+    /// the bird moves. A ranked run waits out the cooldown since the player's
+    /// last ranked run ended in any of their games, which it reads from the
+    /// HighScoreRecord and the other games it lists.
     /// Note: On ER, any signer can play (session/burner wallet support)
     /// Security is provided by the ER's account delegation model
-    pub fn start_game(ctx: Context<SimpleGameAction>) -> Result<()> {
-        let game = &mut ctx.accounts.game;
-        require!(
-            game.game_status != GameStatus::Playing,
-            FlappyError::GameAlreadyStarted
-        );
+    pub fn start_game(ctx: Context<StartGame>, options: StartOptions) -> Result<()> {
+        start_run(ctx.accounts, options, None, ctx.remaining_accounts)
+    }
+
+    /// Player flaps (jumps) - this is the main input during gameplay
+    /// The flap is queued and applied at the start of the next physics frame, so
+    /// the trajectory only depends on which frame an input lands in. That frame
+    /// is recorded in `input_log`; flaps past INPUT_LOG_FRAMES are rejected.
+    /// `advance = true` also runs that frame right away (the original behavior);
+    /// `advance = false` is input-only and leaves stepping to `tick`.
+    /// In ranked and daily runs a flap within `min_flap_interval` frames of
+    /// the last is dropped, emitting `FlapTooSoon`; counted in frames, so
+    /// replays see only the flaps that were kept.
+    /// During a countdown a flap fails with `CountdownRunning`, or, if the
+    /// config lets flaps skip it, ends it and is the run's first input.
+    /// Note: On ER, any signer can play (session/burner wallet support)
+    pub fn flap(ctx: Context<SimpleGameAction>, advance: bool) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        require_not_paused(load_optional_config(accounts.config.as_deref())?.as_ref())?;
+        let game = &mut *accounts.game.load_mut()?;
+        let mut profile = accounts.profile.as_deref_mut();
+        let missions = accounts.missions.as_deref_mut();
+        let mut stats = load_global_stats(accounts.global_stats.as_deref(), game)?;
+        require_solo(game)?;
+        skip_countdown_by_flap(game)?;
+        require_playing(game)?;
         
-        game.score = 0;
-        game.game_status = GameStatus::Playing;
-        game.bird_y = GAME_HEIGHT / 2 * 1000;
-        game.bird_velocity = 0;
-        game.frame_count = 0;
-        game.last_update = Clock::get()?.unix_timestamp;
-        
-        // Reset pipes
-        for i in 0..MAX_PIPES {
-            game.pipes[i] = Pipe {
-                x: -100,
-                gap_y: GAME_HEIGHT / 2,
-                passed: false,
-                active: false,
-            };
+        if queue_flap(game)? {
+            if let Some(profile) = profile.as_deref_mut().filter(|_| !game.practice()) {
+                profile.flaps = profile.flaps.saturating_add(1);
+            }
+            if let Some(stats) = stats.as_mut() {
+                stats.record_flaps(game, 1);
+            }
         }
-        game.next_pipe_spawn_x = GAME_WIDTH;
-        game.seed = Clock::get()?.unix_timestamp as u64;
         
-        msg!("Game started!");
+        // Run one game tick (skipped if the last frame was too recent)
+        if advance {
+            let clock = Clock::get()?;
+            let outcome = update_game_physics(game, clock.slot, clock.unix_timestamp, profile, missions, stats.as_mut());
+            if outcome == TickOutcome::Advanced {
+                mark_updated(game, &clock);
+            }
+        }
+        
+        log_frame(game);
+        store_global_stats(accounts.global_stats.as_deref(), stats)?;
         Ok(())
     }
 
-    /// Player flaps (jumps) - this is the main input during gameplay
+    /// Run up to `frame_count` frames (capped at MAX_ADVANCE_FRAMES) with their
+    /// input in one instruction: bit i of `inputs` flaps before frame i, the
+    /// same as `flap(false)` then `tick` per frame, so it's kept, dropped as
+    /// too soon or logged in `input_log` just as those would be. The cadence
+    /// and MAX_FRAMES_PER_SECOND bound the whole batch, and it stops early on
+    /// game over; inputs for frames not run are ignored, for the client to
+    /// send again. Returns the frames run and the score after them.
+    /// MAX_FRAMES_PER_SECOND is the tighter cap, within MAX_TICKS_PER_BATCH of
+    /// `tick_multiple`, so a batch costs no more compute than one of those.
     /// Note: On ER, any signer can play (session/burner wallet support)
-    pub fn flap(ctx: Context<SimpleGameAction>) -> Result<()> {
-        let game = &mut ctx.accounts.game;
-        require!(
-            game.game_status == GameStatus::Playing,
-            FlappyError::GameNotPlaying
-        );
+    pub fn advance(ctx: Context<SimpleGameAction>, inputs: u64, frame_count: u8) -> Result<AdvanceOutcome> {
+        let accounts = &mut *ctx.accounts;
+        require_not_paused(load_optional_config(accounts.config.as_deref())?.as_ref())?;
+        let game = &mut *accounts.game.load_mut()?;
+        let mut profile = accounts.profile.as_deref_mut();
+        require_solo(game)?;
+        require_ticking(game)?;
+        let mut stats = load_global_stats(accounts.global_stats.as_deref(), game)?;
         
-        // Apply jump velocity
-        game.bird_velocity = JUMP_VELOCITY;
+        let clock = Clock::get()?;
+        let (frames, flaps) = {
+            let mut on_frame = frame_hooks(
+                profile.as_deref_mut(),
+                accounts.missions.as_deref_mut(),
+                stats.as_mut(),
+                clock.unix_timestamp,
+            );
+            advance_with_inputs(game, inputs, frame_count, clock.slot, clock.unix_timestamp, &mut on_frame)?
+        };
+        if let Some(profile) = profile.filter(|_| !game.practice()) {
+            profile.flaps = profile.flaps.saturating_add(flaps as u64);
+        }
+        if let Some(stats) = stats.as_mut() {
+            stats.record_flaps(game, flaps as u64);
+        }
+        if frames > 0 {
+            mark_updated(game, &clock);
+            emit_frame_state(game);
+        }
         
-        // Run one game tick
-        update_game_physics(game)?;
+        log_frame(game);
+        store_global_stats(accounts.global_stats.as_deref(), stats)?;
+        Ok(AdvanceOutcome { frames, score: game.score })
+    }
+
+    /// Dash sideways in a horizontal-control run: forward for `direction` > 0,
+    /// back for < 0. Like `flap(false)` it only changes the velocity; the
+    /// next frame moves the bird. Dashes are not recorded in `input_log`.
+    pub fn move_horizontal(ctx: Context<SimpleGameAction>, direction: i8) -> Result<()> {
+        require_not_paused(load_optional_config(ctx.accounts.config.as_deref())?.as_ref())?;
+        let game = &mut *ctx.accounts.game.load_mut()?;
+        require_playing(game)?;
+        require!(game.horizontal_control(), FlappyError::HorizontalControlDisabled);
         
-        msg!("Flap! Bird Y: {}, Velocity: {}", game.bird_y / 1000, game.bird_velocity / 1000);
+        game.bird_x_velocity = game
+            .bird_x_velocity
+            .saturating_add(direction.signum() as i32 * DASH_IMPULSE)
+            .clamp(-MAX_HORIZONTAL_VELOCITY, MAX_HORIZONTAL_VELOCITY);
+        log_frame(game);
         Ok(())
     }
 
     /// Update game state - called each frame to advance physics
     /// This is the main game loop tick
+    /// Ticks arriving fewer than MIN_SLOTS_PER_TICK slots after the last frame
+    /// succeed as no-ops and return `TickOutcome::TooEarly`
     /// Note: On ER, any signer can play (session/burner wallet support)
-    pub fn tick(ctx: Context<SimpleGameAction>) -> Result<()> {
-        let game = &mut ctx.accounts.game;
-        require!(
-            game.game_status == GameStatus::Playing,
-            FlappyError::GameNotPlaying
-        );
+    pub fn tick(ctx: Context<SimpleGameAction>) -> Result<TickOutcome> {
+        let accounts = &mut *ctx.accounts;
+        require_not_paused(load_optional_config(accounts.config.as_deref())?.as_ref())?;
+        let game = &mut *accounts.game.load_mut()?;
+        require_ticking(game)?;
+        let mut stats = load_global_stats(accounts.global_stats.as_deref(), game)?;
         
-        update_game_physics(game)?;
+        let clock = Clock::get()?;
+        let outcome = update_game_physics(
+            game,
+            clock.slot,
+            clock.unix_timestamp,
+            accounts.profile.as_deref_mut(),
+            accounts.missions.as_deref_mut(),
+            stats.as_mut(),
+        );
         
-        msg!("Tick {}: Bird Y={}, Score={}", game.frame_count, game.bird_y / 1000, game.score);
-        Ok(())
+        if outcome == TickOutcome::Advanced {
+            mark_updated(game, &clock);
+            emit_frame_state(game);
+            log_frame(game);
+        }
+        store_global_stats(accounts.global_stats.as_deref(), stats)?;
+        Ok(outcome)
     }
 
-    /// End the game - called when collision detected or manually
-    /// Note: On ER, any signer can play (session/burner wallet support)
-    pub fn end_game(ctx: Context<SimpleGameAction>) -> Result<()> {
-        let game = &mut ctx.accounts.game;
+    /// Run up to `count` frames in one transaction (capped at MAX_TICKS_PER_BATCH,
+    /// by the slots elapsed since the last frame and by MAX_FRAMES_PER_SECOND),
+    /// for catch-up after network hiccups. Stops early on game over and still succeeds.
+    /// Returns the number of frames actually simulated.
+    pub fn tick_multiple(ctx: Context<SimpleGameAction>, count: u8) -> Result<u8> {
+        let accounts = &mut *ctx.accounts;
+        require_not_paused(load_optional_config(accounts.config.as_deref())?.as_ref())?;
+        let game = &mut *accounts.game.load_mut()?;
+        require_ticking(game)?;
+        let mut stats = load_global_stats(accounts.global_stats.as_deref(), game)?;
+        
+        let clock = Clock::get()?;
+        let frames = {
+            let mut on_frame = frame_hooks(
+                accounts.profile.as_deref_mut(),
+                accounts.missions.as_deref_mut(),
+                stats.as_mut(),
+                clock.unix_timestamp,
+            );
+            advance_frames_rate_limited(game, count, clock.slot, clock.unix_timestamp, &mut on_frame)
+        };
+        if frames > 0 {
+            mark_updated(game, &clock);
+            // The final frame only, however many ran
+            emit_frame_state(game);
+        }
         
-        game.game_status = GameStatus::GameOver;
+        log_frame(game);
+        store_global_stats(accounts.global_stats.as_deref(), stats)?;
+        Ok(frames)
+    }
+
+    /// `tick_multiple` bounded by compute instead of MAX_TICKS_PER_BATCH: up
+    /// to `max_frames` frames, within the cadence and MAX_FRAMES_PER_SECOND,
+    /// stopping before a frame that might not fit in the compute left (see
+    /// TICK_N_FRAME_CU) or when the run ends. Returns the frames simulated and
+    /// the status after them, so a client can call again until caught up.
+    pub fn tick_n(ctx: Context<SimpleGameAction>, max_frames: u16) -> Result<TickNOutcome> {
+        let accounts = &mut *ctx.accounts;
+        require_not_paused(load_optional_config(accounts.config.as_deref())?.as_ref())?;
+        let game = &mut *accounts.game.load_mut()?;
+        require_ticking(game)?;
+        let mut stats = load_global_stats(accounts.global_stats.as_deref(), game)?;
         
-        // Update high score if needed
-        if game.score > game.high_score {
-            game.high_score = game.score;
+        let clock = Clock::get()?;
+        let frames = {
+            let mut on_frame = frame_hooks(
+                accounts.profile.as_deref_mut(),
+                accounts.missions.as_deref_mut(),
+                stats.as_mut(),
+                clock.unix_timestamp,
+            );
+            advance_frames_compute_bounded(
+                game,
+                max_frames,
+                clock.slot,
+                clock.unix_timestamp,
+                remaining_compute_units,
+                &mut on_frame,
+            )
+        };
+        if frames > 0 {
+            mark_updated(game, &clock);
+            emit_frame_state(game);
         }
         
-        msg!("Game Over! Score: {}, High Score: {}", game.score, game.high_score);
-        Ok(())
+        log_frame(game);
+        store_global_stats(accounts.global_stats.as_deref(), stats)?;
+        Ok(TickNOutcome { frames, status: game.game_status() })
     }
 
-    /// Reset game to initial state
-    /// Note: On ER, any signer can play (session/burner wallet support)
-    pub fn reset_game(ctx: Context<SimpleGameAction>) -> Result<()> {
-        let game = &mut ctx.accounts.game;
+    /// Advance a stale run by the wall time since `last_update`, at
+    /// NOMINAL_FRAMES_PER_SECOND and with no new input, up to
+    /// MAX_CATCH_UP_FRAMES. The bird may crash on the way. Time beyond the cap
+    /// is dropped, so calling again converges. A no-op unless the run is
+    /// ticking and untouched for more than CATCH_UP_STALE_SECS, so a run
+    /// being played can't be advanced by someone else; past that anyone may
+    /// call it, e.g. a keeper finishing abandoned games. The frames count
+    /// towards MAX_FRAMES_PER_SECOND like ticks.
+    /// Returns the number of frames simulated.
+    pub fn catch_up(ctx: Context<SimpleGameAction>) -> Result<u32> {
+        let accounts = &mut *ctx.accounts;
+        require_not_paused(load_optional_config(accounts.config.as_deref())?.as_ref())?;
+        let game = &mut *accounts.game.load_mut()?;
         
-        game.score = 0;
-        game.game_status = GameStatus::NotStarted;
-        game.bird_y = GAME_HEIGHT / 2 * 1000;
-        game.bird_velocity = 0;
-        game.frame_count = 0;
-        
-        // Reset pipes
-        for i in 0..MAX_PIPES {
-            game.pipes[i] = Pipe {
-                x: -100,
-                gap_y: GAME_HEIGHT / 2,
-                passed: false,
-                active: false,
+        let mut stats = load_global_stats(accounts.global_stats.as_deref(), game)?;
+        
+        let clock = Clock::get()?;
+        let frames = {
+            let mut on_frame = frame_hooks(
+                accounts.profile.as_deref_mut(),
+                accounts.missions.as_deref_mut(),
+                stats.as_mut(),
+                clock.unix_timestamp,
+            );
+            catch_up_frames(game, clock.unix_timestamp, clock.slot, &mut on_frame)
+        };
+        
+        log_frame(game);
+        store_global_stats(accounts.global_stats.as_deref(), stats)?;
+        Ok(frames)
+    }
+
+    /// Advance an abandoned run: anyone may call it once a Playing run has
+    /// gone CRANK_STALE_SECS without an update, for up to `max_frames` (at
+    /// most MAX_CRANK_FRAMES) frames with no new input, which soon ends it on
+    /// the floor. Staleness is the guard, so a run being played can't be
+    /// cranked; `last_update` moves to now, and a run still going can be
+    /// cranked again once it's stale again. The cranker is tipped
+    /// CRANK_TIP_LAMPORTS from what the game holds above rent, unless the
+    /// player opted out with `set_crank_tip`; on the ER, where the game's
+    /// lamports can't leave it, there's no tip. With the keeper vault and
+    /// the cranker's ledger passed, a crank that advanced the run also
+    /// accrues KEEPER_TIP_LAMPORTS, claimable with `claim_keeper_tips`.
+    /// Returns the frames simulated.
+    pub fn crank_tick(ctx: Context<Crank>, max_frames: u16) -> Result<u32> {
+        let accounts = &mut *ctx.accounts;
+        require_not_paused(load_optional_config(accounts.config.as_deref())?.as_ref())?;
+        let clock = Clock::get()?;
+        let (frames, tip_due, delegated, player) = {
+            let game = &mut *accounts.game.load_mut()?;
+            let mut stats = load_global_stats(accounts.global_stats.as_deref(), game)?;
+            let frames = {
+                let mut on_frame = frame_hooks(None, None, stats.as_mut(), clock.unix_timestamp);
+                crank_frames(game, max_frames, clock.unix_timestamp, clock.slot, &mut on_frame)?
             };
+            log_frame(game);
+            store_global_stats(accounts.global_stats.as_deref(), stats)?;
+            (frames, !game.crank_tip_opt_out() && !game.is_delegated(), game.is_delegated(), game.authority)
+        };
+        
+        let game = accounts.game.to_account_info();
+        let rent = Rent::get()?.minimum_balance(game.data_len());
+        let tip = if tip_due { crank_tip(game.lamports(), rent) } else { 0 };
+        if tip > 0 {
+            **game.try_borrow_mut_lamports()? -= tip;
+            **accounts.cranker.to_account_info().try_borrow_mut_lamports()? += tip;
         }
-        game.next_pipe_spawn_x = GAME_WIDTH;
+        let accrued = if frames > 0 && !delegated {
+            accrue_keeper_tip(accounts, KEEPER_TIP_LAMPORTS, clock.unix_timestamp)?
+        } else {
+            0
+        };
+        emit!(Cranked { player, cranker: accounts.cranker.key(), frames, tip, accrued });
+        Ok(frames)
+    }
+
+    /// End a run no instruction has touched for CRANK_FINALIZE_SECS, as
+    /// `end_game` would, so an abandoned game stops counting as in play.
+    /// Anyone may call it; with the keeper vault and the cranker's ledger
+    /// passed, it accrues KEEPER_FINALIZE_TIP_LAMPORTS. Base layer only for
+    /// the accrual, like `crank_tick`.
+    pub fn crank_finalize(ctx: Context<Crank>) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        require_not_paused(load_optional_config(accounts.config.as_deref())?.as_ref())?;
+        let now = Clock::get()?.unix_timestamp;
+        let (delegated, player, score) = {
+            let game = &mut *accounts.game.load_mut()?;
+            finalize_abandoned(game, now)?;
+            record_global_game_finished(accounts.global_stats.as_deref(), game)?;
+            (game.is_delegated(), game.authority, game.score)
+        };
         
-        msg!("Game reset!");
+        let accrued = if delegated {
+            0
+        } else {
+            accrue_keeper_tip(accounts, KEEPER_FINALIZE_TIP_LAMPORTS, now)?
+        };
+        emit!(Cranked { player, cranker: accounts.cranker.key(), frames: 0, tip: 0, accrued });
+        msg!("Abandoned run finalized, score {}", score);
+        Ok(())
+    }
+
+    /// Whether `crank_tick` tips the cranker from this game's lamports, on
+    /// by default (authority only)
+    pub fn set_crank_tip(ctx: Context<OwnerAction>, enabled: bool) -> Result<()> {
+        ctx.accounts.game.load_mut()?.set_crank_tip_opt_out(!enabled);
+        msg!("Crank tip {}", if enabled { "on" } else { "off" });
         Ok(())
     }
 
     // ========================================
-    // MagicBlock Ephemeral Rollups Functions
+    // Keepers
     // ========================================
 
-    /// Delegate the game account to the Ephemeral Rollup
-    pub fn delegate(ctx: Context<DelegateInput>) -> Result<()> {
-        ctx.accounts.delegate_pda(
-            &ctx.accounts.payer,
-            &[GAME_SEED, ctx.accounts.payer.key().as_ref()],
-            DelegateConfig {
-                validator: ctx.remaining_accounts.first().map(|acc| acc.key()),
-                ..Default::default()
-            },
-        )?;
-        msg!("Game delegated to Ephemeral Rollup");
+    /// Create the vault that pays keepers for `crank_tick` and
+    /// `crank_finalize` (admin only, once)
+    pub fn initialize_keeper_vault(ctx: Context<InitializeKeeperVault>) -> Result<()> {
+        ctx.accounts.keeper_vault.bump = ctx.bumps.keeper_vault;
+        msg!("Keeper vault initialized");
         Ok(())
     }
 
-    /// Commit game state to the base layer
-    pub fn commit(ctx: Context<CommitInput>) -> Result<()> {
-        commit_accounts(
-            &ctx.accounts.payer,
-            vec![&ctx.accounts.game.to_account_info()],
-            &ctx.accounts.magic_context,
-            &ctx.accounts.magic_program,
+    /// Move `lamports` from the admin into the keeper vault. Cranks accrue
+    /// only what the vault holds beyond its rent and what it already owes.
+    pub fn fund_keeper_vault(ctx: Context<FundKeeperVault>, lamports: u64) -> Result<()> {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.admin.to_account_info(),
+                    to: ctx.accounts.keeper_vault.to_account_info(),
+                },
+            ),
+            lamports,
         )?;
-        msg!("Game state committed to base layer");
+        msg!("Keeper vault funded with {}", lamports);
         Ok(())
     }
 
-    /// Undelegate and commit final state
-    pub fn undelegate(ctx: Context<CommitInput>) -> Result<()> {
-        commit_and_undelegate_accounts(
-            &ctx.accounts.payer,
-            vec![&ctx.accounts.game.to_account_info()],
-            &ctx.accounts.magic_context,
-            &ctx.accounts.magic_program,
-        )?;
-        msg!("Game undelegated from Ephemeral Rollup");
+    /// Create the caller's keeper ledger, which cranks accrue tips to
+    pub fn open_keeper_ledger(ctx: Context<OpenKeeperLedger>) -> Result<()> {
+        let ledger = &mut ctx.accounts.keeper_ledger;
+        ledger.keeper = ctx.accounts.keeper.key();
+        ledger.bump = ctx.bumps.keeper_ledger;
+        msg!("Keeper ledger opened for {}", ledger.keeper);
         Ok(())
     }
-}
-
-// ========================================
-// Game Physics & Logic
-// ========================================
 
-fn update_game_physics(game: &mut Account<GameState>) -> Result<()> {
-    game.frame_count += 1;
-    
-    // Apply gravity to velocity
-    game.bird_velocity += GRAVITY;
-    
-    // Clamp velocity
-    if game.bird_velocity > MAX_VELOCITY {
-        game.bird_velocity = MAX_VELOCITY;
+    /// Pay the keeper everything their ledger has accrued from the vault,
+    /// zeroing it in the same instruction
+    pub fn claim_keeper_tips(ctx: Context<ClaimKeeperTips>) -> Result<()> {
+        let ledger = &mut ctx.accounts.keeper_ledger;
+        let amount = ledger.accrued;
+        require!(amount > 0, FlappyError::NoTipsAccrued);
+        ledger.accrued = 0;
+        ledger.claimed = ledger.claimed.saturating_add(amount);
+        
+        let vault = &mut ctx.accounts.keeper_vault;
+        vault.owed = vault.owed.saturating_sub(amount);
+        **vault.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.keeper.to_account_info().try_borrow_mut_lamports()? += amount;
+        
+        msg!("Keeper {} claimed {}", ledger.keeper, amount);
+        Ok(())
     }
-    if game.bird_velocity < -MAX_VELOCITY {
-        game.bird_velocity = -MAX_VELOCITY;
+
+    /// Pause a run, e.g. when the session key expires or the wallet drops
+    /// `tick` and `flap` fail with `GamePaused` until `resume_game`
+    /// Authority only, like `end_game` and `reset_game`.
+    pub fn pause_game(ctx: Context<OwnerAction>) -> Result<()> {
+        let game = &mut *ctx.accounts.game.load_mut()?;
+        pause_run(game, Clock::get()?.unix_timestamp)?;
+        
+        msg!("Game paused at frame {}", game.frame_count);
+        Ok(())
     }
-    
-    // Update bird position
-    game.bird_y += game.bird_velocity;
-    
-    // Check floor/ceiling collision
-    let bird_y_pixels = game.bird_y / 1000;
-    if bird_y_pixels <= 0 || bird_y_pixels + BIRD_SIZE >= GAME_HEIGHT {
-        game.game_status = GameStatus::GameOver;
-        if game.score > game.high_score {
-            game.high_score = game.score;
+
+    /// Resume a paused run from the current slot, so the pause doesn't count
+    /// as elapsed frames. A run paused longer than MAX_PAUSE_SECONDS ends
+    /// instead; that still succeeds, leaving the game over.
+    /// Authority only, like pausing.
+    pub fn resume_game(ctx: Context<OwnerAction>) -> Result<()> {
+        let game = &mut *ctx.accounts.game.load_mut()?;
+        let clock = Clock::get()?;
+        let high_score = game.high_score;
+        
+        if resume_run(game, clock.unix_timestamp, clock.slot)? {
+            msg!("Game resumed at frame {}", game.frame_count);
+            return Ok(());
         }
-        return Ok(());
+        
+        if let Some(profile) = ctx.accounts.profile.as_deref_mut() {
+            profile.record_run_end(game, clock.unix_timestamp);
+        }
+        if let Some(missions) = ctx.accounts.missions.as_deref_mut() {
+            missions.record_run_end(game, clock.unix_timestamp);
+        }
+        record_global_game_finished(ctx.accounts.global_stats.as_deref(), game)?;
+        emit!(game.game_over());
+        emit_new_high_score(game, (game.high_score > high_score).then_some(high_score));
+        msg!("Pause expired - Game Over! Score: {}", game.score);
+        Ok(())
     }
-    
-    // Update pipes
-    for i in 0..MAX_PIPES {
-        if game.pipes[i].active {
-            game.pipes[i].x -= PIPE_SPEED;
-            
-            // Check if pipe passed
-            if !game.pipes[i].passed && game.pipes[i].x + PIPE_WIDTH < BIRD_X {
-                game.pipes[i].passed = true;
-                game.score += 1;
-            }
-            
-            // Deactivate off-screen pipes
-            if game.pipes[i].x + PIPE_WIDTH < 0 {
-                game.pipes[i].active = false;
-            }
-            
-            // Check collision with this pipe
-            if check_pipe_collision(bird_y_pixels, &game.pipes[i]) {
-                game.game_status = GameStatus::GameOver;
-                if game.score > game.high_score {
-                    game.high_score = game.score;
-                }
-                return Ok(());
-            }
+
+    /// End the game - called when collision detected or manually, and the
+    /// only way a practice run ends
+    /// Authority only: a session key plays but can't end the run.
+    pub fn end_game(ctx: Context<OwnerAction>) -> Result<()> {
+        let game = &mut *ctx.accounts.game.load_mut()?;
+        require_ticking(game)?;
+        
+        let now = Clock::get()?.unix_timestamp;
+        finish_run(game, DeathCause::Manual, now);
+        if let Some(profile) = ctx.accounts.profile.as_deref_mut() {
+            profile.record_run_end(game, now);
+        }
+        if let Some(missions) = ctx.accounts.missions.as_deref_mut() {
+            missions.record_run_end(game, now);
         }
+        record_global_game_finished(ctx.accounts.global_stats.as_deref(), game)?;
+        msg!("Game Over! Score: {}, High Score: {}", game.score, game.high_score);
+        Ok(())
     }
-    
-    // Spawn new pipes
-    spawn_pipes(game)?;
-    
-    game.last_update = Clock::get()?.unix_timestamp;
-    
-    Ok(())
-}
 
-fn check_pipe_collision(bird_y: i32, pipe: &Pipe) -> bool {
-    if !pipe.active {
-        return false;
+    /// Reset game to initial state
+    /// Authority only: a session key plays but can't wipe the run.
+    pub fn reset_game(ctx: Context<OwnerAction>) -> Result<()> {
+        let game = &mut *ctx.accounts.game.load_mut()?;
+        
+        reset_run(game);
+        game.set_game_status(GameStatus::NotStarted);
+        
+        msg!("Game reset!");
+        Ok(())
     }
-    
-    // Check if bird is within pipe X range
-    if BIRD_X + BIRD_SIZE > pipe.x && BIRD_X < pipe.x + PIPE_WIDTH {
-        // Check if bird is outside the gap
-        let gap_top = pipe.gap_y - PIPE_GAP / 2;
-        let gap_bottom = pipe.gap_y + PIPE_GAP / 2;
+
+    /// Close the game account and return its rent to the authority
+    /// Only between runs. A delegated account is owned by the delegation program and
+    /// fails the `AccountLoader<GameState>` owner check, so `undelegate` must come first.
+    /// `initialize` afterwards starts from scratch, but the HighScoreRecord
+    /// keeps when the game's last ranked run ended, for the ranked cooldown.
+    pub fn close_game(ctx: Context<CloseGame>) -> Result<()> {
+        let game = &*ctx.accounts.game.load()?;
+        require!(
+            !game.game_status().in_run(),
+            FlappyError::GameInProgress
+        );
+        let record = &mut ctx.accounts.high_score_record;
+        record.claim(game.authority, ctx.bumps.high_score_record);
+        record.close_slot(game);
+        msg!("Game closed for player {}", ctx.accounts.authority.key());
+        Ok(())
+    }
+
+    /// Move a game (and optionally its profile) to a new wallet's PDAs
+    /// Both wallets sign, so nobody can be handed an account they didn't ask
+    /// for. High scores and coins carry over; the new game starts NotStarted
+    /// with fresh pipes, and the old accounts are closed to the old authority.
+    /// Leaderboard entries stay under the old key.
+    pub fn transfer_authority(ctx: Context<TransferAuthority>) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let game = &*accounts.game.load()?;
+        require!(
+            !game.game_status().in_run(),
+            FlappyError::GameInProgress
+        );
+        require!(
+            accounts.profile.is_some() == accounts.new_profile.is_some(),
+            FlappyError::ProfileMismatch
+        );
         
-        if bird_y < gap_top || bird_y + BIRD_SIZE > gap_bottom {
-            return true;
+        let new_authority = accounts.new_authority.key();
+        *accounts.new_game.load_init()? = game.transferred(
+            new_authority,
+            ctx.bumps.new_game,
+            Clock::get()?.unix_timestamp,
+        );
+        let record = &mut accounts.high_score_record;
+        record.claim(game.authority, ctx.bumps.high_score_record);
+        record.close_slot(game);
+        let new_record = &mut accounts.new_high_score_record;
+        new_record.claim(new_authority, ctx.bumps.new_high_score_record);
+        new_record.open_slot(game.game_slot);
+        if let (Some(profile), Some(new_profile)) = (&accounts.profile, &mut accounts.new_profile) {
+            **new_profile = PlayerProfile {
+                authority: new_authority,
+                bump: ctx.bumps.new_profile.unwrap_or_default(),
+                ..(**profile).clone()
+            };
         }
+        
+        msg!("Game moved from {} to {}", accounts.authority.key(), new_authority);
+        Ok(())
     }
-    
-    false
-}
 
-fn spawn_pipes(game: &mut Account<GameState>) -> Result<()> {
-    // Check if we need to spawn a new pipe
-    let mut rightmost_x = 0;
-    for i in 0..MAX_PIPES {
-        if game.pipes[i].active && game.pipes[i].x > rightmost_x {
-            rightmost_x = game.pipes[i].x;
-        }
+    /// Bring a game from an older seed version over to this slot's new game
+    /// The new game must be freshly initialized; its high scores, best run,
+    /// reward progress and coins are replaced by the old game's, whose account
+    /// is then closed to the authority. Runs in progress are not carried over.
+    /// `from_version` is the old game's: 6 to 9.
+    pub fn migrate_game(ctx: Context<MigrateGame>, from_version: u8) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let game = &mut *accounts.game.load_mut()?;
+        require!(
+            !game.game_status().in_run(),
+            FlappyError::GameInProgress
+        );
+        require!(game.high_score == 0, FlappyError::MigrationTargetNotEmpty);
+        
+        let old_authority = legacy::carry_over(from_version, &accounts.old_game.try_borrow_data()?, game)?;
+        require_keys_eq!(old_authority, accounts.authority.key(), FlappyError::InvalidLegacyGame);
+        
+        // Close the old account by hand, as `close =` does for typed ones
+        let old_game = accounts.old_game.to_account_info();
+        let rent = old_game.lamports();
+        **accounts.authority.to_account_info().try_borrow_mut_lamports()? += rent;
+        **old_game.try_borrow_mut_lamports()? = 0;
+        old_game.assign(&anchor_lang::system_program::ID);
+        old_game.resize(0)?;
+        
+        msg!("Game migrated for player {} in slot {}", accounts.authority.key(), game.game_slot);
+        Ok(())
     }
-    
-    // Spawn new pipe if there's space
-    if rightmost_x < GAME_WIDTH - PIPE_SPAWN_DISTANCE || rightmost_x == 0 {
-        // Find an inactive pipe slot
-        for i in 0..MAX_PIPES {
-            if !game.pipes[i].active {
-                // Generate pseudo-random gap position
-                game.seed = game.seed.wrapping_mul(1103515245).wrapping_add(12345);
-                let random_offset = ((game.seed / 65536) % 300) as i32;
-                let gap_y = PIPE_HEIGHT_MIN + PIPE_GAP / 2 + random_offset;
-                
-                game.pipes[i] = Pipe {
-                    x: GAME_WIDTH,
-                    gap_y: gap_y.min(GAME_HEIGHT - PIPE_HEIGHT_MIN - PIPE_GAP / 2),
-                    passed: false,
-                    active: true,
-                };
-                break;
-            }
-        }
+
+    /// Smoke-test run for integrators (devnet/test builds only, `demo` feature)
+    /// Plays DEMO_FRAMES frames from DEMO_SEED on an in-memory state, flapping on
+    /// every frame whose bit is set in `flap_pattern`, and returns the result.
+    /// No account is written, so high scores are never touched.
+    #[cfg(feature = "demo")]
+    pub fn demo_run(_ctx: Context<DemoRun>, flap_pattern: u32) -> Result<DemoResult> {
+        let result = simulate_demo_run(DEMO_SEED, flap_pattern);
+        msg!("Demo run: {} frames, score {}", result.frames, result.score);
+        Ok(result)
     }
-    
-    Ok(())
-}
 
-// ========================================
-// Account Contexts
-// ========================================
+    /// The inputs of the player's high-score run, via return data, for
+    /// rendering a ghost bird
+    pub fn get_best_run(ctx: Context<GetBestRun>) -> Result<BestRun> {
+        Ok(ctx.accounts.game.load()?.best_run())
+    }
 
-// Game version salt - increment to create fresh PDAs (v2 to fix stuck delegation)
-pub const GAME_SEED: &[u8] = b"game_v2";
+    /// The game as a `GameSnapshot`, via return data: a layout-independent
+    /// read for clients (through `simulateTransaction`) and other programs
+    pub fn get_state(ctx: Context<GetState>) -> Result<GameSnapshot> {
+        Ok(ctx.accounts.game.load()?.snapshot())
+    }
 
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(
-        init_if_needed,
-        payer = authority,
-        space = 8 + GameState::INIT_SPACE,
-        seeds = [GAME_SEED, authority.key().as_ref()],
-        bump
-    )]
-    pub game: Account<'info, GameState>,
+    /// Create the player's lifetime stats profile
+    pub fn initialize_profile(ctx: Context<InitializeProfile>) -> Result<()> {
+        let profile = &mut ctx.accounts.profile;
+        profile.authority = ctx.accounts.authority.key();
+        profile.bump = ctx.bumps.profile;
+        msg!("Profile initialized for player {}", profile.authority);
+        Ok(())
+    }
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+    // ========================================
+    // Co-op
+    // ========================================
 
-    pub system_program: Program<'info, System>,
-}
+    /// Start a co-op run: two birds in one pipe field, the game's authority
+    /// playing the first and `partner` the second, each with `flap_as`. The
+    /// run ends when either bird crashes, and each pipe scores once, as the
+    /// leading bird alive passes it (both fly at BIRD_X). Otherwise it starts
+    /// as `start_game` would with `reveal` and `difficulty`, on one life,
+    /// with no practice or horizontal control. Co-op runs aren't ranked and
+    /// leave the high scores alone; solo inputs (`flap`, `advance`) and
+    /// revives reject them.
+    pub fn start_coop(ctx: Context<StartGame>, partner: Pubkey, difficulty: Difficulty, reveal: u64) -> Result<()> {
+        let options = StartOptions {
+            difficulty,
+            reveal,
+            mode: Some(GameMode::Classic),
+            lives: 1,
+            ..Default::default()
+        };
+        start_run(ctx.accounts, options, Some(partner), ctx.remaining_accounts)
+    }
 
-#[derive(Accounts, Session)]
-pub struct GameAction<'info> {
-    #[account(
-        mut,
-        seeds = [GAME_SEED, game.authority.key().as_ref()],
-        bump
-    )]
-    pub game: Account<'info, GameState>,
+    /// Flap bird `player_index` of a co-op run: 0 for the game's authority,
+    /// 1 for the partner. The signer must be that player, or the session key
+    /// of an unexpired session token they issued for this program; on the
+    /// ER, where session tokens don't exist, it must be the player. Queued
+    /// like `flap(false)` for the next frame. Only the first bird's flaps go
+    /// in `input_log`, as co-op runs aren't recorded.
+    pub fn flap_as(ctx: Context<FlapAs>, player_index: u8) -> Result<()> {
+        let accounts = &*ctx.accounts;
+        require_not_paused(load_optional_config(accounts.config.as_deref())?.as_ref())?;
+        let game = &mut *accounts.game.load_mut()?;
+        let player = game.coop_player(player_index)?;
+        let token = accounts.session_token.as_ref().map(|token| token.key());
+        let session = token.as_ref().zip(accounts.session_token.as_deref());
+        require!(
+            acts_for(&player, &accounts.signer.key(), session, Clock::get()?.unix_timestamp),
+            FlappyError::NotCoopPlayer
+        );
+        skip_countdown_by_flap(game)?;
+        require_playing(game)?;
+        
+        queue_coop_flap(game, player_index)?;
+        log_frame(game);
+        Ok(())
+    }
 
-    // Note: signer is NOT mut so session keys work without needing SOL
-    pub signer: Signer<'info>,
+    // ========================================
+    // Missions
+    // ========================================
 
-    #[session(signer = signer, authority = game.authority.key())]
-    pub session_token: Option<Account<'info, SessionToken>>,
-}
+    /// Create the player's missions, filled from the current rotation. Passed
+    /// to gameplay instructions like the profile, they count pipes passed,
+    /// coins collected, games started and scores reached.
+    pub fn initialize_missions(ctx: Context<InitializeMissions>) -> Result<()> {
+        let missions = &mut ctx.accounts.missions;
+        missions.authority = ctx.accounts.authority.key();
+        missions.bump = ctx.bumps.missions;
+        missions.refresh(Clock::get()?.unix_timestamp);
+        msg!("Missions initialized for player {}", missions.authority);
+        Ok(())
+    }
 
-/// Simplified game action context for ER operations (no session token required)
-/// This works on the ER where session token accounts don't exist
-#[derive(Accounts)]
-pub struct SimpleGameAction<'info> {
-    #[account(
-        mut,
-        seeds = [GAME_SEED, game.authority.key().as_ref()],
-        bump
-    )]
-    pub game: Account<'info, GameState>,
+    /// Replace expired missions with the current rotation's, claimed or not
+    pub fn refresh_missions(ctx: Context<RefreshMissions>) -> Result<()> {
+        let replaced = ctx.accounts.missions.refresh(Clock::get()?.unix_timestamp);
+        msg!("{} missions refreshed", replaced);
+        Ok(())
+    }
 
-    /// Must be the game's authority - verified in each instruction
-    pub signer: Signer<'info>,
-}
+    /// Credit the reward of the completed mission in `slot` to the game's
+    /// coin balance, once; it can be claimed after it expires until it's
+    /// refreshed
+    pub fn claim_mission_reward(ctx: Context<ClaimMissionReward>, slot: u8) -> Result<()> {
+        let reward = ctx.accounts.missions.claim(slot)?;
+        let game = &mut *ctx.accounts.game.load_mut()?;
+        game.coins = game.coins.saturating_add(reward as u64);
+        msg!("Mission {} claimed for {} coins", slot, reward);
+        Ok(())
+    }
 
+    // ========================================
+    // Global stats
+    // ========================================
+
+    /// Create the GlobalStats (admin only, once). Gameplay instructions it's
+    /// passed to add to its totals.
+    pub fn initialize_global_stats(ctx: Context<InitializeGlobalStats>) -> Result<()> {
+        ctx.accounts.global_stats.bump = ctx.bumps.global_stats;
+        msg!("Global stats initialized");
+        Ok(())
+    }
+
+    /// Delegate the GlobalStats to an Ephemeral Rollup, so games delegated
+    /// there keep counting; base-layer games stop updating it until
+    /// `undelegate_global_stats` (admin only)
+    pub fn delegate_global_stats(ctx: Context<DelegateGlobalStats>, params: DelegateParams) -> Result<()> {
+        let delegation = params.resolve(ctx.remaining_accounts.first().map(|acc| acc.key()))?;
+        // Set while the account is still ours, as `delegate` does the game's flag
+        {
+            let mut data = ctx.accounts.pda.try_borrow_mut_data()?;
+            let mut stats = GlobalStats::try_deserialize(&mut &data[..])?;
+            stats.is_delegated = true;
+            stats.try_serialize(&mut &mut data[..])?;
+        }
+        ctx.accounts.delegate_pda(&ctx.accounts.payer, &[GLOBAL_STATS_SEED], delegation.config())?;
+        msg!("Global stats delegated to Ephemeral Rollup");
+        Ok(())
+    }
+
+    /// Commit the GlobalStats back to the base layer and undelegate it
+    /// (admin only)
+    pub fn undelegate_global_stats(ctx: Context<UndelegateGlobalStats>) -> Result<()> {
+        ctx.accounts.global_stats.is_delegated = false;
+        // Written out before the commit reads it
+        ctx.accounts.global_stats.exit(&crate::ID)?;
+        commit_and_undelegate_accounts(
+            &ctx.accounts.payer,
+            vec![&ctx.accounts.global_stats.to_account_info()],
+            &ctx.accounts.magic_context,
+            &ctx.accounts.magic_program,
+        )?;
+        msg!("Global stats undelegated from Ephemeral Rollup");
+        Ok(())
+    }
+
+    // ========================================
+    // Config
+    // ========================================
+
+    /// Create the GameConfig physics overrides (admin only, once)
+    pub fn initialize_config(ctx: Context<InitializeConfig>, physics: PhysicsConfig) -> Result<()> {
+        physics.validate()?;
+        let config = &mut ctx.accounts.config;
+        config.physics = physics;
+        config.bump = ctx.bumps.config;
+        msg!("Game config initialized");
+        Ok(())
+    }
+
+    /// Retune the physics used by runs started from now on (admin only)
+    pub fn update_config(ctx: Context<UpdateConfig>, physics: PhysicsConfig) -> Result<()> {
+        physics.validate()?;
+        ctx.accounts.config.physics = physics;
+        msg!("Game config updated");
+        Ok(())
+    }
+
+    /// Set how long after a ranked run ends the next one can start, 0 for no
+    /// limit (admin only). Casual and practice runs start any time.
+    pub fn set_ranked_cooldown(ctx: Context<UpdateConfig>, cooldown_secs: u32) -> Result<()> {
+        ctx.accounts.config.ranked_cooldown_secs = cooldown_secs;
+        msg!("Ranked cooldown set to {}s", cooldown_secs);
+        Ok(())
+    }
+
+    /// Set how many frames apart ranked and daily flaps must be, for runs
+    /// started from now on; 0 restores MIN_FLAP_INTERVAL_FRAMES, 1 allows a
+    /// flap every frame (admin only)
+    pub fn set_min_flap_interval(ctx: Context<UpdateConfig>, frames: u8) -> Result<()> {
+        ctx.accounts.config.min_flap_interval_frames = frames;
+        msg!("Minimum flap interval set to {} frames", frames);
+        Ok(())
+    }
+
+    /// Set which timed pickups ranked and daily runs started from now on can
+    /// get, bit `1 << kind.repr()` for each `Powerup`; none by default (admin
+    /// only)
+    pub fn set_ranked_pickups(ctx: Context<UpdateConfig>, kinds: u8) -> Result<()> {
+        ctx.accounts.config.ranked_pickups = kinds;
+        msg!("Ranked pickups set to {:#010b}", kinds);
+        Ok(())
+    }
+
+    /// Set the score runs started from now on must reach to extend the
+    /// player's win streak; 0 restores STREAK_THRESHOLD (admin only)
+    pub fn set_streak_threshold(ctx: Context<UpdateConfig>, score: u16) -> Result<()> {
+        ctx.accounts.config.streak_threshold = score;
+        msg!("Streak threshold set to {}", ctx.accounts.config.streak_threshold());
+        Ok(())
+    }
+
+    /// Set the coins `revive_with_coins` costs from now on; 0 restores
+    /// REVIVE_COIN_COST (admin only)
+    pub fn set_revive_coin_cost(ctx: Context<UpdateConfig>, coins: u64) -> Result<()> {
+        ctx.accounts.config.revive_coin_cost = coins;
+        msg!("Revive coin cost set to {}", ctx.accounts.config.revive_coin_cost());
+        Ok(())
+    }
+
+    /// Set how long after a run ends `submit_score` still takes it; 0
+    /// restores SUBMIT_WINDOW_SECS (admin only)
+    pub fn set_submit_window(ctx: Context<UpdateConfig>, window_secs: u32) -> Result<()> {
+        ctx.accounts.config.submit_window_secs = window_secs;
+        msg!("Submit window set to {}s", window_secs);
+        Ok(())
+    }
+
+    /// Pause or unpause the whole program in an emergency (admin only).
+    /// Paused, runs can't start, be played or be delegated; ending them,
+    /// `get_state`, `commit` and `undelegate` keep working, so players can
+    /// still finish and leave the ER.
+    pub fn set_paused(ctx: Context<UpdateConfig>, paused: bool) -> Result<()> {
+        ctx.accounts.config.paused = paused;
+        msg!("Program paused: {}", paused);
+        Ok(())
+    }
+
+    /// Set how many pixels the collision box sits in from each side of the
+    /// bird, up to MAX_HITBOX_INSET, for runs started from now on; None
+    /// restores HITBOX_INSET and Some(0) makes it the full sprite (admin only)
+    pub fn set_hitbox_inset(ctx: Context<UpdateConfig>, pixels: Option<u8>) -> Result<()> {
+        require!(pixels.is_none_or(|p| p <= MAX_HITBOX_INSET), FlappyError::InvalidConfig);
+        ctx.accounts.config.hitbox_inset_plus_one = pixels.map_or(0, |p| p + 1);
+        msg!("Hitbox inset set to {}px", ctx.accounts.config.hitbox_inset());
+        Ok(())
+    }
+
+    /// Set how many frames at the start of a run pipes can't kill in, up to
+    /// MAX_GRACE_FRAMES, for runs started from now on; None restores
+    /// GRACE_FRAMES and Some(0) turns the grace period off (admin only)
+    pub fn set_grace_frames(ctx: Context<UpdateConfig>, frames: Option<u8>) -> Result<()> {
+        require!(frames.is_none_or(|f| f <= MAX_GRACE_FRAMES), FlappyError::InvalidConfig);
+        ctx.accounts.config.grace_frames_plus_one = frames.map_or(0, |f| f + 1);
+        msg!("Grace period set to {} frames", ctx.accounts.config.grace_frames());
+        Ok(())
+    }
+
+    /// Set how many ticks runs started from now on count down for before the
+    /// bird moves, 0 for none, and whether a flap during the countdown skips
+    /// the rest of it, starting the run at once with that flap, or is
+    /// rejected (admin only)
+    pub fn set_countdown(ctx: Context<UpdateConfig>, frames: u8, flap_skips: bool) -> Result<()> {
+        ctx.accounts.config.countdown_frames = frames;
+        ctx.accounts.config.flap_skips_countdown = flap_skips;
+        msg!("Countdown set to {} frames, flap skips: {}", frames, flap_skips);
+        Ok(())
+    }
+
+    // ========================================
+    // Rewards
+    // ========================================
+
+    /// Create the reward token mint, a REWARD_MINT_SEED PDA whose mint
+    /// authority is the REWARD_AUTHORITY_SEED PDA and with no freeze authority
+    /// (admin only, once)
+    pub fn initialize_reward_mint(ctx: Context<InitializeRewardMint>) -> Result<()> {
+        let accounts = &ctx.accounts;
+        create_mint(
+            &accounts.reward_mint,
+            &[REWARD_MINT_SEED, &[ctx.bumps.reward_mint]],
+            REWARD_DECIMALS,
+            accounts.reward_authority.key,
+            &accounts.admin,
+            &accounts.token_program,
+            &accounts.system_program,
+        )?;
+        
+        msg!("Reward mint {} initialized", accounts.reward_mint.key());
+        Ok(())
+    }
+
+    /// `end_game`, then mint `score - rewarded_score` reward tokens to the
+    /// player's associated token account if the run is a personal best.
+    /// A run that is already over stays as it ended and only claims its
+    /// reward, which is paid once. The token account must already exist.
+    /// Authority only. Base layer only, as the mint isn't delegated.
+    pub fn end_game_with_reward(ctx: Context<EndGameWithReward>) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let game = &mut *accounts.game.load_mut()?;
+        let now = Clock::get()?.unix_timestamp;
+        if end_once(game, now)? {
+            if let Some(profile) = accounts.profile.as_deref_mut() {
+                profile.record_run_end(game, now);
+            }
+            if let Some(missions) = accounts.missions.as_deref_mut() {
+                missions.record_run_end(game, now);
+            }
+            record_global_game_finished(accounts.global_stats.as_deref(), game)?;
+        }
+        
+        require_keys_eq!(
+            accounts.player_token_account.key(),
+            associated_token_address(&game.authority, &accounts.reward_mint.key()),
+            FlappyError::InvalidRewardAccount
+        );
+        let amount = game.claim_reward();
+        if amount > 0 {
+            mint_tokens(
+                &accounts.reward_mint,
+                &accounts.player_token_account,
+                &accounts.reward_authority,
+                ctx.bumps.reward_authority,
+                &accounts.token_program,
+                amount,
+            )?;
+        }
+        
+        msg!("Game Over! Score: {}, reward: {}", game.score, amount);
+        Ok(())
+    }
+
+    /// Create the registry of score milestones, empty; anyone can pay for
+    /// it, only the admin can fill it
+    pub fn initialize_milestone_registry(ctx: Context<InitializeMilestoneRegistry>) -> Result<()> {
+        ctx.accounts.registry.bump = ctx.bumps.registry;
+        msg!("Milestone registry initialized");
+        Ok(())
+    }
+
+    /// Set up milestone `milestone`, reached with a high score of
+    /// `threshold`: create its MILESTONE_MINT_SEED mint (no decimals, minted
+    /// by the REWARD_AUTHORITY_SEED PDA) with Metaplex metadata for wallets
+    /// to show the NFT by (admin only, once per milestone)
+    pub fn add_milestone(
+        ctx: Context<AddMilestone>,
+        milestone: u8,
+        threshold: u64,
+        name: String,
+        symbol: String,
+        uri: String,
+    ) -> Result<()> {
+        require!(
+            (milestone as usize) < MAX_MILESTONES && threshold > 0,
+            FlappyError::InvalidMilestone
+        );
+        let accounts = &ctx.accounts;
+        create_mint(
+            &accounts.milestone_mint,
+            &[MILESTONE_MINT_SEED, &[milestone], &[ctx.bumps.milestone_mint]],
+            0,
+            accounts.reward_authority.key,
+            &accounts.admin,
+            &accounts.token_program,
+            &accounts.system_program,
+        )?;
+        create_metadata(accounts, ctx.bumps.reward_authority, name, symbol, uri)?;
+        ctx.accounts.registry.thresholds[milestone as usize] = threshold;
+        
+        msg!("Milestone {} at score {}: mint {}", milestone, threshold, ctx.accounts.milestone_mint.key());
+        Ok(())
+    }
+
+    /// Mint the player one token of milestone `milestone`'s NFT, once their
+    /// high score has reached its threshold. The profile records the claim so
+    /// it can't be repeated; the associated token account must already exist.
+    pub fn claim_milestone(ctx: Context<ClaimMilestone>, milestone: u8) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let threshold = accounts.registry.threshold(milestone)?;
+        let high_score = accounts.game.load()?.high_score;
+        accounts.profile.claim_milestone(milestone, threshold, high_score)?;
+        
+        require_keys_eq!(
+            accounts.player_token_account.key(),
+            associated_token_address(&accounts.authority.key(), &accounts.milestone_mint.key()),
+            FlappyError::InvalidRewardAccount
+        );
+        mint_tokens(
+            &accounts.milestone_mint,
+            &accounts.player_token_account,
+            &accounts.reward_authority,
+            ctx.bumps.reward_authority,
+            &accounts.token_program,
+            1,
+        )?;
+        
+        emit!(MilestoneClaimed {
+            player: accounts.authority.key(),
+            milestone,
+            mint: accounts.milestone_mint.key(),
+        });
+        msg!("Milestone {} claimed", milestone);
+        Ok(())
+    }
+
+    // ========================================
+    // Replay Verification
+    // ========================================
+
+    /// Re-simulate the run described by `params` from scratch, in the caller's
+    /// REPLAY_SEED cursor (no game account is touched), and check it reaches
+    /// `claimed_score` after exactly `frames` frames. Runs at most
+    /// `max_frames` frames per call; repeat the same call to resume until it
+    /// returns `Verified`. Divergence fails with `ReplayMismatch`.
+    pub fn verify_replay(ctx: Context<VerifyReplay>, params: ReplayParams, max_frames: u32) -> Result<ReplayOutcome> {
+        let cursor = &mut ctx.accounts.cursor;
+        cursor.bump = ctx.bumps.cursor;
+        let outcome = advance_replay(cursor, &params, max_frames)?;
+        msg!("Replay at frame {}, score {}", cursor.state.frame_count, cursor.state.score);
+        Ok(outcome)
+    }
+
+    // ========================================
+    // Revive
+    // ========================================
+
+    /// Create the treasury that collects revive fees (admin only, once)
+    pub fn initialize_treasury(ctx: Context<InitializeTreasury>, revive_fee: u64) -> Result<()> {
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.revive_fee = revive_fee;
+        treasury.bump = ctx.bumps.treasury;
+        msg!("Treasury initialized, revive fee {}", revive_fee);
+        Ok(())
+    }
+
+    /// Change the revive fee (admin only)
+    pub fn set_revive_fee(ctx: Context<SetReviveFee>, revive_fee: u64) -> Result<()> {
+        ctx.accounts.treasury.revive_fee = revive_fee;
+        msg!("Revive fee set to {}", revive_fee);
+        Ok(())
+    }
+
+    /// Continue a finished run once, for the treasury's revive fee: the bird
+    /// goes back to the center with the pipes around it cleared, keeping the
+    /// score. The run is marked `revived` and no longer ranked. Needs the
+    /// authority's own signature, so base layer only.
+    pub fn revive(ctx: Context<Revive>) -> Result<()> {
+        check_revivable(&*ctx.accounts.game.load()?)?;
+        
+        let fee = ctx.accounts.treasury.revive_fee;
+        if fee > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.authority.to_account_info(),
+                        to: ctx.accounts.treasury.to_account_info(),
+                    },
+                ),
+                fee,
+            )?;
+        }
+        
+        let game = &mut *ctx.accounts.game.load_mut()?;
+        revive_run(game, &Clock::get()?);
+        msg!("Revived at score {}", game.score);
+        Ok(())
+    }
+
+    /// Continue a finished run once as `revive` does, paying from the game's
+    /// coin balance instead of in lamports: REVIVE_COIN_COST coins, or the
+    /// GameConfig's cost. Either revive uses up the run's one revive, and
+    /// either way the run is no longer ranked.
+    pub fn revive_with_coins(ctx: Context<ReviveWithCoins>) -> Result<()> {
+        let config = load_config(&ctx.accounts.config)?;
+        let cost = config.as_ref().map_or(REVIVE_COIN_COST, GameConfig::revive_coin_cost);
+        let game = &mut *ctx.accounts.game.load_mut()?;
+        revive_for_coins(game, cost, &Clock::get()?)?;
+        msg!("Revived at score {} for {} coins, {} left", game.score, cost, game.coins);
+        Ok(())
+    }
+
+    // ========================================
+    // Leaderboard
+    // ========================================
+
+    /// Create the global leaderboard (once)
+    pub fn initialize_leaderboard(ctx: Context<InitializeLeaderboard>) -> Result<()> {
+        ctx.accounts.leaderboard.load_init()?;
+        msg!("Leaderboard initialized");
+        Ok(())
+    }
+    
+    /// Create the global leaderboard for Distance-mode runs (once)
+    pub fn initialize_distance_leaderboard(ctx: Context<InitializeDistanceLeaderboard>) -> Result<()> {
+        ctx.accounts.leaderboard.load_init()?;
+        msg!("Distance leaderboard initialized");
+        Ok(())
+    }
+
+    /// Submit the player's high score, as last synced to their
+    /// `HighScoreRecord`, to the global leaderboard
+    /// A player keeps a single entry holding their best score
+    /// With `distance_leaderboard`, the record's distance high score goes
+    /// there as well
+    /// Once seasons are configured, `season` must be the current season and
+    /// its leaderboard (created on first use) is passed too; the high score
+    /// must have been set after that season started. Likewise `week` must be
+    /// the current `week_of`, whose board is created by the first submission
+    /// of the week, and the high score must have been set in it. Succeeds if
+    /// any board takes the score.
+    /// The score has to be settled on the base layer: the game it came from
+    /// must be back from the ER (`GameDelegated`) with no run in progress
+    /// (`GameNotOver`), and the run must have ended within the GameConfig's
+    /// submit window (`RunTooOld`). The entry keeps the run's last frame
+    /// and seed, which `ScoreSubmitted` reports for replay verification.
+    pub fn submit_score(ctx: Context<SubmitScore>, season: u32, week: u32) -> Result<()> {
+        let record = &ctx.accounts.high_score_record;
+        require!(record.high_score_timestamp != 0, FlappyError::NoHighScore);
+        require_settled(&ctx.accounts.game)?;
+        
+        let now = Clock::get()?.unix_timestamp;
+        let config = load_config(&ctx.accounts.config)?;
+        require_fresh(record, config.as_ref(), now)?;
+        let entry = LeaderboardEntry::for_record(record, now);
+        
+        require!(week == week_of(now), FlappyError::WrongWeek);
+        require!(week_of(record.high_score_timestamp) == week, FlappyError::RunBeforeWeek);
+        
+        let mut taken_elsewhere = false;
+        {
+            let mut board = load_or_init(&ctx.accounts.weekly_leaderboard)?;
+            board.week = week as u64;
+            let count = board.count as usize;
+            if let Submission::Inserted { rank, count } = insert_entry(&mut board.entries, count, entry) {
+                board.count = count as u64;
+                taken_elsewhere = true;
+                msg!("Score {} entered week {} at #{}", entry.score, week, rank + 1);
+            }
+        }
+
+        if let Some(seasons) = load_seasons(&ctx.accounts.season_config)? {
+            if let Some(current) = seasons.season_for_run(now, record.high_score_timestamp)? {
+                require!(season == current, FlappyError::WrongSeason);
+                let board = ctx
+                    .accounts
+                    .season_leaderboard
+                    .as_ref()
+                    .ok_or(FlappyError::WrongSeason)?;
+                let mut board = load_or_init(board)?;
+                board.season = season as u64;
+                let count = board.count as usize;
+                if let Submission::Inserted { rank, count } = insert_entry(&mut board.entries, count, entry) {
+                    board.count = count as u64;
+                    taken_elsewhere = true;
+                    msg!("Score {} entered season {} at #{}", entry.score, season, rank + 1);
+                }
+            }
+        }
+        
+        // Seasons and weeks rank pipes passed; distance only has the global board
+        if let (Some(board), true) = (&ctx.accounts.distance_leaderboard, record.distance_high_score > 0) {
+            let distance = LeaderboardEntry {
+                player: record.authority,
+                score: record.distance_high_score,
+                timestamp: now,
+                mode: ScoringMode::Distance as u8,
+                ..Default::default()
+            };
+            let mut board = board.load_mut()?;
+            let count = board.count as usize;
+            if let Submission::Inserted { rank, count } = insert_entry(&mut board.entries, count, distance) {
+                board.count = count as u64;
+                taken_elsewhere = true;
+                msg!("Distance {} entered the distance leaderboard at #{}", distance.score, rank + 1);
+            }
+        }
+        
+        let mut leaderboard = ctx.accounts.leaderboard.load_mut()?;
+        let count = leaderboard.count as usize;
+        match insert_entry(&mut leaderboard.entries, count, entry) {
+            Submission::Inserted { rank, count } => {
+                leaderboard.count = count as u64;
+                msg!("Score {} entered the leaderboard at #{}", entry.score, rank + 1);
+            }
+            _ if taken_elsewhere => {}
+            Submission::DoesNotQualify => return err!(FlappyError::ScoreTooLow),
+            Submission::NotImproved => return err!(FlappyError::ScoreNotImproved),
+        }
+        emit!(ScoreSubmitted {
+            player: entry.player,
+            score: entry.score,
+            frame: entry.frame,
+            seed: entry.seed,
+            ended_at: entry.ended_at,
+        });
+        Ok(())
+    }
+
+    /// The week `submit_score` takes runs for now, to find its board by
+    pub fn current_week(ctx: Context<CurrentWeek>) -> Result<u32> {
+        Ok(week_of(ctx.accounts.clock.unix_timestamp))
+    }
+
+    /// Copy the game's high score and its run's provenance into the player's
+    /// `HighScoreRecord` (created on first use), which is never delegated,
+    /// so the base layer and `submit_score` see it wherever the game is. A
+    /// score from a run with extra lives or a revive isn't copied, nor one
+    /// below the record's, which may come from another slot. Needs the game
+    /// on the base layer: after `undelegate`, whose ER transaction can't
+    /// write the record, call this to bring it up to date.
+    pub fn sync_high_score(ctx: Context<SyncHighScore>, slot: u8) -> Result<()> {
+        let game = &*ctx.accounts.game.load()?;
+        let record = &mut ctx.accounts.high_score_record;
+        record.claim(game.authority, ctx.bumps.high_score_record);
+        if record.sync(game) {
+            msg!("High score {} from slot {} synced", record.high_score, slot);
+        }
+        Ok(())
+    }
+
+    /// Start seasons: season n runs from `start + n * duration` for `duration`
+    /// seconds (admin only, once, as changing them would renumber history)
+    pub fn initialize_seasons(ctx: Context<InitializeSeasons>, start: i64, duration: i64) -> Result<()> {
+        require!(duration > 0, FlappyError::InvalidConfig);
+        let seasons = &mut ctx.accounts.season_config;
+        seasons.start = start;
+        seasons.duration = duration;
+        seasons.bump = ctx.bumps.season_config;
+        msg!("Seasons of {}s from {}", duration, start);
+        Ok(())
+    }
+
+    // ========================================
+    // Daily Challenge
+    // ========================================
+
+    /// Start a Normal run on today's daily seed, the same layout for every
+    /// player (no `request_game` needed)
+    pub fn start_daily_challenge(ctx: Context<StartDailyChallenge>) -> Result<()> {
+        let game = &mut *ctx.accounts.game.load_mut()?;
+        require!(
+            !game.game_status().in_run(),
+            FlappyError::GameAlreadyStarted
+        );
+        
+        let clock = Clock::get()?;
+        let day = day_of(clock.unix_timestamp);
+        let config = load_config(&ctx.accounts.config)?;
+        require_not_paused(config.as_ref())?;
+        begin_run(
+            game,
+            config.as_ref(),
+            Difficulty::Normal,
+            ScoringMode::PipesPassed,
+            daily_seed(day),
+            &clock,
+        );
+        game.set_game_mode(GameMode::Classic);
+        game.set_daily(true);
+        game.daily_day = day;
+        let record = load_high_score_record(&ctx.accounts.high_score_record)?;
+        check_ranked_cooldown(
+            game,
+            last_ranked_game_over(game, record.as_ref(), ctx.remaining_accounts)?,
+            config.map_or(0, |config| config.ranked_cooldown_secs),
+            clock.unix_timestamp,
+        )?;
+        
+        if let Some(profile) = ctx.accounts.profile.as_deref_mut() {
+            profile.games_played = profile.games_played.saturating_add(1);
+        }
+        if let Some(missions) = ctx.accounts.missions.as_deref_mut() {
+            missions.record_game_started(clock.unix_timestamp);
+        }
+        let global_stats = ctx.accounts.global_stats.as_deref();
+        if let Some(mut stats) = load_global_stats(global_stats, game)? {
+            stats.record_game_started(game);
+            store_global_stats(global_stats, Some(stats))?;
+        }
+        
+        emit!(GameStarted {
+            player: game.authority,
+            seed: game.seed,
+            seed_source: game.seed_source,
+            difficulty: game.difficulty(),
+            timestamp: game.last_update,
+        });
+        msg!("Daily challenge {} started", day);
+        Ok(())
+    }
+
+    /// Create the leaderboard for unix day `day` (anyone, once per day)
+    pub fn initialize_daily_leaderboard(ctx: Context<InitializeDailyLeaderboard>, day: u32) -> Result<()> {
+        ctx.accounts.leaderboard.load_init()?.day = day as u64;
+        msg!("Daily leaderboard {} initialized", day);
+        Ok(())
+    }
+
+    /// Submit a finished daily run to its day's leaderboard, keeping each
+    /// player's best. The run must have been started with
+    /// `start_daily_challenge` and have ended on the same day.
+    pub fn submit_daily_score(ctx: Context<SubmitDailyScore>) -> Result<()> {
+        let game = &*ctx.accounts.game.load()?;
+        let day = daily_board_day(game)?;
+        
+        let mut leaderboard = ctx.accounts.leaderboard.load_mut()?;
+        let entry = LeaderboardEntry::for_run(game, Clock::get()?.unix_timestamp);
+        let count = leaderboard.count as usize;
+        match insert_entry(&mut leaderboard.entries, count, entry) {
+            Submission::Inserted { rank, count } => {
+                leaderboard.count = count as u64;
+                msg!("Day {}: score {} at #{}", day, entry.score, rank + 1);
+                Ok(())
+            }
+            Submission::DoesNotQualify => err!(FlappyError::ScoreTooLow),
+            Submission::NotImproved => err!(FlappyError::ScoreNotImproved),
+        }
+    }
+
+    // ========================================
+    // Tournaments
+    // ========================================
+
+    /// Open tournament `id` for entries between `start_time` and `end_time`
+    /// (unix seconds), ranking runs played on `difficulty`; every entry fee
+    /// goes into the prize pool
+    pub fn create_tournament(
+        ctx: Context<CreateTournament>,
+        id: u64,
+        entry_fee: u64,
+        start_time: i64,
+        end_time: i64,
+        difficulty: Difficulty,
+    ) -> Result<()> {
+        require!(
+            start_time < end_time && end_time > Clock::get()?.unix_timestamp,
+            FlappyError::InvalidTournamentWindow
+        );
+        let tournament = &mut ctx.accounts.tournament;
+        tournament.organizer = ctx.accounts.organizer.key();
+        tournament.id = id;
+        tournament.entry_fee = entry_fee;
+        tournament.start_time = start_time;
+        tournament.end_time = end_time;
+        tournament.difficulty = difficulty;
+        tournament.bump = ctx.bumps.tournament;
+        msg!("Tournament {} created, fee {} lamports", id, entry_fee);
+        Ok(())
+    }
+
+    /// Pay the entry fee into the tournament and register for it
+    pub fn join_tournament(ctx: Context<JoinTournament>) -> Result<()> {
+        let tournament = &mut ctx.accounts.tournament;
+        require!(
+            Clock::get()?.unix_timestamp < tournament.end_time,
+            FlappyError::TournamentClosed
+        );
+        tournament.register(ctx.accounts.player.key())?;
+        
+        let fee = tournament.entry_fee;
+        if fee > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.player.to_account_info(),
+                        to: ctx.accounts.tournament.to_account_info(),
+                    },
+                ),
+                fee,
+            )?;
+        }
+        let tournament = &mut ctx.accounts.tournament;
+        tournament.prize_pool = tournament.prize_pool.saturating_add(fee);
+        
+        msg!("Joined tournament {} ({} entrants)", tournament.id, tournament.entrant_count);
+        Ok(())
+    }
+
+    /// Record a finished run for the tournament; only the player's best counts
+    /// The run must be over, on the tournament's difficulty, and have last
+    /// updated inside the tournament window
+    pub fn submit_tournament_score(ctx: Context<SubmitTournamentScore>) -> Result<()> {
+        let game = &*ctx.accounts.game.load()?;
+        let tournament = &mut ctx.accounts.tournament;
+        tournament.check_run(game)?;
+        
+        if tournament.submit(game.authority, game.score)? {
+            msg!("Tournament {} score {}", tournament.id, game.score);
+        } else {
+            msg!("Tournament {}: score {} does not beat the best so far", tournament.id, game.score);
+        }
+        Ok(())
+    }
+
+    /// Pay the pool out after `end_time`: TOURNAMENT_PRIZE_SHARES to the top
+    /// finishers, passed as remaining accounts in rank order. Shares for places
+    /// nobody finished in, and rounding dust, go back to the organizer. Runs
+    /// once; a second call fails with `TournamentSettled`.
+    pub fn settle_tournament<'info>(ctx: Context<'_, '_, 'info, 'info, SettleTournament<'info>>) -> Result<()> {
+        let tournament = &mut ctx.accounts.tournament;
+        require!(!tournament.settled, FlappyError::TournamentSettled);
+        require!(
+            Clock::get()?.unix_timestamp > tournament.end_time,
+            FlappyError::TournamentNotOver
+        );
+        
+        let (payouts, leftover) = tournament.payouts();
+        require!(
+            ctx.remaining_accounts.len() == payouts.len(),
+            FlappyError::WrongPrizeAccount
+        );
+        tournament.settled = true;
+        tournament.prize_pool = 0;
+        
+        let source = tournament.to_account_info();
+        for ((winner, amount), account) in payouts.iter().zip(ctx.remaining_accounts) {
+            require_keys_eq!(account.key(), *winner, FlappyError::WrongPrizeAccount);
+            **source.try_borrow_mut_lamports()? -= amount;
+            **account.try_borrow_mut_lamports()? += amount;
+        }
+        **source.try_borrow_mut_lamports()? -= leftover;
+        **ctx.accounts.organizer.try_borrow_mut_lamports()? += leftover;
+        
+        msg!("Tournament {} settled: {} winners", tournament.id, payouts.len());
+        Ok(())
+    }
+
+    // ========================================
+    // Head-to-head Matches
+    // ========================================
+
+    /// Challenge `opponent` to match `id`: both play one run on the same pipe
+    /// layout, from a seed fixed now
+    pub fn create_match(ctx: Context<CreateMatch>, id: u64, opponent: Pubkey, difficulty: Difficulty) -> Result<()> {
+        open_match(ctx.accounts, ctx.bumps.game_match, id, opponent, difficulty)
+    }
+
+    /// `create_match` with `stake_lamports` from each player on it: the
+    /// challenger's stake goes into the match now, the opponent's with
+    /// `accept_wager`, and `claim_winnings` pays the pot out once settled
+    pub fn create_wager_match(
+        ctx: Context<CreateMatch>,
+        id: u64,
+        opponent: Pubkey,
+        difficulty: Difficulty,
+        stake_lamports: u64,
+    ) -> Result<()> {
+        require!(stake_lamports > 0, FlappyError::NotAWager);
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.challenger.to_account_info(),
+                    to: ctx.accounts.game_match.to_account_info(),
+                },
+            ),
+            stake_lamports,
+        )?;
+        open_match(ctx.accounts, ctx.bumps.game_match, id, opponent, difficulty)?;
+        let game_match = &mut ctx.accounts.game_match;
+        game_match.stake = stake_lamports;
+        game_match.pot = stake_lamports;
+        Ok(())
+    }
+
+    /// The opponent matches the stake, within WAGER_ACCEPT_SECONDS of the
+    /// challenge. The match's play window starts now.
+    pub fn accept_wager(ctx: Context<AcceptWager>) -> Result<()> {
+        let game_match = &mut ctx.accounts.game_match;
+        require!(game_match.stake > 0, FlappyError::NotAWager);
+        require!(!game_match.accepted(), FlappyError::WagerAccepted);
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now.saturating_sub(game_match.created_at) <= WAGER_ACCEPT_SECONDS,
+            FlappyError::MatchExpired
+        );
+        
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.opponent.to_account_info(),
+                    to: game_match.to_account_info(),
+                },
+            ),
+            game_match.stake,
+        )?;
+        game_match.pot += game_match.stake;
+        game_match.accepted_at = now;
+        msg!("Match {} wager of {} accepted", game_match.id, game_match.stake);
+        Ok(())
+    }
+
+    /// Close a wager the opponent didn't accept within WAGER_ACCEPT_SECONDS,
+    /// refunding the challenger's stake along with the rent
+    pub fn cancel_and_refund(ctx: Context<CancelWager>) -> Result<()> {
+        let game_match = &ctx.accounts.game_match;
+        require!(game_match.stake > 0, FlappyError::NotAWager);
+        require!(!game_match.accepted(), FlappyError::WagerAccepted);
+        require!(
+            Clock::get()?.unix_timestamp.saturating_sub(game_match.created_at) > WAGER_ACCEPT_SECONDS,
+            FlappyError::AcceptWindowOpen
+        );
+        msg!("Match {} cancelled, {} refunded", game_match.id, game_match.pot);
+        Ok(())
+    }
+
+    /// Pay a settled wager's pot to the winner, or half to each player on a
+    /// draw (including when neither finished). Anyone may call it; it pays
+    /// out once.
+    pub fn claim_winnings(ctx: Context<ClaimWinnings>) -> Result<()> {
+        let game_match = &mut ctx.accounts.game_match;
+        require!(game_match.stake > 0, FlappyError::NotAWager);
+        require!(game_match.settled, FlappyError::MatchNotSettled);
+        require!(game_match.pot > 0, FlappyError::WinningsPaid);
+        
+        let (to_challenger, to_opponent) = game_match.payouts();
+        game_match.pot = 0;
+        
+        let source = game_match.to_account_info();
+        **source.try_borrow_mut_lamports()? -= to_challenger + to_opponent;
+        **ctx.accounts.challenger.try_borrow_mut_lamports()? += to_challenger;
+        **ctx.accounts.opponent.try_borrow_mut_lamports()? += to_opponent;
+        
+        msg!("Match {} paid out: {} / {}", game_match.id, to_challenger, to_opponent);
+        Ok(())
+    }
+
+    /// Start the caller's one run for the match on its shared seed, in place
+    /// of `request_game`/`start_game`
+    pub fn start_match_run(ctx: Context<StartMatchRun>) -> Result<()> {
+        let config = load_config(&ctx.accounts.config)?;
+        require_not_paused(config.as_ref())?;
+        let game_match = &mut ctx.accounts.game_match;
+        let game = &mut *ctx.accounts.game.load_mut()?;
+        require!(
+            !game.game_status().in_run(),
+            FlappyError::GameAlreadyStarted
+        );
+        
+        let clock = Clock::get()?;
+        require!(game_match.stake == 0 || game_match.accepted(), FlappyError::WagerNotAccepted);
+        require!(!game_match.expired(clock.unix_timestamp), FlappyError::MatchExpired);
+        let side = game_match.side_mut(game.authority)?;
+        require!(side.game == Pubkey::default(), FlappyError::MatchRunUsed);
+        side.game = ctx.accounts.game.key();
+        
+        begin_run(
+            game,
+            config.as_ref(),
+            game_match.difficulty,
+            ScoringMode::PipesPassed,
+            game_match.seed,
+            &clock,
+        );
+        game.set_game_mode(GameMode::Classic);
+        
+        emit!(GameStarted {
+            player: game.authority,
+            seed: game.seed,
+            seed_source: game.seed_source,
+            difficulty: game.difficulty(),
+            timestamp: game.last_update,
+        });
+        msg!("Match {} run started", game_match.id);
+        Ok(())
+    }
+
+    /// Settle the match from both players' games, which must be on the base
+    /// layer. Once both match runs are over the higher score wins, ties going
+    /// to fewer flaps. After MATCH_TIMEOUT_SECONDS a player who finished wins
+    /// against one who didn't; if neither finished there's no winner. A
+    /// wager settles only once accepted, its window running from then.
+    pub fn report_match_result(ctx: Context<ReportMatchResult>) -> Result<()> {
+        let game_match = &mut ctx.accounts.game_match;
+        require!(!game_match.settled, FlappyError::MatchSettled);
+        require!(game_match.stake == 0 || game_match.accepted(), FlappyError::WagerNotAccepted);
+        
+        let seed = game_match.seed;
+        let challenger = game_match.challenger.record(ctx.accounts.challenger_game.as_ref(), seed)?;
+        let opponent = game_match.opponent.record(ctx.accounts.opponent_game.as_ref(), seed)?;
+        require!(
+            (challenger && opponent) || game_match.expired(Clock::get()?.unix_timestamp),
+            FlappyError::MatchNotFinished
+        );
+        
+        game_match.winner = decide_match(&game_match.challenger, &game_match.opponent);
+        game_match.settled = true;
+        
+        msg!(
+            "Match {} settled: {} - {}",
+            game_match.id,
+            game_match.challenger.score,
+            game_match.opponent.score
+        );
+        Ok(())
+    }
+
+    // ========================================
+    // Challenges
+    // ========================================
+
+    /// Dare `target` to beat `score_to_beat` on run seed `seed` by `expiry`
+    pub fn create_challenge(
+        ctx: Context<CreateChallenge>,
+        target: Pubkey,
+        score_to_beat: u64,
+        seed: u64,
+        expiry: i64,
+    ) -> Result<()> {
+        let challenger = ctx.accounts.challenger.key();
+        require_keys_neq!(challenger, target, FlappyError::InvalidChallenge);
+        require!(expiry > Clock::get()?.unix_timestamp, FlappyError::InvalidChallenge);
+
+        let challenge = &mut ctx.accounts.challenge;
+        challenge.challenger = challenger;
+        challenge.target = target;
+        challenge.score_to_beat = score_to_beat;
+        challenge.seed = seed;
+        challenge.expiry = expiry;
+        challenge.bump = ctx.bumps.challenge;
+
+        emit!(ChallengeCreated { challenger, target, score_to_beat, seed, expiry });
+        msg!("Challenge: {} to beat {} on seed {}", target, score_to_beat, seed);
+        Ok(())
+    }
+
+    /// The target takes the challenge up, starting their run on its seed in
+    /// place of `request_game`/`start_game`. One attempt, before `expiry`.
+    pub fn accept_challenge(ctx: Context<AcceptChallenge>) -> Result<()> {
+        let config = load_config(&ctx.accounts.config)?;
+        require_not_paused(config.as_ref())?;
+        let challenge = &mut ctx.accounts.challenge;
+        let game = &mut *ctx.accounts.game.load_mut()?;
+        require!(!challenge.accepted, FlappyError::ChallengeAccepted);
+        require!(
+            !game.game_status().in_run(),
+            FlappyError::GameAlreadyStarted
+        );
+        let clock = Clock::get()?;
+        require!(clock.unix_timestamp <= challenge.expiry, FlappyError::ChallengeExpired);
+
+        challenge.accepted = true;
+        challenge.game = ctx.accounts.game.key();
+        begin_run(
+            game,
+            config.as_ref(),
+            Difficulty::Normal,
+            ScoringMode::PipesPassed,
+            challenge.seed,
+            &clock,
+        );
+        game.set_game_mode(GameMode::Classic);
+
+        emit!(ChallengeAccepted {
+            challenger: challenge.challenger,
+            target: challenge.target,
+            seed: challenge.seed,
+        });
+        emit!(GameStarted {
+            player: game.authority,
+            seed: game.seed,
+            seed_source: game.seed_source,
+            difficulty: game.difficulty(),
+            timestamp: game.last_update,
+        });
+        msg!("Challenge accepted");
+        Ok(())
+    }
+
+    /// Record whether the target beat the score, once: from their finished
+    /// challenge run, or as a failure once `expiry` has passed without one.
+    /// Anyone may call it; the game is needed unless the challenge expired.
+    pub fn resolve_challenge(ctx: Context<ResolveChallenge>) -> Result<()> {
+        let challenge = &mut ctx.accounts.challenge;
+        require!(!challenge.resolved, FlappyError::ChallengeResolved);
+
+        let now = Clock::get()?.unix_timestamp;
+        let score = match ctx.accounts.game.as_ref() {
+            Some(loader) => challenge.finished_score(loader)?,
+            None => None,
+        };
+        require!(score.is_some() || now > challenge.expiry, FlappyError::ChallengeNotFinished);
+
+        challenge.resolved = true;
+        challenge.score = score.unwrap_or_default();
+        challenge.succeeded = challenge.beaten_by(score);
+
+        emit!(ChallengeResolved {
+            challenger: challenge.challenger,
+            target: challenge.target,
+            score: challenge.score,
+            succeeded: challenge.succeeded,
+        });
+        msg!("Challenge resolved: {}", if challenge.succeeded { "beaten" } else { "failed" });
+        Ok(())
+    }
+
+    /// Withdraw a challenge the target hasn't accepted, reclaiming its rent
+    pub fn cancel_challenge(ctx: Context<CancelChallenge>) -> Result<()> {
+        require!(!ctx.accounts.challenge.accepted, FlappyError::ChallengeAccepted);
+        msg!("Challenge cancelled");
+        Ok(())
+    }
+
+    // ========================================
+    // External Bonuses
+    // ========================================
+
+    /// Create the list of programs allowed to call `award_external_bonus`,
+    /// empty; anyone can pay for it, only the admin can fill it
+    pub fn initialize_cpi_allowlist(ctx: Context<InitializeCpiAllowlist>) -> Result<()> {
+        ctx.accounts.allowlist.bump = ctx.bumps.allowlist;
+        msg!("CPI allowlist initialized");
+        Ok(())
+    }
+
+    /// Let `program` award bonuses, or stop it (admin only)
+    pub fn set_cpi_caller(ctx: Context<SetCpiCaller>, program: Pubkey, allowed: bool) -> Result<()> {
+        ctx.accounts.allowlist.set(program, allowed)?;
+        msg!("CPI caller {} allowed: {}", program, allowed);
+        Ok(())
+    }
+
+    /// Add `points` to the bonus of a run in progress, for another program's
+    /// own challenges. Only through CPI, from a transaction whose top-level
+    /// instruction belongs to a program on the allowlist, signed by the
+    /// player or their session key. The bonus is kept apart from the score,
+    /// so it never counts towards a high score, board or reward.
+    pub fn award_external_bonus(ctx: Context<AwardExternalBonus>, points: u16) -> Result<()> {
+        let accounts = &*ctx.accounts;
+        let caller = cpi_caller(&accounts.instructions)?;
+        require!(accounts.allowlist.allows(&caller), FlappyError::CallerNotAllowed);
+        let game = &mut *accounts.game.load_mut()?;
+        let token = accounts.session_token.as_ref().map(|token| token.key());
+        let session = token.as_ref().zip(accounts.session_token.as_deref());
+        require!(
+            acts_for(&game.authority, &accounts.signer.key(), session, Clock::get()?.unix_timestamp),
+            FlappyError::NotGameAuthority
+        );
+        game.award_bonus(points)?;
+        
+        emit!(ExternalBonusAwarded {
+            player: game.authority,
+            program: caller,
+            points,
+            bonus_score: game.bonus_score,
+        });
+        Ok(())
+    }
+
+    // ========================================
+    // Bird Skins
+    // ========================================
+
+    /// Create the registry of NFT mints unlocking skins, empty; anyone can
+    /// pay for it, only the admin can fill it
+    pub fn initialize_skin_registry(ctx: Context<InitializeSkinRegistry>) -> Result<()> {
+        ctx.accounts.registry.bump = ctx.bumps.registry;
+        msg!("Skin registry initialized");
+        Ok(())
+    }
+
+    /// Let holders of an NFT of `mint` wear skin `skin_id` (admin only).
+    /// A skin can have several mints, one per NFT that unlocks it.
+    pub fn add_skin(ctx: Context<UpdateSkinRegistry>, skin_id: u8, mint: Pubkey) -> Result<()> {
+        require!(skin_id >= FREE_SKINS, FlappyError::FreeSkin);
+        ctx.accounts.registry.add(skin_id, mint)?;
+        msg!("Skin {} unlocked by mint {}", skin_id, mint);
+        Ok(())
+    }
+
+    /// Stop `mint` unlocking skin `skin_id` (admin only). Players already
+    /// wearing it keep it until they change skin.
+    pub fn remove_skin(ctx: Context<UpdateSkinRegistry>, skin_id: u8, mint: Pubkey) -> Result<()> {
+        ctx.accounts.registry.remove(skin_id, mint);
+        msg!("Skin {} no longer unlocked by mint {}", skin_id, mint);
+        Ok(())
+    }
+
+    /// Wear skin `skin_id`; purely cosmetic, for the client to draw. Skins
+    /// below `FREE_SKINS` are open to all; the rest need the registry and,
+    /// as the first remaining account, the authority's token account holding
+    /// an NFT of a mint registered for the skin. Authority only.
+    pub fn set_skin(ctx: Context<SetSkin>, skin_id: u8) -> Result<()> {
+        if skin_id >= FREE_SKINS {
+            let registry = ctx.accounts.registry.as_ref().ok_or(FlappyError::SkinNotOwned)?;
+            let token = ctx.remaining_accounts.first().ok_or(FlappyError::SkinNotOwned)?;
+            require!(
+                token.owner == &TOKEN_PROGRAM_ID || token.owner == &TOKEN_2022_PROGRAM_ID,
+                FlappyError::SkinNotOwned
+            );
+            let mint = held_nft_mint(&token.try_borrow_data()?, &ctx.accounts.signer.key())?;
+            require!(registry.unlocks(skin_id, &mint), FlappyError::SkinNotOwned);
+        }
+        ctx.accounts.game.load_mut()?.bird_skin = skin_id;
+        msg!("Skin set to {}", skin_id);
+        Ok(())
+    }
+
+    // ========================================
+    // MagicBlock Ephemeral Rollups Functions
+    // ========================================
+
+    /// Delegate the game account to the Ephemeral Rollup
+    /// The authority signs; `payer` funds it and may be anyone, e.g. a
+    /// backend sponsoring the player. Unset `params` keep the ER defaults,
+    /// except that the validator may still come from the first remaining
+    /// account. What was chosen is kept in `game.delegation`.
+    pub fn delegate(ctx: Context<DelegateInput>, slot: u8, params: DelegateParams) -> Result<()> {
+        require_not_paused(load_optional_config(ctx.accounts.config.as_deref())?.as_ref())?;
+        let delegation = params.resolve(ctx.remaining_accounts.first().map(|acc| acc.key()))?;
+        
+        // Written while the account is still ours; the delegation copies the
+        // data over, so the ER starts with the flag set. The PDA is raw here,
+        // so the game is read the way `AccountLoader::load_mut` would.
+        {
+            let mut data = ctx.accounts.pda.try_borrow_mut_data()?;
+            require!(data.starts_with(GameState::DISCRIMINATOR), ErrorCode::AccountDiscriminatorMismatch);
+            let game: &mut GameState = bytemuck::from_bytes_mut(&mut data[8..8 + std::mem::size_of::<GameState>()]);
+            game.set_is_delegated(true);
+            game.delegation = delegation;
+        }
+        
+        ctx.accounts.delegate_pda(
+            &ctx.accounts.payer,
+            &[GAME_SEED, ctx.accounts.authority.key().as_ref(), &[slot]],
+            delegation.config(),
+        )?;
+        emit!(Delegated { player: ctx.accounts.authority.key(), validator: delegation.validator() });
+        msg!("Game slot {} delegated to Ephemeral Rollup", slot);
+        Ok(())
+    }
+
+    /// Commit game state to the base layer
+    /// Anyone may pay for a commit, which only publishes the current state.
+    /// A game unchanged since its last commit isn't committed again: the
+    /// call emits `NothingToCommit` instead, so a scheduled keeper pays no
+    /// commit fee for an idle game.
+    pub fn commit(ctx: Context<CommitInput>, slot: u8) -> Result<()> {
+        let committed = {
+            let game = &mut *ctx.accounts.game.load_mut()?;
+            if !mark_committed(game) {
+                emit!(NothingToCommit { player: game.authority, frame_count: game.frame_count });
+                msg!("Game slot {} unchanged since its last commit", slot);
+                return Ok(());
+            }
+            Committed { player: game.authority, score: game.score, frame_count: game.frame_count }
+        };
+        
+        commit_accounts(
+            &ctx.accounts.payer,
+            vec![&ctx.accounts.game.to_account_info()],
+            &ctx.accounts.magic_context,
+            &ctx.accounts.magic_program,
+        )?;
+        emit!(committed);
+        msg!("Game slot {} committed to base layer", slot);
+        Ok(())
+    }
+
+    /// Have `tick` and `tick_multiple` emit a `FrameState` event after they
+    /// run, so a live viewer can follow the game from the logs instead of
+    /// polling the account. Off by default: each event costs a few hundred
+    /// compute units and about 170 characters of the transaction's 10KB log,
+    /// which `tick_multiple` pays once per call, for its final frame.
+    /// Authority only.
+    pub fn set_spectator_mode(ctx: Context<OwnerAction>, enabled: bool) -> Result<()> {
+        ctx.accounts.game.load_mut()?.set_spectator_mode(enabled);
+        msg!("Spectator mode {}", if enabled { "on" } else { "off" });
+        Ok(())
+    }
+
+    /// Have `flap`, `move_horizontal` and the tick instructions log the frame
+    /// as a `frame_log_record` for indexers. Off by default, which saves
+    /// their compute for physics; lifecycle messages are always logged.
+    /// Authority only.
+    pub fn set_verbose_logging(ctx: Context<OwnerAction>, enabled: bool) -> Result<()> {
+        ctx.accounts.game.load_mut()?.set_verbose_logging(enabled);
+        msg!("Verbose logging {}", if enabled { "on" } else { "off" });
+        Ok(())
+    }
+
+    /// Save the base layer from losing a long run to a rollup outage: commit
+    /// every `interval` frames from `tick_and_maybe_commit`. 0 turns it off.
+    pub fn set_commit_interval(ctx: Context<SetCommitInterval>, interval: u16) -> Result<()> {
+        ctx.accounts.game.load_mut()?.commit_interval = interval;
+        msg!("Commit interval set to {} frames", interval);
+        Ok(())
+    }
+
+    /// `tick`, then commit to the base layer when `commit_due`: every
+    /// `commit_interval` frames, and on the frame that ends the run so the
+    /// final score is committed. ER only, like `commit`.
+    pub fn tick_and_maybe_commit(ctx: Context<TickAndCommit>) -> Result<TickOutcome> {
+        let (outcome, committed) = {
+            let accounts = &mut *ctx.accounts;
+            require_not_paused(load_optional_config(accounts.config.as_deref())?.as_ref())?;
+            let game = &mut *accounts.game.load_mut()?;
+            require_ticking(game)?;
+            let mut stats = load_global_stats(accounts.global_stats.as_deref(), game)?;
+            
+            let clock = Clock::get()?;
+            let outcome = update_game_physics(
+                game,
+                clock.slot,
+                clock.unix_timestamp,
+                accounts.profile.as_deref_mut(),
+                accounts.missions.as_deref_mut(),
+                stats.as_mut(),
+            );
+            if outcome == TickOutcome::Advanced {
+                mark_updated(game, &clock);
+                log_frame(game);
+            }
+            store_global_stats(accounts.global_stats.as_deref(), stats)?;
+            let committed = (outcome == TickOutcome::Advanced && commit_due(game)).then(|| {
+                mark_committed(game);
+                Committed { player: game.authority, score: game.score, frame_count: game.frame_count }
+            });
+            (outcome, committed)
+        };
+        
+        // As in `end_and_commit`, the frame is written before the commit reads it
+        if let Some(committed) = committed {
+            commit_accounts(
+                &ctx.accounts.payer,
+                vec![&ctx.accounts.game.to_account_info()],
+                &ctx.accounts.magic_context,
+                &ctx.accounts.magic_program,
+            )?;
+            msg!("Frame {} committed to base layer", committed.frame_count);
+            emit!(committed);
+        }
+        Ok(outcome)
+    }
+
+    /// End the run and commit it to the base layer in the same transaction, so
+    /// a dropped second transaction can't leave the base layer without the new
+    /// high score. A game that isn't started fails with `GameNotPlaying`; on
+    /// one that is already over nothing changes, and the commit is skipped as
+    /// in `commit` if the base layer already has it. The authority signs, as
+    /// the run ends; anyone may pay.
+    pub fn end_and_commit(ctx: Context<EndAndCommit>, slot: u8) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let (committed, high_score) = {
+            let game = &mut *accounts.game.load_mut()?;
+            let now = Clock::get()?.unix_timestamp;
+            if end_once(game, now)? {
+                if let Some(profile) = accounts.profile.as_deref_mut() {
+                    profile.record_run_end(game, now);
+                }
+                if let Some(missions) = accounts.missions.as_deref_mut() {
+                    missions.record_run_end(game, now);
+                }
+                record_global_game_finished(accounts.global_stats.as_deref(), game)?;
+            }
+            if !mark_committed(game) {
+                emit!(NothingToCommit { player: game.authority, frame_count: game.frame_count });
+                msg!("Game slot {} unchanged since its last commit", slot);
+                return Ok(());
+            }
+            let committed = Committed { player: game.authority, score: game.score, frame_count: game.frame_count };
+            (committed, game.high_score)
+        };
+        
+        // The writes are already in the account; the borrow has to end before
+        // the commit reads it
+        commit_accounts(
+            &accounts.payer,
+            vec![&accounts.game.to_account_info()],
+            &accounts.magic_context,
+            &accounts.magic_program,
+        )?;
+        let score = committed.score;
+        emit!(committed);
+        msg!("Game Over! Score: {}, High Score: {} - slot {} committed to base layer", score, high_score, slot);
+        Ok(())
+    }
+
+    /// Undelegate and commit final state
+    /// A delegated game never comes back mid-run: a run in progress (playing or
+    /// paused) fails with `GameInProgress` unless `force`, which ends it first.
+    /// The authority signs; anyone may pay. The player's `HighScoreRecord`
+    /// stays on the base layer, out of this transaction's reach; once the
+    /// game is back, `sync_high_score` brings it up to date.
+    pub fn undelegate(ctx: Context<UndelegateInput>, slot: u8, force: bool) -> Result<()> {
+        let undelegated = {
+            let game = &mut *ctx.accounts.game.load_mut()?;
+            let forced = game.game_status().in_run();
+            if forced {
+                require!(force, FlappyError::GameInProgress);
+                finish_run(game, DeathCause::Undelegated, Clock::get()?.unix_timestamp);
+            }
+            // Cleared before the final commit, so the base layer gets it too
+            game.set_is_delegated(false);
+            // Always committed, changed or not
+            mark_committed(game);
+            Undelegated { player: game.authority, final_score: game.score, forced }
+        };
+        emit!(undelegated);
+        
+        commit_and_undelegate_accounts(
+            &ctx.accounts.payer,
+            vec![&ctx.accounts.game.to_account_info()],
+            &ctx.accounts.magic_context,
+            &ctx.accounts.magic_program,
+        )?;
+        msg!("Game slot {} undelegated from Ephemeral Rollup", slot);
+        Ok(())
+    }
+}
+
+// ========================================
+// Game Physics & Logic
+// ========================================
+
+/// Whether `tick_and_maybe_commit` commits after the frame just run
+fn commit_due(game: &GameState) -> bool {
+    // A countdown tick leaves frame_count, so doesn't count
+    game.commit_interval != 0
+        && match game.game_status() {
+            GameStatus::Playing => game.frame_count % game.commit_interval as u64 == 0,
+            status => status == GameStatus::GameOver,
+        }
+}
+
+/// sha256 of the game's fields other than `committed_digest`, which tells
+/// whether anything changed since the last commit
+fn state_digest(game: &GameState) -> [u8; 32] {
+    let bytes = bytemuck::bytes_of(game);
+    let at = std::mem::offset_of!(GameState, committed_digest);
+    solana_sha256_hasher::hashv(&[&bytes[..at], &bytes[at + 32..]]).to_bytes()
+}
+
+/// Record the game as committed in its current state, before the commit so
+/// the base layer gets the digest too. False if it already was, and there
+/// is nothing new to commit.
+fn mark_committed(game: &mut GameState) -> bool {
+    let digest = state_digest(game);
+    if digest == game.committed_digest {
+        return false;
+    }
+    game.committed_digest = digest;
+    true
+}
+
+/// End the run at `now`: record the high score and emit `GameOver`
+fn finish_run(game: &mut GameState, cause: DeathCause, now: i64) {
+    game.last_update = now;
+    let beaten = game.end_run(cause);
+    emit!(game.game_over());
+    emit_new_high_score(game, beaten);
+}
+
+/// The ending of `end_game_with_reward` and `end_and_commit`: a run in play
+/// ends as Manual at `now`, returning true for the run-end hooks; a finished
+/// run is left as it ended
+fn end_once(game: &mut GameState, now: i64) -> Result<bool> {
+    match game.game_status() {
+        GameStatus::GameOver => Ok(false),
+        status if status.in_run() => {
+            finish_run(game, DeathCause::Manual, now);
+            Ok(true)
+        }
+        _ => err!(FlappyError::GameNotPlaying),
+    }
+}
+
+/// Emit `NewHighScore` if the run that just ended beat `beaten`
+fn emit_new_high_score(game: &GameState, beaten: Option<u64>) {
+    if let Some(old) = beaten {
+        emit!(NewHighScore {
+            player: game.authority,
+            old,
+            new: game.high_score,
+            seed: game.high_score_seed,
+            timestamp: game.high_score_timestamp,
+            frames: game.high_score_frame,
+            flaps: game.best_run_flaps,
+        });
+    }
+}
+
+/// One rate-limited frame of on-chain play at `current_slot`/`now`. Depends
+/// only on its arguments and the game's fields (no runtime), so it runs
+/// off-chain too; the handlers keep `last_update` themselves.
+fn update_game_physics(
+    game: &mut GameState,
+    current_slot: u64,
+    now: i64,
+    profile: Option<&mut PlayerProfile>,
+    missions: Option<&mut Missions>,
+    stats: Option<&mut GlobalStats>,
+) -> TickOutcome {
+    tick_rate_limited(game, current_slot, now, &mut frame_hooks(profile, missions, stats, now))
+}
+
+/// Record that an instruction at `clock` advanced a run still in play
+fn mark_updated(game: &mut GameState, clock: &Clock) {
+    if game.game_status().ticking() {
+        game.last_update = clock.unix_timestamp;
+        game.last_update_slot = clock.slot;
+    }
+}
+
+/// Number of frames the cadence allows between `last_tick_slot` and `current_slot`
+pub fn allowed_steps(last_tick_slot: u64, current_slot: u64) -> u64 {
+    current_slot.saturating_sub(last_tick_slot) / MIN_SLOTS_PER_TICK
+}
+
+/// Run up to `count` frames, bounded by MAX_TICKS_PER_BATCH and the cadence budget.
+/// Each frame is the same step `tick` runs, so N ticks and one batch of N match.
+fn advance_frames(
+    game: &mut GameState,
+    count: u8,
+    current_slot: u64,
+    on_frame: &mut impl FnMut(&mut GameState, &FrameReport),
+) -> u8 {
+    let budget = allowed_steps(game.last_tick_slot, current_slot)
+        .min(count.min(MAX_TICKS_PER_BATCH) as u64) as u8;
+    
+    let mut frames = 0;
+    while frames < budget && game.game_status().ticking() {
+        step_frame(game, on_frame);
+        frames += 1;
+    }
+    if frames > 0 {
+        game.last_tick_slot = current_slot;
+    }
+    frames
+}
+
+/// Run one frame if enough slots have elapsed since the previous one
+fn advance_at_slot(
+    game: &mut GameState,
+    current_slot: u64,
+    on_frame: &mut impl FnMut(&mut GameState, &FrameReport),
+) -> TickOutcome {
+    if allowed_steps(game.last_tick_slot, current_slot) == 0 {
+        return TickOutcome::TooEarly;
+    }
+    game.last_tick_slot = current_slot;
+    step_frame(game, on_frame);
+    TickOutcome::Advanced
+}
+
+/// One tick of a run: a frame of physics, or of the countdown, which moves
+/// nothing and leaves `frame_count`, going live as it reaches zero
+fn step_frame(game: &mut GameState, on_frame: &mut impl FnMut(&mut GameState, &FrameReport)) {
+    if game.game_status() == GameStatus::Countdown {
+        game.countdown_frames_remaining = game.countdown_frames_remaining.saturating_sub(1);
+        if game.countdown_frames_remaining == 0 {
+            game.set_game_status(GameStatus::Playing);
+        }
+        return;
+    }
+    let report = step_physics(game);
+    on_frame(game, &report);
+}
+
+/// Run the frames owed for the wall time from `last_update` to `now`, capped
+/// at MAX_CATCH_UP_FRAMES and counted in the second's frame budget, and move
+/// `last_update` to `now`; nothing unless the run is stale at `now`
+fn catch_up_frames(
+    game: &mut GameState,
+    now: i64,
+    current_slot: u64,
+    on_frame: &mut impl FnMut(&mut GameState, &FrameReport),
+) -> u32 {
+    let elapsed = now.saturating_sub(game.last_update);
+    if !game.game_status().ticking() || elapsed <= CATCH_UP_STALE_SECS {
+        return 0;
+    }
+    let owed = (elapsed as u64).saturating_mul(NOMINAL_FRAMES_PER_SECOND).min(MAX_CATCH_UP_FRAMES) as u32;
+    
+    rate_budget(game, now);
+    let mut frames = 0;
+    while frames < owed && game.game_status().ticking() {
+        step_frame(game, on_frame);
+        frames += 1;
+    }
+    game.ticks_this_second = game.ticks_this_second.saturating_add(frames as u16);
+    game.last_update = now;
+    game.last_update_slot = current_slot;
+    game.last_tick_slot = current_slot;
+    frames
+}
+
+/// `crank_tick`'s frames: up to `max_frames`, capped at MAX_CRANK_FRAMES, of
+/// a run that is Playing and stale at `now`, moving `last_update` to `now`
+fn crank_frames(
+    game: &mut GameState,
+    max_frames: u16,
+    now: i64,
+    current_slot: u64,
+    on_frame: &mut impl FnMut(&mut GameState, &FrameReport),
+) -> Result<u32> {
+    require!(game.game_status() == GameStatus::Playing, FlappyError::GameNotPlaying);
+    require!(now.saturating_sub(game.last_update) > CRANK_STALE_SECS, FlappyError::GameNotStale);
+    
+    let mut frames = 0;
+    while frames < max_frames.min(MAX_CRANK_FRAMES) && game.game_status().ticking() {
+        step_frame(game, on_frame);
+        frames += 1;
+    }
+    game.last_update = now;
+    game.last_update_slot = current_slot;
+    game.last_tick_slot = current_slot;
+    Ok(frames as u32)
+}
+
+/// Tip for a crank from a game holding `lamports`, `rent` of them needed to
+/// stay rent-exempt: CRANK_TIP_LAMPORTS, or nothing if the game can't spare it
+fn crank_tip(lamports: u64, rent: u64) -> u64 {
+    if lamports.saturating_sub(rent) >= CRANK_TIP_LAMPORTS {
+        CRANK_TIP_LAMPORTS
+    } else {
+        0
+    }
+}
+
+/// `crank_finalize`'s check and ending: a run in progress that no
+/// instruction touched for more than CRANK_FINALIZE_SECS ends as Abandoned
+fn finalize_abandoned(game: &mut GameState, now: i64) -> Result<()> {
+    require!(game.game_status().in_run(), FlappyError::GameNotPlaying);
+    require!(now.saturating_sub(game.last_update) > CRANK_FINALIZE_SECS, FlappyError::GameNotStale);
+    finish_run(game, DeathCause::Abandoned, now);
+    Ok(())
+}
+
+/// Accrue up to `tip` to the cranker's keeper ledger for this game, within
+/// the daily cap and what the vault can still pay; nothing without both
+/// accounts. Returns the amount accrued.
+fn accrue_keeper_tip(accounts: &mut Crank, tip: u64, now: i64) -> Result<u64> {
+    let game = accounts.game.key();
+    let (Some(vault), Some(ledger)) = (accounts.keeper_vault.as_deref_mut(), accounts.keeper_ledger.as_deref_mut()) else {
+        return Ok(0);
+    };
+    let info = vault.to_account_info();
+    let rent = Rent::get()?.minimum_balance(info.data_len());
+    let available = info.lamports().saturating_sub(rent).saturating_sub(vault.owed);
+    let amount = keeper::accrue(&mut ledger.games, &game, day_of(now), tip, available);
+    vault.owed += amount;
+    ledger.accrued += amount;
+    Ok(amount)
+}
+
+/// Frames still allowed in unix second `now`; a new second opens a fresh window
+fn rate_budget(game: &mut GameState, now: i64) -> u16 {
+    if now != game.tick_second {
+        game.tick_second = now;
+        game.ticks_this_second = 0;
+    }
+    MAX_FRAMES_PER_SECOND.saturating_sub(game.ticks_this_second)
+}
+
+/// `advance_at_slot` within the wall-clock frame budget
+fn tick_rate_limited(
+    game: &mut GameState,
+    current_slot: u64,
+    now: i64,
+    on_frame: &mut impl FnMut(&mut GameState, &FrameReport),
+) -> TickOutcome {
+    if rate_budget(game, now) == 0 {
+        return TickOutcome::RateLimited;
+    }
+    let outcome = advance_at_slot(game, current_slot, on_frame);
+    if outcome == TickOutcome::Advanced {
+        game.ticks_this_second += 1;
+    }
+    outcome
+}
+
+/// `advance_frames` within the wall-clock frame budget
+fn advance_frames_rate_limited(
+    game: &mut GameState,
+    count: u8,
+    current_slot: u64,
+    now: i64,
+    on_frame: &mut impl FnMut(&mut GameState, &FrameReport),
+) -> u8 {
+    let budget = rate_budget(game, now).min(u8::MAX as u16) as u8;
+    let frames = advance_frames(game, count.min(budget), current_slot, on_frame);
+    game.ticks_this_second += frames as u16;
+    frames
+}
+
+/// Compute units left in this transaction; off-chain there is no meter
+fn remaining_compute_units() -> u64 {
+    #[cfg(target_os = "solana")]
+    {
+        extern "C" {
+            fn sol_remaining_compute_units() -> u64;
+        }
+        unsafe { sol_remaining_compute_units() }
+    }
+    #[cfg(not(target_os = "solana"))]
+    {
+        u64::MAX
+    }
+}
+
+/// Run up to `max_frames` frames of `tick_n`, bounded by the cadence and
+/// the wall-clock budget, while `remaining_cu` leaves room for another
+fn advance_frames_compute_bounded(
+    game: &mut GameState,
+    max_frames: u16,
+    current_slot: u64,
+    now: i64,
+    remaining_cu: impl Fn() -> u64,
+    on_frame: &mut impl FnMut(&mut GameState, &FrameReport),
+) -> u16 {
+    let budget = allowed_steps(game.last_tick_slot, current_slot)
+        .min(rate_budget(game, now) as u64)
+        .min(max_frames as u64) as u16;
+    
+    let mut frames = 0;
+    while frames < budget && game.game_status().ticking() && remaining_cu() >= TICK_N_FRAME_CU + TICK_N_FINISH_CU {
+        step_frame(game, on_frame);
+        frames += 1;
+    }
+    if frames > 0 {
+        game.last_tick_slot = current_slot;
+    }
+    game.ticks_this_second += frames;
+    frames
+}
+
+/// Run up to `count` frames of `advance`, flapping before frame i when bit i
+/// of `inputs` is set, bounded by MAX_ADVANCE_FRAMES, the cadence and the
+/// wall-clock budget. Returns the frames run and the flaps kept.
+fn advance_with_inputs(
+    game: &mut GameState,
+    inputs: u64,
+    count: u8,
+    current_slot: u64,
+    now: i64,
+    on_frame: &mut impl FnMut(&mut GameState, &FrameReport),
+) -> Result<(u8, u32)> {
+    let budget = allowed_steps(game.last_tick_slot, current_slot)
+        .min(rate_budget(game, now) as u64)
+        .min(count.min(MAX_ADVANCE_FRAMES) as u64) as u8;
+    
+    let (mut frames, mut flaps) = (0, 0);
+    while frames < budget && game.game_status().ticking() {
+        if inputs >> frames & 1 != 0 {
+            skip_countdown_by_flap(game)?;
+            if queue_flap(game)? {
+                flaps += 1;
+            }
+        }
+        step_frame(game, on_frame);
+        frames += 1;
+    }
+    if frames > 0 {
+        game.last_tick_slot = current_slot;
+        game.ticks_this_second += frames as u16;
+    }
+    Ok((frames, flaps))
+}
+
+/// What `advance` ran, returned via return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct AdvanceOutcome {
+    /// Frames simulated, fewer than asked when the budget ran out or the
+    /// run ended
+    pub frames: u8,
+    /// Score after them
+    pub score: u64,
+}
+
+/// What `tick_n` ran, returned via return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct TickNOutcome {
+    /// Frames simulated, fewer than asked when the cadence, the wall-clock
+    /// budget or the compute ran out, or the run ended
+    pub frames: u16,
+    /// Status after them
+    pub status: GameStatus,
+}
+
+/// What happened during one frame, for event emission
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct FrameReport {
+    /// Pipes passed this frame
+    pub pipes_passed: u8,
+    /// How many of them were near misses
+    pub near_misses: u8,
+    /// Glide combo points scored this frame
+    pub combo_bonus: u64,
+    /// Coins collected this frame
+    pub coins_collected: u8,
+    /// Distance the world scrolled this frame (fixed-point, scaled by 1000)
+    pub scrolled: u32,
+    /// Set when this frame ended the run
+    pub death: Option<DeathCause>,
+    /// The high score the run beat, when its death set a new one
+    pub beaten_high_score: Option<u64>,
+}
+
+/// Per-frame side effects for on-chain play: events, plus lifetime stats,
+/// mission progress and global totals when the player's profile, missions
+/// and the GlobalStats were passed in
+fn frame_hooks<'a>(
+    mut profile: Option<&'a mut PlayerProfile>,
+    mut missions: Option<&'a mut Missions>,
+    mut stats: Option<&'a mut GlobalStats>,
+    now: i64,
+) -> impl FnMut(&mut GameState, &FrameReport) + 'a {
+    move |game, report| {
+        emit_frame_events(game, report);
+        if let Some(profile) = profile.as_deref_mut().filter(|_| !game.practice()) {
+            profile.record_frame(game, report, now);
+            profile.unlock_achievements(game);
+        }
+        if let Some(missions) = missions.as_deref_mut() {
+            missions.record_frame(game, report, now);
+        }
+        if let Some(stats) = stats.as_deref_mut() {
+            stats.record_frame(game, report);
+        }
+    }
+}
+
+/// The GlobalStats in `account`, if it was passed and can be written on this
+/// layer alongside `game`: writable, owned by this program here (on the base
+/// layer a delegated one isn't), and delegated exactly when the game is, as
+/// an ER can't write an account that isn't. Otherwise the update is skipped.
+fn load_global_stats(account: Option<&AccountInfo>, game: &GameState) -> Result<Option<GlobalStats>> {
+    let Some(account) = account.filter(|account| account.is_writable && account.owner == &crate::ID) else {
+        return Ok(None);
+    };
+    let stats = GlobalStats::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+    Ok((stats.is_delegated == game.is_delegated()).then_some(stats))
+}
+
+/// Write back the GlobalStats `load_global_stats` loaded from `account`
+fn store_global_stats(account: Option<&AccountInfo>, stats: Option<GlobalStats>) -> Result<()> {
+    if let (Some(account), Some(stats)) = (account, stats) {
+        stats.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+    }
+    Ok(())
+}
+
+/// Count the run `game` just ended in the GlobalStats in `account`, if it
+/// can be written
+fn record_global_game_finished(account: Option<&AccountInfo>, game: &GameState) -> Result<()> {
+    let mut stats = load_global_stats(account, game)?;
+    if let Some(stats) = stats.as_mut() {
+        stats.record_game_finished(game);
+    }
+    store_global_stats(account, stats)
+}
+
+/// Log the current frame as one `sol_log_data` record if the game has
+/// verbose logging on; per-frame instructions log nothing otherwise
+fn log_frame(game: &GameState) {
+    if game.verbose_logging() {
+        anchor_lang::solana_program::log::sol_log_data(&[&frame_log_record(game)]);
+    }
+}
+
+/// `frame_count` (u64), bird Y in pixels (i32), `bird_velocity` (i32) and
+/// `score` (u64), little-endian
+pub fn frame_log_record(game: &GameState) -> [u8; 24] {
+    let mut record = [0; 24];
+    record[..8].copy_from_slice(&game.frame_count.to_le_bytes());
+    record[8..12].copy_from_slice(&(game.bird_y / 1000).to_le_bytes());
+    record[12..16].copy_from_slice(&game.bird_velocity.to_le_bytes());
+    record[16..].copy_from_slice(&game.score.to_le_bytes());
+    record
+}
+
+/// Emit `FrameState` for the current frame if the game is in spectator mode
+fn emit_frame_state(game: &GameState) {
+    if game.spectator_mode() {
+        emit!(FrameState::of(game));
+    }
+}
+
+impl FrameState {
+    fn of(game: &GameState) -> Self {
+        let mut state = FrameState {
+            player: game.authority,
+            frame: game.frame_count,
+            score: game.score,
+            bird_y: game.bird_y / 1000,
+            velocity_sign: game.bird_velocity.signum() as i8,
+            ..Default::default()
+        };
+        for pipe in game.pipes[..MAX_PIPES].iter().filter(|pipe| pipe.active()) {
+            state.pipes[state.pipe_count as usize] = PipeView { x: pipe.x / 1000, gap_y: pipe.gap_y };
+            state.pipe_count += 1;
+        }
+        state
+    }
+}
+
+/// Emit the events for a simulated frame
+fn emit_frame_events(game: &GameState, report: &FrameReport) {
+    for pipe in 0..report.pipes_passed {
+        emit!(PipePassed {
+            player: game.authority,
+            score: game.score,
+            frame: game.frame_count,
+            near_miss_bonus: if pipe < report.near_misses { NEAR_MISS_BONUS } else { 0 },
+            combo_bonus: if pipe == 0 { report.combo_bonus } else { 0 },
+        });
+    }
+    for _ in 0..report.coins_collected {
+        emit!(CoinCollected {
+            player: game.authority,
+            coins: game.coins,
+            frame: game.frame_count,
+        });
+    }
+    if report.death.is_some() {
+        emit!(game.game_over());
+        emit_new_high_score(game, report.beaten_high_score);
+    }
+}
+
+/// Advance the simulation by one frame. Pure over `GameState`, no sysvars.
+fn step_physics(game: &mut GameState) -> FrameReport {
+    let mut report = FrameReport::default();
+    // Saturating throughout: a crafted state must end the run, never wrap
+    game.frame_count = game.frame_count.saturating_add(1);
+    
+    // Speed and gap for this frame follow the score at its start
+    game.apply_difficulty();
+    let mode = game.game_mode().params();
+    // The frame runs under the power-up held at its start, a timed one's
+    // last frame included
+    let powerup = game.active_powerup().map(|active| active.kind);
+    let time_scale = game.time_scale();
+    game.tick_powerup();
+    // A gravity flip turns both gravity and flaps around; when it runs out
+    // the bird carries on from its velocity under normal gravity
+    let gravity_sign = match powerup {
+        Some(Powerup::GravityFlip) => -mode.gravity_sign,
+        _ => mode.gravity_sign,
+    };
+    // Slow motion scales how far this frame moves the bird and the pipes
+    let scaled = |v: i32| (v as i64 * time_scale as i64 / TIME_SCALE_NORMAL as i64) as i32;
+    
+    // Apply a queued flap, which breaks any glide combo
+    if game.pending_flap() {
+        game.bird_velocity = game.physics.jump_velocity.saturating_mul(gravity_sign);
+        game.set_pending_flap(false);
+        game.flaps_since_last_pass = game.flaps_since_last_pass.saturating_add(1);
+        game.combo = 0;
+    }
+    
+    // Apply gravity to velocity
+    game.bird_velocity = game.bird_velocity.saturating_add(scaled(game.gravity.saturating_mul(gravity_sign)));
+    
+    // Clamp velocity
+    game.bird_velocity = game.bird_velocity.clamp(-MAX_VELOCITY, MAX_VELOCITY);
+    
+    // Update bird position
+    let mut prev_y_pixels = game.bird_y / 1000;
+    game.bird_y = game.bird_y.saturating_add(scaled(game.bird_velocity));
+    
+    // A co-op partner's bird flaps, falls and moves the same way, at the
+    // same x; `partner` is its Y in pixels before and after
+    let mut partner = None;
+    if game.coop() {
+        if game.coop.pending_flap() {
+            game.coop.bird_velocity = game.physics.jump_velocity.saturating_mul(gravity_sign);
+            game.coop.set_pending_flap(false);
+            game.flaps_since_last_pass = game.flaps_since_last_pass.saturating_add(1);
+            game.combo = 0;
+        }
+        let coop = &mut game.coop;
+        coop.bird_velocity = coop
+            .bird_velocity
+            .saturating_add(scaled(game.gravity.saturating_mul(gravity_sign)))
+            .clamp(-MAX_VELOCITY, MAX_VELOCITY);
+        let prev = coop.bird_y / 1000;
+        coop.bird_y = coop.bird_y.saturating_add(scaled(coop.bird_velocity));
+        partner = Some((prev, coop.bird_y / 1000));
+    }
+    
+    // Check floor/ceiling collision with the inset hitbox, half size while
+    // shrunk; practice bounces the bird back in
+    let shrunk = powerup == Some(Powerup::Shrink);
+    let (inset, hitbox_size) = bird_hitbox(mode.bird_size, game.hitbox_inset, shrunk);
+    let height = game.height();
+    let out_of_bounds = |y: i32| y + inset <= 0 || y + inset + hitbox_size >= height;
+    let mut bird_y_pixels = game.bird_y / 1000;
+    if game.practice() && out_of_bounds(bird_y_pixels) {
+        game.bird_y = game.bird_y.clamp((1 - inset) * 1000, (height - inset - hitbox_size - 1) * 1000);
+        game.bird_velocity = 0;
+        bird_y_pixels = game.bird_y / 1000;
+        prev_y_pixels = bird_y_pixels;
+    }
+    if out_of_bounds(bird_y_pixels) && respawn(game, mode.bird_size) {
+        bird_y_pixels = game.bird_y / 1000;
+        prev_y_pixels = bird_y_pixels;
+    }
+    if out_of_bounds(bird_y_pixels) {
+        let cause = if bird_y_pixels + inset <= 0 {
+            DeathCause::Ceiling
+        } else {
+            DeathCause::Floor
+        };
+        game.coop.lose(0);
+        report.beaten_high_score = game.end_run(cause);
+        report.death = Some(cause);
+        return report;
+    }
+    if let Some((_, y)) = partner.filter(|&(_, y)| out_of_bounds(y)) {
+        let cause = if y + inset <= 0 { DeathCause::Ceiling } else { DeathCause::Floor };
+        game.coop.lose(1);
+        report.beaten_high_score = game.end_run(cause);
+        report.death = Some(cause);
+        return report;
+    }
+    
+    // Pipes move past the bird by pipe_speed plus however far it dashed
+    let bird_dx = step_horizontal(game);
+    let bird_x_pixels = game.bird_x / 1000;
+    
+    // The world scrolls by pipe_speed a frame, as ramped for this frame and
+    // slowed by the time scale; pipes move, pass and collide by this step
+    let pipe_step = scaled(game.pipe_speed);
+    report.scrolled = pipe_step.max(0) as u32;
+    game.distance = game.distance.saturating_add(report.scrolled as u64);
+    game.total_distance = game.total_distance.saturating_add(report.scrolled as u64);
+    if game.scoring() == ScoringMode::Distance {
+        game.score = game.distance / 1000;
+    }
+    
+    // Update pipes, stopping after the last active one
+    let mut remaining = game.active_pipe_count();
+    for i in 0..game.pipe_slots() {
+        if remaining == 0 {
+            break;
+        }
+        if game.pipes[i].active() {
+            remaining -= 1;
+            game.pipes[i].x = game.pipes[i].x.saturating_sub(pipe_step);
+            if game.pipes[i].pipe_type == PIPE_TYPE_OSCILLATING {
+                game.pipes[i].gap_y = oscillating_gap_y(&game.pipes[i], game.frame_count, game.pipe_gap, game.height());
+            }
+            
+            // Pick up a power-up or coin floating in this pipe's gap; a new
+            // power-up replaces the one held, and a magnet pulls coins in
+            if game.pipes[i].shield() && touches_gap_item(bird_x_pixels, bird_y_pixels, mode.bird_size, &game.pipes[i], SHIELD_SIZE) {
+                game.pipes[i].set_shield(false);
+                game.pick_up(Powerup::Shield);
+            }
+            if let Some(kind) = Powerup::from_repr(game.pipes[i].pickup)
+                .filter(|_| touches_gap_item(bird_x_pixels, bird_y_pixels, mode.bird_size, &game.pipes[i], PICKUP_SIZE))
+            {
+                game.pipes[i].pickup = 0;
+                game.pick_up(kind);
+            }
+            let magnetized = powerup == Some(Powerup::Magnet)
+                && within_magnet(bird_x_pixels, bird_y_pixels, mode.bird_size, &game.pipes[i]);
+            if game.pipes[i].coin()
+                && (magnetized || touches_gap_item(bird_x_pixels, bird_y_pixels, mode.bird_size, &game.pipes[i], COIN_SIZE))
+            {
+                game.pipes[i].set_coin(false);
+                // Practice coins aren't credited
+                if !game.practice() {
+                    game.coins = game.coins.saturating_add(1);
+                    report.coins_collected += 1;
+                }
+            }
+            
+            // Check if pipe passed
+            let mut near_miss = false;
+            let mut combo_bonus = 0;
+            if !game.pipes[i].passed() && game.pipes[i].x_pixels() + PIPE_WIDTH < bird_x_pixels {
+                game.pipes[i].set_passed(true);
+                let gliding = game.flaps_since_last_pass == 0 && game.pipe_count() > 0;
+                game.combo = if gliding { game.combo.saturating_add(1).min(MAX_COMBO_BONUS) } else { 0 };
+                game.flaps_since_last_pass = 0;
+                match game.scoring() {
+                    ScoringMode::PipesPassed => game.score = game.score.saturating_add(1),
+                    ScoringMode::Distance => game.pipes_passed = game.pipes_passed.saturating_add(1),
+                }
+                report.pipes_passed += 1;
+                if game.scoring() == ScoringMode::PipesPassed {
+                    combo_bonus = game.combo as u64;
+                }
+                near_miss = game.scoring() == ScoringMode::PipesPassed
+                    && is_near_miss(bird_y_pixels, mode.bird_size, &game.pipes[i], game.pipe_gap, game.physics.near_miss_threshold);
+            }
+            
+            // Deactivate off-screen pipes
+            if game.pipes[i].x_pixels() + PIPE_WIDTH < 0 {
+                game.pipes[i].set_active(false);
+                game.pipe_deactivated(i);
+            }
+            
+            // Check collision of the inset hitbox with this pipe (not in the
+            // grace period or while invulnerable after a respawn); a shield
+            // absorbs one hit, and practice every hit, putting the bird back
+            // in the middle of the gap
+            let relative_speed = pipe_step.saturating_add(bird_dx);
+            if !game.in_grace() && game.frame_count > game.invulnerable_until && check_pipe_collision(
+                prev_y_pixels + inset,
+                bird_y_pixels + inset,
+                game.bird_x + inset * 1000,
+                hitbox_size,
+                &game.pipes[i],
+                game.pipe_gap,
+                relative_speed,
+            ) {
+                if game.practice() || game.take_shield() {
+                    game.bird_y = (game.pipes[i].nearest_gap_y(bird_y_pixels, mode.bird_size) - mode.bird_size / 2) * 1000;
+                    game.bird_velocity = 0;
+                    bird_y_pixels = game.bird_y / 1000;
+                    prev_y_pixels = bird_y_pixels;
+                    continue;
+                }
+                if respawn(game, mode.bird_size) {
+                    bird_y_pixels = game.bird_y / 1000;
+                    prev_y_pixels = bird_y_pixels;
+                    continue;
+                }
+                game.coop.lose(0);
+                report.beaten_high_score = game.end_run(DeathCause::Pipe);
+                game.set_end_pipe(PipeHit {
+                    index: i as u8,
+                    half: game.pipes[i].half_at(bird_y_pixels, mode.bird_size),
+                });
+                report.death = Some(DeathCause::Pipe);
+                return report;
+            }
+            
+            // The partner's bird collides the same way, and the shield is
+            // shared; it doesn't take pickups. Co-op runs have one life.
+            if let Some((prev, y)) = partner.filter(|_| !game.in_grace() && game.frame_count > game.invulnerable_until) {
+                if check_pipe_collision(
+                    prev + inset,
+                    y + inset,
+                    game.bird_x + inset * 1000,
+                    hitbox_size,
+                    &game.pipes[i],
+                    game.pipe_gap,
+                    relative_speed,
+                ) {
+                    if !game.take_shield() {
+                        game.coop.lose(1);
+                        report.beaten_high_score = game.end_run(DeathCause::Pipe);
+                        game.set_end_pipe(PipeHit { index: i as u8, half: game.pipes[i].half_at(y, mode.bird_size) });
+                        report.death = Some(DeathCause::Pipe);
+                        return report;
+                    }
+                    let gap_y = game.pipes[i].nearest_gap_y(y, mode.bird_size) - mode.bird_size / 2;
+                    game.coop.bird_y = gap_y * 1000;
+                    game.coop.bird_velocity = 0;
+                    partner = Some((gap_y, gap_y));
+                }
+            }
+            
+            // Only now is the pass known not to have clipped the pipe
+            if near_miss {
+                game.score = game.score.saturating_add(NEAR_MISS_BONUS);
+                report.near_misses += 1;
+            }
+            game.score = game.score.saturating_add(combo_bonus);
+            report.combo_bonus += combo_bonus;
+        }
+    }
+    
+    // Regrowing where the bird is would kill it: stay small until it's clear
+    if shrunk && game.active_powerup().is_none() && full_size_overlaps(game, mode.bird_size) {
+        game.set_active_powerup(Some(ActivePowerup { kind: Powerup::Shrink, frames_remaining: 1 }));
+    }
+    
+    // Spawn new pipes
+    spawn_pipes(game);
+    report
+}
+
+/// Offset and side in pixels of the collision box within a `bird_size`
+/// sprite, `inset` in from each side but at least 1 px across. Pipe passes
+/// still go by the full sprite, so scoring isn't delayed.
+fn hitbox(bird_size: i32, inset: u8) -> (i32, i32) {
+    let inset = (inset as i32).min((bird_size - 1) / 2).max(0);
+    (inset, bird_size - 2 * inset)
+}
+
+/// `hitbox`, of a sprite half `bird_size` across and centered in it when
+/// `shrunk`
+fn bird_hitbox(bird_size: i32, inset: u8, shrunk: bool) -> (i32, i32) {
+    let size = if shrunk { bird_size / 2 } else { bird_size };
+    let (inset, hitbox_size) = hitbox(size, inset);
+    ((bird_size - size) / 2 + inset, hitbox_size)
+}
+
+/// Whether the bird's full-size hitbox, where it is now, is out of bounds or
+/// inside an active pipe
+fn full_size_overlaps(game: &GameState, bird_size: i32) -> bool {
+    let (inset, hitbox_size) = hitbox(bird_size, game.hitbox_inset);
+    let y = game.bird_y / 1000 + inset;
+    y <= 0
+        || y + hitbox_size >= game.height()
+        || game.pipes[..game.pipe_slots()].iter().any(|pipe| {
+            check_pipe_collision(y, y, game.bird_x + inset * 1000, hitbox_size, pipe, game.pipe_gap, 0)
+        })
+}
+
+/// Spend a spare life on a crash: the bird goes back to the center at rest,
+/// pipes in its column are removed, and pipes can't hit it for the next
+/// RESPAWN_INVULNERABLE_FRAMES frames. False, changing nothing, on the last life.
+fn respawn(game: &mut GameState, bird_size: i32) -> bool {
+    if game.lives <= 1 {
+        return false;
+    }
+    game.lives -= 1;
+    recenter_bird(game, bird_size);
+    game.invulnerable_until = game.frame_count.saturating_add(RESPAWN_INVULNERABLE_FRAMES);
+    true
+}
+
+/// Put the bird back at the center at rest and remove the pipes in its column
+fn recenter_bird(game: &mut GameState, bird_size: i32) {
+    game.bird_y = game.height() / 2 * 1000;
+    game.bird_velocity = 0;
+    let bird_x = game.bird_x / 1000;
+    for pipe in game.pipes[..MAX_PIPES].iter_mut() {
+        if pipe.active() && pipe.x_pixels() < bird_x + bird_size && pipe.x_pixels() + PIPE_WIDTH > bird_x {
+            pipe.set_active(false);
+        }
+    }
+    // Rare enough to recount on the next frame rather than track
+    game.active_pipe_count = 0;
+}
+
+/// Fail unless `game` is over and hasn't been revived yet, by either revive
+fn check_revivable(game: &GameState) -> Result<()> {
+    require!(
+        game.game_status() == GameStatus::GameOver,
+        FlappyError::GameNotOver
+    );
+    require!(!game.revived(), FlappyError::AlreadyRevived);
+    require_solo(game)?;
+    Ok(())
+}
+
+/// `revive_run` paid for with `cost` coins from the balance; fails without
+/// change if the run can't be revived or the balance is short
+fn revive_for_coins(game: &mut GameState, cost: u64, clock: &Clock) -> Result<()> {
+    check_revivable(game)?;
+    game.spend_coins(cost)?;
+    revive_run(game, clock);
+    Ok(())
+}
+
+/// Put a finished run back in play where it ended, as `revive` does, with
+/// the clocks restarted at `clock` so the time spent dead isn't simulated
+fn revive_run(game: &mut GameState, clock: &Clock) {
+    game.set_revived(true);
+    game.set_game_status(GameStatus::Playing);
+    game.set_pending_flap(false);
+    recenter_bird(game, game.game_mode().params().bird_size);
+    mark_updated(game, clock);
+    game.last_tick_slot = clock.slot;
+}
+
+/// Move the bird sideways by its X velocity, stopping at the BIRD_X_MIN to
+/// half-width band, then apply drag. Returns how far it moved
+/// (fixed-point). Outside horizontal-control runs the bird stays at BIRD_X.
+fn step_horizontal(game: &mut GameState) -> i32 {
+    if !game.horizontal_control() {
+        game.bird_x = BIRD_X * 1000;
+        return 0;
+    }
+    let prev_x = game.bird_x;
+    let target = prev_x.saturating_add(game.bird_x_velocity);
+    game.bird_x = target.clamp(BIRD_X_MIN * 1000, game.width() / 2 * 1000);
+    game.bird_x_velocity = if game.bird_x == target {
+        game.bird_x_velocity * (HORIZONTAL_DRAG - 1) / HORIZONTAL_DRAG
+    } else {
+        0
+    };
+    game.bird_x - prev_x
+}
+
+/// Gravity change in percent on `frame` of a run from `run_seed`, 0 when
+/// calm. A pure function of the two so replays see the same gusts.
+pub fn wind_at(run_seed: u64, frame: u64) -> i8 {
+    let period = frame / WIND_PERIOD;
+    if period == 0 {
+        return 0;
+    }
+    let roll = mix64(run_seed ^ mix64(period));
+    let start = roll % (WIND_PERIOD - WIND_GUST_FRAMES + 1);
+    if !(start..start + WIND_GUST_FRAMES).contains(&(frame % WIND_PERIOD)) {
+        return 0;
+    }
+    let strength = WIND_MIN_PERCENT + (roll >> 32) % (WIND_MAX_PERCENT - WIND_MIN_PERCENT + 1);
+    if roll >> 63 == 1 {
+        -(strength as i8)
+    } else {
+        strength as i8
+    }
+}
+
+/// splitmix64 finalizer, to spread seed bits over the whole word
+fn mix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+/// Whether the bird's `bird_size` hitbox (pixels) overlaps a `size` square
+/// item at the center of `pipe`'s gap
+fn touches_gap_item(bird_x: i32, bird_y: i32, bird_size: i32, pipe: &Pipe, size: i32) -> bool {
+    let item_x = pipe.x_pixels() + (PIPE_WIDTH - size) / 2;
+    let item_y = pipe.gap_y - size / 2;
+    bird_x < item_x + size
+        && bird_x + bird_size > item_x
+        && bird_y < item_y + size
+        && bird_y + bird_size > item_y
+}
+
+/// Whether the coin at the center of `pipe`'s gap is within MAGNET_RADIUS of
+/// the center of the `bird_size` bird at `bird_x`, `bird_y` (pixels)
+fn within_magnet(bird_x: i32, bird_y: i32, bird_size: i32, pipe: &Pipe) -> bool {
+    let dx = (pipe.x_pixels() + PIPE_WIDTH / 2 - (bird_x + bird_size / 2)) as i64;
+    let dy = (pipe.gap_y - (bird_y + bird_size / 2)) as i64;
+    dx * dx + dy * dy <= (MAGNET_RADIUS as i64).pow(2)
+}
+
+/// Whether a bird at `bird_y` (pixels) is inside `pipe`'s nearest gap by the
+/// bounds `check_pipe_collision` uses, but less than `threshold` px from an edge
+fn is_near_miss(bird_y: i32, bird_size: i32, pipe: &Pipe, pipe_gap: i32, threshold: i32) -> bool {
+    let gap_y = pipe.nearest_gap_y(bird_y, bird_size);
+    let below_top = bird_y - (gap_y - pipe_gap / 2);
+    let above_bottom = gap_y + pipe_gap / 2 - (bird_y + bird_size);
+    below_top >= 0 && above_bottom >= 0 && below_top.min(above_bottom) < threshold
+}
+
+/// Swept collision over one frame: the bird moved from `prev_y` to `bird_y`
+/// (pixels) and ended at `bird_x`, while the pipe moved `pipe_speed` left
+/// relative to it, to `pipe.x` (all fixed-point). The bird is a `bird_size`
+/// pixel square. Both move linearly and each
+/// gap is one interval, so the bird survives only if it is inside the same
+/// gap at the start and end of the horizontal overlap; a bird that goes from
+/// one gap of a double pipe to the other crosses the strip between them.
+fn check_pipe_collision(
+    prev_y: i32,
+    bird_y: i32,
+    bird_x: i32,
+    bird_size: i32,
+    pipe: &Pipe,
+    pipe_gap: i32,
+    pipe_speed: i32,
+) -> bool {
+    if !pipe.active() {
+        return false;
+    }
+    if pipe_speed < 0 {
+        // The bird dashed back faster than the pipe scrolled: the same sweep
+        // with time reversed
+        let start = Pipe { x: pipe.x.saturating_add(pipe_speed), ..*pipe };
+        return check_pipe_collision(bird_y, prev_y, bird_x, bird_size, &start, pipe_gap, pipe_speed.saturating_neg());
+    }
+    
+    // With the pipe d into its move (0..=pipe_speed), X ranges overlap
+    // while enter < d < exit
+    let prev_x = pipe.x.saturating_add(pipe_speed).saturating_sub(bird_x);
+    let enter = prev_x.saturating_sub(bird_size * 1000);
+    let exit = prev_x.saturating_add(PIPE_WIDTH * 1000);
+    if enter >= pipe_speed || exit <= 0 {
+        return false;
+    }
+    
+    let y_at = |d: i32| {
+        if pipe_speed > 0 {
+            prev_y + (bird_y - prev_y) * d / pipe_speed
+        } else {
+            bird_y
+        }
+    };
+    let (first, last) = (y_at(enter.max(0)), y_at(exit.min(pipe_speed)));
+    !pipe.gap_ys().any(|gap_y| {
+        let gap_top = gap_y - pipe_gap / 2;
+        let gap_bottom = gap_y + pipe_gap / 2;
+        [first, last].iter().all(|&y| y >= gap_top && y + bird_size <= gap_bottom)
+    })
+}
+
+/// Most pipes that can be on a `width` px screen at once `spawn_distance` px
+/// apart: one spawns at the right edge only once the last is more than
+/// `spawn_distance` in, and each stays active until it is fully past the
+/// left edge, whatever the speed
+pub fn pipe_slots(width: i32, spawn_distance: i32) -> usize {
+    ((width + PIPE_WIDTH + 1) / spawn_distance.max(1) + 1) as usize
+}
+
+/// How far (x1000) a pipe spawned at the right edge would be from the nearest
+/// active pipe; None with none active. Kept apart from position, since an
+/// active pipe can sit exactly at x == 0.
+fn spawn_clearance(game: &mut GameState) -> Option<i32> {
+    let slots = game.pipe_slots();
+    match game.active_pipe_count() {
+        0 => None,
+        _ => game.pipes[..slots]
+            .iter()
+            .filter(|p| p.active())
+            .map(|p| game.width() * 1000 - p.x)
+            .min(),
+    }
+}
+
+/// Horizontal distance (x1000) from pipe `i` to the nearest other active
+/// pipe; None if it's the only one
+fn nearest_pipe_gap(game: &GameState, i: usize) -> Option<i32> {
+    let x = game.pipes[i].x;
+    game.pipes
+        .iter()
+        .enumerate()
+        .filter(|&(j, p)| j != i && p.active())
+        .map(|(_, p)| (x - p.x).abs())
+        .min()
+}
+
+/// Whether a pipe spawned now would reach BIRD_X before the run's grace
+/// period is over, so has to wait: as it couldn't kill, the bird would
+/// already be inside it when the grace period ends
+fn reaches_bird_in_grace(game: &GameState) -> bool {
+    let frames_left = (game.grace_frames as u64).saturating_sub(game.frame_count);
+    frames_left as i64 * game.pipe_speed.max(0) as i64 >= ((game.width() - BIRD_X) * 1000) as i64
+}
+
+fn spawn_pipes(game: &mut GameState) {
+    // Spawn a new pipe only once every active one is more than
+    // `pipe_spawn_distance` in from the right edge, so two are never
+    // closer than the bird can thread
+    let slots = game.pipe_slots();
+    let spacing = game.physics.pipe_spawn_distance * 1000;
+    if spawn_clearance(game).is_none_or(|clearance| clearance > spacing) && !reaches_bird_in_grace(game) {
+        // Take the first inactive pipe slot, if there is one
+        let i = game.free_pipe_slot as usize;
+        if i < slots {
+            // Draw the gap position with the run's generator
+            let low = PIPE_HEIGHT_MIN + game.pipe_gap / 2;
+            let high = game.height() - PIPE_HEIGHT_MIN - game.pipe_gap / 2;
+            let (draw, gap_y) = match game.rng_version {
+                rng::RNG_LCG => {
+                    let draw = rng::lcg(&mut game.seed);
+                    (draw, (low + ((draw / 65536) % 300) as i32).min(high))
+                }
+                _ => {
+                    let draw = rng::xorshift64_star(&mut game.seed);
+                    (draw, rng::uniform_in(draw, low, high))
+                }
+            };
+            
+            let mut pipe = Pipe {
+                x: game.width() * 1000,
+                gap_y,
+                active: 1,
+                ..Default::default()
+            };
+            
+            // Past DOUBLE_GAP_MIN_SCORE some pipes have two gaps, and past
+            // OSCILLATION_MIN_SCORE some others move; both draws come from
+            // the same seed step so the gap sequence is unchanged
+            let roll = mix64(draw);
+            let double = if game.pipe_count() > DOUBLE_GAP_MIN_SCORE
+                && roll % 100 < DOUBLE_GAP_CHANCE_PERCENT
+            {
+                double_gap_ys(roll >> 8, game.pipe_gap, game.height())
+            } else {
+                None
+            };
+            if let Some((upper, lower)) = double {
+                pipe.pipe_type = PIPE_TYPE_DOUBLE;
+                pipe.gap_y = upper;
+                pipe.gap_y2 = lower;
+            } else if game.pipe_count() > OSCILLATION_MIN_SCORE
+                && (draw >> 20) % 100 < OSCILLATION_CHANCE_PERCENT
+            {
+                pipe.pipe_type = PIPE_TYPE_OSCILLATING;
+                pipe.phase = ((draw >> 28) % OSCILLATION_PERIOD) as u16;
+                pipe.amplitude = OSCILLATION_AMPLITUDE_MIN
+                    + ((draw >> 36) % (OSCILLATION_AMPLITUDE_MAX - OSCILLATION_AMPLITUDE_MIN + 1) as u64) as i32;
+                pipe.gap_y = oscillating_gap_y(&pipe, game.frame_count, game.pipe_gap, game.height());
+            }
+            // Gap center holds at most one item; a coin takes precedence
+            pipe.set_coin((draw >> 12) % 100 < COIN_CHANCE_PERCENT);
+            pipe.set_shield(!pipe.coin() && (draw >> 44) % 100 < SHIELD_CHANCE_PERCENT);
+            let item = mix64(roll);
+            let kind = Powerup::TIMED[((item >> 8) % Powerup::TIMED.len() as u64) as usize];
+            if !pipe.coin() && !pipe.shield() && item % 100 < PICKUP_CHANCE_PERCENT && game.pickup_allowed(kind) {
+                pipe.pickup = kind.repr();
+            }
+            game.pipes[i] = pipe;
+            game.pipe_spawned(i);
+            debug_assert!(
+                nearest_pipe_gap(game, i).is_none_or(|gap| gap > spacing),
+                "pipes spawned within {} of each other",
+                spacing
+            );
+        }
+    }
+}
+
+/// Gap centers of a double pipe drawn from `roll`: two `pipe_gap` gaps split
+/// by at least DOUBLE_GAP_STRIP_MIN of pipe, with DOUBLE_GAP_EDGE_MIN above
+/// and below. None when both gaps don't fit in a `height` px screen at this
+/// gap size.
+pub fn double_gap_ys(roll: u64, pipe_gap: i32, height: i32) -> Option<(i32, i32)> {
+    let slack = height - 2 * DOUBLE_GAP_EDGE_MIN - DOUBLE_GAP_STRIP_MIN - 2 * pipe_gap;
+    if slack < 0 {
+        return None;
+    }
+    // Split the slack between the top edge and the strip
+    let above = (roll % (slack as u64 + 1)) as i32;
+    let strip = DOUBLE_GAP_STRIP_MIN + ((roll >> 32) % ((slack - above) as u64 + 1)) as i32;
+    let upper_top = DOUBLE_GAP_EDGE_MIN + above;
+    Some((upper_top + pipe_gap / 2, upper_top + pipe_gap + strip + pipe_gap / 2))
+}
+
+/// Gap center of a moving pipe at `frame`: a triangle wave of `pipe.amplitude`
+/// around the middle of a `height` px screen's playable band, kept inside the
+/// gap-center bounds
+pub fn oscillating_gap_y(pipe: &Pipe, frame: u64, pipe_gap: i32, height: i32) -> i32 {
+    let low = PIPE_HEIGHT_MIN + pipe_gap / 2;
+    let high = height - PIPE_HEIGHT_MIN - pipe_gap / 2;
+    let period = OSCILLATION_PERIOD as i32;
+    let t = ((frame % OSCILLATION_PERIOD + pipe.phase as u64) % OSCILLATION_PERIOD) as i32;
+    // Distance from mid-period, 0..=period, mapped onto -amplitude..=amplitude
+    let d = (2 * t - period).abs();
+    let offset = pipe.amplitude * (2 * d - period) / period;
+    ((low + high) / 2 + offset).clamp(low, high.max(low))
+}
+
+/// sha256 commitment to a run's reveal, as stored by `request_game`
+pub fn commitment_for(reveal: u64) -> [u8; 32] {
+    solana_sha256_hasher::hash(&reveal.to_le_bytes()).to_bytes()
+}
+
+/// Check `reveal` against the pending commitment; the reveal must land in a
+/// later slot than the commitment
+fn check_reveal(game: &GameState, reveal: u64, current_slot: u64) -> Result<()> {
+    require!(game.commitment != [0; 32], FlappyError::NoCommitment);
+    require!(current_slot > game.commitment_slot, FlappyError::RevealTooEarly);
+    require!(commitment_for(reveal) == game.commitment, FlappyError::InvalidReveal);
+    Ok(())
+}
+
+/// Check the run can take input and advance
+fn require_playing(game: &GameState) -> Result<()> {
+    require!(game.game_status() != GameStatus::Paused, FlappyError::GamePaused);
+    require!(game.game_status() != GameStatus::Countdown, FlappyError::CountdownRunning);
+    require!(game.game_status() == GameStatus::Playing, FlappyError::GameNotPlaying);
+    Ok(())
+}
+
+/// Check the run is solo, for the inputs and actions co-op runs don't take
+fn require_solo(game: &GameState) -> Result<()> {
+    require!(!game.coop(), FlappyError::CoopGame);
+    Ok(())
+}
+
+/// Queue a flap of co-op bird `index` for the next frame: the first bird's
+/// as `flap` does, the second's in `coop.pending_flap`
+fn queue_coop_flap(game: &mut GameState, index: u8) -> Result<()> {
+    match index {
+        0 => queue_flap(game).map(|_| ()),
+        _ => {
+            game.coop.set_pending_flap(true);
+            Ok(())
+        }
+    }
+}
+
+/// Whether `signer` may act for `player`: it is them, or `session` (a
+/// session token's address and data) is one `player` issued to `signer` for
+/// this program, at its canonical address and unexpired at `now`
+fn acts_for(player: &Pubkey, signer: &Pubkey, session: Option<(&Pubkey, &SessionToken)>, now: i64) -> bool {
+    signer == player
+        || session.is_some_and(|(address, token)| {
+            token.authority == *player
+                && token.session_signer == *signer
+                && token.target_program == crate::ID
+                && now < token.valid_until
+                && *address == session_token_address(player, signer)
+        })
+}
+
+/// Where the session keys program keeps the token `player` issues to `signer`
+/// for this program
+fn session_token_address(player: &Pubkey, signer: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[SessionToken::SEED_PREFIX.as_bytes(), crate::ID.as_ref(), signer.as_ref(), player.as_ref()],
+        &session_keys::ID,
+    )
+    .0
+}
+
+/// Check ticks can advance the run, which may still be counting down
+fn require_ticking(game: &GameState) -> Result<()> {
+    match game.game_status() {
+        GameStatus::Countdown => Ok(()),
+        _ => require_playing(game),
+    }
+}
+
+/// Let a flap through a countdown: the run goes live at once, with the flap
+/// as its first input, if the run allows it
+fn skip_countdown_by_flap(game: &mut GameState) -> Result<()> {
+    if game.game_status() == GameStatus::Countdown {
+        require!(game.flap_skips_countdown(), FlappyError::CountdownRunning);
+        game.countdown_frames_remaining = 0;
+        game.set_game_status(GameStatus::Playing);
+    }
+    Ok(())
+}
+
+/// Queue a flap for the next frame, unless too soon after the last, which
+/// emits `FlapTooSoon` instead. Returns whether it was kept.
+fn queue_flap(game: &mut GameState) -> Result<bool> {
+    if !game.flap_allowed() {
+        emit!(FlapTooSoon {
+            player: game.authority,
+            frame: game.frame_count,
+            next_frame: game.last_flap_frame - 1 + game.min_flap_interval as u64,
+        });
+        return Ok(false);
+    }
+    record_flap(game)?;
+    game.set_pending_flap(true);
+    game.last_flap_frame = game.frame_count + 1;
+    game.flaps_this_run = game.flaps_this_run.saturating_add(1);
+    Ok(true)
+}
+
+/// Log a flap queued after `frame_count` frames; with `run_seed` the log
+/// replays the run
+fn record_flap(game: &mut GameState) -> Result<()> {
+    let frame = game.frame_count;
+    require!(frame < INPUT_LOG_FRAMES, FlappyError::InputLogFull);
+    game.input_log.bits[(frame / 64) as usize] |= 1 << (frame % 64);
+    Ok(())
+}
+
+/// Stop the clock on a run; `last_update` records when
+fn pause_run(game: &mut GameState, now: i64) -> Result<()> {
+    require!(game.game_status() == GameStatus::Playing, FlappyError::GameNotPlaying);
+    game.set_game_status(GameStatus::Paused);
+    game.last_update = now;
+    Ok(())
+}
+
+/// Continue a paused run as of `now`/`slot`. Returns false, with the run over,
+/// if it was paused longer than MAX_PAUSE_SECONDS
+fn resume_run(game: &mut GameState, now: i64, slot: u64) -> Result<bool> {
+    require!(game.game_status() == GameStatus::Paused, FlappyError::GameNotPaused);
+    let expired = now.saturating_sub(game.last_update) > MAX_PAUSE_SECONDS;
+    game.last_update = now;
+    if expired {
+        game.end_run(DeathCause::PauseExpired);
+        return Ok(false);
+    }
+    game.set_game_status(GameStatus::Playing);
+    game.last_tick_slot = slot;
+    Ok(true)
+}
+
+/// The GameConfig, if the admin has created it
+fn load_config(config: &AccountInfo) -> Result<Option<GameConfig>> {
+    if config.owner != &crate::ID || config.data_is_empty() {
+        return Ok(None);
+    }
+    let data = config.try_borrow_data()?;
+    Ok(Some(GameConfig::try_deserialize(&mut &data[..])?))
+}
+
+/// `load_config` for a context's optional GameConfig, None when not passed
+fn load_optional_config(config: Option<&AccountInfo>) -> Result<Option<GameConfig>> {
+    config.map_or(Ok(None), load_config)
+}
+
+/// Fail with `ProgramPaused` while the admin has paused the program; a
+/// GameConfig that doesn't exist yet, or wasn't passed, isn't paused
+fn require_not_paused(config: Option<&GameConfig>) -> Result<()> {
+    require!(!config.is_some_and(|config| config.paused), FlappyError::ProgramPaused);
+    Ok(())
+}
+
+/// Fail unless `game` is owned by this program, so not delegated and its
+/// state is what the base layer holds, and has no run in progress
+fn require_settled(game: &AccountInfo) -> Result<()> {
+    require_keys_eq!(*game.owner, crate::ID, FlappyError::GameDelegated);
+    // Read the way `AccountLoader::load` would, as in `delegate`
+    let data = game.try_borrow_data()?;
+    require!(data.starts_with(GameState::DISCRIMINATOR), ErrorCode::AccountDiscriminatorMismatch);
+    let game: &GameState = bytemuck::from_bytes(&data[8..8 + std::mem::size_of::<GameState>()]);
+    require!(game.game_status() == GameStatus::GameOver, FlappyError::GameNotOver);
+    Ok(())
+}
+
+/// Fail with `RunTooOld` once the record's high score ended longer ago than
+/// the submit window, so an old run can't be entered on a new board
+fn require_fresh(record: &HighScoreRecord, config: Option<&GameConfig>, now: i64) -> Result<()> {
+    let window = config.map_or(SUBMIT_WINDOW_SECS, GameConfig::submit_window);
+    require!(
+        now.saturating_sub(record.high_score_timestamp) <= window as i64,
+        FlappyError::RunTooOld
+    );
+    Ok(())
+}
+
+/// Fail with `CooldownActive` if the run `game` is starting is ranked and
+/// began within `cooldown_secs` of the player's last ranked run ending at
+/// `last_game_over_at`
+fn check_ranked_cooldown(game: &GameState, last_game_over_at: i64, cooldown_secs: u32, now: i64) -> Result<()> {
+    if !game.ranked() {
+        return Ok(());
+    }
+    let remaining = last_game_over_at.saturating_add(cooldown_secs as i64).saturating_sub(now);
+    if remaining > 0 {
+        msg!("Ranked cooldown: {}s left", remaining);
+        return err!(FlappyError::CooldownActive);
+    }
+    Ok(())
+}
+
+/// The HighScoreRecord behind `record`, None if it doesn't exist yet
+fn load_high_score_record(record: &AccountInfo) -> Result<Option<HighScoreRecord>> {
+    if record.owner != &crate::ID || record.data_is_empty() {
+        return Ok(None);
+    }
+    let data = record.try_borrow_data()?;
+    Ok(Some(HighScoreRecord::try_deserialize(&mut &data[..])?))
+}
+
+/// When `game`'s player last ended a ranked run, for the cooldown: the
+/// latest of `game`'s own, the `record`'s for games since closed, and those
+/// of the player's other games in `record.game_slots`, which `others` must
+/// hold in slot order, delegated or not
+fn last_ranked_game_over(game: &GameState, record: Option<&HighScoreRecord>, others: &[AccountInfo]) -> Result<i64> {
+    let Some(record) = record else {
+        return Ok(game.last_game_over_at);
+    };
+    let mut latest = game.last_game_over_at.max(record.last_game_over_at);
+    let mut others = others.iter();
+    for slot in (0..MAX_GAME_SLOTS).filter(|&slot| slot != game.game_slot && record.game_slots & 1 << slot != 0) {
+        let other = others.next().ok_or(FlappyError::MissingGameSlots)?;
+        latest = latest.max(other_game_over(other, &game.authority, slot)?);
+    }
+    Ok(latest)
+}
+
+/// `last_game_over_at` of `authority`'s game in `slot`, read from `info`,
+/// which must be that game
+fn other_game_over(info: &AccountInfo, authority: &Pubkey, slot: u8) -> Result<i64> {
+    let data = info.try_borrow_data()?;
+    let size = 8 + std::mem::size_of::<GameState>();
+    require!(
+        data.len() >= size && data.starts_with(GameState::DISCRIMINATOR),
+        FlappyError::MissingGameSlots
+    );
+    let other: &GameState = bytemuck::from_bytes(&data[8..size]);
+    let address = Pubkey::create_program_address(
+        &[GAME_SEED, authority.as_ref(), &[slot], &[other.bump]],
+        &crate::ID,
+    );
+    require!(address == Ok(*info.key), FlappyError::MissingGameSlots);
+    Ok(other.last_game_over_at)
+}
+
+/// `start_game`, or `start_coop` with the `partner` playing the second bird
+fn start_run(accounts: &mut StartGame, options: StartOptions, partner: Option<Pubkey>, others: &[AccountInfo]) -> Result<()> {
+    let game = &mut *accounts.game.load_mut()?;
+    require!(
+        !game.game_status().in_run(),
+        FlappyError::GameAlreadyStarted
+    );
+    
+    let mode = options.mode.unwrap_or(game.game_mode());
+    require!(
+        mode == GameMode::Classic || options.scoring == ScoringMode::PipesPassed,
+        FlappyError::WrongGameMode
+    );
+    require!((1..=MAX_LIVES).contains(&options.lives), FlappyError::InvalidLives);
+    
+    let clock = Clock::get()?;
+    check_reveal(game, options.reveal, clock.slot)?;
+    let slot_hashes = accounts.slot_hashes.try_borrow_data()?;
+    let (seed, seed_source) = start_seed(options.reveal, &game.authority, &clock, &slot_hashes);
+    drop(slot_hashes);
+    
+    let config = load_config(&accounts.config)?;
+    require_not_paused(config.as_ref())?;
+    begin_run(
+        game,
+        config.as_ref(),
+        options.difficulty,
+        options.scoring,
+        seed,
+        &clock,
+    );
+    game.seed_source = seed_source;
+    game.set_game_mode(mode);
+    game.set_practice(options.practice);
+    game.lives = options.lives;
+    game.set_extra_lives(options.lives > 1);
+    game.set_horizontal_control(options.horizontal_control);
+    game.set_wind_enabled(options.wind.unwrap_or(options.difficulty != Difficulty::Easy));
+    game.commitment = [0; 32];
+    if let Some(partner) = partner {
+        require!(partner != Pubkey::default() && partner != game.authority, FlappyError::InvalidPartner);
+        game.begin_coop(partner);
+    }
+    // Checked on the run as set up; failing reverts the setup with it
+    let record = load_high_score_record(&accounts.high_score_record)?;
+    check_ranked_cooldown(
+        game,
+        last_ranked_game_over(game, record.as_ref(), others)?,
+        config.map_or(0, |config| config.ranked_cooldown_secs),
+        clock.unix_timestamp,
+    )?;
+    
+    if let Some(profile) = accounts.profile.as_deref_mut().filter(|_| !options.practice) {
+        profile.games_played = profile.games_played.saturating_add(1);
+    }
+    if let Some(missions) = accounts.missions.as_deref_mut().filter(|_| !options.practice) {
+        missions.record_game_started(clock.unix_timestamp);
+    }
+    let global_stats = accounts.global_stats.as_deref();
+    if let Some(mut stats) = load_global_stats(global_stats, game)? {
+        stats.record_game_started(game);
+        store_global_stats(global_stats, Some(stats))?;
+    }
+    
+    emit!(GameStarted {
+        player: game.authority,
+        seed: game.seed,
+        seed_source: game.seed_source,
+        difficulty: game.difficulty(),
+        timestamp: game.last_update,
+    });
+    msg!("Game started!");
+    Ok(())
+}
+
+/// Put `game` into a fresh run from `seed`, tuned by the GameConfig if the
+/// admin has created it
+fn begin_run(
+    game: &mut GameState,
+    config: Option<&GameConfig>,
+    difficulty: Difficulty,
+    scoring: ScoringMode,
+    seed: u64,
+    clock: &Clock,
+) {
+    // Snapshot the tuning so a config change mid-run can't affect this run
+    game.physics = config.map_or_else(PhysicsConfig::default, |config| config.physics);
+    game.rng_version = rng::RNG_VERSION;
+    game.min_flap_interval = config.map_or(MIN_FLAP_INTERVAL_FRAMES, GameConfig::min_flap_interval);
+    game.hitbox_inset = config.map_or(HITBOX_INSET, GameConfig::hitbox_inset);
+    game.grace_frames = config.map_or(GRACE_FRAMES, GameConfig::grace_frames);
+    game.streak_threshold = config.map_or(STREAK_THRESHOLD, GameConfig::streak_threshold);
+    game.pickups = PICKUP_KINDS;
+    game.ranked_pickups = config.map_or(0, |config| config.ranked_pickups);
+    game.set_difficulty(difficulty);
+    game.set_scoring(scoring);
+    reset_run(game);
+    // The last run's ending goes with it
+    game.end_cause = 0;
+    game.end_frame = 0;
+    game.end_bird_y = 0;
+    game.end_pipe = 0;
+    game.run_end_recorded = 0;
+    // start_game overrides this with where its seed came from
+    game.seed_source = SEED_SOURCE_FIXED;
+    game.countdown_frames_remaining = config.map_or(0, |config| config.countdown_frames);
+    game.set_flap_skips_countdown(config.is_some_and(|config| config.flap_skips_countdown));
+    game.set_game_status(match game.countdown_frames_remaining {
+        0 => GameStatus::Playing,
+        _ => GameStatus::Countdown,
+    });
+    game.last_update = clock.unix_timestamp;
+    game.last_update_slot = clock.slot;
+    game.last_tick_slot = clock.slot;
+    game.seed = seed;
+    game.run_seed = seed;
+}
+
+/// The associated token account of `owner` for `mint`
+fn associated_token_address(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), TOKEN_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Create `mint` at the PDA signed for by `mint_seeds` as an SPL Token mint
+/// of `decimals`, minted by `authority` and with no freeze authority, paid
+/// for by `payer`
+fn create_mint<'info>(
+    mint: &AccountInfo<'info>,
+    mint_seeds: &[&[u8]],
+    decimals: u8,
+    authority: &Pubkey,
+    payer: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    anchor_lang::system_program::create_account(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            anchor_lang::system_program::CreateAccount {
+                from: payer.clone(),
+                to: mint.clone(),
+            },
+            &[mint_seeds],
+        ),
+        Rent::get()?.minimum_balance(MINT_ACCOUNT_SIZE),
+        MINT_ACCOUNT_SIZE as u64,
+        &TOKEN_PROGRAM_ID,
+    )?;
+    
+    // InitializeMint2: decimals, mint authority, no freeze authority
+    let mut data = vec![20, decimals];
+    data.extend_from_slice(authority.as_ref());
+    data.push(0);
+    anchor_lang::solana_program::program::invoke(
+        &anchor_lang::solana_program::instruction::Instruction {
+            program_id: TOKEN_PROGRAM_ID,
+            accounts: vec![AccountMeta::new(mint.key(), false)],
+            data,
+        },
+        &[mint.clone(), token_program.clone()],
+    )?;
+    Ok(())
+}
+
+/// Mint `amount` of `mint` to token account `to`, signed by the
+/// REWARD_AUTHORITY_SEED PDA `authority`
+fn mint_tokens<'info>(
+    mint: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    authority_bump: u8,
+    token_program: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let mut data = vec![7]; // MintTo
+    data.extend_from_slice(&amount.to_le_bytes());
+    anchor_lang::solana_program::program::invoke_signed(
+        &anchor_lang::solana_program::instruction::Instruction {
+            program_id: TOKEN_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(mint.key(), false),
+                AccountMeta::new(to.key(), false),
+                AccountMeta::new_readonly(authority.key(), true),
+            ],
+            data,
+        },
+        &[mint.clone(), to.clone(), authority.clone(), token_program.clone()],
+        &[&[REWARD_AUTHORITY_SEED, &[authority_bump]]],
+    )?;
+    Ok(())
+}
+
+/// Metaplex CreateMetadataAccountV3 for the milestone mint, with the
+/// REWARD_AUTHORITY_SEED PDA as mint and update authority. Encoded by hand,
+/// like the token instructions: no creators, collection or uses, immutable.
+fn create_metadata(accounts: &AddMilestone, authority_bump: u8, name: String, symbol: String, uri: String) -> Result<()> {
+    let mut data = vec![33];
+    for field in [name, symbol, uri] {
+        field.serialize(&mut data)?;
+    }
+    0u16.serialize(&mut data)?; // seller_fee_basis_points
+    data.extend_from_slice(&[0, 0, 0]); // creators, collection, uses: None
+    data.push(0); // is_mutable: false
+    data.push(0); // collection_details: None
+    anchor_lang::solana_program::program::invoke_signed(
+        &anchor_lang::solana_program::instruction::Instruction {
+            program_id: TOKEN_METADATA_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(accounts.metadata.key(), false),
+                AccountMeta::new_readonly(accounts.milestone_mint.key(), false),
+                AccountMeta::new_readonly(accounts.reward_authority.key(), true),
+                AccountMeta::new(accounts.admin.key(), true),
+                AccountMeta::new_readonly(accounts.reward_authority.key(), true),
+                AccountMeta::new_readonly(accounts.system_program.key(), false),
+            ],
+            data,
+        },
+        &[
+            accounts.metadata.to_account_info(),
+            accounts.milestone_mint.to_account_info(),
+            accounts.reward_authority.to_account_info(),
+            accounts.admin.to_account_info(),
+            accounts.system_program.to_account_info(),
+            accounts.metadata_program.to_account_info(),
+        ],
+        &[&[REWARD_AUTHORITY_SEED, &[authority_bump]]],
+    )?;
+    Ok(())
+}
+
+/// The SeasonConfig, if the admin has created it
+fn load_seasons(config: &AccountInfo) -> Result<Option<SeasonConfig>> {
+    if config.owner != &crate::ID || config.data_is_empty() {
+        return Ok(None);
+    }
+    let data = config.try_borrow_data()?;
+    Ok(Some(SeasonConfig::try_deserialize(&mut &data[..])?))
+}
+
+/// `initialize` and `initialize_with_params`: set up the game, counting a new
+/// player in the GlobalStats
+fn init_game_account(ctx: Context<Initialize>, slot: u8, playfield: Option<(u16, u16)>) -> Result<()> {
+    require!(slot < MAX_GAME_SLOTS, FlappyError::InvalidGameSlot);
+    let record = &mut ctx.accounts.high_score_record;
+    let new_player = record.claim(ctx.accounts.authority.key(), ctx.bumps.high_score_record);
+    record.open_slot(slot);
+    let clock = Clock::get()?;
+    let created = init_game(
+        &ctx.accounts.game,
+        ctx.accounts.authority.key(),
+        ctx.bumps.game,
+        slot,
+        playfield,
+        clock.unix_timestamp,
+    )?;
+    if !created {
+        msg!("Game already initialized for player {} in slot {}", ctx.accounts.authority.key(), slot);
+        return Ok(());
+    }
+    
+    // The HighScoreRecord outlives the player's games, whichever slot comes
+    // first and however often they close and re-create them, so its creation
+    // is what counts a new player. The game's discriminator isn't written
+    // until exit, so load it as `init_game` did.
+    let game = &*load_or_init(&ctx.accounts.game)?;
+    let global_stats = ctx.accounts.global_stats.as_deref();
+    if let Some(mut stats) = load_global_stats(global_stats, game)?.filter(|_| new_player) {
+        stats.unique_players = stats.unique_players.saturating_add(1);
+        store_global_stats(global_stats, Some(stats))?;
+    }
+    
+    msg!("Game initialized for player {} in slot {}", game.authority, slot);
+    Ok(())
+}
+
+/// Set up the game `init_if_needed` created behind `loader`, returning
+/// true; a game that already existed only has its run reset, keeping the
+/// high scores, coins and seed, and returns false. A `playfield` replaces
+/// the game's size either way; without one a new game gets the default.
+fn init_game(
+    loader: &AccountLoader<'_, GameState>,
+    authority: Pubkey,
+    bump: u8,
+    slot: u8,
+    playfield: Option<(u16, u16)>,
+    now: i64,
+) -> Result<bool> {
+    let fresh = is_fresh(loader)?;
+    let game = &mut *load_or_init(loader)?;
+    let (width, height) = playfield.unwrap_or((GAME_WIDTH as u16, GAME_HEIGHT as u16));
+    if !fresh {
+        if playfield.is_some() {
+            (game.width, game.height) = (width, height);
+        }
+        reset_run(game);
+        game.set_game_status(GameStatus::NotStarted);
+        return Ok(false);
+    }
+    game.authority = authority;
+    game.bump = bump;
+    game.game_slot = slot;
+    (game.width, game.height) = (width, height);
+    game.score = 0;
+    game.high_score = 0;
+    game.high_scores = [0; 3];
+    game.set_difficulty(Difficulty::Normal);
+    game.physics = PhysicsConfig::default();
+    game.set_game_status(GameStatus::NotStarted);
+    game.bird_y = game.height() / 2 * 1000; // Center, scaled
+    game.bird_x = BIRD_X * 1000;
+    game.bird_velocity = 0;
+    game.frame_count = 0;
+    game.last_update = now;
+    game.last_tick_slot = 0;
+    game.apply_difficulty();
+    
+    // Initialize empty pipes
+    for i in 0..MAX_PIPES {
+        game.pipes[i] = Pipe {
+            x: -100_000, // Off-screen
+            gap_y: game.height() / 2,
+            ..Default::default()
+        };
+    }
+    game.next_pipe_spawn_x = game.width() + PIPE_SPAWN_DISTANCE;
+    game.seed = now as u64;
+    Ok(true)
+}
+
+/// Whether `init_if_needed` just created the account behind `loader`
+fn is_fresh<T: anchor_lang::ZeroCopy + Owner>(loader: &AccountLoader<'_, T>) -> Result<bool> {
+    Ok(loader.as_ref().try_borrow_data()?[..8].iter().all(|b| *b == 0))
+}
+
+/// Load a zero-copy account from `init_if_needed`, which leaves a new
+/// account's discriminator unset until exit
+fn load_or_init<'a, T: anchor_lang::ZeroCopy + Owner>(
+    loader: &'a AccountLoader<'_, T>,
+) -> Result<std::cell::RefMut<'a, T>> {
+    if is_fresh(loader)? {
+        loader.load_init()
+    } else {
+        loader.load_mut()
+    }
+}
+
+impl SeasonConfig {
+    /// Season in progress at `now`; None before the first one starts
+    pub fn index_at(&self, now: i64) -> Option<u32> {
+        (now >= self.start).then(|| ((now - self.start) / self.duration) as u32)
+    }
+
+    pub fn season_start(&self, index: u32) -> i64 {
+        self.start + index as i64 * self.duration
+    }
+
+    /// The season a run submitted at `now` goes to. A run counts only for
+    /// the season its last update falls in, so one straddling the boundary
+    /// belongs to the new season and a run from an earlier season is refused.
+    fn season_for_run(&self, now: i64, last_update: i64) -> Result<Option<u32>> {
+        let Some(index) = self.index_at(now) else {
+            return Ok(None);
+        };
+        require!(
+            last_update >= self.season_start(index),
+            FlappyError::RunBeforeSeason
+        );
+        Ok(Some(index))
+    }
+}
+
+/// Unix day number of `timestamp`
+pub fn day_of(timestamp: i64) -> u32 {
+    timestamp.div_euclid(SECONDS_PER_DAY) as u32
+}
+
+/// Week number of `timestamp`: whole weeks since the unix epoch, so weeks
+/// roll over Thursday 00:00 UTC
+pub fn week_of(timestamp: i64) -> u32 {
+    timestamp.div_euclid(SECONDS_PER_WEEK) as u32
+}
+
+/// The run seed every daily challenge on `day` uses
+pub fn daily_seed(day: u32) -> u64 {
+    let hash = solana_sha256_hasher::hashv(&[DAILY_SEED_SALT, &day.to_le_bytes()]);
+    u64::from_le_bytes(hash.to_bytes()[..8].try_into().unwrap())
+}
+
+/// The day whose leaderboard a finished daily run goes on: the day it was
+/// started, provided its last frame was still on that day
+fn daily_board_day(game: &GameState) -> Result<u32> {
+    require!(game.daily(), FlappyError::NotDailyRun);
+    require!(!game.revived(), FlappyError::RevivedRun);
+    require!(
+        game.game_status() == GameStatus::GameOver,
+        FlappyError::GameNotOver
+    );
+    require!(
+        day_of(game.last_update) == game.daily_day,
+        FlappyError::DailyRunCrossedDay
+    );
+    Ok(game.daily_day)
+}
+
+/// Newest hash in the SlotHashes sysvar's data, laid out
+/// `[len: u64][slot: u64][hash: 32 bytes]...`; None if it holds none
+pub fn newest_slot_hash(slot_hashes: &[u8]) -> Option<&[u8]> {
+    let len = u64::from_le_bytes(slot_hashes.get(..8)?.try_into().unwrap());
+    slot_hashes.get(16..48).filter(|_| len > 0)
+}
+
+/// `start_game`'s seed and its SEED_SOURCE_*: the player's reveal, their key,
+/// the start slot and timestamp, and the newest slot hash if there is one
+pub fn start_seed(reveal: u64, authority: &Pubkey, clock: &Clock, slot_hashes: &[u8]) -> (u64, u8) {
+    let (recent_hash, source) = match newest_slot_hash(slot_hashes) {
+        Some(hash) => (hash, SEED_SOURCE_SLOT_HASH),
+        None => (&[][..], SEED_SOURCE_CLOCK),
+    };
+    let hash = solana_sha256_hasher::hashv(&[
+        &reveal.to_le_bytes(),
+        authority.as_ref(),
+        &clock.slot.to_le_bytes(),
+        &clock.unix_timestamp.to_le_bytes(),
+        recent_hash,
+    ]);
+    (u64::from_le_bytes(hash.to_bytes()[..8].try_into().unwrap()), source)
+}
+
+/// Run seed from the player's reveal, the start slot and a recent slot hash
+pub fn mix_seed(reveal: u64, slot: u64, recent_hash: &[u8]) -> u64 {
+    let hash = solana_sha256_hasher::hashv(&[&reveal.to_le_bytes(), &slot.to_le_bytes(), recent_hash]);
+    u64::from_le_bytes(hash.to_bytes()[..8].try_into().unwrap())
+}
+
+/// Reset the per-run fields (score, bird, pipes) shared by start and reset
+fn reset_run(game: &mut GameState) {
+    game.score = 0;
+    game.pipes_passed = 0;
+    game.distance = 0;
+    game.apply_difficulty();
+    game.bird_y = game.height() / 2 * 1000;
+    game.bird_velocity = 0;
+    game.bird_x = BIRD_X * 1000;
+    game.bird_x_velocity = 0;
+    game.set_horizontal_control(false);
+    game.set_wind_enabled(false);
+    game.set_practice(false);
+    game.lives = 1;
+    game.set_extra_lives(false);
+    game.invulnerable_until = 0;
+    game.set_revived(false);
+    game.combo = 0;
+    game.flaps_since_last_pass = 0;
+    game.flaps_this_run = 0;
+    game.last_flap_frame = 0;
+    game.countdown_frames_remaining = 0;
+    game.frame_count = 0;
+    game.set_pending_flap(false);
+    game.set_active_powerup(None);
+    game.input_log = InputLog::default();
+    game.set_daily(false);
+    game.coop = CoopState::default();
+    game.bonus_score = 0;
+    
+    // Reset pipes
+    for i in 0..MAX_PIPES {
+        game.pipes[i] = Pipe {
+            x: -100_000,
+            gap_y: game.height() / 2,
+            ..Default::default()
+        };
+    }
+    game.active_pipe_count = 0;
+    game.next_pipe_spawn_x = game.width();
+}
+
+impl GameState {
+    /// Load the physics parameters of the stored difficulty, ramped by score
+    fn apply_difficulty(&mut self) {
+        let params = self.difficulty().params(&self.physics).ramped(self.pipe_count());
+        self.wind = if self.wind_enabled() {
+            wind_at(self.run_seed, self.frame_count)
+        } else {
+            0
+        };
+        self.gravity = params.gravity.saturating_mul(100 + self.wind as i32) / 100;
+        self.pipe_gap = params.pipe_gap;
+        self.pipe_speed = params.pipe_speed;
+    }
+
+    /// A fresh game for `authority` that keeps this one's high scores, best run,
+    /// coins and difficulty and mode choices
+    fn transferred(&self, authority: Pubkey, bump: u8, now: i64) -> GameState {
+        let mut game = GameState {
+            authority,
+            bump,
+            high_score: self.high_score,
+            high_scores: self.high_scores,
+            high_score_timestamp: self.high_score_timestamp,
+            high_score_frame: self.high_score_frame,
+            high_score_seed: self.high_score_seed,
+            last_game_over_at: self.last_game_over_at,
+            distance_high_score: self.distance_high_score,
+            high_score_assisted: self.high_score_assisted,
+            distance_high_score_assisted: self.distance_high_score_assisted,
+            mode_high_scores: self.mode_high_scores,
+            game_mode: self.game_mode,
+            best_run_inputs: self.best_run_inputs,
+            best_run_frames: self.best_run_frames,
+            best_run_flaps: self.best_run_flaps,
+            best_run_seed: self.best_run_seed,
+            rewarded_score: self.rewarded_score,
+            coins: self.coins,
+            total_distance: self.total_distance,
+            difficulty: self.difficulty,
+            commit_interval: self.commit_interval,
+            spectator_mode: self.spectator_mode,
+            crank_tip_opt_out: self.crank_tip_opt_out,
+            verbose_logging: self.verbose_logging,
+            bird_skin: self.bird_skin,
+            game_slot: self.game_slot,
+            width: self.width,
+            height: self.height,
+            game_status: GameStatus::NotStarted as u8,
+            last_update: now,
+            seed: now as u64,
+            ..Default::default()
+        };
+        reset_run(&mut game);
+        game
+    }
+
+    /// Game over now from `cause`: fold in the score and note how the run ended
+    /// (`last_update` is when it ended). Returns the high score it beat, if any.
+    fn end_run(&mut self, cause: DeathCause) -> Option<u64> {
+        self.set_game_status(GameStatus::GameOver);
+        let beaten = self.record_high_score();
+        self.end_frame = self.frame_count;
+        self.set_end_cause(cause);
+        self.end_bird_y = self.bird_y / 1000;
+        self.end_pipe = 0;
+        if self.ranked() {
+            self.last_game_over_at = self.last_update;
+        }
+        beaten
+    }
+
+    /// Solo Classic pipe scores with no practice, extra lives or revive: the
+    /// runs that are rewarded and subject to the ranked cooldown
+    fn ranked(&self) -> bool {
+        self.scoring() == ScoringMode::PipesPassed
+            && self.game_mode() == GameMode::Classic
+            && !self.practice()
+            && !self.extra_lives()
+            && !self.revived()
+            && !self.coop()
+    }
+
+    /// Whether this frame is in the run's first `grace_frames`, when pipes
+    /// pass through the bird
+    fn in_grace(&self) -> bool {
+        self.frame_count <= self.grace_frames as u64
+    }
+
+    /// Whether a flap now would apply at least `min_flap_interval` frames
+    /// after the last one; casual runs flap freely
+    fn flap_allowed(&self) -> bool {
+        !(self.ranked() || self.daily())
+            || self.last_flap_frame == 0
+            || self.frame_count + 1 >= self.last_flap_frame + self.min_flap_interval as u64
+    }
+
+    /// Fold the current score into the overall and per-difficulty high scores,
+    /// keeping the run's inputs as the best run on a new overall record.
+    /// Non-Classic runs only update their `mode_high_scores` entry, and
+    /// practice and co-op runs nothing. Returns the previous `high_score` when the run
+    /// beat it; a tie leaves it, and its provenance, as they were, except
+    /// that the first run to end sets the provenance of a zero high score.
+    fn record_high_score(&mut self) -> Option<u64> {
+        if self.practice() || self.coop() {
+            return None;
+        }
+        if self.scoring() == ScoringMode::PipesPassed {
+            let best = &mut self.mode_high_scores[self.game_mode() as usize];
+            *best = (*best).max(self.score);
+        }
+        // Other modes only compete with themselves
+        if self.game_mode() != GameMode::Classic {
+            return None;
+        }
+        let assisted = self.extra_lives() || self.revived();
+        if self.scoring() == ScoringMode::Distance {
+            if self.score > self.distance_high_score {
+                self.distance_high_score = self.score;
+                self.set_distance_high_score_assisted(assisted);
+            }
+            return None;
+        }
+        let best = &mut self.high_scores[self.difficulty() as usize];
+        *best = (*best).max(self.score);
+        let first = self.high_score == 0 && self.high_score_timestamp == 0;
+        if self.score < self.high_score || (self.score == self.high_score && !first) {
+            return None;
+        }
+        self.set_high_score_assisted(assisted);
+        self.best_run_inputs = self.input_log;
+        self.best_run_frames = self.frame_count as u32;
+        self.best_run_flaps = self.flaps_this_run;
+        self.best_run_seed = self.run_seed;
+        self.high_score_timestamp = self.last_update;
+        self.high_score_frame = self.frame_count;
+        self.high_score_seed = self.run_seed;
+        let old = std::mem::replace(&mut self.high_score, self.score);
+        (self.score > old).then_some(old)
+    }
+
+    /// Pipe slots this run's spawn distance can fill
+    /// Capped at MAX_PIPES: on a wide playfield with close pipes, a pipe
+    /// waits for a slot and spawns further behind the last
+    pub fn pipe_slots(&self) -> usize {
+        pipe_slots(self.width(), self.physics.pipe_spawn_distance).min(MAX_PIPES)
+    }
+
+    /// Playfield width in pixels
+    pub fn width(&self) -> i32 {
+        match self.width {
+            0 => GAME_WIDTH,
+            w => w as i32,
+        }
+    }
+
+    /// Playfield height in pixels
+    pub fn height(&self) -> i32 {
+        match self.height {
+            0 => GAME_HEIGHT,
+            h => h as i32,
+        }
+    }
+
+    /// Number of active pipes, recounting them (and `free_pipe_slot`) first
+    /// if `active_pipe_count` was cleared
+    fn active_pipe_count(&mut self) -> usize {
+        if self.active_pipe_count == 0 {
+            let slots = self.pipe_slots();
+            let active = self.pipes[..slots].iter().filter(|pipe| pipe.active()).count();
+            self.active_pipe_count = active as u8 + 1;
+            self.free_pipe_slot = self.pipes[..slots].iter().position(|pipe| !pipe.active()).unwrap_or(slots) as u8;
+        }
+        self.active_pipe_count as usize - 1
+    }
+
+    /// Note that `pipes[i]` was just deactivated
+    fn pipe_deactivated(&mut self, i: usize) {
+        if self.active_pipe_count != 0 {
+            self.active_pipe_count -= 1;
+            self.free_pipe_slot = self.free_pipe_slot.min(i as u8);
+        }
+    }
+
+    /// Note that the pipe in `free_pipe_slot`, `i`, was just spawned
+    fn pipe_spawned(&mut self, i: usize) {
+        let slots = self.pipe_slots();
+        self.active_pipe_count += 1;
+        self.free_pipe_slot = self.pipes[i + 1..slots]
+            .iter()
+            .position(|pipe| !pipe.active())
+            .map_or(slots, |offset| i + 1 + offset) as u8;
+    }
+
+    /// Pipes passed this run, which drive the ramp whatever the scoring mode
+    pub fn pipe_count(&self) -> u64 {
+        match self.scoring() {
+            ScoringMode::PipesPassed => self.score,
+            ScoringMode::Distance => self.pipes_passed,
+        }
+    }
+
+    /// The high score of the current run's scoring and game mode
+    pub fn mode_high_score(&self) -> u64 {
+        match self.scoring() {
+            ScoringMode::PipesPassed => self.mode_high_scores[self.game_mode() as usize],
+            ScoringMode::Distance => self.distance_high_score,
+        }
+    }
+
+    /// Reward owed for the finished run: the points it improved on the best
+    /// score already rewarded, if it is the personal best. Marks them paid.
+    /// Only ranked runs are rewarded.
+    /// Add an allowlisted program's `points` to the run's `bonus_score`
+    fn award_bonus(&mut self, points: u16) -> Result<()> {
+        require!(self.game_status().ticking(), FlappyError::GameNotPlaying);
+        self.bonus_score = self.bonus_score.saturating_add(points as u64);
+        Ok(())
+    }
+
+    fn claim_reward(&mut self) -> u64 {
+        if self.game_status() != GameStatus::GameOver
+            || !self.ranked()
+            || self.score < self.high_score
+        {
+            return 0;
+        }
+        let amount = self.score.saturating_sub(self.rewarded_score);
+        self.rewarded_score = self.rewarded_score.max(self.score);
+        amount
+    }
+
+    /// Deduct `amount` from the coin balance, failing without change if
+    /// short; the shortfall is logged with the error
+    pub fn spend_coins(&mut self, amount: u64) -> Result<()> {
+        let Some(left) = self.coins.checked_sub(amount) else {
+            msg!("{} coins short", amount - self.coins);
+            return err!(FlappyError::InsufficientCoins);
+        };
+        self.coins = left;
+        Ok(())
+    }
+}
+
+impl PlayerProfile {
+    fn record_frame(&mut self, game: &mut GameState, report: &FrameReport, now: i64) {
+        self.frames_survived = self.frames_survived.saturating_add(1);
+        self.pipes_passed = self.pipes_passed.saturating_add(report.pipes_passed as u64);
+        self.coins_collected = self.coins_collected.saturating_add(report.coins_collected as u64);
+        self.total_distance = self.total_distance.saturating_add(report.scrolled as u64);
+        if report.death.is_some() {
+            self.record_run_end(game, now);
+        }
+    }
+
+    /// Record the ended run once, however many times it's ended. `best_score`
+    /// counts Classic pipes, so other runs don't touch it; the streak counts
+    /// every run but practice.
+    fn record_run_end(&mut self, game: &mut GameState, now: i64) {
+        if std::mem::replace(&mut game.run_end_recorded, 1) != 0 || game.practice() {
+            return;
+        }
+        if game.scoring() == ScoringMode::PipesPassed && game.game_mode() == GameMode::Classic {
+            self.record_game_over(game.score, now);
+        }
+        self.record_streak(game.pipe_count() >= game.streak_threshold as u64);
+        self.record_xp(run_xp(game));
+        self.unlock_achievements(game);
+    }
+
+    /// Add `xp`, emitting `LevelUp` with the new level if it reaches one,
+    /// however many levels that is
+    fn record_xp(&mut self, xp: u64) {
+        self.xp = self.xp.saturating_add(xp);
+        let level = level_for_xp(self.xp);
+        if level > self.level {
+            self.level = level;
+            emit!(LevelUp { player: self.authority, new_level: level });
+        }
+    }
+
+    /// Extend the streak for a run that reached the threshold, emitting
+    /// `StreakExtended`, or break it
+    fn record_streak(&mut self, extended: bool) {
+        if !extended {
+            self.current_streak = 0;
+            return;
+        }
+        self.current_streak = self.current_streak.saturating_add(1);
+        self.best_streak = self.best_streak.max(self.current_streak);
+        emit!(StreakExtended { player: self.authority, streak: self.current_streak });
+    }
+
+    fn record_game_over(&mut self, score: u64, now: i64) {
+        if score > self.best_score {
+            self.best_score = score;
+            self.best_score_timestamp = now;
+        }
+    }
+
+    /// Set the bits of newly earned achievements, emitting `AchievementUnlocked`
+    /// once for each; returns the new bits
+    fn unlock_achievements(&mut self, game: &GameState) -> u64 {
+        let new = evaluate_achievements(game, self) & !self.achievements;
+        self.achievements |= new;
+        for id in 0..u64::BITS as u8 {
+            if new & (1 << id) != 0 {
+                emit!(AchievementUnlocked { player: self.authority, id });
+            }
+        }
+        new
+    }
+
+    /// Mark milestone `milestone` claimed, if `high_score` reaches its
+    /// `threshold` and it isn't already
+    fn claim_milestone(&mut self, milestone: u8, threshold: u64, high_score: u64) -> Result<()> {
+        let bit = 1u64 << milestone;
+        require!(self.milestones_claimed & bit == 0, FlappyError::MilestoneClaimed);
+        require!(high_score >= threshold, FlappyError::MilestoneNotReached);
+        self.milestones_claimed |= bit;
+        Ok(())
+    }
+}
+
+impl MilestoneRegistry {
+    /// Score milestone `milestone` is reached at, once it's set up
+    fn threshold(&self, milestone: u8) -> Result<u64> {
+        match self.thresholds.get(milestone as usize) {
+            Some(&threshold) if threshold > 0 => Ok(threshold),
+            _ => err!(FlappyError::MilestoneNotConfigured),
+        }
+    }
+}
+
+impl GlobalStats {
+    fn record_game_started(&mut self, game: &GameState) {
+        if !game.practice() {
+            self.total_games_started = self.total_games_started.saturating_add(1);
+        }
+    }
+
+    fn record_game_finished(&mut self, game: &GameState) {
+        if !game.practice() {
+            self.total_games_finished = self.total_games_finished.saturating_add(1);
+        }
+    }
+
+    fn record_flaps(&mut self, game: &GameState, flaps: u64) {
+        if !game.practice() {
+            self.total_flaps = self.total_flaps.saturating_add(flaps);
+        }
+    }
+
+    fn record_frame(&mut self, game: &GameState, report: &FrameReport) {
+        if game.practice() {
+            return;
+        }
+        self.total_pipes_passed = self.total_pipes_passed.saturating_add(report.pipes_passed as u64);
+        if report.death.is_some() {
+            self.record_game_finished(game);
+        }
+    }
+}
+
+impl HighScoreRecord {
+    /// Set up a record `init_if_needed` may have just created for
+    /// `authority`; returns whether it had
+    fn claim(&mut self, authority: Pubkey, bump: u8) -> bool {
+        let created = self.authority == Pubkey::default();
+        self.authority = authority;
+        self.bump = bump;
+        created
+    }
+
+    /// Take `game`'s high score with its provenance, and its distance high
+    /// score, where they're unassisted and beat the record's. Returns
+    /// whether either was taken.
+    fn sync(&mut self, game: &GameState) -> bool {
+        let mut changed = false;
+        if game.high_score_timestamp != 0
+            && !game.high_score_assisted()
+            && (game.high_score > self.high_score || self.high_score_timestamp == 0)
+        {
+            self.high_score = game.high_score;
+            self.high_score_timestamp = game.high_score_timestamp;
+            self.high_score_frame = game.high_score_frame;
+            self.high_score_seed = game.high_score_seed;
+            self.best_run_frames = game.best_run_frames;
+            self.best_run_flaps = game.best_run_flaps;
+            self.game_slot = game.game_slot;
+            changed = true;
+        }
+        if !game.distance_high_score_assisted() && game.distance_high_score > self.distance_high_score {
+            self.distance_high_score = game.distance_high_score;
+            changed = true;
+        }
+        changed
+    }
+
+    /// Note the player has a game in `slot`
+    fn open_slot(&mut self, slot: u8) {
+        self.game_slots |= 1 << slot;
+    }
+
+    /// Note `game` is going away, keeping when its last ranked run ended
+    fn close_slot(&mut self, game: &GameState) {
+        self.game_slots &= !(1 << game.game_slot);
+        self.last_game_over_at = self.last_game_over_at.max(game.last_game_over_at);
+    }
+}
+
+impl MissionKind {
+    pub const ALL: [MissionKind; 4] = [
+        MissionKind::PassNPipes,
+        MissionKind::CollectNCoins,
+        MissionKind::PlayNGames,
+        MissionKind::ScoreAtLeastX,
+    ];
+
+    /// Target of a daily or weekly mission of this kind at difficulty `tier`
+    fn target(self, weekly: bool, tier: usize) -> u32 {
+        let targets = match (self, weekly) {
+            (MissionKind::PassNPipes, false) => [20, 50, 100],
+            (MissionKind::PassNPipes, true) => [300, 600, 1_000],
+            (MissionKind::CollectNCoins, false) => [5, 15, 30],
+            (MissionKind::CollectNCoins, true) => [50, 100, 200],
+            (MissionKind::PlayNGames, false) => [3, 5, 10],
+            (MissionKind::PlayNGames, true) => [20, 35, 50],
+            (MissionKind::ScoreAtLeastX, false) => [10, 25, 50],
+            (MissionKind::ScoreAtLeastX, true) => [50, 75, 100],
+        };
+        targets[tier]
+    }
+}
+
+impl Mission {
+    pub fn complete(&self) -> bool {
+        self.target > 0 && self.progress >= self.target
+    }
+}
+
+/// The mission slot `slot` holds in the rotation at `now`: the daily slots
+/// one of each kind a day and the weekly slots two kinds a week, each at a
+/// tier drawn from the day or week and expiring at its end
+pub fn rotation_mission(slot: usize, now: i64) -> Mission {
+    let weekly = slot >= DAILY_MISSION_SLOTS;
+    let (period, length) = if weekly {
+        (week_of(now), SECONDS_PER_WEEK)
+    } else {
+        (day_of(now), SECONDS_PER_DAY)
+    };
+    let kind = MissionKind::ALL[(period as usize + slot) % MissionKind::ALL.len()];
+    let tier = (mix64((period as u64) << 8 | slot as u64) % MISSION_TIERS as u64) as usize;
+    let rewards = if weekly { WEEKLY_MISSION_REWARD_COINS } else { DAILY_MISSION_REWARD_COINS };
+    Mission {
+        kind,
+        target: kind.target(weekly, tier),
+        progress: 0,
+        reward_coins: rewards[tier],
+        expires_at: (period as i64 + 1) * length,
+        claimed: false,
+    }
+}
+
+impl Missions {
+    /// Move every unexpired, unfinished mission of `kind` to `progress` of
+    /// its current progress, capped at the target, emitting
+    /// `MissionCompleted` for any that finish
+    fn update(&mut self, kind: MissionKind, now: i64, progress: impl Fn(u32) -> u32) {
+        for (slot, mission) in self.missions.iter_mut().enumerate() {
+            if mission.kind != kind || now >= mission.expires_at || mission.complete() {
+                continue;
+            }
+            mission.progress = progress(mission.progress).min(mission.target);
+            if mission.complete() {
+                emit!(MissionCompleted { player: self.authority, slot: slot as u8, kind });
+            }
+        }
+    }
+
+    fn record_frame(&mut self, game: &GameState, report: &FrameReport, now: i64) {
+        if game.practice() {
+            return;
+        }
+        if report.pipes_passed > 0 {
+            self.update(MissionKind::PassNPipes, now, |p| p.saturating_add(report.pipes_passed as u32));
+        }
+        if report.coins_collected > 0 {
+            self.update(MissionKind::CollectNCoins, now, |p| p.saturating_add(report.coins_collected as u32));
+        }
+        if report.death.is_some() {
+            self.record_run_end(game, now);
+        }
+    }
+
+    fn record_game_started(&mut self, now: i64) {
+        self.update(MissionKind::PlayNGames, now, |p| p.saturating_add(1));
+    }
+
+    /// Count `game`'s score towards score missions; a run ended more than
+    /// once counts the same
+    fn record_run_end(&mut self, game: &GameState, now: i64) {
+        if game.practice() {
+            return;
+        }
+        let score = game.score.min(u32::MAX as u64) as u32;
+        self.update(MissionKind::ScoreAtLeastX, now, |p| p.max(score));
+    }
+
+    /// Replace every mission expired at `now` with the rotation's; returns
+    /// how many were
+    fn refresh(&mut self, now: i64) -> u8 {
+        let mut replaced = 0;
+        for (slot, mission) in self.missions.iter_mut().enumerate() {
+            if now >= mission.expires_at {
+                *mission = rotation_mission(slot, now);
+                replaced += 1;
+            }
+        }
+        replaced
+    }
+
+    /// Mark the completed mission in `slot` claimed, returning its reward
+    fn claim(&mut self, slot: u8) -> Result<u32> {
+        let mission = self.missions.get_mut(slot as usize).ok_or(FlappyError::InvalidMissionSlot)?;
+        require!(mission.complete(), FlappyError::MissionNotComplete);
+        require!(!mission.claimed, FlappyError::MissionAlreadyClaimed);
+        mission.claimed = true;
+        Ok(mission.reward_coins)
+    }
+}
+
+/// XP the finished run `game` earns
+pub fn run_xp(game: &GameState) -> u64 {
+    game.score.saturating_mul(XP_PER_POINT).saturating_add(game.frame_count / FRAMES_PER_XP)
+}
+
+/// Level of a profile with `xp`: level n from LEVEL_XP_BASE * n^2, up to
+/// u16::MAX
+pub fn level_for_xp(xp: u64) -> u16 {
+    (xp / LEVEL_XP_BASE).isqrt().min(u16::MAX as u64) as u16
+}
+
+/// Every achievement `game` and `profile` currently satisfy, as a bitfield
+pub fn evaluate_achievements(game: &GameState, profile: &PlayerProfile) -> u64 {
+    let frame = game.frame_count;
+    let flapless = frame >= FLAPLESS_FRAMES
+        && !(frame - FLAPLESS_FRAMES..frame).any(|f| game.input_log.flapped_after(f));
+    [
+        (ACHIEVEMENT_FIRST_PIPE, profile.pipes_passed >= 1),
+        (ACHIEVEMENT_SCORE_10, game.pipe_count() >= 10),
+        (ACHIEVEMENT_SCORE_25, game.pipe_count() >= 25),
+        (ACHIEVEMENT_SCORE_50, game.pipe_count() >= 50),
+        (ACHIEVEMENT_SCORE_100, game.pipe_count() >= 100),
+        (ACHIEVEMENT_NO_FLAPS, flapless),
+        (ACHIEVEMENT_SURVIVOR, profile.frames_survived >= SURVIVOR_FRAMES),
+        (ACHIEVEMENT_STREAK_5, profile.best_streak >= STREAK_ACHIEVEMENT_RUNS),
+        (ACHIEVEMENT_LONG_HAUL, profile.total_distance / 1000 >= LONG_HAUL_PIXELS),
+        (ACHIEVEMENT_MARATHON, profile.total_distance / 1000 >= MARATHON_PIXELS),
+    ]
+    .into_iter()
+    .filter(|(_, earned)| *earned)
+    .fold(0, |bits, (id, _)| bits | 1 << id)
+}
+
+// ========================================
+// Tournament Logic
+// ========================================
+
+impl Tournament {
+    fn register(&mut self, player: Pubkey) -> Result<()> {
+        let count = self.entrant_count as usize;
+        require!(
+            !self.entrants[..count].iter().any(|e| e.player == player),
+            FlappyError::AlreadyJoined
+        );
+        require!(count < TOURNAMENT_MAX_ENTRANTS, FlappyError::TournamentFull);
+        self.entrants[count] = TournamentEntry { player, ..Default::default() };
+        self.entrant_count += 1;
+        Ok(())
+    }
+
+    /// Fail unless `game` is a finished run this tournament ranks
+    fn check_run(&self, game: &GameState) -> Result<()> {
+        require!(
+            game.game_status() == GameStatus::GameOver,
+            FlappyError::GameNotOver
+        );
+        require!(
+            (self.start_time..=self.end_time).contains(&game.last_update),
+            FlappyError::RunOutsideTournament
+        );
+        require!(game.difficulty() == self.difficulty, FlappyError::WrongDifficulty);
+        require!(
+            game.scoring() == ScoringMode::PipesPassed,
+            FlappyError::WrongScoringMode
+        );
+        require!(game.game_mode() == GameMode::Classic, FlappyError::WrongGameMode);
+        require!(!game.practice(), FlappyError::PracticeRun);
+        require!(!game.extra_lives(), FlappyError::ExtraLivesRun);
+        require!(!game.revived(), FlappyError::RevivedRun);
+        require_solo(game)
+    }
+
+    /// Keep `score` if it beats the player's best; returns whether it did
+    fn submit(&mut self, player: Pubkey, score: u64) -> Result<bool> {
+        let count = self.entrant_count as usize;
+        let entry = self.entrants[..count]
+            .iter_mut()
+            .find(|e| e.player == player)
+            .ok_or(FlappyError::NotInTournament)?;
+        if entry.submission != 0 && score <= entry.score {
+            return Ok(false);
+        }
+        self.submissions += 1;
+        entry.score = score;
+        entry.submission = self.submissions;
+        Ok(true)
+    }
+
+    /// Winners in rank order with their prizes, and what's left for the
+    /// organizer. Ranked by score, then by who submitted it first.
+    fn payouts(&self) -> (Vec<(Pubkey, u64)>, u64) {
+        let mut ranked: Vec<&TournamentEntry> = self.entrants[..self.entrant_count as usize]
+            .iter()
+            .filter(|e| e.submission != 0)
+            .collect();
+        ranked.sort_by_key(|e| (std::cmp::Reverse(e.score), e.submission));
+        
+        let payouts: Vec<(Pubkey, u64)> = ranked
+            .iter()
+            .zip(TOURNAMENT_PRIZE_SHARES)
+            .map(|(e, share)| (e.player, (self.prize_pool as u128 * share as u128 / 100) as u64))
+            .collect();
+        let paid: u64 = payouts.iter().map(|(_, amount)| amount).sum();
+        (payouts, self.prize_pool - paid)
+    }
+}
+
+// ========================================
+// Match Logic
+// ========================================
+
+/// Set up match `id` in `accounts.game_match` from a seed fixed now
+fn open_match(accounts: &mut CreateMatch, bump: u8, id: u64, opponent: Pubkey, difficulty: Difficulty) -> Result<()> {
+    let challenger = accounts.challenger.key();
+    require_keys_neq!(challenger, opponent, FlappyError::InvalidMatch);
+    
+    let clock = Clock::get()?;
+    let slot_hashes = accounts.slot_hashes.try_borrow_data()?;
+    let recent_hash = slot_hashes.get(16..48).unwrap_or_default();
+    let seed = mix_seed(id, clock.slot, recent_hash);
+    drop(slot_hashes);
+    
+    let game_match = &mut accounts.game_match;
+    game_match.id = id;
+    game_match.seed = seed;
+    game_match.difficulty = difficulty;
+    game_match.created_at = clock.unix_timestamp;
+    game_match.challenger = MatchSide { player: challenger, ..Default::default() };
+    game_match.opponent = MatchSide { player: opponent, ..Default::default() };
+    game_match.bump = bump;
+    
+    msg!("Match {} created: {} vs {}", id, challenger, opponent);
+    Ok(())
+}
+
+impl Match {
+    /// Past the play window, which for a wager starts when it's accepted
+    fn expired(&self, now: i64) -> bool {
+        let start = if self.stake > 0 { self.accepted_at } else { self.created_at };
+        now.saturating_sub(start) > MATCH_TIMEOUT_SECONDS
+    }
+
+    fn accepted(&self) -> bool {
+        self.accepted_at != 0
+    }
+
+    /// Lamports the pot pays (challenger, opponent): all of it to the winner,
+    /// else half each
+    fn payouts(&self) -> (u64, u64) {
+        match self.winner {
+            Some(winner) if winner == self.challenger.player => (self.pot, 0),
+            Some(_) => (0, self.pot),
+            None => (self.pot / 2, self.pot - self.pot / 2),
+        }
+    }
+
+    fn side_mut(&mut self, player: Pubkey) -> Result<&mut MatchSide> {
+        if player == self.challenger.player {
+            Ok(&mut self.challenger)
+        } else if player == self.opponent.player {
+            Ok(&mut self.opponent)
+        } else {
+            err!(FlappyError::NotInMatch)
+        }
+    }
+}
+
+impl MatchSide {
+    /// Take the result from `game` if it's this side's finished match run
+    /// (and wasn't revived); returns whether the side has finished
+    fn record(&mut self, game: Option<&AccountLoader<GameState>>, seed: u64) -> Result<bool> {
+        if let Some(loader) = game {
+            let game = loader.load()?;
+            require_keys_eq!(game.authority, self.player, FlappyError::NotInMatch);
+            if loader.key() == self.game
+                && game.run_seed == seed
+                && game.game_status() == GameStatus::GameOver
+                && !game.revived()
+            {
+                self.finished = true;
+                self.score = game.score;
+                self.flaps = game.input_log.flaps();
+            }
+        }
+        Ok(self.finished)
+    }
+}
+
+/// The better finished side: higher score, then fewer flaps; None on a draw
+/// or if neither finished
+fn decide_match(a: &MatchSide, b: &MatchSide) -> Option<Pubkey> {
+    let rank = |side: &MatchSide| side.finished.then_some((side.score, std::cmp::Reverse(side.flaps)));
+    match rank(a).cmp(&rank(b)) {
+        std::cmp::Ordering::Greater => Some(a.player),
+        std::cmp::Ordering::Less => Some(b.player),
+        std::cmp::Ordering::Equal => None,
+    }
+}
+
+// ========================================
+// Challenge Logic
+// ========================================
+
+impl Challenge {
+    /// Score of the challenge run in `game`, once it's over: the game it
+    /// was accepted in, still on the challenge seed and not revived
+    fn finished_score(&self, loader: &AccountLoader<GameState>) -> Result<Option<u64>> {
+        require_keys_eq!(loader.key(), self.game, FlappyError::WrongChallengeGame);
+        let game = loader.load()?;
+        Ok((game.run_seed == self.seed
+            && game.game_status() == GameStatus::GameOver
+            && !game.revived()
+            && game.last_update <= self.expiry)
+            .then_some(game.score))
+    }
+
+    /// Whether a finished run's `score` beats the challenge
+    fn beaten_by(&self, score: Option<u64>) -> bool {
+        score.is_some_and(|score| score > self.score_to_beat)
+    }
+}
+
+// ========================================
+// Demo Run
+// ========================================
+
+pub const DEMO_SEED: u64 = 0x5EED;
+pub const DEMO_FRAMES: u32 = 32;
+
+/// Result of a demo run, returned from `demo_run` via return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DemoResult {
+    pub score: u64,
+    /// Frames actually simulated (fewer than DEMO_FRAMES if the bird crashed)
+    pub frames: u32,
+    /// Final bird Y (fixed-point, scaled by 1000)
+    pub bird_y: i32,
+    pub game_over: bool,
+}
+
+/// A high-score run, returned from `get_best_run` via return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BestRun {
+    pub score: u64,
+    /// Seed the run started from
+    pub seed: u64,
+    pub frames: u32,
+    /// Flap frames, in the `input_log` format
+    pub inputs: InputLog,
+}
+
+impl GameState {
+    pub fn best_run(&self) -> BestRun {
+        BestRun {
+            score: self.high_score,
+            seed: self.best_run_seed,
+            frames: self.best_run_frames,
+            inputs: self.best_run_inputs,
+        }
+    }
+}
+
+/// Play a fresh run from `seed` for DEMO_FRAMES frames, flapping on frame i
+/// when bit i of `flap_pattern` is set. Same per-frame logic as `flap`/`tick`.
+pub fn simulate_demo_run(seed: u64, flap_pattern: u32) -> DemoResult {
+    let mut game = GameState {
+        seed,
+        ..Default::default()
+    };
+    reset_run(&mut game);
+    game.set_game_status(GameStatus::Playing);
+
+    let mut frames = 0;
+    while frames < DEMO_FRAMES && game.game_status() == GameStatus::Playing {
+        if flap_pattern & (1 << frames) != 0 {
+            game.set_pending_flap(true);
+        }
+        step_physics(&mut game);
+        frames += 1;
+    }
+
+    DemoResult {
+        score: game.score,
+        frames,
+        bird_y: game.bird_y,
+        game_over: game.game_status() == GameStatus::GameOver,
+    }
+}
+
+// ========================================
+// External Bonuses
+// ========================================
+
+impl CpiAllowlist {
+    pub fn allows(&self, program: &Pubkey) -> bool {
+        self.programs[..self.count as usize].contains(program)
+    }
+
+    /// Add or remove `program`; listing a listed one or removing an unlisted
+    /// one changes nothing
+    fn set(&mut self, program: Pubkey, allowed: bool) -> Result<()> {
+        let count = self.count as usize;
+        match (self.programs[..count].iter().position(|p| *p == program), allowed) {
+            (None, true) => {
+                require!(count < MAX_CPI_CALLERS, FlappyError::AllowlistFull);
+                self.programs[count] = program;
+                self.count += 1;
+            }
+            (Some(i), false) => {
+                self.programs[i] = self.programs[count - 1];
+                self.programs[count - 1] = Pubkey::default();
+                self.count -= 1;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Program of the top-level instruction that CPI'd into this one, read from
+/// the instructions sysvar. Fails when called directly.
+fn cpi_caller(instructions: &AccountInfo) -> Result<Pubkey> {
+    let current = anchor_lang::solana_program::sysvar::instructions::get_instruction_relative(0, instructions)?;
+    require_keys_neq!(current.program_id, crate::ID, FlappyError::CallerNotAllowed);
+    Ok(current.program_id)
+}
+
+// ========================================
+// Bird Skins
+// ========================================
+
+impl SkinRegistry {
+    pub fn unlocks(&self, skin_id: u8, mint: &Pubkey) -> bool {
+        self.skins[..self.count as usize]
+            .iter()
+            .any(|skin| skin.skin_id == skin_id && skin.mint == *mint)
+    }
+
+    /// Register `mint` for `skin_id`; registering it again changes nothing
+    fn add(&mut self, skin_id: u8, mint: Pubkey) -> Result<()> {
+        if self.unlocks(skin_id, &mint) {
+            return Ok(());
+        }
+        let count = self.count as usize;
+        require!(count < MAX_SKIN_MINTS, FlappyError::SkinRegistryFull);
+        self.skins[count] = SkinMint { skin_id, mint };
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Unregister `mint` for `skin_id`, if it is
+    fn remove(&mut self, skin_id: u8, mint: Pubkey) {
+        let count = self.count as usize;
+        if let Some(i) = self.skins[..count]
+            .iter()
+            .position(|skin| skin.skin_id == skin_id && skin.mint == mint)
+        {
+            self.skins[i] = self.skins[count - 1];
+            self.skins[count - 1] = SkinMint::default();
+            self.count -= 1;
+        }
+    }
+}
+
+/// Mint of the NFT held in SPL token account `data`: the account is
+/// initialized, owned by `authority` and holds exactly one token. Reads the
+/// fixed layout both token programs share, so no token crate is needed.
+fn held_nft_mint(data: &[u8], authority: &Pubkey) -> Result<Pubkey> {
+    require!(data.len() >= TOKEN_ACCOUNT_LEN, FlappyError::SkinNotOwned);
+    let mint = Pubkey::try_from(&data[0..32]).unwrap();
+    let owner = Pubkey::try_from(&data[32..64]).unwrap();
+    let amount = u64::from_le_bytes(data[64..72].try_into().unwrap());
+    let initialized = data[108] != 0;
+    require!(
+        initialized && owner == *authority && amount == 1,
+        FlappyError::SkinNotOwned
+    );
+    Ok(mint)
+}
+
+// ========================================
+// State Snapshot
+// ========================================
+
+/// First byte of every `GameSnapshot`; bumped when fields are added, which
+/// only ever happens at the end
+pub const SNAPSHOT_VERSION: u8 = 6;
+
+/// An axis-aligned rectangle in screen pixels, `y` growing downward
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PixelRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+/// What a client needs to draw a game, returned from `get_state` via return
+/// data. Kept apart from `GameState` so its encoding doesn't move when the
+/// account layout does.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct GameSnapshot {
+    /// SNAPSHOT_VERSION
+    pub version: u8,
+    pub status: GameStatus,
+    pub score: u64,
+    pub high_score: u64,
+    pub frame_count: u64,
+    /// The bird's hitbox, in pixels
+    pub bird: PixelRect,
+    /// Fixed-point, scaled by 1000: pixels per frame
+    pub bird_velocity: i32,
+    pub bird_x_velocity: i32,
+    /// The solid parts of the active pipes, top to bottom within each pipe
+    pub pipes: Vec<PixelRect>,
+    /// Length and flaps of the run that set `high_score` (version 2)
+    pub best_run_frames: u32,
+    pub best_run_flaps: u32,
+    /// Pixels scrolled while playing over every run (version 3)
+    pub total_distance: u64,
+    /// Power-up held (version 4)
+    pub powerup: Option<ActivePowerup>,
+    /// Playfield size in pixels (version 5)
+    pub width: u16,
+    pub height: u16,
+    /// The partner's bird in a co-op run, like `bird` (version 6)
+    pub partner_bird: Option<PixelRect>,
+}
+
+impl GameState {
+    pub fn snapshot(&self) -> GameSnapshot {
+        let bird_size = self.game_mode().params().bird_size;
+        let mut pipes = Vec::new();
+        for pipe in self.pipes.iter().filter(|p| p.active()) {
+            let column = |top: i32, bottom: i32| PixelRect {
+                x: pipe.x_pixels(),
+                y: top,
+                width: PIPE_WIDTH,
+                height: bottom - top,
+            };
+            let mut top = 0;
+            for gap_y in pipe.gap_ys() {
+                pipes.push(column(top, gap_y - self.pipe_gap / 2));
+                top = gap_y + self.pipe_gap / 2;
+            }
+            pipes.push(column(top, self.height()));
+        }
+        GameSnapshot {
+            version: SNAPSHOT_VERSION,
+            status: self.game_status(),
+            score: self.score,
+            high_score: self.high_score,
+            frame_count: self.frame_count,
+            bird: PixelRect {
+                x: self.bird_x / 1000,
+                y: self.bird_y / 1000,
+                width: bird_size,
+                height: bird_size,
+            },
+            bird_velocity: self.bird_velocity,
+            bird_x_velocity: self.bird_x_velocity,
+            pipes,
+            best_run_frames: self.best_run_frames,
+            best_run_flaps: self.best_run_flaps,
+            total_distance: self.total_distance / 1000,
+            powerup: self.active_powerup(),
+            width: self.width() as u16,
+            height: self.height() as u16,
+            partner_bird: self.coop().then_some(PixelRect {
+                x: self.bird_x / 1000,
+                y: self.coop.bird_y / 1000,
+                width: bird_size,
+                height: bird_size,
+            }),
+        }
+    }
+}
+
+// ========================================
+// Replay Verification
+// ========================================
+
+pub const REPLAY_SEED: &[u8] = b"replay";
+
+/// A run to re-simulate: everything that determines it, plus the claim
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug, Default)]
+pub struct ReplayParams {
+    /// The run's `run_seed`
+    pub seed: u64,
+    pub difficulty: Difficulty,
+    /// The run's `physics` snapshot
+    pub physics: PhysicsConfig,
+    pub inputs: InputLog,
+    /// Frames the run lasted
+    pub frames: u64,
+    pub claimed_score: u64,
+    pub scoring: ScoringMode,
+    /// The run's `wind_enabled`
+    pub wind: bool,
+    pub mode: GameMode,
+    /// The run's `rng_version`
+    pub rng_version: u8,
+    /// The run's `hitbox_inset`
+    pub hitbox_inset: u8,
+    /// The run's `grace_frames`
+    pub grace_frames: u8,
+    /// The run's `ranked_pickups`
+    pub ranked_pickups: u8,
+    /// The game's `width` and `height`
+    pub width: u16,
+    pub height: u16,
+}
+
+/// Progress of `verify_replay`, returned via return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ReplayOutcome {
+    /// Out of `max_frames`; call again to continue from the cursor
+    InProgress { frame: u64 },
+    /// Re-simulated to the end and the claimed score matches
+    Verified,
+}
+
+/// The state `start_game` would have produced for this replay
+fn replay_start_state(params: &ReplayParams) -> GameState {
+    let mut game = GameState {
+        difficulty: params.difficulty as u8,
+        scoring: params.scoring as u8,
+        physics: params.physics,
+        seed: params.seed,
+        run_seed: params.seed,
+        rng_version: params.rng_version,
+        hitbox_inset: params.hitbox_inset,
+        grace_frames: params.grace_frames,
+        pickups: PICKUP_KINDS,
+        ranked_pickups: params.ranked_pickups,
+        width: params.width,
+        height: params.height,
+        ..Default::default()
+    };
+    reset_run(&mut game);
+    game.set_wind_enabled(params.wind);
+    game.set_game_mode(params.mode);
+    game.set_game_status(GameStatus::Playing);
+    game
+}
+
+/// Re-simulate up to `max_frames` more frames of `cursor`'s replay, starting
+/// it over if `params` differ from the ones it holds
+fn advance_replay(cursor: &mut ReplayCursor, params: &ReplayParams, max_frames: u32) -> Result<ReplayOutcome> {
+    // Only tunings a GameConfig could hold, so the physics stays in range
+    params.physics.validate()?;
+    if cursor.params != *params {
+        cursor.params = *params;
+        cursor.state = replay_start_state(params);
+    }
+    let state = &mut cursor.state;
+    
+    let mut budget = max_frames;
+    while budget > 0 && state.frame_count < params.frames && state.game_status() == GameStatus::Playing {
+        state.set_pending_flap(params.inputs.flapped_after(state.frame_count));
+        step_physics(state);
+        budget -= 1;
+    }
+    
+    // Dying before the claimed length is a divergence too
+    let ended = state.frame_count == params.frames;
+    if !ended && state.game_status() == GameStatus::Playing {
+        return Ok(ReplayOutcome::InProgress { frame: state.frame_count });
+    }
+    require!(ended && state.score == params.claimed_score, FlappyError::ReplayMismatch);
+    Ok(ReplayOutcome::Verified)
+}
+
+// ========================================
+// Account Contexts
+// ========================================
+
+// Game version salt - increment to create fresh PDAs (v2 to fix stuck delegation,
+// v3 for the larger GameState with per-run physics and oscillating pipes,
+// v4 for fixed-point pipe X positions, v5 for per-player game slots, v7 for
+// 8 pipe slots, v8 for the zero-copy layout, v9 for the delegation parameters,
+// v10 for the run's end and the `reserved` space).
+// Game PDAs are [GAME_SEED, authority, [slot]].
+pub const GAME_SEED: &[u8] = b"game_v10";
+
+#[derive(Accounts)]
+#[instruction(slot: u8)]
+pub struct Initialize<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + GameState::INIT_SPACE,
+        seeds = [GAME_SEED, authority.key().as_ref(), slot.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub game: AccountLoader<'info, GameState>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    /// CHECK: the GlobalStats, which counts the new player when passed; see
+    /// `load_global_stats`
+    pub global_stats: Option<UncheckedAccount<'info>>,
+
+    /// Notes the slot in `game_slots`, created if needed
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + HighScoreRecord::INIT_SPACE,
+        seeds = [HIGH_SCORE_SEED, authority.key().as_ref()],
+        bump
+    )]
+    pub high_score_record: Account<'info, HighScoreRecord>,
+}
+
+#[derive(Accounts, Session)]
+pub struct GameAction<'info> {
+    #[account(
+        mut,
+        seeds = [GAME_SEED, game.load()?.authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump
+    )]
+    pub game: AccountLoader<'info, GameState>,
+
+    // Note: signer is NOT mut so session keys work without needing SOL
+    pub signer: Signer<'info>,
+
+    #[session(signer = signer, authority = game.load().map(|game| game.authority).unwrap_or_default())]
+    pub session_token: Option<Account<'info, SessionToken>>,
+}
+
+/// Simplified game action context for ER operations (no session token required)
+/// This works on the ER where session token accounts don't exist
+#[derive(Accounts)]
+pub struct SimpleGameAction<'info> {
+    #[account(
+        mut,
+        seeds = [GAME_SEED, game.load()?.authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump
+    )]
+    pub game: AccountLoader<'info, GameState>,
+
+    /// Must be the game's authority - verified in each instruction
+    pub signer: Signer<'info>,
+
+    /// Optional lifetime stats, updated alongside the game when passed
+    #[account(
+        mut,
+        seeds = [PROFILE_SEED, game.load()?.authority.key().as_ref()],
+        bump = profile.bump
+    )]
+    pub profile: Option<Account<'info, PlayerProfile>>,
+
+    /// Optional missions, advanced alongside the game when passed
+    #[account(
+        mut,
+        seeds = [MISSIONS_SEED, game.load()?.authority.key().as_ref()],
+        bump = missions.bump
+    )]
+    pub missions: Option<Account<'info, Missions>>,
+
+    /// CHECK: the GlobalStats, updated alongside the game when passed and
+    /// writable on this layer; see `load_global_stats`
+    pub global_stats: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: the GameConfig PDA, which may not exist yet. Checked for the
+    /// emergency pause when passed; optional so older clients keep working.
+    #[account(seeds = [CONFIG_SEED], bump)]
+    pub config: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct FlapAs<'info> {
+    #[account(
+        mut,
+        seeds = [GAME_SEED, game.load()?.authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump
+    )]
+    pub game: AccountLoader<'info, GameState>,
+
+    /// The bird's player, or their session key; checked in `flap_as`
+    pub signer: Signer<'info>,
+
+    /// The session token the player issued to `signer`, when it isn't them
+    pub session_token: Option<Account<'info, SessionToken>>,
+
+    /// CHECK: the GameConfig PDA, which may not exist yet. Checked for the
+    /// emergency pause when passed.
+    #[account(seeds = [CONFIG_SEED], bump)]
+    pub config: Option<UncheckedAccount<'info>>,
+}
+
+/// Accounts for `crank_tick` and `crank_finalize`
+#[derive(Accounts)]
+pub struct Crank<'info> {
+    #[account(
+        mut,
+        seeds = [GAME_SEED, game.load()?.authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump
+    )]
+    pub game: AccountLoader<'info, GameState>,
+
+    /// Anyone; receives the tip
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+
+    /// CHECK: the GlobalStats, updated alongside the game when passed and
+    /// writable on this layer; see `load_global_stats`
+    pub global_stats: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: the GameConfig PDA, which may not exist yet. Checked for the
+    /// emergency pause when passed.
+    #[account(seeds = [CONFIG_SEED], bump)]
+    pub config: Option<UncheckedAccount<'info>>,
+
+    /// Owes the keeper tip; passed with `keeper_ledger` for the crank to accrue one
+    #[account(mut, seeds = [KEEPER_VAULT_SEED], bump = keeper_vault.bump)]
+    pub keeper_vault: Option<Box<Account<'info, KeeperVault>>>,
+
+    /// The cranker's ledger, which the tip accrues to
+    #[account(mut, seeds = [KEEPER_LEDGER_SEED, cranker.key().as_ref()], bump = keeper_ledger.bump)]
+    pub keeper_ledger: Option<Box<Account<'info, KeeperLedger>>>,
+}
+
+/// `SimpleGameAction` for managing the run rather than playing it: the
+/// signer must be the game's authority itself, so a session key can play
+/// the run but not end, reset, pause or resume it
+#[derive(Accounts)]
+pub struct OwnerAction<'info> {
+    #[account(
+        mut,
+        seeds = [GAME_SEED, game.load()?.authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump,
+        constraint = game.load()?.authority == signer.key() @ FlappyError::NotGameAuthority
+    )]
+    pub game: AccountLoader<'info, GameState>,
+
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROFILE_SEED, game.load()?.authority.key().as_ref()],
+        bump = profile.bump
+    )]
+    pub profile: Option<Account<'info, PlayerProfile>>,
+
+    /// Optional missions, advanced alongside the game when passed
+    #[account(
+        mut,
+        seeds = [MISSIONS_SEED, game.load()?.authority.key().as_ref()],
+        bump = missions.bump
+    )]
+    pub missions: Option<Account<'info, Missions>>,
+
+    /// CHECK: the GlobalStats, updated alongside the game when passed and
+    /// writable on this layer; see `load_global_stats`
+    pub global_stats: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct StartGame<'info> {
+    #[account(
+        mut,
+        seeds = [GAME_SEED, game.load()?.authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump
+    )]
+    pub game: AccountLoader<'info, GameState>,
+
+    pub signer: Signer<'info>,
+
+    /// Optional lifetime stats, updated alongside the game when passed
+    #[account(
+        mut,
+        seeds = [PROFILE_SEED, game.load()?.authority.key().as_ref()],
+        bump = profile.bump
+    )]
+    pub profile: Option<Account<'info, PlayerProfile>>,
+
+    /// Optional missions, advanced alongside the game when passed
+    #[account(
+        mut,
+        seeds = [MISSIONS_SEED, game.load()?.authority.key().as_ref()],
+        bump = missions.bump
+    )]
+    pub missions: Option<Account<'info, Missions>>,
+
+    /// CHECK: the GlobalStats, updated alongside the game when passed and
+    /// writable on this layer; see `load_global_stats`
+    pub global_stats: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: address-checked; raw bytes are read since the sysvar is too large to deserialize
+    #[account(address = anchor_lang::solana_program::sysvar::slot_hashes::ID)]
+    pub slot_hashes: UncheckedAccount<'info>,
+
+    /// CHECK: the GameConfig PDA, which may not exist yet; required rather than
+    /// optional so a client can't skip the overrides once they do
+    #[account(seeds = [CONFIG_SEED], bump)]
+    pub config: UncheckedAccount<'info>,
+
+    /// CHECK: the player's HighScoreRecord, which may not exist yet; read for
+    /// the ranked cooldown, with the player's other games in its
+    /// `game_slots` passed as remaining accounts in slot order
+    #[account(seeds = [HIGH_SCORE_SEED, game.load()?.authority.key().as_ref()], bump)]
+    pub high_score_record: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseGame<'info> {
+    #[account(
+        mut,
+        seeds = [GAME_SEED, authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump,
+        has_one = authority,
+        close = authority
+    )]
+    pub game: AccountLoader<'info, GameState>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Keeps the game's last ranked game over, created if needed
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + HighScoreRecord::INIT_SPACE,
+        seeds = [HIGH_SCORE_SEED, authority.key().as_ref()],
+        bump
+    )]
+    pub high_score_record: Account<'info, HighScoreRecord>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TransferAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [GAME_SEED, authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump,
+        has_one = authority,
+        close = authority
+    )]
+    pub game: AccountLoader<'info, GameState>,
+
+    #[account(
+        init,
+        payer = new_authority,
+        space = 8 + GameState::INIT_SPACE,
+        seeds = [GAME_SEED, new_authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub new_game: AccountLoader<'info, GameState>,
+
+    /// Optional: pass both profiles to move the lifetime stats too
+    #[account(
+        mut,
+        seeds = [PROFILE_SEED, authority.key().as_ref()],
+        bump = profile.bump,
+        close = authority
+    )]
+    pub profile: Option<Account<'info, PlayerProfile>>,
+
+    #[account(
+        init,
+        payer = new_authority,
+        space = 8 + PlayerProfile::INIT_SPACE,
+        seeds = [PROFILE_SEED, new_authority.key().as_ref()],
+        bump
+    )]
+    pub new_profile: Option<Account<'info, PlayerProfile>>,
+
+    /// Keeps the game's last ranked game over, created if needed
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + HighScoreRecord::INIT_SPACE,
+        seeds = [HIGH_SCORE_SEED, authority.key().as_ref()],
+        bump
+    )]
+    pub high_score_record: Box<Account<'info, HighScoreRecord>>,
+
+    /// Notes the new game's slot, created if needed
+    #[account(
+        init_if_needed,
+        payer = new_authority,
+        space = 8 + HighScoreRecord::INIT_SPACE,
+        seeds = [HIGH_SCORE_SEED, new_authority.key().as_ref()],
+        bump
+    )]
+    pub new_high_score_record: Box<Account<'info, HighScoreRecord>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Pays rent for the new accounts
+    #[account(mut)]
+    pub new_authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(from_version: u8)]
+pub struct MigrateGame<'info> {
+    /// CHECK: the same authority and slot's game under `from_version`'s seed,
+    /// parsed by hand with the `legacy` layout; a delegated one fails the
+    /// owner check
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [legacy::game_seed(from_version)?, authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub old_game: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [GAME_SEED, authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump,
+        has_one = authority
+    )]
+    pub game: AccountLoader<'info, GameState>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+pub const PROFILE_SEED: &[u8] = b"profile";
+pub const MISSIONS_SEED: &[u8] = b"missions";
+pub const GLOBAL_STATS_SEED: &[u8] = b"global_stats";
+
+#[derive(Accounts)]
+pub struct InitializeProfile<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PlayerProfile::INIT_SPACE,
+        seeds = [PROFILE_SEED, authority.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, PlayerProfile>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeGlobalStats<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + GlobalStats::INIT_SPACE,
+        seeds = [GLOBAL_STATS_SEED],
+        bump
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+
+    #[account(mut, address = CONFIG_ADMIN @ FlappyError::Unauthorized)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[delegate]
+#[derive(Accounts)]
+pub struct DelegateGlobalStats<'info> {
+    pub payer: Signer<'info>,
+    #[account(address = CONFIG_ADMIN @ FlappyError::Unauthorized)]
+    pub admin: Signer<'info>,
+    /// CHECK: The GlobalStats PDA to delegate
+    #[account(mut, del, seeds = [GLOBAL_STATS_SEED], bump)]
+    pub pda: AccountInfo<'info>,
+}
+
+#[commit]
+#[derive(Accounts)]
+pub struct UndelegateGlobalStats<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(address = CONFIG_ADMIN @ FlappyError::Unauthorized)]
+    pub admin: Signer<'info>,
+    #[account(mut, seeds = [GLOBAL_STATS_SEED], bump = global_stats.bump)]
+    pub global_stats: Account<'info, GlobalStats>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeMissions<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Missions::INIT_SPACE,
+        seeds = [MISSIONS_SEED, authority.key().as_ref()],
+        bump
+    )]
+    pub missions: Account<'info, Missions>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RefreshMissions<'info> {
+    #[account(mut, seeds = [MISSIONS_SEED, authority.key().as_ref()], bump = missions.bump)]
+    pub missions: Account<'info, Missions>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimMissionReward<'info> {
+    #[account(mut, seeds = [MISSIONS_SEED, authority.key().as_ref()], bump = missions.bump)]
+    pub missions: Account<'info, Missions>,
+
+    /// Any of the player's games, whose balance the coins go to
+    #[account(
+        mut,
+        seeds = [GAME_SEED, authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump
+    )]
+    pub game: AccountLoader<'info, GameState>,
+
+    pub authority: Signer<'info>,
+}
+
+// The PDA here is an untyped AccountInfo (the `del` macro needs it raw), so the
+// cached `game.bump` can't be read and the bump is still derived. Delegation
+// runs once per session, so it is not on the hot path.
 #[delegate]
 #[derive(Accounts)]
-pub struct DelegateInput<'info> {
+#[instruction(slot: u8)]
+pub struct DelegateInput<'info> {
+    pub payer: Signer<'info>,
+    /// Owner of the game; the seeds tie the PDA to it
+    pub authority: Signer<'info>,
+    /// CHECK: The PDA to delegate
+    #[account(mut, del, seeds = [GAME_SEED, authority.key().as_ref(), slot.to_le_bytes().as_ref()], bump)]
+    pub pda: AccountInfo<'info>,
+    /// CHECK: the GameConfig PDA, which may not exist yet. Checked for the
+    /// emergency pause when passed; optional so older clients keep working.
+    #[account(seeds = [CONFIG_SEED], bump)]
+    pub config: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct SetCommitInterval<'info> {
+    #[account(
+        mut,
+        seeds = [GAME_SEED, authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump,
+        has_one = authority
+    )]
+    pub game: AccountLoader<'info, GameState>,
+
+    pub authority: Signer<'info>,
+}
+
+/// `SimpleGameAction` plus the accounts a commit needs
+#[commit]
+#[derive(Accounts)]
+pub struct TickAndCommit<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GAME_SEED, game.load()?.authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump
+    )]
+    pub game: AccountLoader<'info, GameState>,
+
+    #[account(
+        mut,
+        seeds = [PROFILE_SEED, game.load()?.authority.key().as_ref()],
+        bump = profile.bump
+    )]
+    pub profile: Option<Account<'info, PlayerProfile>>,
+
+    /// Optional missions, advanced alongside the game when passed
+    #[account(
+        mut,
+        seeds = [MISSIONS_SEED, game.load()?.authority.key().as_ref()],
+        bump = missions.bump
+    )]
+    pub missions: Option<Account<'info, Missions>>,
+
+    /// CHECK: the GlobalStats, updated alongside the game when passed and
+    /// writable on this layer; see `load_global_stats`
+    pub global_stats: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: the GameConfig PDA, which may not exist yet. Checked for the
+    /// emergency pause when passed.
+    #[account(seeds = [CONFIG_SEED], bump)]
+    pub config: Option<UncheckedAccount<'info>>,
+}
+
+// v2 for the ranked cooldown and the `reserved` space
+pub const CONFIG_SEED: &[u8] = b"config_v2";
+pub const REWARD_MINT_SEED: &[u8] = b"reward_mint";
+pub const REWARD_AUTHORITY_SEED: &[u8] = b"reward_authority";
+/// Size of an SPL Token mint account
+pub const MINT_ACCOUNT_SIZE: usize = 82;
+
+#[derive(Accounts)]
+pub struct InitializeRewardMint<'info> {
+    /// CHECK: created here as an SPL Token mint
+    #[account(mut, seeds = [REWARD_MINT_SEED], bump)]
+    pub reward_mint: UncheckedAccount<'info>,
+
+    /// CHECK: PDA that signs mints; holds no data
+    #[account(seeds = [REWARD_AUTHORITY_SEED], bump)]
+    pub reward_authority: UncheckedAccount<'info>,
+
+    #[account(mut, address = CONFIG_ADMIN @ FlappyError::Unauthorized)]
+    pub admin: Signer<'info>,
+
+    /// CHECK: address-checked
+    #[account(address = TOKEN_PROGRAM_ID)]
+    pub token_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EndGameWithReward<'info> {
+    #[account(
+        mut,
+        seeds = [GAME_SEED, game.load()?.authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump,
+        constraint = game.load()?.authority == signer.key() @ FlappyError::NotGameAuthority
+    )]
+    pub game: AccountLoader<'info, GameState>,
+
+    pub signer: Signer<'info>,
+
+    /// Optional lifetime stats, updated alongside the game when passed
+    #[account(
+        mut,
+        seeds = [PROFILE_SEED, game.load()?.authority.key().as_ref()],
+        bump = profile.bump
+    )]
+    pub profile: Option<Account<'info, PlayerProfile>>,
+
+    /// Optional missions, advanced alongside the game when passed
+    #[account(
+        mut,
+        seeds = [MISSIONS_SEED, game.load()?.authority.key().as_ref()],
+        bump = missions.bump
+    )]
+    pub missions: Option<Account<'info, Missions>>,
+
+    /// CHECK: the GlobalStats, updated alongside the game when passed and
+    /// writable on this layer; see `load_global_stats`
+    pub global_stats: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: the reward mint; the token program checks it
+    #[account(mut, seeds = [REWARD_MINT_SEED], bump)]
+    pub reward_mint: UncheckedAccount<'info>,
+
+    /// CHECK: PDA that signs mints; holds no data
+    #[account(seeds = [REWARD_AUTHORITY_SEED], bump)]
+    pub reward_authority: UncheckedAccount<'info>,
+
+    /// CHECK: must be the game authority's associated token account for the
+    /// reward mint; checked in the instruction
+    #[account(mut)]
+    pub player_token_account: UncheckedAccount<'info>,
+
+    /// CHECK: address-checked
+    #[account(address = TOKEN_PROGRAM_ID)]
+    pub token_program: UncheckedAccount<'info>,
+}
+
+pub const MILESTONE_REGISTRY_SEED: &[u8] = b"milestone_registry";
+pub const MILESTONE_MINT_SEED: &[u8] = b"milestone_mint";
+
+#[derive(Accounts)]
+pub struct InitializeMilestoneRegistry<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MilestoneRegistry::INIT_SPACE,
+        seeds = [MILESTONE_REGISTRY_SEED],
+        bump
+    )]
+    pub registry: Account<'info, MilestoneRegistry>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(milestone: u8)]
+pub struct AddMilestone<'info> {
+    #[account(mut, seeds = [MILESTONE_REGISTRY_SEED], bump = registry.bump)]
+    pub registry: Account<'info, MilestoneRegistry>,
+
+    /// CHECK: created here as an SPL Token mint
+    #[account(mut, seeds = [MILESTONE_MINT_SEED, &[milestone]], bump)]
+    pub milestone_mint: UncheckedAccount<'info>,
+
+    /// CHECK: the mint's Metaplex metadata PDA, created and checked by the
+    /// metadata program
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+
+    /// CHECK: PDA that signs mints; holds no data
+    #[account(seeds = [REWARD_AUTHORITY_SEED], bump)]
+    pub reward_authority: UncheckedAccount<'info>,
+
+    #[account(mut, address = CONFIG_ADMIN @ FlappyError::Unauthorized)]
+    pub admin: Signer<'info>,
+
+    /// CHECK: address-checked
+    #[account(address = TOKEN_PROGRAM_ID)]
+    pub token_program: UncheckedAccount<'info>,
+
+    /// CHECK: address-checked
+    #[account(address = TOKEN_METADATA_PROGRAM_ID)]
+    pub metadata_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(milestone: u8)]
+pub struct ClaimMilestone<'info> {
+    #[account(
+        mut,
+        seeds = [PROFILE_SEED, authority.key().as_ref()],
+        bump = profile.bump,
+        has_one = authority
+    )]
+    pub profile: Account<'info, PlayerProfile>,
+
+    /// Any of the player's games; its high score must reach the milestone
+    #[account(
+        seeds = [GAME_SEED, authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump,
+        has_one = authority
+    )]
+    pub game: AccountLoader<'info, GameState>,
+
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [MILESTONE_REGISTRY_SEED], bump = registry.bump)]
+    pub registry: Account<'info, MilestoneRegistry>,
+
+    /// CHECK: the milestone's mint; the token program checks it
+    #[account(mut, seeds = [MILESTONE_MINT_SEED, &[milestone]], bump)]
+    pub milestone_mint: UncheckedAccount<'info>,
+
+    /// CHECK: PDA that signs mints; holds no data
+    #[account(seeds = [REWARD_AUTHORITY_SEED], bump)]
+    pub reward_authority: UncheckedAccount<'info>,
+
+    /// CHECK: must be the authority's associated token account for the
+    /// milestone mint; checked in the instruction
+    #[account(mut)]
+    pub player_token_account: UncheckedAccount<'info>,
+
+    /// CHECK: address-checked
+    #[account(address = TOKEN_PROGRAM_ID)]
+    pub token_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + GameConfig::INIT_SPACE,
+        seeds = [CONFIG_SEED],
+        bump
+    )]
+    pub config: Account<'info, GameConfig>,
+
+    #[account(mut, address = CONFIG_ADMIN @ FlappyError::Unauthorized)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(mut, seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, GameConfig>,
+
+    #[account(address = CONFIG_ADMIN @ FlappyError::Unauthorized)]
+    pub admin: Signer<'info>,
+}
+
+pub const TREASURY_SEED: &[u8] = b"treasury";
+
+#[derive(Accounts)]
+pub struct InitializeTreasury<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Treasury::INIT_SPACE,
+        seeds = [TREASURY_SEED],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(mut, address = CONFIG_ADMIN @ FlappyError::Unauthorized)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub const KEEPER_VAULT_SEED: &[u8] = b"keeper_vault";
+pub const KEEPER_LEDGER_SEED: &[u8] = b"keeper_ledger";
+
+#[derive(Accounts)]
+pub struct InitializeKeeperVault<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + KeeperVault::INIT_SPACE,
+        seeds = [KEEPER_VAULT_SEED],
+        bump
+    )]
+    pub keeper_vault: Account<'info, KeeperVault>,
+
+    #[account(mut, address = CONFIG_ADMIN @ FlappyError::Unauthorized)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundKeeperVault<'info> {
+    #[account(mut, seeds = [KEEPER_VAULT_SEED], bump = keeper_vault.bump)]
+    pub keeper_vault: Account<'info, KeeperVault>,
+
+    #[account(mut, address = CONFIG_ADMIN @ FlappyError::Unauthorized)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenKeeperLedger<'info> {
+    #[account(
+        init,
+        payer = keeper,
+        space = 8 + KeeperLedger::INIT_SPACE,
+        seeds = [KEEPER_LEDGER_SEED, keeper.key().as_ref()],
+        bump
+    )]
+    pub keeper_ledger: Box<Account<'info, KeeperLedger>>,
+
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimKeeperTips<'info> {
+    #[account(
+        mut,
+        seeds = [KEEPER_LEDGER_SEED, keeper.key().as_ref()],
+        bump = keeper_ledger.bump,
+        has_one = keeper
+    )]
+    pub keeper_ledger: Box<Account<'info, KeeperLedger>>,
+
+    #[account(mut, seeds = [KEEPER_VAULT_SEED], bump = keeper_vault.bump)]
+    pub keeper_vault: Account<'info, KeeperVault>,
+
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetReviveFee<'info> {
+    #[account(mut, seeds = [TREASURY_SEED], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(address = CONFIG_ADMIN @ FlappyError::Unauthorized)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Revive<'info> {
+    #[account(
+        mut,
+        seeds = [GAME_SEED, game.load()?.authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump,
+        has_one = authority
+    )]
+    pub game: AccountLoader<'info, GameState>,
+
+    #[account(mut, seeds = [TREASURY_SEED], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+
+    /// The authority itself pays; a session key can't move its lamports
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReviveWithCoins<'info> {
+    #[account(
+        mut,
+        seeds = [GAME_SEED, game.load()?.authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump,
+        constraint = game.load()?.authority == signer.key() @ FlappyError::NotGameAuthority
+    )]
+    pub game: AccountLoader<'info, GameState>,
+
+    /// The authority itself, so a session key can't spend the coins
+    pub signer: Signer<'info>,
+
+    /// CHECK: the GameConfig PDA, which may not exist yet
+    #[account(seeds = [CONFIG_SEED], bump)]
+    pub config: UncheckedAccount<'info>,
+}
+
+pub const CPI_ALLOWLIST_SEED: &[u8] = b"cpi_allowlist";
+
+#[derive(Accounts)]
+pub struct InitializeCpiAllowlist<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + CpiAllowlist::INIT_SPACE,
+        seeds = [CPI_ALLOWLIST_SEED],
+        bump
+    )]
+    pub allowlist: Account<'info, CpiAllowlist>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetCpiCaller<'info> {
+    #[account(mut, seeds = [CPI_ALLOWLIST_SEED], bump = allowlist.bump)]
+    pub allowlist: Account<'info, CpiAllowlist>,
+
+    #[account(address = CONFIG_ADMIN @ FlappyError::Unauthorized)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AwardExternalBonus<'info> {
+    #[account(
+        mut,
+        seeds = [GAME_SEED, game.load()?.authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump
+    )]
+    pub game: AccountLoader<'info, GameState>,
+
+    /// The player, or their session key; checked in `award_external_bonus`
+    pub signer: Signer<'info>,
+
+    /// The session token the player issued to `signer`, when it isn't them
+    pub session_token: Option<Account<'info, SessionToken>>,
+
+    #[account(seeds = [CPI_ALLOWLIST_SEED], bump = allowlist.bump)]
+    pub allowlist: Account<'info, CpiAllowlist>,
+
+    /// CHECK: address-checked; read for the calling program
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+pub const SKIN_REGISTRY_SEED: &[u8] = b"skin_registry";
+
+#[derive(Accounts)]
+pub struct InitializeSkinRegistry<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + SkinRegistry::INIT_SPACE,
+        seeds = [SKIN_REGISTRY_SEED],
+        bump
+    )]
+    pub registry: Account<'info, SkinRegistry>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateSkinRegistry<'info> {
+    #[account(mut, seeds = [SKIN_REGISTRY_SEED], bump = registry.bump)]
+    pub registry: Account<'info, SkinRegistry>,
+
+    #[account(address = CONFIG_ADMIN @ FlappyError::Unauthorized)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetSkin<'info> {
+    #[account(
+        mut,
+        seeds = [GAME_SEED, game.load()?.authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump,
+        constraint = game.load()?.authority == signer.key() @ FlappyError::NotGameAuthority
+    )]
+    pub game: AccountLoader<'info, GameState>,
+
+    pub signer: Signer<'info>,
+
+    /// Needed for skins from `FREE_SKINS` up
+    #[account(seeds = [SKIN_REGISTRY_SEED], bump = registry.bump)]
+    pub registry: Option<Account<'info, SkinRegistry>>,
+}
+
+pub const TOURNAMENT_SEED: &[u8] = b"tournament";
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct CreateTournament<'info> {
+    #[account(
+        init,
+        payer = organizer,
+        space = 8 + Tournament::INIT_SPACE,
+        seeds = [TOURNAMENT_SEED, organizer.key().as_ref(), id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub tournament: Box<Account<'info, Tournament>>,
+
+    #[account(mut)]
+    pub organizer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct JoinTournament<'info> {
+    #[account(
+        mut,
+        seeds = [TOURNAMENT_SEED, tournament.organizer.as_ref(), tournament.id.to_le_bytes().as_ref()],
+        bump = tournament.bump
+    )]
+    pub tournament: Box<Account<'info, Tournament>>,
+
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitTournamentScore<'info> {
+    #[account(
+        mut,
+        seeds = [TOURNAMENT_SEED, tournament.organizer.as_ref(), tournament.id.to_le_bytes().as_ref()],
+        bump = tournament.bump
+    )]
+    pub tournament: Box<Account<'info, Tournament>>,
+
+    #[account(
+        seeds = [GAME_SEED, game.load()?.authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump,
+        has_one = authority
+    )]
+    pub game: AccountLoader<'info, GameState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SettleTournament<'info> {
+    #[account(
+        mut,
+        seeds = [TOURNAMENT_SEED, tournament.organizer.as_ref(), tournament.id.to_le_bytes().as_ref()],
+        bump = tournament.bump,
+        has_one = organizer
+    )]
+    pub tournament: Box<Account<'info, Tournament>>,
+
+    /// CHECK: receives leftover prize money; checked by `has_one`
+    #[account(mut)]
+    pub organizer: UncheckedAccount<'info>,
+}
+
+// v2 for wagers
+pub const MATCH_SEED: &[u8] = b"match_v2";
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct CreateMatch<'info> {
+    #[account(
+        init,
+        payer = challenger,
+        space = 8 + Match::INIT_SPACE,
+        seeds = [MATCH_SEED, challenger.key().as_ref(), id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub game_match: Account<'info, Match>,
+
+    #[account(mut)]
+    pub challenger: Signer<'info>,
+
+    /// CHECK: address-checked; raw bytes are read since the sysvar is too large to deserialize
+    #[account(address = anchor_lang::solana_program::sysvar::slot_hashes::ID)]
+    pub slot_hashes: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct StartMatchRun<'info> {
+    #[account(
+        mut,
+        seeds = [MATCH_SEED, game_match.challenger.player.as_ref(), game_match.id.to_le_bytes().as_ref()],
+        bump = game_match.bump
+    )]
+    pub game_match: Account<'info, Match>,
+
+    #[account(
+        mut,
+        seeds = [GAME_SEED, authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump,
+        has_one = authority
+    )]
+    pub game: AccountLoader<'info, GameState>,
+
+    pub authority: Signer<'info>,
+
+    /// CHECK: the GameConfig PDA, which may not exist yet. Checked for
+    /// the emergency pause.
+    #[account(seeds = [CONFIG_SEED], bump)]
+    pub config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReportMatchResult<'info> {
+    #[account(
+        mut,
+        seeds = [MATCH_SEED, game_match.challenger.player.as_ref(), game_match.id.to_le_bytes().as_ref()],
+        bump = game_match.bump
+    )]
+    pub game_match: Account<'info, Match>,
+
+    /// Each player's match game; may be left out for a player who never
+    /// started one, once the match has expired
+    pub challenger_game: Option<AccountLoader<'info, GameState>>,
+
+    pub opponent_game: Option<AccountLoader<'info, GameState>>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptWager<'info> {
+    #[account(
+        mut,
+        seeds = [MATCH_SEED, game_match.challenger.player.as_ref(), game_match.id.to_le_bytes().as_ref()],
+        bump = game_match.bump,
+        constraint = game_match.opponent.player == opponent.key() @ FlappyError::NotInMatch
+    )]
+    pub game_match: Account<'info, Match>,
+
+    #[account(mut)]
+    pub opponent: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelWager<'info> {
+    #[account(
+        mut,
+        seeds = [MATCH_SEED, challenger.key().as_ref(), game_match.id.to_le_bytes().as_ref()],
+        bump = game_match.bump,
+        close = challenger
+    )]
+    pub game_match: Account<'info, Match>,
+
+    #[account(mut)]
+    pub challenger: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimWinnings<'info> {
+    #[account(
+        mut,
+        seeds = [MATCH_SEED, game_match.challenger.player.as_ref(), game_match.id.to_le_bytes().as_ref()],
+        bump = game_match.bump
+    )]
+    pub game_match: Account<'info, Match>,
+
+    /// CHECK: paid out to; address-checked against the match
+    #[account(mut, address = game_match.challenger.player @ FlappyError::NotInMatch)]
+    pub challenger: UncheckedAccount<'info>,
+
+    /// CHECK: paid out to; address-checked against the match
+    #[account(mut, address = game_match.opponent.player @ FlappyError::NotInMatch)]
+    pub opponent: UncheckedAccount<'info>,
+}
+
+pub const CHALLENGE_SEED: &[u8] = b"challenge";
+
+#[derive(Accounts)]
+#[instruction(target: Pubkey, score_to_beat: u64, seed: u64)]
+pub struct CreateChallenge<'info> {
+    #[account(
+        init,
+        payer = challenger,
+        space = 8 + Challenge::INIT_SPACE,
+        seeds = [CHALLENGE_SEED, challenger.key().as_ref(), target.as_ref(), seed.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub challenge: Account<'info, Challenge>,
+
+    #[account(mut)]
+    pub challenger: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptChallenge<'info> {
+    #[account(
+        mut,
+        seeds = [CHALLENGE_SEED, challenge.challenger.as_ref(), challenge.target.as_ref(), challenge.seed.to_le_bytes().as_ref()],
+        bump = challenge.bump,
+        constraint = challenge.target == authority.key() @ FlappyError::NotChallengeTarget
+    )]
+    pub challenge: Account<'info, Challenge>,
+
+    #[account(
+        mut,
+        seeds = [GAME_SEED, authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump,
+        has_one = authority
+    )]
+    pub game: AccountLoader<'info, GameState>,
+
+    pub authority: Signer<'info>,
+
+    /// CHECK: the GameConfig PDA, which may not exist yet. Checked for
+    /// the emergency pause.
+    #[account(seeds = [CONFIG_SEED], bump)]
+    pub config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveChallenge<'info> {
+    #[account(
+        mut,
+        seeds = [CHALLENGE_SEED, challenge.challenger.as_ref(), challenge.target.as_ref(), challenge.seed.to_le_bytes().as_ref()],
+        bump = challenge.bump
+    )]
+    pub challenge: Account<'info, Challenge>,
+
+    /// The game the challenge was accepted in; may be left out once expired
+    pub game: Option<AccountLoader<'info, GameState>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelChallenge<'info> {
+    #[account(
+        mut,
+        seeds = [CHALLENGE_SEED, challenger.key().as_ref(), challenge.target.as_ref(), challenge.seed.to_le_bytes().as_ref()],
+        bump = challenge.bump,
+        close = challenger
+    )]
+    pub challenge: Account<'info, Challenge>,
+
+    #[account(mut)]
+    pub challenger: Signer<'info>,
+}
+
+// The boards below were bumped once more for the entries' run provenance
+pub const LEADERBOARD_SEED: &[u8] = b"leaderboard_v3";
+pub const DISTANCE_LEADERBOARD_SEED: &[u8] = b"leaderboard_distance_v2";
+
+#[derive(Accounts)]
+pub struct InitializeLeaderboard<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + std::mem::size_of::<Leaderboard>(),
+        seeds = [LEADERBOARD_SEED],
+        bump
+    )]
+    pub leaderboard: AccountLoader<'info, Leaderboard>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeDistanceLeaderboard<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + std::mem::size_of::<Leaderboard>(),
+        seeds = [DISTANCE_LEADERBOARD_SEED],
+        bump
+    )]
+    pub leaderboard: AccountLoader<'info, Leaderboard>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub const DAILY_SEED: &[u8] = b"daily_v2";
+
+#[derive(Accounts)]
+pub struct StartDailyChallenge<'info> {
+    #[account(
+        mut,
+        seeds = [GAME_SEED, game.load()?.authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump
+    )]
+    pub game: AccountLoader<'info, GameState>,
+
+    pub signer: Signer<'info>,
+
+    /// Optional lifetime stats, updated alongside the game when passed
+    #[account(
+        mut,
+        seeds = [PROFILE_SEED, game.load()?.authority.key().as_ref()],
+        bump = profile.bump
+    )]
+    pub profile: Option<Account<'info, PlayerProfile>>,
+
+    /// Optional missions, advanced alongside the game when passed
+    #[account(
+        mut,
+        seeds = [MISSIONS_SEED, game.load()?.authority.key().as_ref()],
+        bump = missions.bump
+    )]
+    pub missions: Option<Account<'info, Missions>>,
+
+    /// CHECK: the GlobalStats, updated alongside the game when passed and
+    /// writable on this layer; see `load_global_stats`
+    pub global_stats: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: the GameConfig PDA, which may not exist yet
+    #[account(seeds = [CONFIG_SEED], bump)]
+    pub config: UncheckedAccount<'info>,
+
+    /// CHECK: the player's HighScoreRecord, as in `StartGame`
+    #[account(seeds = [HIGH_SCORE_SEED, game.load()?.authority.key().as_ref()], bump)]
+    pub high_score_record: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(day: u32)]
+pub struct InitializeDailyLeaderboard<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + std::mem::size_of::<DailyLeaderboard>(),
+        seeds = [DAILY_SEED, day.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub leaderboard: AccountLoader<'info, DailyLeaderboard>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitDailyScore<'info> {
+    #[account(
+        seeds = [GAME_SEED, game.load()?.authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump,
+        has_one = authority
+    )]
+    pub game: AccountLoader<'info, GameState>,
+
+    /// The board of the day the run was started
+    #[account(mut, seeds = [DAILY_SEED, game.load()?.daily_day.to_le_bytes().as_ref()], bump)]
+    pub leaderboard: AccountLoader<'info, DailyLeaderboard>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(season: u32, week: u32)]
+pub struct SubmitScore<'info> {
+    #[account(
+        seeds = [HIGH_SCORE_SEED, authority.key().as_ref()],
+        bump = high_score_record.bump,
+        has_one = authority
+    )]
+    pub high_score_record: Account<'info, HighScoreRecord>,
+
+    /// CHECK: the game the record's high score came from; `submit_score`
+    /// checks it's back on the base layer with its run over
+    #[account(
+        seeds = [GAME_SEED, authority.key().as_ref(), high_score_record.game_slot.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub game: UncheckedAccount<'info>,
+
+    /// CHECK: the GameConfig PDA, which may not exist yet, for the submit window
+    #[account(seeds = [CONFIG_SEED], bump)]
+    pub config: UncheckedAccount<'info>,
+
+    #[account(mut, seeds = [LEADERBOARD_SEED], bump)]
+    pub leaderboard: AccountLoader<'info, Leaderboard>,
+
+    /// Takes the record's distance high score when passed
+    #[account(mut, seeds = [DISTANCE_LEADERBOARD_SEED], bump)]
+    pub distance_leaderboard: Option<AccountLoader<'info, Leaderboard>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: the SeasonConfig PDA, which may not exist yet; required rather
+    /// than optional so a client can't skip the season board once it does
+    #[account(seeds = [SEASON_CONFIG_SEED], bump)]
+    pub season_config: UncheckedAccount<'info>,
+
+    /// The current season's board, needed once seasons are configured
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + std::mem::size_of::<SeasonLeaderboard>(),
+        seeds = [SEASON_SEED, season.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub season_leaderboard: Option<AccountLoader<'info, SeasonLeaderboard>>,
+
+    /// The current week's board, created by its first submission
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + std::mem::size_of::<WeeklyLeaderboard>(),
+        seeds = [WEEKLY_SEED, week.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub weekly_leaderboard: AccountLoader<'info, WeeklyLeaderboard>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub const WEEKLY_SEED: &[u8] = b"weekly";
+pub const HIGH_SCORE_SEED: &[u8] = b"highscore";
+
+#[derive(Accounts)]
+#[instruction(slot: u8)]
+pub struct SyncHighScore<'info> {
+    #[account(
+        seeds = [GAME_SEED, authority.key().as_ref(), slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump,
+        has_one = authority
+    )]
+    pub game: AccountLoader<'info, GameState>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + HighScoreRecord::INIT_SPACE,
+        seeds = [HIGH_SCORE_SEED, authority.key().as_ref()],
+        bump
+    )]
+    pub high_score_record: Account<'info, HighScoreRecord>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CurrentWeek<'info> {
+    pub clock: Sysvar<'info, Clock>,
+}
+
+pub const SEASON_CONFIG_SEED: &[u8] = b"season_config";
+pub const SEASON_SEED: &[u8] = b"season_v2";
+
+#[derive(Accounts)]
+pub struct InitializeSeasons<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + SeasonConfig::INIT_SPACE,
+        seeds = [SEASON_CONFIG_SEED],
+        bump
+    )]
+    pub season_config: Account<'info, SeasonConfig>,
+
+    #[account(mut, address = CONFIG_ADMIN @ FlappyError::Unauthorized)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyReplay<'info> {
+    #[account(
+        init_if_needed,
+        payer = verifier,
+        space = 8 + ReplayCursor::INIT_SPACE,
+        seeds = [REPLAY_SEED, verifier.key().as_ref()],
+        bump
+    )]
+    pub cursor: Box<Account<'info, ReplayCursor>>,
+
+    #[account(mut)]
+    pub verifier: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GetBestRun<'info> {
+    #[account(
+        seeds = [GAME_SEED, game.load()?.authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump
+    )]
+    pub game: AccountLoader<'info, GameState>,
+}
+
+#[derive(Accounts)]
+pub struct GetState<'info> {
+    #[account(
+        seeds = [GAME_SEED, game.load()?.authority.key().as_ref(), game.load()?.game_slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump
+    )]
+    pub game: AccountLoader<'info, GameState>,
+}
+
+#[cfg(feature = "demo")]
+#[derive(Accounts)]
+pub struct DemoRun<'info> {
+    pub signer: Signer<'info>,
+}
+
+#[commit]
+#[derive(Accounts)]
+#[instruction(slot: u8)]
+pub struct CommitInput<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: only names the game; checked by `has_one`
+    pub authority: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [GAME_SEED, authority.key().as_ref(), slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump,
+        has_one = authority
+    )]
+    pub game: AccountLoader<'info, GameState>,
+}
+
+/// `CommitInput` for the commits that also change the game, which the
+/// authority has to sign for
+#[commit]
+#[derive(Accounts)]
+#[instruction(slot: u8)]
+pub struct UndelegateInput<'info> {
+    #[account(mut)]
     pub payer: Signer<'info>,
-    /// CHECK: The PDA to delegate
-    #[account(mut, del, seeds = [GAME_SEED, payer.key().as_ref()], bump)]
-    pub pda: AccountInfo<'info>,
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [GAME_SEED, authority.key().as_ref(), slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump,
+        has_one = authority
+    )]
+    pub game: AccountLoader<'info, GameState>,
+}
+
+/// `UndelegateInput` plus `OwnerAction`'s optional accounts, updated as the
+/// run ends
+#[commit]
+#[derive(Accounts)]
+#[instruction(slot: u8)]
+pub struct EndAndCommit<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [GAME_SEED, authority.key().as_ref(), slot.to_le_bytes().as_ref()],
+        bump = game.load()?.bump,
+        has_one = authority
+    )]
+    pub game: AccountLoader<'info, GameState>,
+
+    /// Optional lifetime stats, updated alongside the game when passed
+    #[account(
+        mut,
+        seeds = [PROFILE_SEED, authority.key().as_ref()],
+        bump = profile.bump
+    )]
+    pub profile: Option<Account<'info, PlayerProfile>>,
+
+    /// Optional missions, advanced alongside the game when passed
+    #[account(
+        mut,
+        seeds = [MISSIONS_SEED, authority.key().as_ref()],
+        bump = missions.bump
+    )]
+    pub missions: Option<Account<'info, Missions>>,
+
+    /// CHECK: the GlobalStats, updated alongside the game when passed and
+    /// writable on this layer; see `load_global_stats`
+    pub global_stats: Option<UncheckedAccount<'info>>,
+}
+
+// ========================================
+// Account Data
+// ========================================
+
+/// Zero-copy, so a tick touches only the bytes it uses instead of running
+/// the whole account through borsh. Fields are grouped by alignment so the
+/// layout has no padding; enums are stored as their discriminant and flags as
+/// 0/1, read and written through the accessors below.
+#[account(zero_copy)]
+#[derive(InitSpace, borsh::BorshSerialize, borsh::BorshDeserialize)]
+pub struct GameState {
+    /// Player who owns this game
+    pub authority: Pubkey,
+    /// Current score
+    pub score: u64,
+    /// Highest score achieved
+    pub high_score: u64,
+    /// Best score per difficulty, indexed by `Difficulty as usize`;
+    /// `high_score` stays the best across all of them
+    pub high_scores: [u64; 3],
+    /// Best PipesPassed score per mode, indexed by `GameMode as usize`
+    /// (Classic's is `high_score`); only Classic counts anywhere else
+    pub mode_high_scores: [u64; 3],
+    /// Best Distance score; `high_score`/`high_scores` are PipesPassed only
+    pub distance_high_score: u64,
+    /// Coin balance; kept across runs and spent by later features
+    pub coins: u64,
+    /// Highest score reward tokens have been minted for
+    pub rewarded_score: u64,
+    /// Frame counter for timing
+    pub frame_count: u64,
+    /// Last update timestamp
+    pub last_update: i64,
+    /// Slot of `last_update`; unlike the unix time it doesn't depend on the
+    /// ER's clock agreeing with the base layer
+    pub last_update_slot: u64,
+    /// Slot of the last physics frame, used to enforce tick cadence
+    pub last_tick_slot: u64,
+    /// Unix second that `ticks_this_second` counts frames for
+    pub tick_second: i64,
+    /// Random seed for pipe generation
+    pub seed: u64,
+    /// Seed the run started from (`seed` advances as pipes spawn)
+    pub run_seed: u64,
+    /// Pending `request_game` commitment (all zero when none)
+    pub commitment: [u8; 32],
+    /// Slot the commitment was made in
+    pub commitment_slot: u64,
+    /// Flap frames of the current run, frozen once it stops playing
+    pub input_log: InputLog,
+    /// `input_log` of the run that set `high_score`, kept across resets for
+    /// client-side ghosts
+    pub best_run_inputs: InputLog,
+    pub best_run_seed: u64,
+    /// Pipes passed in a Distance run (in a PipesPassed run that's the score)
+    pub pipes_passed: u64,
+    /// Distance scrolled this run (fixed-point, scaled by 1000)
+    pub distance: u64,
+    /// Last frame of the post-respawn immunity to pipes
+    pub invulnerable_until: u64,
+    /// `frame_count` when the last run ended
+    pub end_frame: u64,
+    /// Tuning snapshotted from GameConfig when the run started
+    pub physics: PhysicsConfig,
+    /// Parameters of the last `delegate`, kept after undelegating
+    pub delegation: DelegationConfig,
+    /// Pipe data (up to MAX_PIPES pipes on screen)
+    #[max_len(8)]
+    pub pipes: [Pipe; 8],
+    /// Bird Y position (fixed-point, scaled by 1000)
+    pub bird_y: i32,
+    /// Bird velocity (fixed-point, scaled by 1000)
+    pub bird_velocity: i32,
+    /// Bird X position (fixed-point, scaled by 1000); BIRD_X unless
+    /// `horizontal_control`
+    pub bird_x: i32,
+    /// Bird X velocity (fixed-point, scaled by 1000)
+    pub bird_x_velocity: i32,
+    /// Physics of the current frame: the `difficulty` preset, with pipe speed
+    /// and gap ramped by score and gravity scaled by `wind`
+    pub gravity: i32,
+    pub pipe_gap: i32,
+    pub pipe_speed: i32,
+    /// X position for next pipe spawn
+    pub next_pipe_spawn_x: i32,
+    pub best_run_frames: u32,
+    /// Unix day of a daily challenge run
+    pub daily_day: u32,
+    /// Frames simulated during `tick_second`
+    pub ticks_this_second: u16,
+    /// Flaps applied since the last pipe was passed
+    pub flaps_since_last_pass: u16,
+    /// Frames between `tick_and_maybe_commit` commits, 0 for none
+    pub commit_interval: u16,
+    /// `GameStatus`
+    pub game_status: u8,
+    /// Canonical PDA bump, cached at initialize so gameplay contexts skip re-deriving it
+    pub bump: u8,
+    /// Which of the player's game slots this is, part of the PDA seeds
+    pub game_slot: u8,
+    /// `Difficulty` of the current (or last) run
+    pub difficulty: u8,
+    /// `ScoringMode`: what `score` counts this run; fixed at start
+    pub scoring: u8,
+    /// `GameMode` of the current (or last) run; kept through resets as the
+    /// default for the next `start_game`
+    pub game_mode: u8,
+    /// Flap input waiting to be applied on the next frame
+    pub pending_flap: u8,
+    /// The `Powerup` held, by `Powerup::repr`, 0 for none; read through
+    /// `active_powerup`. Was a shield flag, whose 1 still reads as a shield.
+    pub powerup: u8,
+    /// The run was started by `start_daily_challenge`, on unix day `daily_day`
+    pub daily: u8,
+    /// Experimental: `move_horizontal` may move the bird this run; fixed at start
+    pub horizontal_control: u8,
+    /// Gusts from `wind_at` apply this run; fixed at start. Off for daily
+    /// challenges and matches.
+    pub wind_enabled: u8,
+    /// Current gust, in percent of gravity (0 when calm), already included in
+    /// `gravity`
+    pub wind: i8,
+    /// Practice run: collisions bounce instead of ending it, and nothing is
+    /// recorded. Set only by `start_game`, cleared on reset.
+    pub practice: u8,
+    /// Lives left, counting the current one
+    pub lives: u8,
+    /// The run started with more than one life, so it isn't ranked
+    pub extra_lives: u8,
+    /// The run was continued with `revive`, so it isn't ranked
+    pub revived: u8,
+    /// Bonus the last pipe scored for being passed in a glide, 0 after a flap
+    pub combo: u8,
+    /// Set by `delegate` and cleared by `undelegate`; part of the committed
+    /// state, so both layers agree on it after a commit
+    pub is_delegated: u8,
+    /// `DeathCause` of the last run that ended, plus one; 0 before any has
+    pub end_cause: u8,
+    /// `tick`/`tick_multiple` emit `FrameState`; set by `set_spectator_mode`
+    pub spectator_mode: u8,
+    /// Per-frame instructions log a `frame_log_record`; set by `set_verbose_logging`
+    pub verbose_logging: u8,
+    /// Active pipes plus one, kept up to date by the physics so its pipe
+    /// loops can stop early; 0 has the next frame recount, as after a reset
+    pub active_pipe_count: u8,
+    /// First inactive slot in `pipes[..pipe_slots()]`, or `pipe_slots()` if
+    /// none; valid while `active_pipe_count` is nonzero
+    pub free_pipe_slot: u8,
+    /// Cosmetic skin the client draws the bird with; set by `set_skin` and
+    /// kept across runs
+    pub bird_skin: u8,
+    /// Frames this run's flaps must be apart if it's ranked or daily,
+    /// snapshotted from the GameConfig at the start
+    pub min_flap_interval: u8,
+    /// Generator this run's pipes are drawn with, one of the `rng::RNG_*`
+    /// versions; set at the start, so replays reproduce older runs
+    pub rng_version: u8,
+    /// Unix time the last ranked run ended, for the ranked cooldown; 0 before any has
+    pub last_game_over_at: i64,
+    /// When (`last_update` as the run ended), at which frame and from which
+    /// `run_seed` `high_score` was set, written along with it
+    pub high_score_timestamp: i64,
+    pub high_score_frame: u64,
+    pub high_score_seed: u64,
+    /// Flaps in the current run, counted by `flap`
+    pub flaps_this_run: u32,
+    /// `flaps_this_run` of the run that set `high_score`; with
+    /// `best_run_frames` this gives the run's pace
+    pub best_run_flaps: u32,
+    /// Frame this run's last flap applied on (queued after one frame fewer);
+    /// 0 before its first
+    pub last_flap_frame: u64,
+    /// Pixels the collision box sits in from each side of the bird,
+    /// snapshotted from the GameConfig at the start
+    pub hitbox_inset: u8,
+    /// Frames at the start of this run pipes can't kill in, snapshotted from
+    /// the GameConfig at the start; revives and respawns don't get it again
+    pub grace_frames: u8,
+    /// Ticks left in the countdown while `Countdown`
+    pub countdown_frames_remaining: u8,
+    /// Whether a flap during this run's countdown ends it, snapshotted from
+    /// the GameConfig at the start
+    pub flap_skips_countdown: u8,
+    /// Bird's Y in pixels when the last run ended
+    pub end_bird_y: i32,
+    /// Index of the pipe that ended the last run plus one, 0 if none did,
+    /// and the `PipeHalf` it was hit on
+    pub end_pipe: u8,
+    pub end_pipe_half: u8,
+    /// Score this run must reach to extend the win streak, snapshotted from
+    /// the GameConfig at the start
+    pub streak_threshold: u16,
+    /// Set once this run's end is recorded on the player's profile, so ending
+    /// it again doesn't count it twice
+    pub run_end_recorded: u8,
+    /// Which SEED_SOURCE_* this run's seed came from, for verifying it
+    pub seed_source: u8,
+    pub padding: [u8; 2],
+    /// Distance scrolled while playing over every run, practice included
+    /// (fixed-point, scaled by 1000); survives resets
+    pub total_distance: u64,
+    /// Timed pickups this run can get, bit `1 << kind.repr()` per `Powerup`:
+    /// `ranked_pickups` in ranked and daily runs and `pickups` otherwise,
+    /// both set at the start
+    pub pickups: u8,
+    pub ranked_pickups: u8,
+    /// Frames left of a timed `powerup`, counting the frame it runs out in;
+    /// 0 for a shield, which lasts until it's used
+    pub powerup_frames: u8,
+    pub padding2: u8,
+    /// Playfield size in pixels, set by `initialize` and kept across runs;
+    /// 0 for GAME_WIDTH and GAME_HEIGHT, as in games from before it was
+    /// stored
+    pub width: u16,
+    pub height: u16,
+    /// `state_digest` as of the last commit, which `commit` skips while it
+    /// still matches; zero until the first
+    pub committed_digest: [u8; 32],
+    /// Whether the run that set `high_score` / `distance_high_score` had
+    /// extra lives or a revive, which keeps it out of the `HighScoreRecord`
+    pub high_score_assisted: u8,
+    pub distance_high_score_assisted: u8,
+    /// Set by `set_crank_tip(false)`: `crank_tick` doesn't tip from this game
+    pub crank_tip_opt_out: u8,
+    pub padding3: u8,
+    /// The second bird and its player in a co-op run; all zero, so inactive,
+    /// in a solo one and in accounts from before co-op
+    pub coop: CoopState,
+    /// Points allowlisted programs awarded this run with
+    /// `award_external_bonus`, kept out of `score` so they never reach a
+    /// high score, leaderboard or reward
+    pub bonus_score: u64,
+    /// Zeroed space that later fields are taken from, so existing accounts
+    /// read them as zero instead of needing a new seed
+    pub reserved: [u8; 344],
+}
+
+// Not derived: a zeroed difficulty byte reads as Easy, not the Normal default
+impl Default for GameState {
+    fn default() -> Self {
+        GameState {
+            difficulty: Difficulty::default() as u8,
+            physics: PhysicsConfig::default(),
+            ..bytemuck::Zeroable::zeroed()
+        }
+    }
+}
+
+// Typed views of the fields stored as plain bytes
+impl GameState {
+    pub fn game_status(&self) -> GameStatus {
+        GameStatus::from_repr(self.game_status)
+    }
+
+    pub fn set_game_status(&mut self, status: GameStatus) {
+        self.game_status = status as u8;
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        Difficulty::from_repr(self.difficulty)
+    }
+
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.difficulty = difficulty as u8;
+    }
+
+    pub fn scoring(&self) -> ScoringMode {
+        ScoringMode::from_repr(self.scoring)
+    }
+
+    pub fn set_scoring(&mut self, scoring: ScoringMode) {
+        self.scoring = scoring as u8;
+    }
+
+    pub fn game_mode(&self) -> GameMode {
+        GameMode::from_repr(self.game_mode)
+    }
+
+    pub fn set_game_mode(&mut self, mode: GameMode) {
+        self.game_mode = mode as u8;
+    }
+
+    pub fn pending_flap(&self) -> bool {
+        self.pending_flap != 0
+    }
+
+    pub fn set_pending_flap(&mut self, pending: bool) {
+        self.pending_flap = pending as u8;
+    }
+
+    pub fn active_powerup(&self) -> Option<ActivePowerup> {
+        Powerup::from_repr(self.powerup).map(|kind| ActivePowerup {
+            kind,
+            frames_remaining: self.powerup_frames,
+        })
+    }
+
+    pub fn set_active_powerup(&mut self, powerup: Option<ActivePowerup>) {
+        self.powerup = powerup.map_or(0, |active| active.kind.repr());
+        self.powerup_frames = powerup.map_or(0, |active| active.frames_remaining);
+    }
+
+    /// Hold `kind` for its full duration, replacing any power-up held
+    fn pick_up(&mut self, kind: Powerup) {
+        self.set_active_powerup(Some(ActivePowerup { kind, frames_remaining: kind.frames() }));
+    }
+
+    /// Count a frame off a timed power-up, dropping it once it runs out
+    fn tick_powerup(&mut self) {
+        if self.powerup_frames > 0 {
+            self.powerup_frames -= 1;
+            if self.powerup_frames == 0 {
+                self.set_active_powerup(None);
+            }
+        }
+    }
+
+    pub fn has_shield(&self) -> bool {
+        self.active_powerup().is_some_and(|active| active.kind == Powerup::Shield)
+    }
+
+    /// Use up the shield, if one is held
+    fn take_shield(&mut self) -> bool {
+        let shielded = self.has_shield();
+        if shielded {
+            self.set_active_powerup(None);
+        }
+        shielded
+    }
+
+    /// Share of a frame the world moves this frame: slowed in slow motion
+    pub fn time_scale(&self) -> u16 {
+        match self.active_powerup() {
+            Some(ActivePowerup { kind: Powerup::SlowMotion, .. }) => SLOW_TIME_SCALE,
+            _ => TIME_SCALE_NORMAL,
+        }
+    }
+
+    /// Whether timed pickup `kind` can spawn this run
+    pub fn pickup_allowed(&self, kind: Powerup) -> bool {
+        let kinds = if self.ranked() || self.daily() { self.ranked_pickups } else { self.pickups };
+        kinds & 1 << kind.repr() != 0
+    }
+
+    pub fn daily(&self) -> bool {
+        self.daily != 0
+    }
+
+    pub fn set_daily(&mut self, daily: bool) {
+        self.daily = daily as u8;
+    }
+
+    pub fn horizontal_control(&self) -> bool {
+        self.horizontal_control != 0
+    }
+
+    pub fn set_horizontal_control(&mut self, enabled: bool) {
+        self.horizontal_control = enabled as u8;
+    }
+
+    pub fn wind_enabled(&self) -> bool {
+        self.wind_enabled != 0
+    }
+
+    pub fn set_wind_enabled(&mut self, enabled: bool) {
+        self.wind_enabled = enabled as u8;
+    }
+
+    pub fn practice(&self) -> bool {
+        self.practice != 0
+    }
+
+    pub fn flap_skips_countdown(&self) -> bool {
+        self.flap_skips_countdown != 0
+    }
+
+    pub fn set_flap_skips_countdown(&mut self, skips: bool) {
+        self.flap_skips_countdown = skips as u8;
+    }
+
+    pub fn set_practice(&mut self, practice: bool) {
+        self.practice = practice as u8;
+    }
+
+    pub fn extra_lives(&self) -> bool {
+        self.extra_lives != 0
+    }
+
+    pub fn set_extra_lives(&mut self, extra_lives: bool) {
+        self.extra_lives = extra_lives as u8;
+    }
+
+    pub fn revived(&self) -> bool {
+        self.revived != 0
+    }
+
+    pub fn set_revived(&mut self, revived: bool) {
+        self.revived = revived as u8;
+    }
+
+    pub fn high_score_assisted(&self) -> bool {
+        self.high_score_assisted != 0
+    }
+
+    pub fn set_high_score_assisted(&mut self, assisted: bool) {
+        self.high_score_assisted = assisted as u8;
+    }
+
+    pub fn distance_high_score_assisted(&self) -> bool {
+        self.distance_high_score_assisted != 0
+    }
+
+    pub fn set_distance_high_score_assisted(&mut self, assisted: bool) {
+        self.distance_high_score_assisted = assisted as u8;
+    }
+
+    pub fn end_cause(&self) -> Option<DeathCause> {
+        DeathCause::from_repr(self.end_cause.checked_sub(1)?)
+    }
+
+    pub fn set_end_cause(&mut self, cause: DeathCause) {
+        self.end_cause = cause as u8 + 1;
+    }
+
+    pub fn end_pipe(&self) -> Option<PipeHit> {
+        Some(PipeHit {
+            index: self.end_pipe.checked_sub(1)?,
+            half: PipeHalf::from_repr(self.end_pipe_half),
+        })
+    }
+
+    pub fn set_end_pipe(&mut self, hit: PipeHit) {
+        self.end_pipe = hit.index + 1;
+        self.end_pipe_half = hit.half as u8;
+    }
+
+    /// `GameOver` for the run that just ended
+    fn game_over(&self) -> GameOver {
+        GameOver {
+            player: self.authority,
+            score: self.score,
+            high_score: self.mode_high_score(),
+            frame: self.end_frame,
+            cause: self.end_cause().unwrap_or(DeathCause::Manual),
+            bird_y: self.end_bird_y,
+            pipe: self.end_pipe(),
+        }
+    }
+
+    pub fn is_delegated(&self) -> bool {
+        self.is_delegated != 0
+    }
+
+    pub fn set_is_delegated(&mut self, delegated: bool) {
+        self.is_delegated = delegated as u8;
+    }
+
+    pub fn spectator_mode(&self) -> bool {
+        self.spectator_mode != 0
+    }
+
+    /// Whether the run is co-op, started by `start_coop`
+    pub fn coop(&self) -> bool {
+        self.coop.active != 0
+    }
+
+    /// Make the run just begun co-op, `partner`'s bird starting where the
+    /// first does
+    fn begin_coop(&mut self, partner: Pubkey) {
+        self.coop = CoopState {
+            partner,
+            bird_y: self.bird_y,
+            bird_velocity: self.bird_velocity,
+            active: 1,
+            alive: [1, 1],
+            pending_flap: 0,
+        };
+    }
+
+    /// Who plays co-op bird `index`: the authority the first, the partner
+    /// the second
+    pub fn coop_player(&self, index: u8) -> Result<Pubkey> {
+        require!(self.coop(), FlappyError::NotCoopGame);
+        match index {
+            0 => Ok(self.authority),
+            1 => Ok(self.coop.partner),
+            _ => err!(FlappyError::InvalidPlayerIndex),
+        }
+    }
+
+    pub fn crank_tip_opt_out(&self) -> bool {
+        self.crank_tip_opt_out != 0
+    }
+
+    pub fn set_crank_tip_opt_out(&mut self, opt_out: bool) {
+        self.crank_tip_opt_out = opt_out as u8;
+    }
+
+    pub fn set_spectator_mode(&mut self, enabled: bool) {
+        self.spectator_mode = enabled as u8;
+    }
+
+    pub fn verbose_logging(&self) -> bool {
+        self.verbose_logging != 0
+    }
+
+    pub fn set_verbose_logging(&mut self, enabled: bool) {
+        self.verbose_logging = enabled as u8;
+    }
+}
+
+/// Bit f set: a flap was queued after f frames, i.e. applied on frame f + 1
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct InputLog {
+    pub bits: [u64; INPUT_LOG_WORDS],
+}
+
+// Arrays this long have no derived Default
+impl Default for InputLog {
+    fn default() -> Self {
+        InputLog { bits: [0; INPUT_LOG_WORDS] }
+    }
+}
+
+impl InputLog {
+    pub fn flapped_after(&self, frame: u64) -> bool {
+        frame < INPUT_LOG_FRAMES && self.bits[(frame / 64) as usize] & (1 << (frame % 64)) != 0
+    }
+
+    /// Number of frames with a flap
+    pub fn flaps(&self) -> u32 {
+        self.bits.iter().map(|word| word.count_ones()).sum()
+    }
+}
+
+/// Scratch state for a `verify_replay` spanning several transactions
+#[account]
+#[derive(InitSpace, Default)]
+pub struct ReplayCursor {
+    /// The replay in progress
+    pub params: ReplayParams,
+    /// Simulation state after `state.frame_count` frames
+    pub state: GameState,
+    pub bump: u8,
+}
+
+/// Singleton physics overrides, tunable without a redeploy
+#[account]
+#[derive(InitSpace)]
+pub struct GameConfig {
+    pub physics: PhysicsConfig,
+    pub bump: u8,
+    /// Seconds a ranked run can't start after the last ranked run ended; 0 for none
+    pub ranked_cooldown_secs: u32,
+    /// Frames a ranked or daily run's flaps must be apart; 0 for the default
+    /// MIN_FLAP_INTERVAL_FRAMES
+    pub min_flap_interval_frames: u8,
+    /// Hitbox inset in pixels plus one; 0 for the default HITBOX_INSET
+    pub hitbox_inset_plus_one: u8,
+    /// Grace period in frames plus one; 0 for the default GRACE_FRAMES
+    pub grace_frames_plus_one: u8,
+    /// Ticks a run counts down for after it starts, before the bird moves;
+    /// 0 for none
+    pub countdown_frames: u8,
+    /// Whether a flap during the countdown ends it, or is rejected
+    pub flap_skips_countdown: bool,
+    /// Score a run must reach to extend the win streak; 0 for the default
+    /// STREAK_THRESHOLD
+    pub streak_threshold: u16,
+    /// Timed pickups ranked and daily runs can get, bit `1 << kind.repr()`
+    /// per `Powerup`
+    pub ranked_pickups: u8,
+    /// Coins `revive_with_coins` costs; 0 for the default REVIVE_COIN_COST
+    pub revive_coin_cost: u64,
+    /// Emergency switch: while set, runs can't start, be played or be
+    /// delegated; see `require_not_paused`
+    pub paused: bool,
+    /// Seconds after a run ends that `submit_score` takes it; 0 for the
+    /// default SUBMIT_WINDOW_SECS
+    pub submit_window_secs: u32,
+    /// Zeroed space that later fields are taken from, as in GameState
+    pub reserved: [u8; 43],
+}
+
+impl GameConfig {
+    fn hitbox_inset(&self) -> u8 {
+        match self.hitbox_inset_plus_one {
+            0 => HITBOX_INSET,
+            n => n - 1,
+        }
+    }
+
+    fn grace_frames(&self) -> u8 {
+        match self.grace_frames_plus_one {
+            0 => GRACE_FRAMES,
+            n => n - 1,
+        }
+    }
+
+    fn min_flap_interval(&self) -> u8 {
+        match self.min_flap_interval_frames {
+            0 => MIN_FLAP_INTERVAL_FRAMES,
+            frames => frames,
+        }
+    }
+
+    fn streak_threshold(&self) -> u16 {
+        match self.streak_threshold {
+            0 => STREAK_THRESHOLD,
+            score => score,
+        }
+    }
+
+    fn revive_coin_cost(&self) -> u64 {
+        match self.revive_coin_cost {
+            0 => REVIVE_COIN_COST,
+            coins => coins,
+        }
+    }
+
+    fn submit_window(&self) -> u32 {
+        match self.submit_window_secs {
+            0 => SUBMIT_WINDOW_SECS,
+            secs => secs,
+        }
+    }
+}
+
+/// Collects revive fees
+#[account]
+#[derive(InitSpace)]
+pub struct Treasury {
+    /// Lamports `revive` charges
+    pub revive_fee: u64,
+    pub bump: u8,
+}
+
+/// Pays keepers the tips their cranks accrued
+#[account]
+#[derive(InitSpace)]
+pub struct KeeperVault {
+    /// Accrued to ledgers and not yet claimed
+    pub owed: u64,
+    pub bump: u8,
+}
+
+/// What one game earned a keeper on one unix day
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct GameAccrual {
+    pub game: Pubkey,
+    pub day: u32,
+    pub amount: u64,
+}
+
+/// A keeper's tips, per KEEPER_LEDGER_SEED and keeper
+#[account]
+#[derive(InitSpace)]
+pub struct KeeperLedger {
+    pub keeper: Pubkey,
+    /// Owed by the vault, paid out by `claim_keeper_tips`
+    pub accrued: u64,
+    /// Paid out so far
+    pub claimed: u64,
+    /// Today's accruals per game, for the daily cap; see `keeper::accrue`
+    pub games: [GameAccrual; keeper::KEEPER_LEDGER_GAMES],
+    pub bump: u8,
+}
+
+pub const MAX_CPI_CALLERS: usize = 8;
+
+/// Programs trusted to CPI into `award_external_bonus`
+#[account]
+#[derive(InitSpace, Default)]
+pub struct CpiAllowlist {
+    /// Number of filled entries
+    pub count: u8,
+    pub programs: [Pubkey; MAX_CPI_CALLERS],
+    pub bump: u8,
+}
+
+/// Skins 0 to `FREE_SKINS - 1` need no NFT
+pub const FREE_SKINS: u8 = 3;
+pub const MAX_SKIN_MINTS: usize = 32;
+/// Size of an SPL token account without extensions
+const TOKEN_ACCOUNT_LEN: usize = 165;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct SkinMint {
+    pub skin_id: u8,
+    pub mint: Pubkey,
+}
+
+/// NFT mints that unlock the paid skins, admin maintained
+#[account]
+#[derive(InitSpace, Default)]
+pub struct SkinRegistry {
+    /// Number of filled entries
+    pub count: u8,
+    pub skins: [SkinMint; MAX_SKIN_MINTS],
+    pub bump: u8,
+}
+
+/// Balance knobs (fixed-point where the constants are); Normal difficulty
+/// plays exactly these, Easy and Hard are offsets from them
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct PhysicsConfig {
+    pub gravity: i32,
+    pub jump_velocity: i32,
+    pub pipe_speed: i32,
+    pub pipe_gap: i32,
+    pub pipe_spawn_distance: i32,
+    /// Pixels from a gap edge that count as a near miss
+    pub near_miss_threshold: i32,
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        PhysicsConfig {
+            gravity: GRAVITY,
+            jump_velocity: JUMP_VELOCITY,
+            pipe_speed: PIPE_SPEED,
+            pipe_gap: PIPE_GAP,
+            pipe_spawn_distance: PIPE_SPAWN_DISTANCE,
+            near_miss_threshold: NEAR_MISS_THRESHOLD,
+        }
+    }
+}
+
+impl PhysicsConfig {
+    /// Reject tunings the game can't be played with, including after the
+    /// Easy/Hard offsets
+    fn validate(&self) -> Result<()> {
+        require!(
+            self.gravity > 100
+                && self.gravity < MAX_VELOCITY
+                && (-MAX_VELOCITY..0).contains(&self.jump_velocity)
+                && self.pipe_speed > 1000
+                && self.pipe_speed <= RAMP_SPEED_MAX
+                && self.pipe_gap >= RAMP_GAP_MIN
+                && self.pipe_gap <= GAME_HEIGHT - 2 * PIPE_HEIGHT_MIN - 20
+                && self.pipe_spawn_distance > PIPE_WIDTH + BIRD_SIZE
+                && self.pipe_spawn_distance < GAME_WIDTH
+                && pipe_slots(GAME_WIDTH, self.pipe_spawn_distance) <= MAX_PIPES
+                && (0..=(RAMP_GAP_MIN - BIRD_SIZE) / 2).contains(&self.near_miss_threshold),
+            FlappyError::InvalidConfig
+        );
+        Ok(())
+    }
+}
+
+/// `DelegateParams` as applied, stored on the game
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug, Default)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct DelegationConfig {
+    /// `Pubkey::default()` for any validator
+    pub validator: Pubkey,
+    /// Seconds, 0 for none
+    pub time_limit: u64,
+    pub commit_frequency_ms: u32,
+    pub padding: [u8; 4],
+}
+
+impl DelegationConfig {
+    pub fn validator(&self) -> Option<Pubkey> {
+        (self.validator != Pubkey::default()).then_some(self.validator)
+    }
+
+    fn config(&self) -> DelegateConfig {
+        DelegateConfig { commit_frequency_ms: self.commit_frequency_ms, validator: self.validator() }
+    }
+}
+
+/// Lifetime statistics for a player; survives `reset_game`
+#[account]
+#[derive(InitSpace, Default)]
+pub struct PlayerProfile {
+    pub authority: Pubkey,
+    /// Runs started
+    pub games_played: u64,
+    pub pipes_passed: u64,
+    pub flaps: u64,
+    /// Physics frames simulated while playing
+    pub frames_survived: u64,
+    pub best_score: u64,
+    /// When `best_score` was set
+    pub best_score_timestamp: i64,
+    pub bump: u8,
+    pub coins_collected: u64,
+    /// Bit `id` set once achievement `id` (the ACHIEVEMENT_* constants) is earned
+    pub achievements: u64,
+    /// Bit `id` set once milestone `id`'s NFT is claimed
+    pub milestones_claimed: u64,
+    /// Runs in a row, up to the last one, that reached their streak
+    /// threshold; practice runs are skipped
+    pub current_streak: u32,
+    pub best_streak: u32,
+    /// Distance scrolled while playing (fixed-point, scaled by 1000)
+    pub total_distance: u64,
+    /// Earned at the end of every run but practice; see `run_xp`
+    pub xp: u64,
+    /// `level_for_xp(xp)`
+    pub level: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default, InitSpace)]
+pub enum MissionKind {
+    /// Pass `target` pipes
+    #[default]
+    PassNPipes,
+    /// Collect `target` coins
+    CollectNCoins,
+    /// Start `target` runs
+    PlayNGames,
+    /// End a run with a score of at least `target`
+    ScoreAtLeastX,
+}
+
+/// One mission: `progress` towards `target` until `expires_at`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default, InitSpace)]
+pub struct Mission {
+    pub kind: MissionKind,
+    pub target: u32,
+    /// Counts up to `target`; for ScoreAtLeastX, the best score so far
+    pub progress: u32,
+    /// Coins `claim_mission_reward` credits once it's complete
+    pub reward_coins: u32,
+    /// When it stops counting and `refresh_missions` can replace it
+    pub expires_at: i64,
+    pub claimed: bool,
+}
+
+/// Totals over every player, for a stats page; practice runs aren't counted
+#[account]
+#[derive(InitSpace, Default)]
+pub struct GlobalStats {
+    pub total_games_started: u64,
+    pub total_games_finished: u64,
+    pub total_pipes_passed: u64,
+    pub total_flaps: u64,
+    /// Players counted by `initialize` creating their HighScoreRecord, once
+    /// per wallet; a wallet that only ever received a game by
+    /// `transfer_authority` isn't counted
+    pub unique_players: u64,
+    /// Set by `delegate_global_stats` and cleared by
+    /// `undelegate_global_stats`: only games on the same layer update it
+    pub is_delegated: bool,
+    pub bump: u8,
+}
+
+/// A player's high score, outside the game so it stays on the base layer
+/// while the game is delegated. Only unassisted runs land here, from
+/// whichever of the player's slots set the best; `submit_score` reads it.
+#[account]
+#[derive(InitSpace, Default)]
+pub struct HighScoreRecord {
+    pub authority: Pubkey,
+    pub high_score: u64,
+    /// The game's `high_score_*` provenance and best run stats for it; a
+    /// zero `high_score_timestamp` means nothing was synced yet
+    pub high_score_timestamp: i64,
+    pub high_score_frame: u64,
+    pub high_score_seed: u64,
+    pub best_run_frames: u32,
+    pub best_run_flaps: u32,
+    /// Slot of the game the high score came from
+    pub game_slot: u8,
+    pub distance_high_score: u64,
+    pub bump: u8,
+    /// Bit `slot` set while the player has a game in that slot, so a ranked
+    /// start can be made to see them all; see `last_ranked_game_over`
+    pub game_slots: u8,
+    /// Latest `last_game_over_at` of the player's closed and transferred
+    /// games, so a fresh game in their place doesn't reset the ranked cooldown
+    pub last_game_over_at: i64,
+}
+
+/// A player's missions: DAILY_MISSION_SLOTS daily ones, then weekly ones;
+/// practice runs don't count towards them
+#[account]
+#[derive(InitSpace, Default)]
+pub struct Missions {
+    pub authority: Pubkey,
+    pub missions: [Mission; MISSION_SLOTS],
+    pub bump: u8,
+}
+
+pub const MAX_MILESTONES: usize = 16;
+
+/// High scores that earn a commemorative NFT, admin maintained; the NFT of
+/// milestone `id` is minted from its MILESTONE_MINT_SEED PDA
+#[account]
+#[derive(InitSpace, Default)]
+pub struct MilestoneRegistry {
+    /// Score milestone `id` is reached at; 0 until `add_milestone`
+    pub thresholds: [u64; MAX_MILESTONES],
+    pub bump: u8,
+}
+
+pub const TOURNAMENT_MAX_ENTRANTS: usize = 32;
+/// Percent of the pool for 1st, 2nd and 3rd
+pub const TOURNAMENT_PRIZE_SHARES: [u64; 3] = [50, 30, 20];
+
+/// A timed tournament; entry fees accumulate in `prize_pool`, held as
+/// lamports on this account on top of its rent
+#[account]
+#[derive(InitSpace, Default)]
+pub struct Tournament {
+    pub organizer: Pubkey,
+    pub id: u64,
+    /// Lamports to join
+    pub entry_fee: u64,
+    /// Window a submitted run must have last updated in (unix seconds)
+    pub start_time: i64,
+    pub end_time: i64,
+    /// The one difficulty submitted runs are played on, so scores compare
+    pub difficulty: Difficulty,
+    pub prize_pool: u64,
+    pub settled: bool,
+    /// Improving submissions so far; orders ties
+    pub submissions: u64,
+    pub entrant_count: u32,
+    /// Entrants in join order
+    pub entrants: [TournamentEntry; TOURNAMENT_MAX_ENTRANTS],
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Default, Debug)]
+pub struct TournamentEntry {
+    pub player: Pubkey,
+    /// Best submitted score
+    pub score: u64,
+    /// `submissions` count that set `score`; 0 until the player submits
+    pub submission: u64,
+}
+
+/// How long after creation a match can still be started, and after which it
+/// can be settled without both results
+pub const MATCH_TIMEOUT_SECONDS: i64 = 24 * 60 * 60;
+/// How long the opponent has to accept a wager before the challenger can
+/// cancel it
+pub const WAGER_ACCEPT_SECONDS: i64 = 24 * 60 * 60;
+
+/// A two-player race on one stored `seed`; every match run's `run_seed` is
+/// this seed, so both players get the same pipe layout
+#[account]
+#[derive(InitSpace, Default)]
+pub struct Match {
+    pub id: u64,
+    pub seed: u64,
+    pub difficulty: Difficulty,
+    pub created_at: i64,
+    pub challenger: MatchSide,
+    pub opponent: MatchSide,
+    /// Set once settled; None for a draw
+    pub winner: Option<Pubkey>,
+    pub settled: bool,
+    pub bump: u8,
+    /// Lamports each player puts on the match; 0 for no wager
+    pub stake: u64,
+    /// Stakes held by the match until `claim_winnings` pays them out
+    pub pot: u64,
+    /// When the opponent accepted the wager; 0 until then
+    pub accepted_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Default, Debug)]
+pub struct MatchSide {
+    pub player: Pubkey,
+    /// The game the match run was started in; default until then
+    pub game: Pubkey,
+    pub finished: bool,
+    pub score: u64,
+    pub flaps: u32,
+}
+
+/// A dare from `challenger` to `target`: beat `score_to_beat` on run seed
+/// `seed` before `expiry`
+#[account]
+#[derive(InitSpace, Default)]
+pub struct Challenge {
+    pub challenger: Pubkey,
+    pub target: Pubkey,
+    pub score_to_beat: u64,
+    pub seed: u64,
+    /// Unix time the challenge run must have ended by
+    pub expiry: i64,
+    /// The target's game the run was started in; default until accepted
+    pub game: Pubkey,
+    pub accepted: bool,
+    pub resolved: bool,
+    /// Set on resolving: the run's score, 0 if it never finished, and
+    /// whether it beat `score_to_beat`
+    pub score: u64,
+    pub succeeded: bool,
+    pub bump: u8,
+}
+
+pub const LEADERBOARD_SIZE: usize = 100;
+pub const DAILY_LEADERBOARD_SIZE: usize = 50;
+pub const WEEKLY_LEADERBOARD_SIZE: usize = 25;
+
+/// Season schedule set by the admin
+#[account]
+#[derive(InitSpace)]
+pub struct SeasonConfig {
+    /// When season 0 starts (unix seconds)
+    pub start: i64,
+    /// Length of each season in seconds
+    pub duration: i64,
+    pub bump: u8,
+}
+
+/// One season's top-100, sorted like `Leaderboard`; kept after the season ends
+#[account(zero_copy)]
+pub struct SeasonLeaderboard {
+    /// Number of filled entries
+    pub count: u64,
+    pub season: u64,
+    pub entries: [LeaderboardEntry; LEADERBOARD_SIZE],
+}
+
+/// One day's top-50 daily challenge scores, sorted like `Leaderboard`
+#[account(zero_copy)]
+pub struct DailyLeaderboard {
+    /// Number of filled entries
+    pub count: u64,
+    /// Unix day number
+    pub day: u64,
+    pub entries: [LeaderboardEntry; DAILY_LEADERBOARD_SIZE],
+}
+
+/// One week's top-25, sorted like `Leaderboard`; small to keep the rent of
+/// a board a week low
+#[account(zero_copy)]
+pub struct WeeklyLeaderboard {
+    /// Number of filled entries
+    pub count: u64,
+    /// `week_of` number
+    pub week: u64,
+    pub entries: [LeaderboardEntry; WEEKLY_LEADERBOARD_SIZE],
+}
+
+/// Global top-100, sorted by score descending (zero-copy: ~8KB)
+#[account(zero_copy)]
+pub struct Leaderboard {
+    /// Number of filled entries
+    pub count: u64,
+    pub entries: [LeaderboardEntry; LEADERBOARD_SIZE],
+}
+
+#[zero_copy]
+#[derive(Default, PartialEq, Eq, Debug)]
+pub struct LeaderboardEntry {
+    pub player: Pubkey,
+    pub score: u64,
+    /// When the score was submitted
+    pub timestamp: i64,
+    /// The run's provenance, as the game's `high_score_*` fields hold it for
+    /// the run that set the high score: when it ended, its last frame and seed
+    pub ended_at: i64,
+    pub frame: u64,
+    pub seed: u64,
+    /// ScoringMode the score was earned in
+    pub mode: u8,
+    pub padding: [u8; 3],
+    /// The run's flap count, for breaking ties
+    pub flaps: u32,
+}
+
+impl LeaderboardEntry {
+    fn for_record(record: &HighScoreRecord, now: i64) -> Self {
+        LeaderboardEntry {
+            player: record.authority,
+            score: record.high_score,
+            timestamp: now,
+            ended_at: record.high_score_timestamp,
+            frame: record.high_score_frame,
+            seed: record.high_score_seed,
+            mode: ScoringMode::PipesPassed as u8,
+            flaps: record.best_run_flaps,
+            ..Default::default()
+        }
+    }
+
+    fn for_run(game: &GameState, now: i64) -> Self {
+        LeaderboardEntry {
+            player: game.authority,
+            score: game.score,
+            timestamp: now,
+            ended_at: game.last_update,
+            frame: game.end_frame,
+            seed: game.run_seed,
+            mode: game.scoring() as u8,
+            flaps: game.flaps_this_run,
+            ..Default::default()
+        }
+    }
+}
+
+/// Part of the zero-copy GameState: a co-op run's second bird, which the
+/// physics moves and collides alongside the first
+#[zero_copy]
+#[derive(InitSpace, Default, PartialEq, Eq, borsh::BorshSerialize, borsh::BorshDeserialize)]
+pub struct CoopState {
+    /// Plays the second bird, with `flap_as(1)`
+    pub partner: Pubkey,
+    /// The second bird, as `bird_y`/`bird_velocity` are the first
+    pub bird_y: i32,
+    pub bird_velocity: i32,
+    pub active: u8,
+    /// Per bird, by player index: cleared for the one that crashed
+    pub alive: [u8; 2],
+    /// The second bird's flap waiting for the next frame
+    pub pending_flap: u8,
+}
+
+impl CoopState {
+    pub fn alive(&self, index: usize) -> bool {
+        self.alive[index] != 0
+    }
+
+    /// Note that bird `index` crashed, in a co-op run
+    pub fn lose(&mut self, index: usize) {
+        if self.active != 0 {
+            self.alive[index] = 0;
+        }
+    }
+
+    pub fn pending_flap(&self) -> bool {
+        self.pending_flap != 0
+    }
+
+    pub fn set_pending_flap(&mut self, pending: bool) {
+        self.pending_flap = pending as u8;
+    }
+}
+
+/// Part of the zero-copy GameState: flags are 0/1 bytes behind accessors
+#[zero_copy]
+#[derive(InitSpace, Default, PartialEq, Eq, borsh::BorshSerialize, borsh::BorshDeserialize)]
+pub struct Pipe {
+    /// X position of pipe (fixed-point, scaled by 1000)
+    pub x: i32,
+    /// Y position of gap center
+    pub gap_y: i32,
+    /// Y position of the lower gap's center (double pipes only; `gap_y` is
+    /// the upper one)
+    pub gap_y2: i32,
+    /// Oscillation half-height in pixels (moving pipes only)
+    pub amplitude: i32,
+    /// Oscillation offset in frames (moving pipes only)
+    pub phase: u16,
+    /// PIPE_TYPE_STATIC, PIPE_TYPE_OSCILLATING or PIPE_TYPE_DOUBLE
+    pub pipe_type: u8,
+    /// Whether bird has passed this pipe
+    pub passed: u8,
+    /// Whether pipe is active
+    pub active: u8,
+    /// Shield power-up waiting at the center of the gap
+    pub shield: u8,
+    /// Coin waiting at the center of the gap
+    pub coin: u8,
+    /// Timed pickup waiting at the center of the gap, by `Powerup::repr`;
+    /// 0 for none
+    pub pickup: u8,
+}
+
+impl Pipe {
+    pub fn passed(&self) -> bool {
+        self.passed != 0
+    }
+
+    pub fn set_passed(&mut self, passed: bool) {
+        self.passed = passed as u8;
+    }
+
+    pub fn active(&self) -> bool {
+        self.active != 0
+    }
+
+    pub fn set_active(&mut self, active: bool) {
+        self.active = active as u8;
+    }
+
+    pub fn shield(&self) -> bool {
+        self.shield != 0
+    }
+
+    pub fn set_shield(&mut self, shield: bool) {
+        self.shield = shield as u8;
+    }
+
+    pub fn coin(&self) -> bool {
+        self.coin != 0
+    }
+
+    pub fn set_coin(&mut self, coin: bool) {
+        self.coin = coin as u8;
+    }
+
+    /// Pixel X position, for collision and pass checks
+    pub fn x_pixels(&self) -> i32 {
+        self.x / 1000
+    }
+
+    /// Centers of this pipe's gaps
+    pub fn gap_ys(&self) -> impl Iterator<Item = i32> {
+        std::iter::once(self.gap_y).chain((self.pipe_type == PIPE_TYPE_DOUBLE).then_some(self.gap_y2))
+    }
+
+    /// Center of the gap nearest a `bird_size` bird at `bird_y` (pixels)
+    pub fn nearest_gap_y(&self, bird_y: i32, bird_size: i32) -> i32 {
+        let center = bird_y + bird_size / 2;
+        self.gap_ys().min_by_key(|y| (y - center).abs()).unwrap_or(self.gap_y)
+    }
+
+    /// Which side of its nearest gap a `bird_size` bird at `bird_y` is on
+    pub fn half_at(&self, bird_y: i32, bird_size: i32) -> PipeHalf {
+        if bird_y + bird_size / 2 < self.nearest_gap_y(bird_y, bird_size) {
+            PipeHalf::Top
+        } else {
+            PipeHalf::Bottom
+        }
+    }
+}
+
+/// Optional `delegate` settings; `None` keeps the ER default
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct DelegateParams {
+    /// Validator to delegate to; any when unset
+    pub validator: Option<Pubkey>,
+    /// How often the validator commits on its own, at least MIN_COMMIT_FREQUENCY_MS
+    pub commit_frequency_ms: Option<u32>,
+    /// Seconds the delegation is meant to last, up to MAX_DELEGATION_TIME_LIMIT.
+    /// Recorded only: the delegation program has no time limit to pass it to.
+    pub time_limit: Option<u64>,
+}
+
+impl DelegateParams {
+    /// Check the ranges and fill in the defaults; `fallback_validator` is
+    /// used when no validator is given
+    fn resolve(self, fallback_validator: Option<Pubkey>) -> Result<DelegationConfig> {
+        let commit_frequency_ms = self.commit_frequency_ms.unwrap_or(DelegateConfig::default().commit_frequency_ms);
+        require!(commit_frequency_ms >= MIN_COMMIT_FREQUENCY_MS, FlappyError::InvalidDelegateParams);
+        if let Some(time_limit) = self.time_limit {
+            require!((1..=MAX_DELEGATION_TIME_LIMIT).contains(&time_limit), FlappyError::InvalidDelegateParams);
+        }
+        Ok(DelegationConfig {
+            validator: self.validator.or(fallback_validator).unwrap_or_default(),
+            time_limit: self.time_limit.unwrap_or(0),
+            commit_frequency_ms,
+            padding: [0; 4],
+        })
+    }
+}
+
+/// Settings chosen when starting a run
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct StartOptions {
+    pub difficulty: Difficulty,
+    /// Secret committed to by `request_game`
+    pub reveal: u64,
+    pub scoring: ScoringMode,
+    /// Experimental: let `move_horizontal` dash the bird forward and back
+    pub horizontal_control: bool,
+    /// Wind gusts; None means on except in Easy
+    pub wind: Option<bool>,
+    /// None keeps the game's current mode. Only Classic supports Distance
+    /// scoring.
+    pub mode: Option<GameMode>,
+    /// Invincible run that counts for nothing; end it with `end_game`
+    pub practice: bool,
+    /// 1 to MAX_LIVES; runs with more than one aren't ranked
+    pub lives: u8,
+}
+
+/// What a run's `score` counts; the physics is the same either way
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug, Default)]
+pub enum ScoringMode {
+    /// One point per pipe passed (the classic game)
+    #[default]
+    PipesPassed,
+    /// One point per pixel the world scrolls, so the score grows every frame
+    Distance,
+}
+
+impl ScoringMode {
+    /// Inverse of `as u8`; unknown bytes read as the default
+    pub fn from_repr(repr: u8) -> Self {
+        [ScoringMode::PipesPassed, ScoringMode::Distance]
+            .get(repr as usize)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Rule variants; only Classic runs count for the leaderboards, rewards and
+/// the profile
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug, Default)]
+pub enum GameMode {
+    #[default]
+    Classic,
+    /// Gravity pulls up and flaps push down
+    Inverted,
+    /// Half-size bird hitbox
+    Tiny,
+}
+
+impl GameMode {
+    /// Inverse of `as u8`; unknown bytes read as the default
+    pub fn from_repr(repr: u8) -> Self {
+        [GameMode::Classic, GameMode::Inverted, GameMode::Tiny]
+            .get(repr as usize)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Per-run physics parameters derived from a `GameMode`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ModeParams {
+    /// 1, or -1 to flip gravity and flaps
+    pub gravity_sign: i32,
+    /// Side of the bird's square hitbox in pixels
+    pub bird_size: i32,
+}
+
+impl GameMode {
+    pub fn params(self) -> ModeParams {
+        match self {
+            GameMode::Classic => ModeParams { gravity_sign: 1, bird_size: BIRD_SIZE },
+            GameMode::Inverted => ModeParams { gravity_sign: -1, bird_size: BIRD_SIZE },
+            GameMode::Tiny => ModeParams { gravity_sign: 1, bird_size: BIRD_SIZE / 2 },
+        }
+    }
+}
+
+/// Physics presets; Normal is the classic game tuned by the constants above
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug, Default)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+/// Per-run physics parameters derived from a `Difficulty`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DifficultyParams {
+    pub gravity: i32,
+    pub pipe_gap: i32,
+    pub pipe_speed: i32,
+}
+
+impl Difficulty {
+    /// Inverse of `as u8`; unknown bytes read as the default
+    pub fn from_repr(repr: u8) -> Self {
+        [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard]
+            .get(repr as usize)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// This preset applied to the run's tuning; with the defaults Easy is
+    /// 350/180/3.0 and Hard 450/130/6.0
+    pub fn params(self, base: &PhysicsConfig) -> DifficultyParams {
+        let (gravity, pipe_gap, pipe_speed) = match self {
+            Difficulty::Easy => (-50, 20, -1000),
+            Difficulty::Normal => (0, 0, 0),
+            Difficulty::Hard => (50, -30, 2000),
+        };
+        DifficultyParams {
+            gravity: base.gravity.saturating_add(gravity),
+            pipe_gap: base.pipe_gap.saturating_add(pipe_gap),
+            pipe_speed: base.pipe_speed.saturating_add(pipe_speed),
+        }
+    }
+}
+
+impl DifficultyParams {
+    /// Apply the progressive ramp for `score`; a pure function of the score so
+    /// replays see the same physics
+    pub fn ramped(self, score: u64) -> DifficultyParams {
+        let level = (score / RAMP_PIPES_PER_LEVEL).min(i32::MAX as u64) as i32;
+        DifficultyParams {
+            gravity: self.gravity,
+            pipe_gap: self
+                .pipe_gap
+                .saturating_sub(level.saturating_mul(RAMP_GAP_STEP))
+                .max(RAMP_GAP_MIN.min(self.pipe_gap)),
+            pipe_speed: self
+                .pipe_speed
+                .saturating_add(level.saturating_mul(RAMP_SPEED_STEP))
+                .min(RAMP_SPEED_MAX.max(self.pipe_speed)),
+        }
+    }
+}
+
+/// Result of a tick, returned via return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TickOutcome {
+    /// Physics advanced one frame
+    Advanced,
+    /// Too few slots since the last frame; nothing changed
+    TooEarly,
+    /// MAX_FRAMES_PER_SECOND already reached this second; nothing changed
+    RateLimited,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Default)]
+pub enum GameStatus {
+    #[default]
+    NotStarted,
+    Playing,
+    GameOver,
+    /// Mid-run, clock stopped by `pause_game`
+    Paused,
+    /// Started, with ticks counting down `countdown_frames_remaining`
+    /// before the bird moves
+    Countdown,
+}
+
+impl GameStatus {
+    /// Inverse of `as u8`; unknown bytes read as the default
+    pub fn from_repr(repr: u8) -> Self {
+        [GameStatus::NotStarted, GameStatus::Playing, GameStatus::GameOver, GameStatus::Paused, GameStatus::Countdown]
+            .get(repr as usize)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// A run is underway, counting down, playing or paused
+    pub fn in_run(self) -> bool {
+        matches!(self, GameStatus::Playing | GameStatus::Paused | GameStatus::Countdown)
+    }
+
+    /// Ticks advance the run: playing or counting down
+    pub fn ticking(self) -> bool {
+        matches!(self, GameStatus::Playing | GameStatus::Countdown)
+    }
+}
+
+/// The part of a pipe above its gap, or below
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PipeHalf {
+    Top,
+    Bottom,
+}
+
+impl PipeHalf {
+    /// Inverse of `as u8`; unknown bytes read as `Bottom`
+    pub fn from_repr(repr: u8) -> Self {
+        match repr {
+            0 => PipeHalf::Top,
+            _ => PipeHalf::Bottom,
+        }
+    }
+}
+
+/// Something the bird picks up from a pipe gap and holds, one at a time
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Powerup {
+    /// Absorbs the next pipe collision; never runs out
+    Shield,
+    /// Gravity and flaps flip
+    GravityFlip,
+    /// The world runs at SLOW_TIME_SCALE
+    SlowMotion,
+    /// The bird collides as half its size
+    Shrink,
+    /// Coins within MAGNET_RADIUS are collected
+    Magnet,
+}
+
+impl Powerup {
+    /// The timed pickups, which spawn apart from shields
+    pub const TIMED: [Powerup; 4] = [Powerup::GravityFlip, Powerup::SlowMotion, Powerup::Shrink, Powerup::Magnet];
+
+    /// Byte stored for this power-up; 0 is none
+    pub const fn repr(self) -> u8 {
+        self as u8 + 1
+    }
+
+    /// Inverse of `repr`; 0 and unknown bytes read as none
+    pub fn from_repr(repr: u8) -> Option<Self> {
+        match repr {
+            1 => Some(Powerup::Shield),
+            2 => Some(Powerup::GravityFlip),
+            3 => Some(Powerup::SlowMotion),
+            4 => Some(Powerup::Shrink),
+            5 => Some(Powerup::Magnet),
+            _ => None,
+        }
+    }
+
+    /// Frames it lasts once picked up; 0 for one that doesn't run out
+    pub fn frames(self) -> u8 {
+        match self {
+            Powerup::Shield => 0,
+            Powerup::GravityFlip => FLIP_FRAMES,
+            Powerup::SlowMotion => SLOW_FRAMES,
+            Powerup::Shrink => SHRINK_FRAMES,
+            Powerup::Magnet => MAGNET_FRAMES,
+        }
+    }
+}
+
+/// The power-up the bird holds, and for how many more frames
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ActivePowerup {
+    pub kind: Powerup,
+    /// 0 for one that doesn't run out
+    pub frames_remaining: u8,
+}
+
+/// The pipe a run crashed into: its slot in `pipes` and the half hit
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PipeHit {
+    pub index: u8,
+    pub half: PipeHalf,
+}
+
+/// How a run ended
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DeathCause {
+    Floor,
+    Ceiling,
+    Pipe,
+    /// Ended by `end_game` or `end_and_commit`
+    Manual,
+    /// Paused for longer than MAX_PAUSE_SECONDS
+    PauseExpired,
+    /// Ended by a forced `undelegate`
+    Undelegated,
+    /// Left untouched for CRANK_FINALIZE_SECS and ended by `crank_finalize`
+    Abandoned,
+}
+
+impl DeathCause {
+    /// Inverse of `as u8`
+    pub fn from_repr(repr: u8) -> Option<Self> {
+        [
+            DeathCause::Floor,
+            DeathCause::Ceiling,
+            DeathCause::Pipe,
+            DeathCause::Manual,
+            DeathCause::PauseExpired,
+            DeathCause::Undelegated,
+            DeathCause::Abandoned,
+        ]
+        .get(repr as usize)
+        .copied()
+    }
+}
+
+// ========================================
+// Events
+// ========================================
+
+#[event]
+pub struct GameStarted {
+    pub player: Pubkey,
+    pub seed: u64,
+    /// One of the SEED_SOURCE_* constants
+    pub seed_source: u8,
+    pub difficulty: Difficulty,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PipePassed {
+    pub player: Pubkey,
+    /// Score after passing the pipe
+    pub score: u64,
+    pub frame: u64,
+    /// Extra points for a near miss, included in `score`
+    pub near_miss_bonus: u64,
+    /// Extra points for the glide combo, included in `score`
+    pub combo_bonus: u64,
+}
+
+#[event]
+pub struct CoinCollected {
+    pub player: Pubkey,
+    /// Coin balance after the pickup
+    pub coins: u64,
+    pub frame: u64,
+}
+
+#[event]
+pub struct GameOver {
+    pub player: Pubkey,
+    pub score: u64,
+    pub high_score: u64,
+    pub frame: u64,
+    pub cause: DeathCause,
+    /// Bird's Y in pixels as the run ended
+    pub bird_y: i32,
+    /// The pipe hit, for a `Pipe` death
+    pub pipe: Option<PipeHit>,
+}
+
+/// A run beat the player's high score; `seed` and `timestamp` are the
+/// game's `high_score_seed` and `high_score_timestamp`, for checking it later
+#[event]
+pub struct NewHighScore {
+    pub player: Pubkey,
+    pub old: u64,
+    pub new: u64,
+    pub seed: u64,
+    pub timestamp: i64,
+    /// How long the run lasted and how often it flapped
+    pub frames: u64,
+    pub flaps: u32,
+}
+
+/// What a viewer needs to draw the frame, from `tick` in spectator mode;
+/// fixed-size so the log cost is fixed too
+#[event]
+#[derive(Default)]
+pub struct FrameState {
+    pub player: Pubkey,
+    pub frame: u64,
+    pub score: u64,
+    /// Bird Y in pixels
+    pub bird_y: i32,
+    /// Sign of the bird's velocity: -1 rising, 0, 1 falling
+    pub velocity_sign: i8,
+    /// Active pipes, in `pipes[..pipe_count]`
+    pub pipe_count: u8,
+    pub pipes: [PipeView; MAX_PIPES],
+}
+
+/// A pipe in `FrameState`, in pixels
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct PipeView {
+    pub x: i32,
+    pub gap_y: i32,
+}
+
+#[event]
+pub struct AchievementUnlocked {
+    pub player: Pubkey,
+    /// One of the ACHIEVEMENT_* constants
+    pub id: u8,
+}
+
+#[event]
+pub struct MissionCompleted {
+    pub player: Pubkey,
+    pub slot: u8,
+    pub kind: MissionKind,
+}
+
+#[event]
+pub struct LevelUp {
+    pub player: Pubkey,
+    pub new_level: u16,
+}
+
+#[event]
+pub struct StreakExtended {
+    pub player: Pubkey,
+    /// Runs in the streak, counting the one that just ended
+    pub streak: u32,
+}
+
+#[event]
+pub struct ExternalBonusAwarded {
+    pub player: Pubkey,
+    /// The allowlisted program that awarded it
+    pub program: Pubkey,
+    pub points: u16,
+    /// The run's `bonus_score` after it
+    pub bonus_score: u64,
+}
+
+#[event]
+pub struct Delegated {
+    pub player: Pubkey,
+    /// Validator from the first remaining account, if one was given
+    pub validator: Option<Pubkey>,
+}
+
+#[event]
+pub struct Committed {
+    pub player: Pubkey,
+    pub score: u64,
+    pub frame_count: u64,
+}
+
+/// `crank_tick` advanced an abandoned run, or `crank_finalize` ended one
+#[event]
+pub struct Cranked {
+    pub player: Pubkey,
+    pub cranker: Pubkey,
+    pub frames: u32,
+    /// Lamports the cranker got from the game
+    pub tip: u64,
+    /// Lamports accrued to the cranker's keeper ledger
+    pub accrued: u64,
+}
+
+/// `submit_score` entered a run; `frame` and `seed` identify it for a replay
+#[event]
+pub struct ScoreSubmitted {
+    pub player: Pubkey,
+    pub score: u64,
+    pub frame: u64,
+    pub seed: u64,
+    pub ended_at: i64,
+}
+
+/// `commit` found the game as it was last committed and skipped the commit
+#[event]
+pub struct NothingToCommit {
+    pub player: Pubkey,
+    pub frame_count: u64,
+}
+
+#[event]
+pub struct ChallengeCreated {
+    pub challenger: Pubkey,
+    pub target: Pubkey,
+    pub score_to_beat: u64,
+    pub seed: u64,
+    pub expiry: i64,
+}
+
+#[event]
+pub struct ChallengeAccepted {
+    pub challenger: Pubkey,
+    pub target: Pubkey,
+    pub seed: u64,
+}
+
+#[event]
+pub struct ChallengeResolved {
+    pub challenger: Pubkey,
+    pub target: Pubkey,
+    pub score: u64,
+    pub succeeded: bool,
+}
+
+#[event]
+pub struct MilestoneClaimed {
+    pub player: Pubkey,
+    pub milestone: u8,
+    pub mint: Pubkey,
+}
+
+/// A ranked or daily flap was dropped for coming too soon after the last
+#[event]
+pub struct FlapTooSoon {
+    pub player: Pubkey,
+    /// `frame_count` when it arrived
+    pub frame: u64,
+    /// First `frame_count` a flap is accepted at
+    pub next_frame: u64,
+}
+
+#[event]
+pub struct Undelegated {
+    pub player: Pubkey,
+    pub final_score: u64,
+    /// A run was in progress and `undelegate(force)` ended it
+    pub forced: bool,
+}
+
+#[error_code]
+pub enum FlappyError {
+    #[msg("Game is not in playing state")]
+    GameNotPlaying,
+    #[msg("Game has already started")]
+    GameAlreadyStarted,
+    #[msg("Game is not over")]
+    GameNotOver,
+    #[msg("Score does not qualify for the leaderboard")]
+    ScoreTooLow,
+    #[msg("Player already has an equal or better leaderboard entry")]
+    ScoreNotImproved,
+    #[msg("Game is in progress")]
+    GameInProgress,
+    #[msg("Not enough coins")]
+    InsufficientCoins,
+    #[msg("No pending run request")]
+    NoCommitment,
+    #[msg("Reveal must come in a later slot than the request")]
+    RevealTooEarly,
+    #[msg("Reveal does not match the commitment")]
+    InvalidReveal,
+    #[msg("Game is paused")]
+    GamePaused,
+    #[msg("Game is not paused")]
+    GameNotPaused,
+    #[msg("Pass both the old and the new profile, or neither")]
+    ProfileMismatch,
+    #[msg("Game slot out of range")]
+    InvalidGameSlot,
+    #[msg("Only the config admin can do this")]
+    Unauthorized,
+    #[msg("Physics config out of range")]
+    InvalidConfig,
+    #[msg("Run is past the input log window")]
+    InputLogFull,
+    #[msg("Replay does not reproduce the claimed run")]
+    ReplayMismatch,
+    #[msg("Tournament must end after it starts, in the future")]
+    InvalidTournamentWindow,
+    #[msg("Tournament has ended")]
+    TournamentClosed,
+    #[msg("Already joined this tournament")]
+    AlreadyJoined,
+    #[msg("Tournament is full")]
+    TournamentFull,
+    #[msg("Not entered in this tournament")]
+    NotInTournament,
+    #[msg("Run was not played during the tournament")]
+    RunOutsideTournament,
+    #[msg("Tournament has not ended yet")]
+    TournamentNotOver,
+    #[msg("Tournament already settled")]
+    TournamentSettled,
+    #[msg("Prize accounts must be the winners in rank order")]
+    WrongPrizeAccount,
+    #[msg("A match needs two different players")]
+    InvalidMatch,
+    #[msg("Not a player in this match")]
+    NotInMatch,
+    #[msg("Match run already started")]
+    MatchRunUsed,
+    #[msg("Match has expired")]
+    MatchExpired,
+    #[msg("Match runs are not both over yet")]
+    MatchNotFinished,
+    #[msg("Match already settled")]
+    MatchSettled,
+    #[msg("Not the player's reward token account")]
+    InvalidRewardAccount,
+    #[msg("Run was not started as a daily challenge")]
+    NotDailyRun,
+    #[msg("Daily run did not finish on the day it started")]
+    DailyRunCrossedDay,
+    #[msg("Not the current season, or its leaderboard is missing")]
+    WrongSeason,
+    #[msg("Run was last played before the current season started")]
+    RunBeforeSeason,
+    #[msg("Run was scored in a mode this board does not rank")]
+    WrongScoringMode,
+    #[msg("Horizontal control is not enabled for this run")]
+    HorizontalControlDisabled,
+    #[msg("Not available in this game mode")]
+    WrongGameMode,
+    #[msg("Practice runs are not recorded")]
+    PracticeRun,
+    #[msg("Lives must be between 1 and MAX_LIVES")]
+    InvalidLives,
+    #[msg("Runs with extra lives are not ranked")]
+    ExtraLivesRun,
+    #[msg("This run has already been revived")]
+    AlreadyRevived,
+    #[msg("Revived runs are not ranked")]
+    RevivedRun,
+    #[msg("Not a game account of the previous version")]
+    InvalidLegacyGame,
+    #[msg("New game already has a high score")]
+    MigrationTargetNotEmpty,
+    #[msg("No migration from this game version")]
+    UnsupportedGameVersion,
+    #[msg("Caller program is not on the CPI allowlist")]
+    CallerNotAllowed,
+    #[msg("CPI allowlist is full")]
+    AllowlistFull,
+    #[msg("Delegation parameters out of range")]
+    InvalidDelegateParams,
+    #[msg("Only the game's authority can do this")]
+    NotGameAuthority,
+    #[msg("Ranked cooldown since the last ranked run has not passed")]
+    CooldownActive,
+    #[msg("A ranked start needs the player's other games, in slot order")]
+    MissingGameSlots,
+    #[msg("Match has no stake")]
+    NotAWager,
+    #[msg("Wager has not been accepted")]
+    WagerNotAccepted,
+    #[msg("Wager was already accepted")]
+    WagerAccepted,
+    #[msg("Opponent can still accept the wager")]
+    AcceptWindowOpen,
+    #[msg("Match has not been settled")]
+    MatchNotSettled,
+    #[msg("Match winnings already paid")]
+    WinningsPaid,
+    #[msg("A challenge needs another player and an expiry in the future")]
+    InvalidChallenge,
+    #[msg("Only the challenge's target can do this")]
+    NotChallengeTarget,
+    #[msg("Challenge was already accepted")]
+    ChallengeAccepted,
+    #[msg("Challenge has expired")]
+    ChallengeExpired,
+    #[msg("Challenge run is not over and the challenge hasn't expired")]
+    ChallengeNotFinished,
+    #[msg("Challenge already resolved")]
+    ChallengeResolved,
+    #[msg("Not the game the challenge was accepted in")]
+    WrongChallengeGame,
+    #[msg("Skins below FREE_SKINS are free and can't be registered")]
+    FreeSkin,
+    #[msg("Skin registry is full")]
+    SkinRegistryFull,
+    #[msg("No NFT unlocking this skin was shown")]
+    SkinNotOwned,
+    #[msg("Milestone id out of range or threshold zero")]
+    InvalidMilestone,
+    #[msg("Milestone has not been set up")]
+    MilestoneNotConfigured,
+    #[msg("High score hasn't reached the milestone")]
+    MilestoneNotReached,
+    #[msg("Milestone already claimed")]
+    MilestoneClaimed,
+    #[msg("Not the current week")]
+    WrongWeek,
+    #[msg("Run last updated in an earlier week")]
+    RunBeforeWeek,
+    #[msg("Run is still counting down")]
+    CountdownRunning,
+    #[msg("No mission in that slot")]
+    InvalidMissionSlot,
+    #[msg("Mission is not complete")]
+    MissionNotComplete,
+    #[msg("Mission reward already claimed")]
+    MissionAlreadyClaimed,
+    #[msg("Program is paused by the admin")]
+    ProgramPaused,
+    #[msg("Playfield size out of range")]
+    InvalidPlayfield,
+    #[msg("No high score synced to the player's record yet")]
+    NoHighScore,
+    #[msg("Game is delegated to the ER; undelegate it first")]
+    GameDelegated,
+    #[msg("Run ended too long ago to submit")]
+    RunTooOld,
+    #[msg("Game was updated too recently to crank")]
+    GameNotStale,
+    #[msg("No keeper tips accrued to claim")]
+    NoTipsAccrued,
+    #[msg("Co-op runs don't take this; use flap_as")]
+    CoopGame,
+    #[msg("Not a co-op run")]
+    NotCoopGame,
+    #[msg("A co-op partner must be another player")]
+    InvalidPartner,
+    #[msg("Co-op runs have players 0 and 1")]
+    InvalidPlayerIndex,
+    #[msg("Signer is neither that bird's player nor their session key")]
+    NotCoopPlayer,
+    #[msg("Run was played on a different difficulty than the tournament")]
+    WrongDifficulty,
 }
 
-#[commit]
-#[derive(Accounts)]
-pub struct CommitInput<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(mut, seeds = [GAME_SEED, payer.key().as_ref()], bump)]
-    pub game: Account<'info, GameState>,
-}
 
-// ========================================
-// Account Data
-// ========================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn demo_run_golden() {
+        // Determinism canary: a physics change that moves these values must
+        // update them on purpose.
+        assert_eq!(
+            simulate_demo_run(DEMO_SEED, 0),
+            DemoResult { score: 0, frames: 29, bird_y: 370_000, game_over: true }
+        );
+        assert_eq!(
+            simulate_demo_run(DEMO_SEED, 0x8888_8888),
+            DemoResult { score: 0, frames: 32, bird_y: 56_800, game_over: false }
+        );
+    }
+
+    fn ignore(_: &mut GameState, _: &FrameReport) {}
+
+    fn playing_at_slot(slot: u64) -> GameState {
+        let mut game = GameState::default();
+        reset_run(&mut game);
+        game.set_game_status(GameStatus::Playing);
+        game.last_tick_slot = slot;
+        game
+    }
+
+    /// The default playfield, a portrait one and the widest, flattest allowed
+    const PLAYFIELDS: [(u16, u16); 3] = [(600, 400), (400, 600), (MAX_PLAYFIELD_SIZE, MIN_PLAYFIELD_SIZE)];
+
+    fn playing_on(width: u16, height: u16) -> GameState {
+        let mut game = GameState { width, height, ..Default::default() };
+        reset_run(&mut game);
+        game.set_game_status(GameStatus::Playing);
+        game
+    }
+
+    #[test]
+    fn tick_in_same_slot_is_too_early() {
+        let mut game = playing_at_slot(100);
+        assert_eq!(advance_at_slot(&mut game, 100, &mut ignore), TickOutcome::TooEarly);
+        assert_eq!(game.frame_count, 0);
+        assert_eq!(game.last_tick_slot, 100);
+    }
+
+    #[test]
+    fn tick_after_min_slots_advances() {
+        let mut game = playing_at_slot(100);
+        assert_eq!(advance_at_slot(&mut game, 100 + MIN_SLOTS_PER_TICK, &mut ignore), TickOutcome::Advanced);
+        assert_eq!(game.frame_count, 1);
+        assert_eq!(game.last_tick_slot, 100 + MIN_SLOTS_PER_TICK);
+        // A second tick in the same slot is a no-op
+        assert_eq!(advance_at_slot(&mut game, 100 + MIN_SLOTS_PER_TICK, &mut ignore), TickOutcome::TooEarly);
+        assert_eq!(game.frame_count, 1);
+    }
+
+    #[test]
+    fn late_tick_advances_a_single_frame() {
+        let mut game = playing_at_slot(100);
+        assert_eq!(allowed_steps(100, 100 + 5 * MIN_SLOTS_PER_TICK), 5);
+        assert_eq!(advance_at_slot(&mut game, 100 + 5 * MIN_SLOTS_PER_TICK, &mut ignore), TickOutcome::Advanced);
+        assert_eq!(game.frame_count, 1);
+    }
+
+    #[test]
+    fn allowed_steps_never_underflows() {
+        assert_eq!(allowed_steps(200, 100), 0);
+    }
+
+    fn encode(game: &GameState) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        game.serialize(&mut bytes).unwrap();
+        bytes
+    }
+
+    /// Test pilot: flap when falling below the next gap's center
+    fn autopilot_flap(game: &GameState) -> bool {
+        let target = game.pipes[..MAX_PIPES]
+            .iter()
+            .filter(|p| p.active() && p.x_pixels() + PIPE_WIDTH >= BIRD_X)
+            .min_by_key(|p| p.x)
+            .map_or(GAME_HEIGHT / 2, |p| p.gap_y);
+        game.bird_velocity > 0 && game.bird_y / 1000 + BIRD_SIZE / 2 > target + 10
+    }
+
+    #[test]
+    fn spawned_pipes_keep_their_spacing() {
+        for (width, height) in PLAYFIELDS {
+            let mut game = playing_on(width, height);
+            game.seed = 1_700_000_000;
+            let mut saw_pipe_at_zero = false;
+            let mut spawned = 0;
+            for _ in 0..3_000 {
+                // Hold the bird at the next gap's center so the run never ends
+                let target = game.pipes[..MAX_PIPES]
+                    .iter()
+                    .filter(|p| p.active() && p.x_pixels() + PIPE_WIDTH >= BIRD_X)
+                    .min_by_key(|p| p.x)
+                    .map_or(game.height() / 2, |p| p.gap_y);
+                game.bird_y = (target - BIRD_SIZE / 2) * 1000;
+                game.bird_velocity = 0;
+                step_physics(&mut game);
+                assert!(game.game_status() == GameStatus::Playing);
+                
+                let mut xs: Vec<i32> = game.pipes.iter().filter(|p| p.active()).map(|p| p.x).collect();
+                xs.sort();
+                saw_pipe_at_zero |= xs.contains(&0);
+                for pair in xs.windows(2) {
+                    assert!(pair[1] - pair[0] > PIPE_SPAWN_DISTANCE * 1000, "pipes at {:?}", xs);
+                }
+                // New pipes come in at the right edge, gaps inside the screen
+                for pipe in game.pipes.iter().filter(|p| p.active() && p.x == width as i32 * 1000) {
+                    assert!(pipe.gap_y - game.pipe_gap / 2 > 0 && pipe.gap_y + game.pipe_gap / 2 < height as i32);
+                    spawned += 1;
+                }
+            }
+            assert!(saw_pipe_at_zero);
+            assert!(spawned > 0);
+        }
+    }
+
+    #[test]
+    fn tick_multiple_matches_repeated_ticks() {
+        let mut ticked = playing_at_slot(0);
+        ticked.seed = 42;
+        let mut batched = ticked;
+
+        // Flap decisions land between batches of 5 frames, identically for both
+        for round in 0..400u64 {
+            if autopilot_flap(&ticked) {
+                ticked.set_pending_flap(true);
+                batched.set_pending_flap(true);
+            }
+            for i in 1..=5 {
+                if ticked.game_status() == GameStatus::Playing {
+                    advance_at_slot(&mut ticked, round * 5 + i, &mut ignore);
+                }
+            }
+            advance_frames(&mut batched, 5, round * 5 + 5, &mut ignore);
+            // Slot bookkeeping differs when the run ends mid-batch; compare the rest
+            batched.last_tick_slot = ticked.last_tick_slot;
+            assert_eq!(encode(&ticked), encode(&batched), "diverged in round {}", round);
+            if ticked.game_status() != GameStatus::Playing {
+                break;
+            }
+        }
+        assert!(ticked.score >= 3, "autopilot should pass a few pipes");
+    }
+
+    #[test]
+    fn flap_trajectory_ignores_input_interleaving() {
+        // Same inputs per frame, delivered as flap(advance) vs flap(input-only) + tick,
+        // with some flaps sent twice within a frame window
+        let mut advanced = playing_at_slot(0);
+        let mut queued = advanced;
+        for frame in 1..=120u64 {
+            if frame % 9 == 0 {
+                // flap(true)
+                advanced.set_pending_flap(true);
+                advance_at_slot(&mut advanced, frame, &mut ignore);
+                // flap(false), flap(false), tick
+                queued.set_pending_flap(true);
+                queued.set_pending_flap(true);
+                advance_at_slot(&mut queued, frame, &mut ignore);
+            } else {
+                advance_at_slot(&mut advanced, frame, &mut ignore);
+                advance_at_slot(&mut queued, frame, &mut ignore);
+            }
+            assert_eq!(encode(&advanced), encode(&queued), "diverged at frame {}", frame);
+        }
+    }
+
+    #[test]
+    fn early_flap_is_applied_on_the_next_frame() {
+        let mut game = playing_at_slot(10);
+        game.set_pending_flap(true);
+        assert_eq!(advance_at_slot(&mut game, 10, &mut ignore), TickOutcome::TooEarly);
+        assert!(game.pending_flap());
+        assert_eq!(game.bird_velocity, 0);
+        assert_eq!(advance_at_slot(&mut game, 11, &mut ignore), TickOutcome::Advanced);
+        assert!(!game.pending_flap());
+        assert_eq!(game.bird_velocity, JUMP_VELOCITY + GRAVITY);
+    }
+
+    fn run_until_over(game: &mut GameState, mut flap: impl FnMut(&GameState) -> bool) -> Vec<FrameReport> {
+        let mut reports = Vec::new();
+        let mut slot = game.last_tick_slot;
+        while game.game_status() == GameStatus::Playing && game.frame_count < 5_000 {
+            game.set_pending_flap(game.pending_flap() | flap(game));
+            slot += 1;
+            advance_at_slot(game, slot, &mut |_, r| reports.push(*r));
+        }
+        reports
+    }
+
+    #[test]
+    fn reports_floor_and_ceiling_deaths() {
+        for (width, height) in PLAYFIELDS {
+            let mut game = playing_on(width, height);
+            assert_eq!(game.bird_y, height as i32 / 2 * 1000);
+            let reports = run_until_over(&mut game, |_| false);
+            assert_eq!(reports.last().unwrap().death, Some(DeathCause::Floor));
+            assert_eq!(reports.iter().filter(|r| r.death.is_some()).count(), 1);
+            // At the bottom of this playfield, not the default one
+            let (inset, hitbox_size) = hitbox(BIRD_SIZE, game.hitbox_inset);
+            assert!(game.end_bird_y + inset + hitbox_size >= height as i32);
+            assert!(game.end_bird_y < height as i32);
+
+            let mut game = playing_on(width, height);
+            let reports = run_until_over(&mut game, |_| true);
+            assert_eq!(reports.last().unwrap().death, Some(DeathCause::Ceiling));
+        }
+    }
+
+    #[test]
+    fn reports_pipe_passes_and_pipe_death() {
+        let mut game = playing_at_slot(0);
+        game.seed = 7;
+        // Fly level with the center until a pipe gap is missed
+        let reports = run_until_over(&mut game, |g| g.bird_velocity > 0 && g.bird_y / 1000 > GAME_HEIGHT / 2);
+        let passes: u64 = reports.iter().map(|r| r.pipes_passed as u64).sum();
+        assert_eq!(passes, game.score);
+        assert_eq!(reports.last().unwrap().death, Some(DeathCause::Pipe));
+    }
+
+    #[test]
+    fn profile_tracks_a_run_and_saturates() {
+        let mut profile = PlayerProfile {
+            authority: Pubkey::default(),
+            games_played: 0,
+            pipes_passed: u64::MAX - 1,
+            flaps: 0,
+            frames_survived: 0,
+            best_score: 0,
+            best_score_timestamp: 0,
+            bump: 0,
+            coins_collected: 0,
+            achievements: 0,
+            milestones_claimed: 0,
+            current_streak: 0,
+            best_streak: 0,
+            total_distance: 0,
+            xp: u64::MAX - 1,
+            level: 0,
+        };
+        let mut game = playing_at_slot(0);
+        game.seed = 42;
+        // Fly well for 600 frames, then stop flapping and hit the floor
+        let reports = run_until_over(&mut game, |g| g.frame_count < 600 && autopilot_flap(g));
+        assert!(game.score > 0);
+        for report in &reports {
+            profile.record_frame(&mut game, report, 1_234);
+        }
+        assert_eq!(profile.frames_survived, game.frame_count);
+        assert_eq!(profile.pipes_passed, u64::MAX);
+        assert_eq!((profile.xp, profile.level), (u64::MAX, u16::MAX));
+        assert_eq!(profile.best_score, game.score);
+        assert_eq!(profile.best_score_timestamp, 1_234);
+
+        // A worse run leaves the best score alone
+        profile.record_game_over(0, 9_999);
+        assert_eq!(profile.best_score_timestamp, 1_234);
+    }
+
+    #[test]
+    fn difficulty_sets_run_physics_and_its_own_high_score() {
+        let mut game = playing_at_slot(0);
+        assert_eq!(game.pipe_gap, PIPE_GAP);
+        assert_eq!(game.pipe_speed, PIPE_SPEED);
+
+        game.set_difficulty(Difficulty::Hard);
+        reset_run(&mut game);
+        assert_eq!(game.gravity, Difficulty::Hard.params(&PhysicsConfig::default()).gravity);
+        advance_at_slot(&mut game, 1, &mut ignore);
+        assert_eq!(game.bird_velocity, Difficulty::Hard.params(&PhysicsConfig::default()).gravity);
+
+        // A Hard pipe gap rejects a bird the Normal gap would let through
+        let pipe = Pipe { x: BIRD_X * 1000, gap_y: 200, active: 1, ..Default::default() };
+        let bird_y = 200 - PIPE_GAP / 2 + 5;
+        assert!(!check_pipe_collision(bird_y, bird_y, BIRD_X * 1000, BIRD_SIZE, &pipe, PIPE_GAP, 0));
+        assert!(check_pipe_collision(bird_y, bird_y, BIRD_X * 1000, BIRD_SIZE, &pipe, game.pipe_gap, 0));
+
+        game.score = 7;
+        game.record_high_score();
+        game.set_difficulty(Difficulty::Easy);
+        game.score = 20;
+        game.record_high_score();
+        assert_eq!(game.high_scores, [20, 0, 7]);
+        assert_eq!(game.high_score, 20);
+    }
+
+    #[test]
+    fn ramp_steps_every_ten_points_and_respects_caps() {
+        let normal = Difficulty::Normal.params(&PhysicsConfig::default());
+        assert_eq!(normal.ramped(9), normal);
+        assert_eq!(normal.ramped(10).pipe_speed, PIPE_SPEED + RAMP_SPEED_STEP);
+        assert_eq!(normal.ramped(10).pipe_gap, PIPE_GAP - RAMP_GAP_STEP);
+        for difficulty in [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard] {
+            let capped = difficulty.params(&PhysicsConfig::default()).ramped(u64::MAX);
+            assert_eq!(capped.pipe_speed, RAMP_SPEED_MAX);
+            assert_eq!(capped.pipe_gap, RAMP_GAP_MIN);
+        }
+    }
+
+    #[test]
+    fn long_run_ramps_deterministically_from_score() {
+        // Start deep into the ramp so the caps are reached within one run
+        for start_score in [0, 55, 150] {
+            let mut game = playing_at_slot(0);
+            game.seed = 42;
+            game.score = start_score;
+            let mut slot = 0;
+            let mut speeds = Vec::new();
+            while game.game_status() == GameStatus::Playing && game.frame_count < 5_000 {
+                let score_at_start = game.score;
+                game.set_pending_flap(game.pending_flap() | autopilot_flap(&game));
+                slot += 1;
+                advance_at_slot(&mut game, slot, &mut ignore);
+                let expected = Difficulty::Normal.params(&PhysicsConfig::default()).ramped(score_at_start);
+                assert_eq!((game.pipe_speed, game.pipe_gap), (expected.pipe_speed, expected.pipe_gap));
+                assert!(game.pipe_speed <= RAMP_SPEED_MAX && game.pipe_gap >= RAMP_GAP_MIN);
+                speeds.push(game.pipe_speed);
+            }
+            assert!(speeds.windows(2).all(|w| w[0] <= w[1]));
+        }
+
+        // Starting over drops back to the preset
+        let mut game = playing_at_slot(0);
+        game.score = 200;
+        reset_run(&mut game);
+        assert_eq!(game.pipe_speed, PIPE_SPEED);
+        assert_eq!(game.pipe_gap, PIPE_GAP);
+    }
+
+    #[test]
+    fn oscillating_gap_stays_in_bounds_and_repeats() {
+        let low = PIPE_HEIGHT_MIN + PIPE_GAP / 2;
+        let high = GAME_HEIGHT - PIPE_HEIGHT_MIN - PIPE_GAP / 2;
+        let pipe = Pipe {
+            pipe_type: PIPE_TYPE_OSCILLATING,
+            phase: 17,
+            amplitude: OSCILLATION_AMPLITUDE_MAX,
+            ..Default::default()
+        };
+        let ys: Vec<i32> = (0..OSCILLATION_PERIOD * 2)
+            .map(|frame| oscillating_gap_y(&pipe, frame, PIPE_GAP, GAME_HEIGHT))
+            .collect();
+        assert!(ys.iter().all(|y| (low..=high).contains(y)));
+        assert_eq!(ys.iter().min(), Some(&low));
+        assert_eq!(ys.iter().max(), Some(&high));
+        assert_eq!(ys[..OSCILLATION_PERIOD as usize], ys[OSCILLATION_PERIOD as usize..]);
+    }
+
+    #[test]
+    fn moving_pipes_spawn_only_past_min_score() {
+        let spawn_types = |score: u64| {
+            let mut game = playing_at_slot(0);
+            game.score = score;
+            (0..200)
+                .map(|seed| {
+                    game.seed = 1_700_000_000 + seed * 7_919;
+                    game.pipes = [Pipe::default(); MAX_PIPES];
+                    game.active_pipe_count = 0;
+                    spawn_pipes(&mut game);
+                    game.pipes[0].pipe_type
+                })
+                .collect::<Vec<_>>()
+        };
+        assert!(spawn_types(OSCILLATION_MIN_SCORE).iter().all(|&t| t == PIPE_TYPE_STATIC));
+        let late = spawn_types(OSCILLATION_MIN_SCORE + 1);
+        assert!(late.contains(&PIPE_TYPE_OSCILLATING));
+        assert!(late.contains(&PIPE_TYPE_STATIC));
+    }
+
+    #[test]
+    fn collision_follows_the_moving_gap() {
+        let mut game = playing_at_slot(0);
+        game.score = OSCILLATION_MIN_SCORE + 1;
+        game.pipes[0] = Pipe {
+            x: BIRD_X * 1000 + game.pipe_speed,
+            active: 1,
+            pipe_type: PIPE_TYPE_OSCILLATING,
+            phase: 0,
+            amplitude: OSCILLATION_AMPLITUDE_MAX,
+            ..Default::default()
+        };
+        // Park the bird at the stale gap center; the gap has moved by frame 1
+        let stale = oscillating_gap_y(&game.pipes[0], 0, game.pipe_gap, GAME_HEIGHT);
+        game.bird_y = (stale - BIRD_SIZE / 2) * 1000;
+        game.bird_velocity = -GRAVITY;
+        let prev_y = game.bird_y / 1000;
+        let report = step_physics(&mut game);
+        assert_eq!(game.pipes[0].gap_y, oscillating_gap_y(&game.pipes[0], 1, game.pipe_gap, GAME_HEIGHT));
+        let bird_y = game.bird_y / 1000;
+        assert_eq!(
+            report.death == Some(DeathCause::Pipe),
+            check_pipe_collision(prev_y, bird_y, BIRD_X * 1000, BIRD_SIZE, &game.pipes[0], game.pipe_gap, game.pipe_speed)
+        );
+    }
+
+    /// A playing game with one pipe right on top of the bird
+    fn pipe_on_bird(gap_y: i32) -> GameState {
+        let mut game = playing_at_slot(0);
+        game.pipes[0] = Pipe {
+            x: BIRD_X * 1000 + game.pipe_speed,
+            gap_y,
+            active: 1,
+            ..Default::default()
+        };
+        game
+    }
+
+    #[test]
+    fn shield_is_picked_up_in_the_gap() {
+        let mut game = pipe_on_bird(GAME_HEIGHT / 2);
+        game.pipes[0].set_shield(true);
+        game.bird_y = (GAME_HEIGHT / 2 - BIRD_SIZE / 2) * 1000;
+        game.bird_velocity = -GRAVITY;
+        step_physics(&mut game);
+        assert!(game.has_shield());
+        assert!(!game.pipes[0].shield());
+        assert!(game.game_status() == GameStatus::Playing);
+    }
+
+    #[test]
+    fn shield_absorbs_one_pipe_hit() {
+        let mut game = pipe_on_bird(GAME_HEIGHT / 2);
+        game.pick_up(Powerup::Shield);
+        // Well above the gap, inside the top pipe
+        game.bird_y = 60 * 1000;
+        let report = step_physics(&mut game);
+        assert_eq!(report.death, None);
+        assert!(!game.has_shield());
+        assert_eq!(game.bird_y, (GAME_HEIGHT / 2 - BIRD_SIZE / 2) * 1000);
+        assert_eq!(game.bird_velocity, 0);
+
+        // No shield left: the next hit ends the run
+        game.bird_y = 60 * 1000;
+        assert_eq!(step_physics(&mut game).death, Some(DeathCause::Pipe));
+    }
+
+    #[test]
+    fn shield_does_not_save_floor_deaths() {
+        let mut game = playing_at_slot(0);
+        game.pick_up(Powerup::Shield);
+        let reports = run_until_over(&mut game, |_| false);
+        assert_eq!(reports.last().unwrap().death, Some(DeathCause::Floor));
+    }
+
+    #[test]
+    fn shield_spawns_follow_the_seed_and_round_trip() {
+        let spawn = |seed: u64| {
+            let mut game = playing_at_slot(0);
+            game.seed = seed;
+            spawn_pipes(&mut game);
+            game
+        };
+        let shielded: Vec<u64> = (0..500u64)
+            .map(|i| 1_700_000_000 + i * 7_919)
+            .filter(|&seed| spawn(seed).pipes[0].shield())
+            .collect();
+        assert!(!shielded.is_empty() && shielded.len() < 500);
+        assert!(shielded.iter().all(|&seed| spawn(seed).pipes[0].shield()));
+
+        // State survives the Borsh round trip that commit/undelegate performs
+        let mut game = spawn(shielded[0]);
+        game.pick_up(Powerup::Shield);
+        let bytes = encode(&game);
+        let decoded = GameState::deserialize(&mut bytes.as_slice()).unwrap();
+        assert!(decoded.has_shield() && decoded.pipes[0].shield());
+        assert_eq!(encode(&decoded), bytes);
+    }
+
+    #[test]
+    fn coins_are_collected_reported_and_banked() {
+        let mut game = pipe_on_bird(GAME_HEIGHT / 2);
+        game.pipes[0].set_coin(true);
+        game.coins = 5;
+        game.bird_y = (GAME_HEIGHT / 2 - BIRD_SIZE / 2) * 1000;
+        game.bird_velocity = -GRAVITY;
+        let report = step_physics(&mut game);
+        assert_eq!(report.coins_collected, 1);
+        assert!(!game.pipes[0].coin());
+        assert_eq!(game.coins, 6);
+
+        // The balance survives a reset
+        reset_run(&mut game);
+        assert_eq!(game.coins, 6);
+
+        // Coin and shield never share a gap
+        for i in 0..500u64 {
+            game.seed = 1_700_000_000 + i * 7_919;
+            game.pipes = [Pipe::default(); MAX_PIPES];
+            spawn_pipes(&mut game);
+            assert!(!(game.pipes[0].coin() && game.pipes[0].shield()));
+        }
+    }
+
+    #[test]
+    fn spend_coins_rejects_overdraw() {
+        let mut game = GameState { coins: 3, ..Default::default() };
+        assert!(game.spend_coins(4).is_err());
+        assert_eq!(game.coins, 3);
+        game.spend_coins(3).unwrap();
+        assert_eq!(game.coins, 0);
+    }
+
+    #[test]
+    fn reveal_must_match_and_follow_the_commitment() {
+        let mut game = GameState::default();
+        assert!(check_reveal(&game, 7, 10).is_err());
+
+        game.commitment = commitment_for(7);
+        game.commitment_slot = 10;
+        assert_eq!(check_reveal(&game, 7, 10), err!(FlappyError::RevealTooEarly));
+        assert_eq!(check_reveal(&game, 8, 11), err!(FlappyError::InvalidReveal));
+        assert!(check_reveal(&game, 7, 11).is_ok());
+    }
+
+    #[test]
+    fn seed_depends_on_every_input() {
+        let base = mix_seed(7, 11, &[1; 32]);
+        assert_eq!(base, mix_seed(7, 11, &[1; 32]));
+        assert_ne!(base, mix_seed(8, 11, &[1; 32]));
+        assert_ne!(base, mix_seed(7, 12, &[1; 32]));
+        assert_ne!(base, mix_seed(7, 11, &[2; 32]));
+    }
+
+    /// Old end-of-frame check, kept to show what the swept test catches
+    fn discrete_collision(bird_y: i32, pipe: &Pipe, pipe_gap: i32) -> bool {
+        BIRD_X + BIRD_SIZE > pipe.x_pixels()
+            && BIRD_X < pipe.x_pixels() + PIPE_WIDTH
+            && (bird_y < pipe.gap_y - pipe_gap / 2 || bird_y + BIRD_SIZE > pipe.gap_y + pipe_gap / 2)
+    }
+
+    #[test]
+    fn swept_collision_catches_corner_clipping() {
+        // Falling fast past the top pipe's lower-left corner: above the gap when
+        // the leading edge arrives, inside it by the end of the frame
+        let mut game = playing_at_slot(0);
+        game.pipes[0] = Pipe {
+            x: (BIRD_X + BIRD_SIZE + 1) * 1000,
+            gap_y: 200,
+            active: 1,
+            ..Default::default()
+        };
+        let gap_top = 200 - PIPE_GAP / 2;
+        game.bird_y = (gap_top - 8) * 1000;
+        game.bird_velocity = MAX_VELOCITY;
+        let prev_y = game.bird_y / 1000;
+        let report = step_physics(&mut game);
+        let bird_y = game.bird_y / 1000;
+        assert!(bird_y >= gap_top && game.pipes[0].x_pixels() < BIRD_X + BIRD_SIZE);
+        assert!(!discrete_collision(bird_y, &game.pipes[0], game.pipe_gap));
+        assert!(check_pipe_collision(prev_y, bird_y, BIRD_X * 1000, BIRD_SIZE, &game.pipes[0], game.pipe_gap, game.pipe_speed));
+        assert_eq!(report.death, Some(DeathCause::Pipe));
+    }
+
+    #[test]
+    fn swept_collision_catches_a_pipe_crossing_the_bird_in_one_frame() {
+        // A pipe fast enough to jump from fully right of the bird to fully left
+        let speed = (PIPE_WIDTH + BIRD_SIZE + 10) * 1000;
+        let pipe = Pipe {
+            x: (BIRD_X + BIRD_SIZE + 5) * 1000 - speed,
+            gap_y: 200,
+            active: 1,
+            ..Default::default()
+        };
+        assert!(!discrete_collision(40, &pipe, PIPE_GAP));
+        assert!(check_pipe_collision(40, 40, BIRD_X * 1000, BIRD_SIZE, &pipe, PIPE_GAP, speed));
+        // Flying through the gap at the same speed is fine
+        assert!(!check_pipe_collision(185, 185, BIRD_X * 1000, BIRD_SIZE, &pipe, PIPE_GAP, speed));
+    }
+
+    #[test]
+    fn tick_bursts_are_capped_per_second() {
+        let mut game = playing_at_slot(0);
+        game.bird_y = 100 * 1000;
+        // 500 ticks in fresh slots within two wall-clock seconds
+        let mut outcomes = Vec::new();
+        for slot in 1..=500u64 {
+            let now = 1_000 + (slot > 250) as i64;
+            game.set_pending_flap(game.pending_flap() | autopilot_flap(&game));
+            outcomes.push(tick_rate_limited(&mut game, slot, now, &mut ignore));
+        }
+        let advanced = outcomes.iter().filter(|o| **o == TickOutcome::Advanced).count();
+        assert_eq!(advanced as u64, 2 * MAX_FRAMES_PER_SECOND as u64);
+        assert_eq!(game.frame_count, advanced as u64);
+        assert_eq!(outcomes[MAX_FRAMES_PER_SECOND as usize], TickOutcome::RateLimited);
+    }
+
+    #[test]
+    fn batches_share_the_per_second_budget() {
+        let mut game = playing_at_slot(0);
+        game.bird_y = 100 * 1000;
+        // A late batch still catches up a handful of extra frames
+        assert_eq!(advance_frames_rate_limited(&mut game, 8, 8, 50, &mut ignore), 8);
+        let mut slot = 8;
+        let mut frames = 8u64;
+        for _ in 0..10 {
+            slot += 10;
+            game.bird_velocity = JUMP_VELOCITY;
+            frames += advance_frames_rate_limited(&mut game, 10, slot, 50, &mut ignore) as u64;
+        }
+        assert_eq!(frames, MAX_FRAMES_PER_SECOND as u64);
+        assert_eq!(tick_rate_limited(&mut game, slot + 1, 50, &mut ignore), TickOutcome::RateLimited);
+        // The next second opens a new window
+        assert_eq!(tick_rate_limited(&mut game, slot + 1, 51, &mut ignore), TickOutcome::Advanced);
+    }
+
+    #[test]
+    fn tick_multiple_stops_at_game_over() {
+        let mut game = playing_at_slot(0);
+        // Without flaps the bird hits the floor after 29 frames
+        let frames = advance_frames(&mut game, MAX_TICKS_PER_BATCH, 1_000, &mut ignore);
+        assert_eq!(frames, 29);
+        assert_eq!(game.frame_count, 29);
+        assert!(game.game_status() == GameStatus::GameOver);
+    }
+
+    #[test]
+    fn tick_multiple_is_capped() {
+        let mut game = playing_at_slot(0);
+        game.bird_velocity = JUMP_VELOCITY * 2;
+        assert_eq!(advance_frames(&mut game, u8::MAX, 3, &mut ignore), 3);
+        let mut game = playing_at_slot(0);
+        game.bird_y = 100 * 1000;
+        assert_eq!(advance_frames(&mut game, u8::MAX, 1_000, &mut ignore), MAX_TICKS_PER_BATCH);
+        assert_eq!(game.frame_count, MAX_TICKS_PER_BATCH as u64);
+    }
+
+    #[test]
+    fn transfer_keeps_lifetime_fields_and_starts_fresh() {
+        let mut old = playing_at_slot(0);
+        old.high_scores = [3, 9, 1];
+        old.high_score = 9;
+        old.coins = 40;
+        old.set_difficulty(Difficulty::Hard);
+        old.game_slot = 2;
+        run_until_over(&mut old, |_| false);
+        old.high_score_timestamp = 1_000;
+        old.high_score_frame = 640;
+        old.high_score_seed = 77;
+        old.high_score_assisted = 1;
+        old.distance_high_score_assisted = 1;
+        old.last_game_over_at = 900;
+        
+        let new_authority = Pubkey::new_unique();
+        let game = old.transferred(new_authority, 254, 1_234);
+        assert_eq!((game.authority, game.bump), (new_authority, 254));
+        assert_eq!((game.high_score, game.high_scores, game.coins), (9, [3, 9, 1], 40));
+        assert_eq!((game.high_score_timestamp, game.high_score_frame, game.high_score_seed), (1_000, 640, 77));
+        assert_eq!((game.high_score_assisted, game.distance_high_score_assisted), (1, 1));
+        assert_eq!(game.last_game_over_at, 900);
+        assert_eq!(game.total_distance, old.total_distance);
+        assert_eq!(game.game_slot, 2);
+        assert!(game.game_status() == GameStatus::NotStarted);
+        assert_eq!((game.score, game.frame_count), (0, 0));
+        assert!(game.pipes.iter().all(|p| !p.active()));
+        assert_eq!(game.pipe_gap, Difficulty::Hard.params(&PhysicsConfig::default()).pipe_gap);
+    }
+
+    #[test]
+    fn run_plays_its_physics_snapshot() {
+        assert!(PhysicsConfig::default().validate().is_ok());
+        let tuned = PhysicsConfig {
+            gravity: 500,
+            jump_velocity: -7000,
+            pipe_speed: 9500,
+            pipe_gap: 150,
+            pipe_spawn_distance: 300,
+            near_miss_threshold: 5,
+        };
+        assert!(tuned.validate().is_ok());
+        assert_eq!(
+            Difficulty::Easy.params(&tuned),
+            DifficultyParams { gravity: 450, pipe_gap: 170, pipe_speed: 8500 }
+        );
+        
+        let mut game = GameState { physics: tuned, ..Default::default() };
+        reset_run(&mut game);
+        game.set_game_status(GameStatus::Playing);
+        assert_eq!((game.gravity, game.pipe_gap, game.pipe_speed), (500, 150, 9500));
+        game.set_pending_flap(true);
+        step_physics(&mut game);
+        assert_eq!(game.bird_velocity, -7000 + 500);
+        
+        // Fractional speed: the first pipe is 9.5 px further left each frame
+        let x = game.pipes[0].x;
+        step_physics(&mut game);
+        assert_eq!(game.pipes[0].x, x - 9500);
+        
+        for bad in [
+            PhysicsConfig { gravity: 0, ..tuned },
+            PhysicsConfig { jump_velocity: 1000, ..tuned },
+            PhysicsConfig { pipe_gap: GAME_HEIGHT, ..tuned },
+            PhysicsConfig { pipe_spawn_distance: PIPE_WIDTH, ..tuned },
+            PhysicsConfig { near_miss_threshold: -1, ..tuned },
+            PhysicsConfig { near_miss_threshold: RAMP_GAP_MIN, ..tuned },
+        ] {
+            assert_eq!(bad.validate(), err!(FlappyError::InvalidConfig));
+        }
+    }
+
+    #[test]
+    fn seed_and_input_log_replay_the_run() {
+        let mut game = playing_at_slot(0);
+        game.seed = 1_700_000_000;
+        game.run_seed = game.seed;
+        while game.game_status() == GameStatus::Playing && game.frame_count < 1_000 {
+            if autopilot_flap(&game) {
+                record_flap(&mut game).unwrap();
+                game.set_pending_flap(true);
+            }
+            step_physics(&mut game);
+        }
+        assert!(game.score > 0);
+        
+        let mut replay = playing_at_slot(0);
+        replay.seed = game.run_seed;
+        while replay.frame_count < game.frame_count {
+            replay.set_pending_flap(game.input_log.flapped_after(replay.frame_count));
+            step_physics(&mut replay);
+        }
+        assert_eq!(
+            (replay.score, replay.bird_y, replay.bird_velocity, replay.seed),
+            (game.score, game.bird_y, game.bird_velocity, game.seed)
+        );
+        assert!(replay.game_status() == game.game_status());
+        
+        reset_run(&mut game);
+        assert_eq!(game.input_log, InputLog::default());
+    }
+
+    /// An autopiloted run from `seed`, as the parameters to replay it
+    fn recorded_run(seed: u64) -> ReplayParams {
+        let mut params = ReplayParams { seed, physics: PhysicsConfig::default(), ..Default::default() };
+        let mut game = replay_start_state(&params);
+        while game.game_status() == GameStatus::Playing && game.frame_count < 600 {
+            if autopilot_flap(&game) {
+                record_flap(&mut game).unwrap();
+                game.set_pending_flap(true);
+            }
+            step_physics(&mut game);
+        }
+        params.inputs = game.input_log;
+        params.frames = game.frame_count;
+        params.claimed_score = game.score;
+        params
+    }
+
+    #[test]
+    fn replay_verifies_across_calls() {
+        let params = recorded_run(1_700_000_000);
+        assert!(params.claimed_score > 0);
+        let mut cursor = ReplayCursor::default();
+        let mut calls = 0;
+        while let ReplayOutcome::InProgress { frame } = advance_replay(&mut cursor, &params, 100).unwrap() {
+            calls += 1;
+            assert_eq!(frame, calls * 100);
+        }
+        assert_eq!(calls, (params.frames - 1) / 100);
+        assert_eq!(cursor.state.frame_count, params.frames);
+    }
+
+    #[test]
+    fn replay_rejects_a_wrong_claim() {
+        let honest = recorded_run(1_700_000_000);
+        let inflated = ReplayParams { claimed_score: honest.claimed_score + 1, ..honest };
+        let longer = ReplayParams { frames: honest.frames + 50, ..honest };
+        let mut no_flaps = honest;
+        no_flaps.inputs = InputLog::default();
+        for params in [inflated, longer, no_flaps] {
+            let mut cursor = ReplayCursor::default();
+            assert_eq!(advance_replay(&mut cursor, &params, u32::MAX), err!(FlappyError::ReplayMismatch));
+        }
+        
+        // A cursor left mid-way by other params starts over
+        let mut cursor = ReplayCursor::default();
+        advance_replay(&mut cursor, &inflated, 100).unwrap();
+        assert_eq!(advance_replay(&mut cursor, &honest, u32::MAX), Ok(ReplayOutcome::Verified));
+    }
+
+    #[test]
+    fn flaps_past_the_log_window_are_rejected() {
+        let mut game = playing_at_slot(0);
+        game.frame_count = INPUT_LOG_FRAMES - 1;
+        record_flap(&mut game).unwrap();
+        assert!(game.input_log.flapped_after(INPUT_LOG_FRAMES - 1));
+        game.frame_count = INPUT_LOG_FRAMES;
+        assert_eq!(record_flap(&mut game), err!(FlappyError::InputLogFull));
+        assert!(!game.input_log.flapped_after(INPUT_LOG_FRAMES));
+        assert_eq!(game.input_log.flaps(), 1);
+    }
+
+    #[test]
+    fn pause_stops_the_clock_until_resume() {
+        let mut game = playing_at_slot(10);
+        game.bird_y = 100 * 1000;
+        assert_eq!(resume_run(&mut game, 1_000, 10), err!(FlappyError::GameNotPaused));
+        pause_run(&mut game, 1_000).unwrap();
+        assert!(game.game_status() == GameStatus::Paused && game.game_status().in_run());
+        assert_eq!(require_playing(&game), err!(FlappyError::GamePaused));
+        assert_eq!(pause_run(&mut game, 1_001), err!(FlappyError::GameNotPlaying));
+        
+        // Resuming 500 slots later doesn't hand out 500 slots of catch-up frames
+        assert_eq!(resume_run(&mut game, 1_000 + MAX_PAUSE_SECONDS, 510), Ok(true));
+        assert!(game.game_status() == GameStatus::Playing);
+        assert_eq!(game.last_update, 1_000 + MAX_PAUSE_SECONDS);
+        assert_eq!(advance_frames(&mut game, MAX_TICKS_PER_BATCH, 512, &mut ignore), 2);
+    }
+
+    #[test]
+    fn resuming_after_the_pause_limit_ends_the_run() {
+        let mut game = playing_at_slot(0);
+        game.score = 12;
+        pause_run(&mut game, 1_000).unwrap();
+        assert_eq!(resume_run(&mut game, 1_001 + MAX_PAUSE_SECONDS, 1), Ok(false));
+        assert!(game.game_status() == GameStatus::GameOver);
+        assert_eq!(game.high_score, 12);
+    }
+
+    fn tournament_with(pool: u64, players: u8) -> (Tournament, Vec<Pubkey>) {
+        let mut tournament = Tournament { prize_pool: pool, ..Default::default() };
+        let players: Vec<Pubkey> = (0..players).map(|i| Pubkey::new_from_array([i + 1; 32])).collect();
+        for player in &players {
+            tournament.register(*player).unwrap();
+        }
+        (tournament, players)
+    }
+
+    #[test]
+    fn tournament_entry_is_once_per_player_and_capped() {
+        let (mut tournament, players) = tournament_with(0, TOURNAMENT_MAX_ENTRANTS as u8);
+        assert_eq!(tournament.register(players[0]), err!(FlappyError::AlreadyJoined));
+        assert_eq!(tournament.register(Pubkey::new_unique()), err!(FlappyError::TournamentFull));
+        assert_eq!(
+            tournament.submit(Pubkey::new_unique(), 10),
+            err!(FlappyError::NotInTournament)
+        );
+    }
+
+    #[test]
+    fn tournament_keeps_best_score_and_breaks_ties_by_submission() {
+        let (mut tournament, p) = tournament_with(1_000, 4);
+        assert_eq!(tournament.submit(p[0], 20), Ok(true));
+        assert_eq!(tournament.submit(p[1], 30), Ok(true));
+        assert_eq!(tournament.submit(p[2], 30), Ok(true));
+        assert_eq!(tournament.submit(p[1], 25), Ok(false));
+        assert_eq!(tournament.submit(p[1], 30), Ok(false));
+        // p[3] never submits, so only three places are paid
+        let (payouts, leftover) = tournament.payouts();
+        assert_eq!(payouts, vec![(p[1], 500), (p[2], 300), (p[0], 200)]);
+        assert_eq!(leftover, 0);
+        
+        // Improving moves p[0] to the top
+        assert_eq!(tournament.submit(p[0], 40), Ok(true));
+        assert_eq!(tournament.payouts().0[0], (p[0], 500));
+    }
+
+    #[test]
+    fn unclaimed_places_and_dust_stay_with_the_organizer() {
+        let (mut tournament, p) = tournament_with(1_001, 2);
+        tournament.submit(p[1], 5).unwrap();
+        let (payouts, leftover) = tournament.payouts();
+        assert_eq!(payouts, vec![(p[1], 500)]);
+        assert_eq!(leftover, 501);
+        
+        let (empty, _) = tournament_with(7, 0);
+        assert_eq!(empty.payouts(), (vec![], 7));
+    }
+
+    #[test]
+    fn a_tournament_ranks_only_runs_on_its_difficulty() {
+        let clock = Clock { slot: 1_000, unix_timestamp: 1_000, ..Default::default() };
+        let tournament = Tournament { start_time: 900, end_time: 2_000, ..Default::default() };
+        let mut game = GameState::default();
+        for difficulty in [Difficulty::Normal, Difficulty::Easy] {
+            begin_run(&mut game, None, difficulty, ScoringMode::PipesPassed, 7, &clock);
+            assert_eq!(tournament.check_run(&game), err!(FlappyError::GameNotOver));
+            end_once(&mut game, 1_100).unwrap();
+        }
+        assert_eq!(tournament.check_run(&game), err!(FlappyError::WrongDifficulty));
+        
+        let easy = Tournament { difficulty: Difficulty::Easy, ..tournament };
+        assert_eq!(easy.check_run(&game), Ok(()));
+        game.last_update = 2_001;
+        assert_eq!(easy.check_run(&game), err!(FlappyError::RunOutsideTournament));
+    }
+
+    fn side(player: u8, score: u64, flaps: u32) -> MatchSide {
+        MatchSide {
+            player: Pubkey::new_from_array([player; 32]),
+            finished: true,
+            score,
+            flaps,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn match_goes_to_higher_score_then_fewer_flaps() {
+        let (a, b) = (side(1, 10, 30), side(2, 12, 40));
+        assert_eq!(decide_match(&a, &b), Some(b.player));
+        assert_eq!(decide_match(&a, &side(2, 10, 31)), Some(a.player));
+        assert_eq!(decide_match(&a, &side(2, 10, 30)), None);
+        
+        // A finished side beats one that never finished, whatever the score
+        let no_show = MatchSide { finished: false, ..side(2, 99, 0) };
+        assert_eq!(decide_match(&a, &no_show), Some(a.player));
+        assert_eq!(decide_match(&no_show, &MatchSide { finished: false, ..a }), None);
+    }
+
+    #[test]
+    fn wagers_pay_the_pot_to_the_winner_and_split_a_draw() {
+        let mut game_match = Match {
+            challenger: side(1, 10, 30),
+            opponent: side(2, 12, 40),
+            stake: 5,
+            pot: 11,
+            created_at: 1_000_000,
+            ..Default::default()
+        };
+        // The play window only opens once the wager is accepted
+        assert!(!game_match.accepted());
+        assert!(game_match.expired(1_000_000));
+        game_match.accepted_at = 1_000_100;
+        assert!(!game_match.expired(1_000_100 + MATCH_TIMEOUT_SECONDS));
+        assert!(game_match.expired(1_000_101 + MATCH_TIMEOUT_SECONDS));
+
+        game_match.winner = decide_match(&game_match.challenger, &game_match.opponent);
+        assert_eq!(game_match.payouts(), (0, 11));
+        game_match.winner = Some(game_match.challenger.player);
+        assert_eq!(game_match.payouts(), (11, 0));
+        game_match.winner = None;
+        assert_eq!(game_match.payouts(), (5, 6));
+    }
+
+    #[test]
+    fn match_runs_start_from_the_shared_seed() {
+        // Two games with different histories play the same pipes once started
+        let mut a = GameState { seed: 42, score: 7, difficulty: Difficulty::Easy as u8, ..Default::default() };
+        let mut b = GameState::default();
+        let clock = Clock { slot: 5, ..Default::default() };
+        for game in [&mut a, &mut b] {
+            begin_run(game, None, Difficulty::Hard, ScoringMode::PipesPassed, 1_700_000_000, &clock);
+            run_until_over(game, autopilot_flap);
+        }
+        assert_eq!(a.run_seed, b.run_seed);
+        assert_eq!(a.frame_count, b.frame_count);
+        assert_eq!(a.score, b.score);
+        assert_eq!(a.pipes.map(|p| (p.x, p.gap_y)), b.pipes.map(|p| (p.x, p.gap_y)));
+        assert!(a.score > 0);
+    }
+
+    #[test]
+    fn a_new_record_keeps_its_inputs_through_reset() {
+        let params = recorded_run(1_700_000_000);
+        let mut game = replay_start_state(&params);
+        game.input_log = params.inputs;
+        game.frame_count = params.frames;
+        game.score = params.claimed_score;
+        game.record_high_score();
+        let best = game.best_run();
+        assert_eq!(best, BestRun {
+            score: params.claimed_score,
+            seed: 1_700_000_000,
+            frames: params.frames as u32,
+            inputs: params.inputs,
+        });
+        
+        // A lower score later doesn't replace it, and reset leaves it alone
+        reset_run(&mut game);
+        record_flap(&mut game).unwrap();
+        game.score = 1;
+        game.record_high_score();
+        reset_run(&mut game);
+        assert_eq!(game.best_run(), best);
+    }
+
+    #[test]
+    fn snapshot_reports_the_bird_and_solid_pipe_parts_in_pixels() {
+        let mut game = playing_at_slot(0);
+        game.score = 3;
+        game.pipes[0] = Pipe { x: 100_500, gap_y: 200, active: 1, ..Default::default() };
+        game.pipes[1] = Pipe { x: 300_000, gap_y: 120, gap_y2: 330, pipe_type: PIPE_TYPE_DOUBLE, active: 1, ..Default::default() };
+        game.pipes[2] = Pipe { x: 200_000, gap_y: 200, ..Default::default() };
+        let snapshot = game.snapshot();
+        assert_eq!((snapshot.version, snapshot.score, snapshot.frame_count), (SNAPSHOT_VERSION, 3, 0));
+        assert!(snapshot.status == GameStatus::Playing);
+        assert_eq!(snapshot.bird, PixelRect { x: BIRD_X, y: game.bird_y / 1000, width: BIRD_SIZE, height: BIRD_SIZE });
+        
+        let half = game.pipe_gap / 2;
+        let rect = |x, top: i32, bottom: i32| PixelRect { x, y: top, width: PIPE_WIDTH, height: bottom - top };
+        assert_eq!(snapshot.pipes, vec![
+            rect(100, 0, 200 - half),
+            rect(100, 200 + half, GAME_HEIGHT),
+            rect(300, 0, 120 - half),
+            rect(300, 120 + half, 330 - half),
+            rect(300, 330 + half, GAME_HEIGHT),
+        ]);
+        // Clients can check the version before decoding the rest
+        assert_eq!(snapshot.try_to_vec().unwrap()[0], SNAPSHOT_VERSION);
+    }
+
+    #[test]
+    fn rewards_pay_each_personal_best_point_once() {
+        let mut game = playing_at_slot(0);
+        game.score = 12;
+        finish_run(&mut game, DeathCause::Pipe, 0);
+        assert_eq!(game.claim_reward(), 12);
+        assert_eq!(game.claim_reward(), 0);
+        
+        // A worse run pays nothing; beating the record pays the difference
+        for (score, reward) in [(8, 0), (12, 0), (20, 8)] {
+            reset_run(&mut game);
+            game.set_game_status(GameStatus::Playing);
+            game.score = score;
+            assert_eq!(game.claim_reward(), 0);
+            finish_run(&mut game, DeathCause::Pipe, 0);
+            assert_eq!(game.claim_reward(), reward);
+        }
+        assert_eq!(game.rewarded_score, 20);
+    }
+
+    #[test]
+    fn achievements_follow_their_conditions() {
+        let bit = |id: u8| 1u64 << id;
+        let mut game = playing_at_slot(0);
+        let mut profile = PlayerProfile::default();
+        assert_eq!(evaluate_achievements(&game, &profile), 0);
+        
+        profile.pipes_passed = 1;
+        assert_eq!(evaluate_achievements(&game, &profile), bit(ACHIEVEMENT_FIRST_PIPE));
+        profile.pipes_passed = 0;
+        for (score, ids) in [
+            (9, vec![]),
+            (10, vec![ACHIEVEMENT_SCORE_10]),
+            (25, vec![ACHIEVEMENT_SCORE_10, ACHIEVEMENT_SCORE_25]),
+            (50, vec![ACHIEVEMENT_SCORE_10, ACHIEVEMENT_SCORE_25, ACHIEVEMENT_SCORE_50]),
+            (100, vec![ACHIEVEMENT_SCORE_10, ACHIEVEMENT_SCORE_25, ACHIEVEMENT_SCORE_50, ACHIEVEMENT_SCORE_100]),
+        ] {
+            game.score = score;
+            assert_eq!(evaluate_achievements(&game, &profile), ids.into_iter().map(bit).sum::<u64>());
+        }
+        game.score = 0;
+        
+        profile.frames_survived = SURVIVOR_FRAMES - 1;
+        assert_eq!(evaluate_achievements(&game, &profile), 0);
+        profile.frames_survived = SURVIVOR_FRAMES;
+        assert_eq!(evaluate_achievements(&game, &profile), bit(ACHIEVEMENT_SURVIVOR));
+    }
+
+    #[test]
+    fn flapless_achievement_needs_a_full_window() {
+        let profile = PlayerProfile::default();
+        let mut game = playing_at_slot(0);
+        record_flap(&mut game).unwrap();
+        game.frame_count = FLAPLESS_FRAMES;
+        // The flap queued after frame 0 is inside the last 100 frames
+        assert_eq!(evaluate_achievements(&game, &profile), 0);
+        game.frame_count = FLAPLESS_FRAMES + 1;
+        assert_eq!(evaluate_achievements(&game, &profile), 1 << ACHIEVEMENT_NO_FLAPS);
+        game.frame_count = FLAPLESS_FRAMES - 1;
+        game.input_log = InputLog::default();
+        assert_eq!(evaluate_achievements(&game, &profile), 0);
+    }
+
+    #[test]
+    fn achievements_unlock_once() {
+        let mut game = playing_at_slot(0);
+        let mut profile = PlayerProfile::default();
+        game.score = 10;
+        assert_eq!(profile.unlock_achievements(&game), 1 << ACHIEVEMENT_SCORE_10);
+        assert_eq!(profile.unlock_achievements(&game), 0);
+        
+        // Earned bits stay set on a later, worse run
+        game.score = 25;
+        assert_eq!(profile.unlock_achievements(&game), 1 << ACHIEVEMENT_SCORE_25);
+        game.score = 0;
+        assert_eq!(profile.unlock_achievements(&game), 0);
+        assert_eq!(profile.achievements, 1 << ACHIEVEMENT_SCORE_10 | 1 << ACHIEVEMENT_SCORE_25);
+    }
+
+    #[test]
+    fn daily_seed_is_shared_per_day() {
+        let day = day_of(1_700_000_000);
+        assert_eq!(day, 19_675);
+        assert_eq!(daily_seed(day), daily_seed(day_of(19_675 * SECONDS_PER_DAY)));
+        assert_ne!(daily_seed(day), daily_seed(day + 1));
+        assert_eq!(day_of(19_676 * SECONDS_PER_DAY - 1), day);
+        assert_eq!(day_of(19_676 * SECONDS_PER_DAY), day + 1);
+    }
+
+    #[test]
+    fn daily_runs_post_only_to_their_own_day() {
+        let midnight = 19_676 * SECONDS_PER_DAY;
+        let mut game = playing_at_slot(0);
+        game.set_daily(true);
+        game.daily_day = day_of(midnight - 60);
+        game.last_update = midnight - 1;
+        assert_eq!(daily_board_day(&game), err!(FlappyError::GameNotOver));
+        
+        game.set_game_status(GameStatus::GameOver);
+        assert_eq!(daily_board_day(&game), Ok(19_675));
+        
+        // Started yesterday, still flying after midnight: no board takes it
+        game.last_update = midnight;
+        assert_eq!(daily_board_day(&game), err!(FlappyError::DailyRunCrossedDay));
+        
+        // Any other start clears the flag
+        reset_run(&mut game);
+        game.set_game_status(GameStatus::GameOver);
+        assert_eq!(daily_board_day(&game), err!(FlappyError::NotDailyRun));
+    }
+
+    #[test]
+    fn season_index_cuts_over_at_the_boundary() {
+        let seasons = SeasonConfig { start: 1_700_000_000, duration: 1_000, bump: 0 };
+        assert_eq!(seasons.index_at(1_699_999_999), None);
+        assert_eq!(seasons.index_at(1_700_000_000), Some(0));
+        assert_eq!(seasons.index_at(1_700_000_999), Some(0));
+        assert_eq!(seasons.index_at(1_700_001_000), Some(1));
+        assert_eq!(seasons.season_start(3), 1_700_003_000);
+        assert_eq!(seasons.season_for_run(1_699_999_999, 0), Ok(None));
+    }
+
+    #[test]
+    fn a_run_counts_for_exactly_one_season() {
+        let seasons = SeasonConfig { start: 1_700_000_000, duration: 1_000, bump: 0 };
+        let boundary = seasons.season_start(1);
+        
+        // Ended in season 0: counts there, and is refused once season 1 starts
+        assert_eq!(seasons.season_for_run(boundary - 1, boundary - 1), Ok(Some(0)));
+        assert_eq!(
+            seasons.season_for_run(boundary, boundary - 1),
+            err!(FlappyError::RunBeforeSeason)
+        );
+        
+        // Started in season 0 but last updated in season 1: season 1 only
+        assert_eq!(seasons.season_for_run(boundary + 5, boundary), Ok(Some(1)));
+    }
+
+    #[test]
+    fn catch_up_runs_elapsed_frames_and_converges() {
+        let mut game = playing_at_slot(0);
+        game.bird_velocity = game.physics.jump_velocity;
+        game.last_update = 1_000;
+        assert_eq!(catch_up_frames(&mut game, 1_000, 5, &mut ignore), 0);
+        // Two seconds owe 40 frames, which use up the second's budget
+        assert_eq!(catch_up_frames(&mut game, 1_002, 6, &mut ignore), 40);
+        assert!(game.game_status() == GameStatus::Playing);
+        assert_eq!((game.frame_count, game.last_update, game.last_tick_slot), (40, 1_002, 6));
+        assert_eq!(rate_budget(&mut game, 1_002), 0);
+        
+        // A long absence is capped, and the bird falls to its death on the way
+        let frames = catch_up_frames(&mut game, 1_600, 7, &mut ignore);
+        assert!(frames as u64 <= MAX_CATCH_UP_FRAMES);
+        assert!(game.game_status() == GameStatus::GameOver);
+        assert_eq!(game.last_update, 1_600);
+        assert_eq!(catch_up_frames(&mut game, 1_700, 8, &mut ignore), 0);
+        assert_eq!(game.last_update, 1_600);
+    }
+
+    #[test]
+    fn catch_up_caps_and_drops_the_excess() {
+        let mut game = playing_at_slot(0);
+        game.last_update = 1_000;
+        game.physics.gravity = 0;
+        reset_run(&mut game);
+        // With no gravity the bird hovers, so only the cap stops an hour's catch-up
+        let frames = catch_up_frames(&mut game, 1_000 + 3_600, 1, &mut ignore);
+        assert_eq!(frames as u64, MAX_CATCH_UP_FRAMES);
+        assert!(game.game_status() == GameStatus::Playing);
+        assert_eq!(catch_up_frames(&mut game, 1_000 + 3_600, 2, &mut ignore), 0);
+    }
+
+    #[test]
+    fn update_game_physics_needs_no_runtime() {
+        let mut game = playing_at_slot(10);
+        let mut profile = PlayerProfile::default();
+        game.last_update = 1_000;
+        assert_eq!(update_game_physics(&mut game, 10, 1_001, Some(&mut profile), None, None), TickOutcome::TooEarly);
+        assert_eq!(update_game_physics(&mut game, 11, 1_001, Some(&mut profile), None, None), TickOutcome::Advanced);
+        assert_eq!(update_game_physics(&mut game, 12, 1_001, None, None, None), TickOutcome::Advanced);
+        assert_eq!((game.frame_count, profile.frames_survived), (2, 1));
+        // Timestamps are the handlers' job
+        assert_eq!(game.last_update, 1_000);
+        
+        mark_updated(&mut game, &Clock { slot: 12, unix_timestamp: 1_001, ..Default::default() });
+        assert_eq!((game.last_update, game.last_update_slot), (1_001, 12));
+    }
+
+    #[test]
+    fn extreme_bird_positions_saturate_instead_of_wrapping() {
+        let mut game = playing_at_slot(0);
+        // A million frames of falling at full speed from the bottom of the i32 range
+        for _ in 0..1_000_000 {
+            game.set_game_status(GameStatus::Playing);
+            game.bird_y = i32::MAX - 1;
+            game.bird_velocity = MAX_VELOCITY;
+            assert_eq!(step_physics(&mut game).death, Some(DeathCause::Floor));
+            assert_eq!(game.bird_y, i32::MAX);
+        }
+        assert_eq!(game.frame_count, 1_000_000);
+        
+        game.set_game_status(GameStatus::Playing);
+        game.bird_y = i32::MIN + 1;
+        game.bird_velocity = -MAX_VELOCITY;
+        game.gravity = 0;
+        game.physics.gravity = 0;
+        assert_eq!(step_physics(&mut game).death, Some(DeathCause::Ceiling));
+        assert_eq!(game.bird_y, i32::MIN);
+    }
+
+    #[test]
+    fn counters_saturate_at_their_limits() {
+        let mut game = playing_at_slot(0);
+        game.seed = 1_700_000_000;
+        game.score = u64::MAX - 1;
+        game.frame_count = u64::MAX - 2;
+        let mut passed = 0;
+        for _ in 0..5_000 {
+            game.set_pending_flap(autopilot_flap(&game));
+            passed += step_physics(&mut game).pipes_passed;
+            if game.game_status() != GameStatus::Playing {
+                break;
+            }
+        }
+        assert!(passed >= 2);
+        assert_eq!(game.frame_count, u64::MAX);
+        assert_eq!(game.score, u64::MAX);
+        assert_eq!(game.high_score, u64::MAX);
+        
+        // Moving pipes at the last frame numbers stay inside the play band
+        let pipe = Pipe { amplitude: OSCILLATION_AMPLITUDE_MAX, phase: u16::MAX, ..Default::default() };
+        let gap_y = oscillating_gap_y(&pipe, u64::MAX, PIPE_GAP, GAME_HEIGHT);
+        assert!((PIPE_HEIGHT_MIN + PIPE_GAP / 2..=GAME_HEIGHT - PIPE_HEIGHT_MIN - PIPE_GAP / 2).contains(&gap_y));
+    }
+
+    #[test]
+    fn replays_reject_out_of_range_physics() {
+        let mut params = recorded_run(1_700_000_000);
+        params.physics.gravity = i32::MAX;
+        let mut cursor = ReplayCursor::default();
+        assert_eq!(advance_replay(&mut cursor, &params, 100), err!(FlappyError::InvalidConfig));
+    }
+
+    #[test]
+    fn distance_mode_plays_the_same_run_and_scores_the_scroll() {
+        let mut pipes = playing_at_slot(0);
+        pipes.seed = 1_700_000_000;
+        let mut distance = pipes;
+        distance.set_scoring(ScoringMode::Distance);
+        while pipes.game_status() == GameStatus::Playing && pipes.frame_count < 5_000 {
+            let flap = autopilot_flap(&pipes);
+            pipes.set_pending_flap(flap);
+            distance.set_pending_flap(flap);
+            step_physics(&mut pipes);
+            step_physics(&mut distance);
+            assert_eq!((distance.bird_y, distance.bird_velocity), (pipes.bird_y, pipes.bird_velocity));
+            assert!(distance.pipes == pipes.pipes);
+            assert_eq!(distance.pipes_passed, pipes.score);
+            assert_eq!(distance.score, distance.distance / 1000);
+        }
+        assert!(distance.game_status() == pipes.game_status());
+        assert!(pipes.score > 0);
+        assert!(distance.score > pipes.score);
+        
+        // Each mode keeps its own record, and only pipes earn rewards
+        assert_eq!((pipes.high_score, pipes.distance_high_score), (pipes.score, 0));
+        assert_eq!((distance.high_score, distance.distance_high_score), (0, distance.score));
+        assert_eq!(distance.mode_high_score(), distance.score);
+        assert_eq!(distance.claim_reward(), 0);
+        assert_eq!(pipes.claim_reward(), pipes.score);
+    }
+
+    #[test]
+    fn leaderboard_entries_carry_the_scoring_mode() {
+        let mut game = playing_at_slot(0);
+        game.score = 42;
+        assert_eq!(LeaderboardEntry::for_run(&game, 7).mode, ScoringMode::PipesPassed as u8);
+        game.set_scoring(ScoringMode::Distance);
+        let entry = LeaderboardEntry::for_run(&game, 7);
+        assert_eq!((entry.score, entry.timestamp, entry.mode), (42, 7, ScoringMode::Distance as u8));
+        
+        let mut profile = PlayerProfile::default();
+        profile.record_run_end(&mut game, 7);
+        assert_eq!(profile.best_score, 0);
+    }
+
+    #[test]
+    fn classic_runs_keep_the_bird_at_bird_x() {
+        let mut game = GameState { bird_x: 0, bird_x_velocity: MAX_HORIZONTAL_VELOCITY, ..playing_at_slot(0) };
+        step_physics(&mut game);
+        assert_eq!(game.bird_x, BIRD_X * 1000);
+    }
+
+    #[test]
+    fn dashes_decay_and_stop_at_the_band_edges() {
+        let mut game = playing_at_slot(0);
+        game.set_horizontal_control(true);
+        game.bird_x_velocity = DASH_IMPULSE;
+        assert_eq!(step_horizontal(&mut game), DASH_IMPULSE);
+        assert_eq!(game.bird_x_velocity, DASH_IMPULSE * (HORIZONTAL_DRAG - 1) / HORIZONTAL_DRAG);
+        for _ in 0..100 {
+            step_horizontal(&mut game);
+        }
+        assert_eq!(game.bird_x_velocity, 0);
+        let drifted = game.bird_x;
+        assert!(drifted > (BIRD_X + 2) * 1000 && drifted < (BIRD_X + 10) * 1000);
+        
+        game.bird_x = (BIRD_X_MIN + 1) * 1000;
+        game.bird_x_velocity = -MAX_HORIZONTAL_VELOCITY;
+        assert_eq!(step_horizontal(&mut game), -1000);
+        assert_eq!((game.bird_x, game.bird_x_velocity), (BIRD_X_MIN * 1000, 0));
+        game.bird_x = BIRD_X_MAX * 1000;
+        game.bird_x_velocity = MAX_HORIZONTAL_VELOCITY;
+        assert_eq!(step_horizontal(&mut game), 0);
+        assert_eq!(game.bird_x, BIRD_X_MAX * 1000);
+    }
+
+    #[test]
+    fn pipe_collisions_follow_the_dashing_bird() {
+        // Outside the gap, with the pipe just ahead of the pinned bird
+        let pipe = Pipe { x: (BIRD_X + BIRD_SIZE + 10) * 1000, gap_y: 200, active: 1, ..Default::default() };
+        assert!(!check_pipe_collision(40, 40, BIRD_X * 1000, BIRD_SIZE, &pipe, PIPE_GAP, 0));
+        assert!(check_pipe_collision(40, 40, (BIRD_X + 20) * 1000, BIRD_SIZE, &pipe, PIPE_GAP, 0));
+        
+        // Dashing back out of a pipe faster than it scrolls still hits it
+        let behind = Pipe { x: (BIRD_X + BIRD_SIZE + 1) * 1000, ..pipe };
+        assert!(check_pipe_collision(40, 40, BIRD_X * 1000, BIRD_SIZE, &behind, PIPE_GAP, -2000));
+        assert!(!check_pipe_collision(185, 185, BIRD_X * 1000, BIRD_SIZE, &behind, PIPE_GAP, -2000));
+        
+        // And a pipe counts as passed once it's behind the bird, not BIRD_X
+        let mut game = playing_at_slot(0);
+        game.set_horizontal_control(true);
+        game.bird_x = BIRD_X_MAX * 1000;
+        game.pipes[0] = Pipe { x: (BIRD_X + 10) * 1000, active: 1, ..pipe };
+        game.bird_y = (200 - BIRD_SIZE / 2) * 1000;
+        game.bird_velocity = -GRAVITY;
+        assert_eq!(step_physics(&mut game).pipes_passed, 1);
+        assert_eq!(game.score, 1);
+    }
+
+    #[test]
+    fn wind_schedule_is_a_function_of_the_seed() {
+        let schedule = |seed| (0..2_000).map(|frame| wind_at(seed, frame)).collect::<Vec<_>>();
+        let gusts = schedule(1_700_000_000);
+        assert_eq!(gusts, schedule(1_700_000_000));
+        assert_ne!(gusts, schedule(1_700_000_001));
+        
+        // Calm to start, then one steady gust per period
+        assert!(gusts[..WIND_PERIOD as usize].iter().all(|w| *w == 0));
+        for period in gusts.chunks(WIND_PERIOD as usize).skip(1) {
+            let windy: Vec<_> = period.iter().filter(|w| **w != 0).collect();
+            assert_eq!(windy.len() as u64, WIND_GUST_FRAMES);
+            assert!(windy.iter().all(|w| *w == windy[0]));
+            assert!((WIND_MIN_PERCENT..=WIND_MAX_PERCENT).contains(&(windy[0].unsigned_abs() as u64)));
+        }
+    }
+
+    #[test]
+    fn windy_runs_replay_and_calm_runs_ignore_the_wind() {
+        let params = ReplayParams { seed: 1_700_000_000, wind: true, physics: PhysicsConfig::default(), ..Default::default() };
+        let mut windy = replay_start_state(&params);
+        let mut calm = replay_start_state(&ReplayParams { wind: false, ..params });
+        let mut gusty_frames = 0;
+        while windy.game_status() == GameStatus::Playing && windy.frame_count < 1_000 {
+            if autopilot_flap(&windy) {
+                record_flap(&mut windy).unwrap();
+                windy.set_pending_flap(true);
+            }
+            step_physics(&mut windy);
+            step_physics(&mut calm);
+            assert_eq!(windy.wind, wind_at(windy.run_seed, windy.frame_count));
+            assert_eq!(calm.wind, 0);
+            if windy.wind != 0 {
+                gusty_frames += 1;
+                assert_ne!(windy.gravity, GRAVITY);
+            }
+        }
+        assert!(gusty_frames > 0);
+        
+        let params = ReplayParams {
+            inputs: windy.input_log,
+            frames: windy.frame_count,
+            claimed_score: windy.score,
+            ..params
+        };
+        let mut cursor = ReplayCursor::default();
+        assert_eq!(advance_replay(&mut cursor, &params, u32::MAX), Ok(ReplayOutcome::Verified));
+    }
+
+    #[test]
+    fn inverted_birds_fall_up_and_flap_down() {
+        let mut game = playing_at_slot(0);
+        game.set_game_mode(GameMode::Inverted);
+        let reports = run_until_over(&mut game, |_| false);
+        assert_eq!(reports.last().unwrap().death, Some(DeathCause::Ceiling));
+        
+        let mut game = playing_at_slot(0);
+        game.set_game_mode(GameMode::Inverted);
+        game.set_pending_flap(true);
+        step_physics(&mut game);
+        assert_eq!(game.bird_velocity, -JUMP_VELOCITY - GRAVITY);
+        let reports = run_until_over(&mut game, |_| true);
+        assert_eq!(reports.last().unwrap().death, Some(DeathCause::Floor));
+    }
+
+    #[test]
+    fn tiny_birds_fit_where_classic_ones_clip() {
+        let pipe = Pipe { x: BIRD_X * 1000, gap_y: 200, active: 1, ..Default::default() };
+        let bird_y = 200 + PIPE_GAP / 2 - 20;
+        let tiny = GameMode::Tiny.params().bird_size;
+        assert!(check_pipe_collision(bird_y, bird_y, BIRD_X * 1000, BIRD_SIZE, &pipe, PIPE_GAP, 0));
+        assert!(!check_pipe_collision(bird_y, bird_y, BIRD_X * 1000, tiny, &pipe, PIPE_GAP, 0));
+        
+        // Near the floor too
+        let mut game = playing_at_slot(0);
+        game.set_game_mode(GameMode::Tiny);
+        game.bird_y = (GAME_HEIGHT - BIRD_SIZE) * 1000;
+        game.bird_velocity = -GRAVITY;
+        assert_eq!(step_physics(&mut game).death, None);
+    }
+
+    #[test]
+    fn other_modes_keep_their_own_high_scores() {
+        let mut game = playing_at_slot(0);
+        game.set_game_mode(GameMode::Inverted);
+        game.score = 5;
+        game.set_game_status(GameStatus::GameOver);
+        game.record_high_score();
+        assert_eq!((game.high_score, game.high_scores), (0, [0; 3]));
+        assert_eq!(game.mode_high_scores, [0, 5, 0]);
+        assert_eq!(game.mode_high_score(), 5);
+        assert_eq!(game.claim_reward(), 0);
+        let mut profile = PlayerProfile::default();
+        profile.record_run_end(&mut game, 7);
+        assert_eq!(profile.best_score, 0);
+        
+        // The mode sticks through a reset and a transfer
+        reset_run(&mut game);
+        assert_eq!(game.game_mode(), GameMode::Inverted);
+        let moved = game.transferred(Pubkey::new_unique(), 255, 0);
+        assert_eq!((moved.game_mode(), moved.mode_high_scores), (GameMode::Inverted, [0, 5, 0]));
+        
+        game.set_game_mode(GameMode::Classic);
+        game.score = 3;
+        game.record_high_score();
+        assert_eq!((game.high_score, game.mode_high_scores), (3, [3, 5, 0]));
+    }
+
+    #[test]
+    fn practice_runs_bounce_and_record_nothing() {
+        let mut game = playing_at_slot(0);
+        game.set_practice(true);
+        game.seed = 1_700_000_000;
+        run_until_over(&mut game, |_| false);
+        assert!(game.game_status() == GameStatus::Playing);
+        assert_eq!(game.frame_count, 5_000);
+        assert!(game.score > 0);
+        
+        let mut game = playing_at_slot(0);
+        game.set_practice(true);
+        run_until_over(&mut game, |_| true);
+        assert!(game.game_status() == GameStatus::Playing);
+        assert!(game.bird_y >= 1000);
+        
+        game.set_game_status(GameStatus::GameOver);
+        game.record_high_score();
+        assert_eq!((game.high_score, game.mode_high_scores), (0, [0; 3]));
+        assert_eq!(game.claim_reward(), 0);
+        
+        let mut profile = PlayerProfile::default();
+        let report = FrameReport { pipes_passed: 1, death: Some(DeathCause::Manual), ..Default::default() };
+        frame_hooks(Some(&mut profile), None, None, 7)(&mut game, &report);
+        profile.record_run_end(&mut game, 7);
+        assert_eq!((profile.frames_survived, profile.pipes_passed, profile.best_score), (0, 0, 0));
+        
+        // Starting over clears the flag
+        reset_run(&mut game);
+        assert!(!game.practice());
+    }
+
+    #[test]
+    fn practice_coins_are_not_credited() {
+        let mut game = playing_at_slot(0);
+        game.set_practice(true);
+        game.bird_y = (200 - BIRD_SIZE / 2) * 1000;
+        game.bird_velocity = -GRAVITY;
+        game.pipes[0] = Pipe { x: BIRD_X * 1000, gap_y: 200, active: 1, coin: 1, ..Default::default() };
+        let report = step_physics(&mut game);
+        assert!(!game.pipes[0].coin());
+        assert_eq!((game.coins, report.coins_collected), (0, 0));
+    }
+
+    /// A playing game on `lives` lives where the bird neither falls nor
+    /// scrolls past pipes on its own
+    fn hovering_with_lives(lives: u8) -> GameState {
+        let mut game = playing_at_slot(0);
+        game.physics.gravity = 0;
+        game.lives = lives;
+        game.set_extra_lives(lives > 1);
+        game.bird_y = (GAME_HEIGHT / 2 - BIRD_SIZE / 2) * 1000;
+        game
+    }
+
+    /// No active pipe covers the bird's column (freed slots may respawn pipes
+    /// at the right edge)
+    fn column_clear(game: &GameState) -> bool {
+        game.pipes[..MAX_PIPES]
+            .iter()
+            .all(|p| !p.active() || p.x_pixels() >= BIRD_X + BIRD_SIZE || p.x_pixels() + PIPE_WIDTH <= BIRD_X)
+    }
+
+    #[test]
+    fn a_spare_life_respawns_the_bird_instead_of_ending_the_run() {
+        let mut game = playing_at_slot(0);
+        game.lives = 2;
+        let mut reports = Vec::new();
+        let mut respawned_at = None;
+        while game.game_status() == GameStatus::Playing {
+            reports.push(step_physics(&mut game));
+            if respawned_at.is_none() && game.lives == 1 {
+                respawned_at = Some(game.frame_count);
+                assert_eq!((game.bird_y, game.bird_velocity), (GAME_HEIGHT / 2 * 1000, 0));
+                assert_eq!(game.invulnerable_until, game.frame_count + RESPAWN_INVULNERABLE_FRAMES);
+            }
+        }
+        // Pipes are the only thing it is immune to: it falls to the floor twice
+        let respawned_at = respawned_at.unwrap();
+        assert!(reports[..respawned_at as usize].iter().all(|r| r.death.is_none()));
+        assert_eq!(reports.last().unwrap().death, Some(DeathCause::Floor));
+        assert_eq!(game.lives, 1);
+    }
+
+    #[test]
+    fn respawning_clears_pipes_over_the_bird_and_ignores_pipes_for_a_while() {
+        let mut game = hovering_with_lives(3);
+        // Crashing into one pipe while the next is about to reach the bird
+        game.pipes[0] = Pipe { x: BIRD_X * 1000, gap_y: 80, active: 1, ..Default::default() };
+        game.pipes[1] = Pipe { x: (BIRD_X + BIRD_SIZE + 5) * 1000, gap_y: 80, active: 1, ..Default::default() };
+        assert_eq!(step_physics(&mut game).death, None);
+        assert_eq!(game.lives, 2);
+        assert!(game.pipes[1].active() && game.pipes[1].gap_y == 80);
+        assert!(column_clear(&game));
+        assert_eq!(game.bird_y, GAME_HEIGHT / 2 * 1000);
+        
+        // The second pipe sweeps right through the bird without a hit
+        while game.frame_count < game.invulnerable_until {
+            assert_eq!(step_physics(&mut game).death, None);
+            assert_eq!(game.lives, 2);
+        }
+        assert!(game.pipes[1].x_pixels() + PIPE_WIDTH < BIRD_X + BIRD_SIZE);
+        
+        // Once the window is over pipes hit again, until the last life goes
+        // (placed by hand, so the pipes are recounted)
+        game.pipes[1].set_active(false);
+        game.pipes[2] = Pipe { x: BIRD_X * 1000, gap_y: 80, active: 1, ..Default::default() };
+        game.active_pipe_count = 0;
+        step_physics(&mut game);
+        assert_eq!(game.lives, 1);
+        game.frame_count = game.invulnerable_until;
+        game.pipes[2] = Pipe { x: BIRD_X * 1000, gap_y: 80, active: 1, ..Default::default() };
+        game.active_pipe_count = 0;
+        assert_eq!(step_physics(&mut game).death, Some(DeathCause::Pipe));
+        assert!(game.game_status() == GameStatus::GameOver);
+    }
+
+    #[test]
+    fn floor_respawn_removes_a_pipe_sitting_on_the_center() {
+        let mut game = hovering_with_lives(2);
+        game.bird_y = (GAME_HEIGHT - BIRD_SIZE) * 1000;
+        // The bird is in the gap at the floor, but the respawn point is wall
+        game.pipes[0] = Pipe { x: BIRD_X * 1000, gap_y: GAME_HEIGHT - 60, active: 1, ..Default::default() };
+        assert_eq!(step_physics(&mut game).death, None);
+        assert_eq!((game.lives, game.bird_y), (1, GAME_HEIGHT / 2 * 1000));
+        assert!(column_clear(&game));
+    }
+
+    #[test]
+    fn extra_life_runs_are_unranked_and_reset_to_one_life() {
+        let mut game = hovering_with_lives(3);
+        game.score = 4;
+        game.set_game_status(GameStatus::GameOver);
+        game.record_high_score();
+        assert_eq!(game.claim_reward(), 0);
+        reset_run(&mut game);
+        assert_eq!((game.lives, game.extra_lives(), game.invulnerable_until), (1, false, 0));
+    }
+
+    #[test]
+    fn revive_continues_the_run_from_the_center() {
+        let mut game = playing_at_slot(0);
+        game.seed = 1_700_000_000;
+        run_until_over(&mut game, autopilot_flap);
+        run_until_over(&mut game, |_| false);
+        assert!(game.game_status() == GameStatus::GameOver);
+        let (score, frame) = (game.score, game.frame_count);
+        assert!(score > 0);
+        
+        let clock = Clock { slot: 9_000, unix_timestamp: 1_234, ..Default::default() };
+        revive_run(&mut game, &clock);
+        assert!(game.game_status() == GameStatus::Playing && game.revived());
+        assert_eq!((game.score, game.frame_count), (score, frame));
+        assert_eq!((game.bird_y, game.bird_velocity), (GAME_HEIGHT / 2 * 1000, 0));
+        assert!(column_clear(&game));
+        assert_eq!((game.last_update, game.last_update_slot, game.last_tick_slot), (1_234, 9_000, 9_000));
+        
+        // A revived run earns nothing and can't be taken to the daily board
+        run_until_over(&mut game, |_| false);
+        assert_eq!(game.claim_reward(), 0);
+        game.set_daily(true);
+        game.daily_day = day_of(game.last_update);
+        assert_eq!(daily_board_day(&game), err!(FlappyError::RevivedRun));
+        
+        reset_run(&mut game);
+        assert!(!game.revived());
+    }
+
+    /// Score and report of the frame in which a still bird at `bird_y`
+    /// (pixels) passes a pipe whose gap is 120..280 and that still overlaps
+    /// it for the first 2 px of the move
+    fn pass_at(bird_y: i32) -> (u64, FrameReport) {
+        let mut game = playing_at_slot(0);
+        game.physics.gravity = 0;
+        game.bird_y = bird_y * 1000;
+        game.pipes[0] = Pipe { x: (BIRD_X - PIPE_WIDTH + 2) * 1000, gap_y: 200, active: 1, ..Default::default() };
+        let report = step_physics(&mut game);
+        assert_eq!(report.pipes_passed, 1);
+        (game.score, report)
+    }
+
+    #[test]
+    fn near_misses_score_a_bonus() {
+        let gap_top = 200 - PIPE_GAP / 2;
+        let gap_bottom = 200 + PIPE_GAP / 2;
+        assert_eq!(pass_at(200 - BIRD_SIZE / 2).0, 1);
+        for bird_y in [gap_top, gap_top + NEAR_MISS_THRESHOLD - 1, gap_bottom - BIRD_SIZE] {
+            let (score, report) = pass_at(bird_y);
+            assert_eq!((score, report.near_misses, report.death), (1 + NEAR_MISS_BONUS, 1, None));
+        }
+        assert_eq!(pass_at(gap_top + NEAR_MISS_THRESHOLD).0, 1);
+        
+        // One pixel closer is a collision, and no bonus
+        let (score, report) = pass_at(gap_top - 1);
+        assert_eq!((score, report.near_misses, report.death), (1, 0, Some(DeathCause::Pipe)));
+    }
+
+    /// A hovering bird (flaps change nothing) in front of `pipes` evenly
+    /// spaced pipes 40 frames apart, the first passed on frame 1
+    fn glide_course(pipes: usize) -> GameState {
+        let mut game = playing_at_slot(0);
+        game.physics.gravity = 0;
+        game.physics.jump_velocity = 0;
+        game.bird_y = (200 - BIRD_SIZE / 2) * 1000;
+        for i in 0..pipes {
+            let x = (BIRD_X - PIPE_WIDTH + 2) * 1000 + i as i32 * 40 * PIPE_SPEED;
+            game.pipes[i] = Pipe { x, gap_y: 200, active: 1, ..Default::default() };
+        }
+        game
+    }
+
+    /// Step to the next pipe pass, flapping `flap_at` frames in; returns
+    /// that frame's report
+    fn glide_to_next_pass(game: &mut GameState, flap_at: Option<u64>) -> FrameReport {
+        for frame in 0.. {
+            game.set_pending_flap(flap_at == Some(frame));
+            let report = step_physics(game);
+            if report.pipes_passed > 0 {
+                return report;
+            }
+        }
+        unreachable!()
+    }
+
+    #[test]
+    fn gliding_through_pipes_builds_a_combo() {
+        let mut game = glide_course(3);
+        assert_eq!(glide_to_next_pass(&mut game, None).combo_bonus, 0);
+        assert_eq!(glide_to_next_pass(&mut game, None).combo_bonus, 1);
+        assert_eq!(glide_to_next_pass(&mut game, None).combo_bonus, 2);
+        assert_eq!((game.score, game.combo), (1 + 2 + 3, 2));
+        
+        // It stops growing at the cap
+        game.combo = MAX_COMBO_BONUS;
+        game.pipes[0] = Pipe { x: (BIRD_X - PIPE_WIDTH + 2) * 1000, gap_y: 200, active: 1, ..Default::default() };
+        assert_eq!(glide_to_next_pass(&mut game, None).combo_bonus, MAX_COMBO_BONUS as u64);
+    }
+
+    #[test]
+    fn a_flap_between_pipes_breaks_the_combo() {
+        let mut game = glide_course(3);
+        glide_to_next_pass(&mut game, None);
+        let report = glide_to_next_pass(&mut game, Some(10));
+        assert_eq!((report.combo_bonus, game.combo, game.flaps_since_last_pass), (0, 0, 0));
+        assert_eq!(game.score, 2);
+        // The pass after the flap starts a new streak
+        assert_eq!(glide_to_next_pass(&mut game, None).combo_bonus, 1);
+        
+        game.set_pending_flap(true);
+        step_physics(&mut game);
+        assert_eq!((game.combo, game.flaps_since_last_pass), (0, 1));
+        reset_run(&mut game);
+        assert_eq!((game.combo, game.flaps_since_last_pass), (0, 0));
+    }
+
+    #[test]
+    fn double_gaps_stay_apart_and_on_screen() {
+        for pipe_gap in RAMP_GAP_MIN..=PIPE_GAP {
+            for roll in (0..500u64).map(mix64) {
+                let (upper, lower) = double_gap_ys(roll, pipe_gap, GAME_HEIGHT).unwrap();
+                assert!(upper - pipe_gap / 2 >= DOUBLE_GAP_EDGE_MIN);
+                assert!((lower - pipe_gap / 2) - (upper + pipe_gap / 2) >= DOUBLE_GAP_STRIP_MIN);
+                assert!(lower + pipe_gap / 2 <= GAME_HEIGHT - DOUBLE_GAP_EDGE_MIN);
+            }
+        }
+        // Easy's wider gap doesn't fit twice until the ramp narrows it
+        assert_eq!(double_gap_ys(0, 180, GAME_HEIGHT), None);
+    }
+
+    #[test]
+    fn double_pipes_spawn_only_past_min_score() {
+        let spawned = |score: u64| {
+            let mut game = playing_at_slot(0);
+            game.score = score;
+            game.pipe_gap = PIPE_GAP - RAMP_GAP_STEP;
+            (0..200)
+                .map(|seed| {
+                    game.seed = 1_700_000_000 + seed * 7_919;
+                    game.pipes = [Pipe::default(); MAX_PIPES];
+                    game.active_pipe_count = 0;
+                    spawn_pipes(&mut game);
+                    game.pipes[0]
+                })
+                .collect::<Vec<_>>()
+        };
+        assert!(spawned(DOUBLE_GAP_MIN_SCORE).iter().all(|p| p.pipe_type != PIPE_TYPE_DOUBLE));
+        let late = spawned(DOUBLE_GAP_MIN_SCORE + 1);
+        let doubles: Vec<_> = late.iter().filter(|p| p.pipe_type == PIPE_TYPE_DOUBLE).collect();
+        assert!(!doubles.is_empty() && doubles.len() < late.len());
+        assert!(doubles.iter().all(|p| p.gap_y2 - p.gap_y >= PIPE_GAP - RAMP_GAP_STEP + DOUBLE_GAP_STRIP_MIN));
+    }
+
+    fn double_pipe() -> Pipe {
+        // Upper gap 20..180, strip 180..200, lower gap 200..360
+        Pipe {
+            x: BIRD_X * 1000,
+            gap_y: 100,
+            gap_y2: 280,
+            active: 1,
+            pipe_type: PIPE_TYPE_DOUBLE,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn double_pipe_lets_the_bird_through_either_gap() {
+        let pipe = double_pipe();
+        let hit = |prev_y: i32, bird_y: i32| {
+            check_pipe_collision(prev_y, bird_y, BIRD_X * 1000, BIRD_SIZE, &pipe, PIPE_GAP, PIPE_SPEED)
+        };
+        assert!(!hit(20, 20));
+        assert!(!hit(180 - BIRD_SIZE, 180 - BIRD_SIZE));
+        assert!(!hit(200, 200));
+        assert!(!hit(360 - BIRD_SIZE, 360 - BIRD_SIZE));
+        assert!(!hit(220, 230));
+        // A static pipe only has the upper gap
+        let single = Pipe { pipe_type: PIPE_TYPE_STATIC, ..pipe };
+        assert!(check_pipe_collision(200, 200, BIRD_X * 1000, BIRD_SIZE, &single, PIPE_GAP, PIPE_SPEED));
+    }
+
+    #[test]
+    fn double_pipe_strip_between_the_gaps_is_solid() {
+        let pipe = double_pipe();
+        let hit = |prev_y: i32, bird_y: i32| {
+            check_pipe_collision(prev_y, bird_y, BIRD_X * 1000, BIRD_SIZE, &pipe, PIPE_GAP, PIPE_SPEED)
+        };
+        // Inside the strip, or poking into it from either gap
+        assert!(hit(180, 180));
+        assert!(hit(180 - BIRD_SIZE + 1, 180 - BIRD_SIZE + 1));
+        assert!(hit(199, 199));
+        // Crossing from one gap to the other during the overlap
+        assert!(hit(150, 200));
+        assert!(hit(200, 150));
+    }
+
+    #[test]
+    fn passing_a_double_pipe_scores_once() {
+        let mut game = playing_at_slot(0);
+        game.pipes[0] = Pipe { x: (BIRD_X - PIPE_WIDTH) * 1000, ..double_pipe() };
+        game.bird_y = 260 * 1000;
+        game.bird_velocity = 0;
+        let report = step_physics(&mut game);
+        assert!(game.game_status() == GameStatus::Playing);
+        assert_eq!(report.pipes_passed, 1);
+        assert_eq!(game.score, 1);
+    }
+
+    #[test]
+    fn valid_spawn_distances_fit_in_the_pipe_slots() {
+        let tuned = PhysicsConfig::default();
+        for pipe_spawn_distance in PIPE_WIDTH + BIRD_SIZE + 1..GAME_WIDTH {
+            let config = PhysicsConfig { pipe_spawn_distance, ..tuned };
+            assert!(config.validate().is_ok());
+            assert!(pipe_slots(GAME_WIDTH, pipe_spawn_distance) <= MAX_PIPES);
+        }
+        // The densest spacing needs every slot; the default leaves most unused
+        assert_eq!(pipe_slots(GAME_WIDTH, PIPE_WIDTH + BIRD_SIZE + 1), MAX_PIPES);
+        assert_eq!(pipe_slots(GAME_WIDTH, PIPE_SPAWN_DISTANCE), 3);
+    }
+
+    #[test]
+    fn on_screen_pipes_never_outnumber_the_slots() {
+        for pipe_spawn_distance in [PIPE_WIDTH + BIRD_SIZE + 1, 120, PIPE_SPAWN_DISTANCE, GAME_WIDTH - 1] {
+            for pipe_speed in [1001, PIPE_SPEED, RAMP_SPEED_MAX] {
+                let mut game = playing_at_slot(0);
+                game.seed = 1_700_000_000;
+                // Practice so the bird outlives every pipe
+                game.set_practice(true);
+                game.physics.pipe_spawn_distance = pipe_spawn_distance;
+                game.pipe_speed = pipe_speed;
+                let slots = game.pipe_slots();
+                for _ in 0..2_000 {
+                    step_physics(&mut game);
+                    game.pipe_speed = pipe_speed;
+                    assert!(game.pipes[slots..].iter().all(|p| !p.active()));
+                    // A free slot was always there when one was due
+                    let rightmost = game.pipes.iter().filter(|p| p.active()).map(|p| p.x).max();
+                    assert!(rightmost.is_some_and(|x| x >= (GAME_WIDTH - pipe_spawn_distance) * 1000 - pipe_speed));
+                }
+                assert!(game.game_status() == GameStatus::Playing);
+            }
+        }
+    }
+
+    /// A legacy account body with every byte zero
+    fn zeroed<T: AnchorDeserialize>(len: usize) -> T {
+        T::deserialize(&mut &vec![0; len][..]).unwrap()
+    }
+
+    /// `old` as the data of its account, discriminator included
+    fn legacy_account_data(old: &impl AnchorSerialize) -> Vec<u8> {
+        let mut data = GameState::DISCRIMINATOR.to_vec();
+        old.serialize(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn legacy_layouts_keep_their_on_chain_size() {
+        // The zero-copy GameState with and without the delegation
+        // parameters, and the borsh one before it, with 8 and 5 pipes
+        assert_eq!(legacy::GameStateV9::INIT_SPACE, 1600);
+        assert_eq!(legacy::GameStateV8::INIT_SPACE, 1600 - DelegationConfig::INIT_SPACE);
+        assert_eq!(legacy::GameStateV7::INIT_SPACE, 1541);
+        assert_eq!(legacy::GameStateV6::INIT_SPACE, 1472);
+        let v6: legacy::GameStateV6 = zeroed(legacy::GameStateV6::INIT_SPACE);
+        assert_eq!(legacy_account_data(&v6).len(), 8 + 1472);
+    }
+
+    #[test]
+    fn migration_carries_lifetime_fields_from_a_v7_game() {
+        let mut old: legacy::GameStateV7 = zeroed(legacy::GameStateV7::INIT_SPACE);
+        old.authority = Pubkey::new_unique();
+        old.game_slot = 2;
+        old.game_status = GameStatus::Playing as u8;
+        old.high_score = 41;
+        old.high_scores = [12, 41, 7];
+        old.distance_high_score = 900;
+        old.mode_high_scores = [41, 3, 5];
+        old.best_run_inputs[3] = 0b1011;
+        old.best_run_frames = 777;
+        old.best_run_seed = 99;
+        old.rewarded_score = 40;
+        old.coins = 123;
+        old.score = 17;
+        old.combo = 2;
+        let legacy = legacy::GameStateV7::try_from_account_data(7, &legacy_account_data(&old)).unwrap();
+        assert_eq!((legacy.authority, legacy.game_slot, legacy.combo), (old.authority, 2, 2));
+        
+        let mut game = playing_at_slot(0);
+        reset_run(&mut game);
+        game.set_game_status(GameStatus::NotStarted);
+        legacy.carry_over(&mut game);
+        assert_eq!(game.high_score, 41);
+        assert_eq!(game.high_scores, [12, 41, 7]);
+        assert_eq!(game.distance_high_score, 900);
+        assert_eq!(game.mode_high_scores, [41, 3, 5]);
+        assert_eq!(game.best_run_inputs.bits, old.best_run_inputs);
+        assert_eq!((game.best_run_frames, game.best_run_seed), (777, 99));
+        assert_eq!((game.rewarded_score, game.coins), (40, 123));
+        // The old run stays behind
+        assert_eq!((game.score, game.combo), (0, 0));
+        assert!(game.game_status() == GameStatus::NotStarted);
+    }
+
+    #[test]
+    fn migration_reads_a_v6_game_as_v7() {
+        let mut old: legacy::GameStateV6 = zeroed(legacy::GameStateV6::INIT_SPACE);
+        old.authority = Pubkey::new_unique();
+        old.high_score = 41;
+        old.pipes[4].gap_y = 200;
+        old.coins = 123;
+        old.combo = 2;
+        let data = legacy_account_data(&old);
+        let legacy = legacy::GameStateV7::try_from_account_data(6, &data).unwrap();
+        assert_eq!((legacy.authority, legacy.high_score, legacy.coins, legacy.combo), (old.authority, 41, 123, 2));
+        assert_eq!((legacy.pipes[4].gap_y, legacy.pipes[5].gap_y), (200, 0));
+        // Read as the other version, the pipe array misaligns everything after it
+        assert!(legacy::GameStateV7::try_from_account_data(7, &data).is_err());
+    }
+
+    #[test]
+    fn migration_rejects_data_that_is_not_a_game() {
+        let mut data = legacy_account_data(&zeroed::<legacy::GameStateV7>(legacy::GameStateV7::INIT_SPACE));
+        assert!(legacy::GameStateV7::try_from_account_data(7, &data[..100]).is_err());
+        assert_eq!(
+            legacy::GameStateV7::try_from_account_data(5, &data).map(|_| ()),
+            err!(FlappyError::UnsupportedGameVersion)
+        );
+        assert!(legacy::game_seed(10).is_err());
+        data[0] ^= 1;
+        assert_eq!(
+            legacy::GameStateV7::try_from_account_data(7, &data).map(|_| ()),
+            err!(FlappyError::InvalidLegacyGame)
+        );
+    }
+
+    /// Everything the game logic reads or writes, folded into `acc`, in a way
+    /// that doesn't depend on how GameState is laid out
+    fn logic_fingerprint(game: &GameState, acc: u64) -> u64 {
+        let pipes = game.pipes.iter().flat_map(|p| {
+            [
+                p.x as u64, p.gap_y as u64, p.gap_y2 as u64, p.amplitude as u64, p.phase as u64,
+                p.pipe_type as u64, p.active() as u64, p.passed() as u64, p.shield() as u64, p.coin() as u64,
+            ]
+        });
+        [
+            game.game_status() as u64, game.frame_count, game.score, game.high_score,
+            game.bird_y as u64, game.bird_velocity as u64, game.bird_x as u64, game.bird_x_velocity as u64,
+            game.seed, game.gravity as u64, game.pipe_gap as u64, game.pipe_speed as u64, game.wind as u64,
+            game.coins, game.has_shield() as u64, game.lives as u64, game.invulnerable_until,
+            game.combo as u64, game.flaps_since_last_pass as u64, game.distance, game.pipes_passed,
+            game.high_scores.iter().sum(), game.mode_high_scores.iter().sum(), game.distance_high_score,
+            game.input_log.flaps() as u64, game.best_run_frames as u64,
+        ]
+        .into_iter()
+        .chain(pipes)
+        .fold(acc, |acc, v| mix64(acc ^ v))
+    }
+
+    #[test]
+    fn zero_copy_runs_match_the_borsh_layout_runs() {
+        // (frames, score, fingerprint) of each scenario, recorded with the
+        // borsh GameState before it went zero-copy
+        let expected = [
+            (1468, 25, 0xcdd3f31da347e828),
+            (1147, 30, 0x03400f407cf775e7),
+            (2552, 15023, 0xa219d03378c10594),
+            (2164, 42, 0x6822c38fbc2c3c2a),
+            (2034, 40, 0xcbd68da3cc0cfb5d),
+            (1021, 16, 0xf43e4d4bb437c05d),
+            (3000, 80, 0x2d903376f77a6be5),
+        ];
+        let scenarios: [fn(&mut GameState); 7] = [
+            |_| {},
+            |g| g.set_difficulty(Difficulty::Hard),
+            |g| g.set_scoring(ScoringMode::Distance),
+            |g| {
+                g.set_wind_enabled(true);
+                g.lives = 3;
+                g.set_extra_lives(true);
+            },
+            |g| g.set_game_mode(GameMode::Tiny),
+            |g| g.set_horizontal_control(true),
+            |g| g.set_practice(true),
+        ];
+        for (i, (setup, expected)) in scenarios.iter().zip(expected).enumerate() {
+            // Played in place in account-like bytes, as `load_mut` hands it out
+            let mut data = vec![0u64; std::mem::size_of::<GameState>() / 8];
+            let game: &mut GameState = bytemuck::from_bytes_mut(bytemuck::cast_slice_mut(&mut data));
+            *game = playing_at_slot(0);
+            game.seed = 1_700_000_000 + i as u64 * 7_919;
+            game.run_seed = game.seed;
+            setup(game);
+            game.apply_difficulty();
+            let mut acc = 0;
+            let mut slot = 0;
+            while game.game_status() == GameStatus::Playing && game.frame_count < 3_000 {
+                game.set_pending_flap(game.pending_flap() | autopilot_flap(game));
+                if game.horizontal_control() && game.frame_count % 50 == 0 {
+                    game.bird_x_velocity = DASH_IMPULSE;
+                }
+                slot += 1;
+                advance_at_slot(game, slot, &mut ignore);
+                acc = logic_fingerprint(game, acc);
+            }
+            assert_eq!((game.frame_count, game.score, acc), expected, "scenario {i}");
+        }
+    }
+
+    #[test]
+    fn zero_copy_layout_is_the_borsh_encoding() {
+        assert_eq!(GameState::INIT_SPACE, std::mem::size_of::<GameState>());
+        assert_eq!(Pipe::INIT_SPACE, std::mem::size_of::<Pipe>());
+        let mut game = playing_at_slot(7);
+        game.authority = Pubkey::new_unique();
+        game.set_difficulty(Difficulty::Hard);
+        game.pick_up(Powerup::Shield);
+        game.wind = -3;
+        game.set_is_delegated(true);
+        game.input_log.bits[5] = u64::MAX;
+        spawn_pipes(&mut game);
+        let mut encoded = Vec::new();
+        game.serialize(&mut encoded).unwrap();
+        assert_eq!(encoded, bytemuck::bytes_of(&game));
+        let decoded = GameState::deserialize(&mut &encoded[..]).unwrap();
+        assert_eq!(bytemuck::bytes_of(&decoded), bytemuck::bytes_of(&game));
+    }
+
+    #[test]
+    fn cpi_allowlist_adds_removes_and_fills_up() {
+        let mut allowlist = CpiAllowlist::default();
+        let programs: Vec<Pubkey> = (0..=MAX_CPI_CALLERS).map(|_| Pubkey::new_unique()).collect();
+        assert!(!allowlist.allows(&programs[0]));
+
+        allowlist.set(programs[0], true).unwrap();
+        allowlist.set(programs[0], true).unwrap();
+        assert_eq!(allowlist.count, 1);
+        assert!(allowlist.allows(&programs[0]));
+
+        // Removing an unlisted program changes nothing
+        allowlist.set(programs[1], false).unwrap();
+        assert_eq!(allowlist.count, 1);
+
+        for program in &programs[1..MAX_CPI_CALLERS] {
+            allowlist.set(*program, true).unwrap();
+        }
+        assert_eq!(allowlist.set(programs[MAX_CPI_CALLERS], true), err!(FlappyError::AllowlistFull));
+
+        // The last entry moves into the removed one's place
+        allowlist.set(programs[0], false).unwrap();
+        assert_eq!(allowlist.count as usize, MAX_CPI_CALLERS - 1);
+        assert!(!allowlist.allows(&programs[0]));
+        assert_eq!(allowlist.programs[0], programs[MAX_CPI_CALLERS - 1]);
+        assert_eq!(allowlist.programs[MAX_CPI_CALLERS - 1], Pubkey::default());
+        assert!(programs[1..MAX_CPI_CALLERS].iter().all(|p| allowlist.allows(p)));
+        allowlist.set(programs[MAX_CPI_CALLERS], true).unwrap();
+        assert!(allowlist.allows(&programs[MAX_CPI_CALLERS]));
+    }
+
+    #[test]
+    fn commit_is_due_every_interval_and_on_the_game_over_frame() {
+        let mut game = playing_at_slot(0);
+        game.frame_count = 10;
+        assert!(!commit_due(&game));
+
+        game.commit_interval = 5;
+        assert!(commit_due(&game));
+        game.frame_count = 12;
+        assert!(!commit_due(&game));
+
+        // The crash frame commits whatever its number, so the final score is kept
+        let mut slot = 0;
+        while game.game_status() == GameStatus::Playing {
+            slot += 1;
+            advance_at_slot(&mut game, slot, &mut ignore);
+        }
+        game.frame_count = 13;
+        assert!(commit_due(&game));
+    }
+
+    #[test]
+    fn migration_carries_lifetime_fields_from_a_v8_game() {
+        let mut old: legacy::GameStateV8 = zeroed(legacy::GameStateV8::INIT_SPACE);
+        old.authority = Pubkey::new_unique();
+        old.high_score = 41;
+        old.mode_high_scores = [41, 3, 5];
+        old.best_run_inputs[3] = 0b1011;
+        old.best_run_frames = 777;
+        old.coins = 123;
+        old.score = 17;
+        old.commit_interval = 30;
+        let data = legacy_account_data(&old);
+        assert_eq!(data.len(), 8 + legacy::GameStateV8::INIT_SPACE);
+
+        let mut game = GameState::default();
+        reset_run(&mut game);
+        assert_eq!(legacy::carry_over(8, &data, &mut game), Ok(old.authority));
+        assert_eq!((game.high_score, game.mode_high_scores, game.coins), (41, [41, 3, 5], 123));
+        assert_eq!((game.best_run_inputs.bits, game.best_run_frames), (old.best_run_inputs, 777));
+        assert_eq!((game.score, game.commit_interval), (0, 0));
+        // Read as another version, the layout misaligns
+        assert!(legacy::carry_over(7, &data, &mut game).is_err());
+    }
+
+    #[test]
+    fn delegate_params_default_to_the_er_config_and_check_ranges() {
+        let fallback = Pubkey::new_unique();
+        let none = DelegateParams::default().resolve(None).unwrap();
+        assert_eq!(none.validator(), None);
+        assert_eq!(none.time_limit, 0);
+        assert_eq!(none.config().commit_frequency_ms, DelegateConfig::default().commit_frequency_ms);
+        assert_eq!(DelegateParams::default().resolve(Some(fallback)).unwrap().validator(), Some(fallback));
+
+        let validator = Pubkey::new_unique();
+        let chosen = DelegateParams { validator: Some(validator), commit_frequency_ms: Some(5_000), time_limit: Some(3_600) }
+            .resolve(Some(fallback))
+            .unwrap();
+        assert_eq!((chosen.validator(), chosen.commit_frequency_ms, chosen.time_limit), (Some(validator), 5_000, 3_600));
+        assert_eq!(chosen.config().validator, Some(validator));
+
+        for params in [
+            DelegateParams { commit_frequency_ms: Some(MIN_COMMIT_FREQUENCY_MS - 1), ..Default::default() },
+            DelegateParams { time_limit: Some(0), ..Default::default() },
+            DelegateParams { time_limit: Some(MAX_DELEGATION_TIME_LIMIT + 1), ..Default::default() },
+        ] {
+            assert_eq!(params.resolve(None), err!(FlappyError::InvalidDelegateParams));
+        }
+    }
+
+    #[test]
+    fn migration_carries_lifetime_fields_from_a_v9_game() {
+        let mut old: legacy::GameStateV9 = zeroed(legacy::GameStateV9::INIT_SPACE);
+        old.authority = Pubkey::new_unique();
+        old.high_score = 41;
+        old.coins = 123;
+        old.delegation[0] = 7;
+        let data = legacy_account_data(&old);
+        let mut game = GameState::default();
+        assert_eq!(legacy::carry_over(9, &data, &mut game), Ok(old.authority));
+        assert_eq!((game.high_score, game.coins), (41, 123));
+        assert_eq!(game.delegation, DelegationConfig::default());
+    }
+
+    #[test]
+    fn a_run_remembers_the_frame_and_cause_it_ended_with() {
+        let mut game = playing_at_slot(0);
+        assert_eq!(game.end_cause(), None);
+        let mut slot = 0;
+        while game.game_status() == GameStatus::Playing {
+            slot += 1;
+            advance_at_slot(&mut game, slot, &mut ignore);
+        }
+        assert_eq!((game.end_frame, game.end_cause()), (game.frame_count, Some(DeathCause::Floor)));
+
+        // Kept through a reset, until the next run ends
+        let frames = game.frame_count;
+        reset_run(&mut game);
+        game.set_game_status(GameStatus::Playing);
+        assert_eq!((game.end_frame, game.end_cause()), (frames, Some(DeathCause::Floor)));
+        game.frame_count = 3;
+        finish_run(&mut game, DeathCause::Manual, 0);
+        assert_eq!((game.end_frame, game.end_cause()), (3, Some(DeathCause::Manual)));
+
+        for cause in [DeathCause::Pipe, DeathCause::PauseExpired, DeathCause::Undelegated] {
+            game.set_end_cause(cause);
+            assert_eq!(game.end_cause(), Some(cause));
+        }
+    }
+
+    #[test]
+    fn ranked_runs_wait_out_the_cooldown_after_a_ranked_run() {
+        let mut game = playing_at_slot(0);
+        finish_run(&mut game, DeathCause::Manual, 1_000);
+        assert_eq!(game.last_game_over_at, 1_000);
+
+        reset_run(&mut game);
+        game.set_game_status(GameStatus::Playing);
+        let ended = game.last_game_over_at;
+        assert_eq!(check_ranked_cooldown(&game, ended, 60, 1_059), err!(FlappyError::CooldownActive));
+        assert!(check_ranked_cooldown(&game, ended, 60, 1_060).is_ok());
+        assert!(check_ranked_cooldown(&game, ended, 0, 1_000).is_ok());
+        // Casual and practice runs aren't held back
+        game.set_practice(true);
+        assert!(check_ranked_cooldown(&game, ended, 60, 1_000).is_ok());
+        game.set_practice(false);
+        game.set_game_mode(GameMode::Tiny);
+        assert!(check_ranked_cooldown(&game, ended, 60, 1_000).is_ok());
+
+        // ...nor do they start one
+        finish_run(&mut game, DeathCause::Manual, 2_000);
+        assert_eq!(game.last_game_over_at, 1_000);
+    }
+
+    #[test]
+    fn a_new_high_score_records_when_where_and_from_which_seed_it_was_set() {
+        let mut game = playing_at_slot(0);
+        game.run_seed = 77;
+        game.score = 12;
+        game.frame_count = 300;
+        finish_run(&mut game, DeathCause::Manual, 1_000);
+        assert_eq!(game.high_score, 12);
+        assert_eq!((game.high_score_timestamp, game.high_score_frame, game.high_score_seed), (1_000, 300, 77));
+        let entry = LeaderboardEntry::for_run(&game, 1_005);
+        assert_eq!((entry.ended_at, entry.frame, entry.seed), (1_000, 300, 77));
+
+        // A tie leaves the record and its provenance alone, with no event
+        reset_run(&mut game);
+        game.set_game_status(GameStatus::Playing);
+        game.run_seed = 78;
+        game.score = 12;
+        game.frame_count = 250;
+        game.last_update = 2_000;
+        assert_eq!(game.end_run(DeathCause::Pipe), None);
+        assert_eq!((game.high_score_timestamp, game.high_score_frame, game.high_score_seed), (1_000, 300, 77));
+
+        // Beating it by dying in the physics reports the old record
+        let mut game = playing_at_slot(0);
+        game.high_score = 3;
+        game.score = 4;
+        let mut slot = 0;
+        let mut beaten = None;
+        while game.game_status() == GameStatus::Playing {
+            slot += 1;
+            advance_at_slot(&mut game, slot, &mut |_: &mut GameState, report: &FrameReport| {
+                beaten = beaten.or(report.beaten_high_score);
+            });
+        }
+        assert_eq!((beaten, game.high_score, game.high_score_frame), (Some(3), 4, game.frame_count));
+    }
+
+    #[test]
+    fn frame_state_packs_the_active_pipes_in_pixels() {
+        let mut game = playing_at_slot(0);
+        game.score = 3;
+        game.bird_velocity = -400;
+        game.pipes[2] = Pipe { x: 150_000, gap_y: 90, active: 1, ..Default::default() };
+        game.pipes[5] = Pipe { x: 320_500, gap_y: 200, active: 1, ..Default::default() };
+        game.pipes[6] = Pipe { x: 999_000, gap_y: 10, active: 0, ..Default::default() };
+        let state = FrameState::of(&game);
+        assert_eq!((state.bird_y, state.velocity_sign, state.score), (game.bird_y / 1000, -1, 3));
+        assert_eq!(state.pipe_count, game.pipes[..MAX_PIPES].iter().filter(|pipe| pipe.active()).count() as u8);
+        assert!(state.pipes[..state.pipe_count as usize].contains(&PipeView { x: 320, gap_y: 200 }));
+        assert!(!state.pipes.contains(&PipeView { x: 999, gap_y: 10 }));
+        assert!(!game.spectator_mode());
+    }
+
+    #[test]
+    fn frame_log_records_pack_the_frame_little_endian() {
+        let mut game = playing_at_slot(0);
+        game.frame_count = 0x0102;
+        game.bird_y = 250_999;
+        game.bird_velocity = -7;
+        game.score = 9;
+        let record = frame_log_record(&game);
+        assert_eq!(u64::from_le_bytes(record[..8].try_into().unwrap()), 0x0102);
+        assert_eq!(i32::from_le_bytes(record[8..12].try_into().unwrap()), 250);
+        assert_eq!(i32::from_le_bytes(record[12..16].try_into().unwrap()), -7);
+        assert_eq!(u64::from_le_bytes(record[16..].try_into().unwrap()), 9);
+        assert!(!game.verbose_logging());
+    }
+
+    /// `step_physics` as it was before `active_pipe_count`: every frame scans
+    /// all the pipe slots, both to update them and to find a free one
+    fn step_physics_scanning(game: &mut GameState) -> FrameReport {
+        game.active_pipe_count = 0;
+        let report = step_physics(game);
+        game.active_pipe_count = 0;
+        report
+    }
+
+    #[test]
+    fn tracked_pipe_counts_match_scanning_every_frame() {
+        for seed in [1, 0xF1A9, 1_700_000_000] {
+            let mut tracked = playing_at_slot(0);
+            tracked.seed = seed;
+            tracked.set_practice(true);
+            let mut scanned = tracked;
+            for frame in 0..10_000u64 {
+                // Flap now and then to keep the bird up and the pipes varied
+                let flap = mix64(seed ^ frame) % 9 == 0;
+                tracked.set_pending_flap(flap);
+                scanned.set_pending_flap(flap);
+                assert_eq!(step_physics(&mut tracked), step_physics_scanning(&mut scanned));
+
+                // Recounting gives what the tracked game kept up, and the
+                // rest of the state is the same byte for byte
+                assert_eq!(tracked.active_pipe_count(), scanned.active_pipe_count(), "frame {frame}");
+                assert_eq!(bytemuck::bytes_of(&tracked), bytemuck::bytes_of(&scanned), "frame {frame}");
+            }
+        }
+    }
+
+    #[test]
+    fn the_best_run_keeps_its_length_and_flaps_through_resets() {
+        let mut game = playing_at_slot(0);
+        game.score = 5;
+        game.frame_count = 400;
+        game.flaps_this_run = 31;
+        finish_run(&mut game, DeathCause::Manual, 0);
+        assert_eq!((game.best_run_frames, game.best_run_flaps), (400, 31));
+        assert_eq!(LeaderboardEntry::for_run(&game, 0).flaps, 31);
+        let snapshot = game.snapshot();
+        assert_eq!((snapshot.best_run_frames, snapshot.best_run_flaps), (400, 31));
+
+        // A new run counts from zero; a tie or a reset leaves the best run be
+        reset_run(&mut game);
+        assert_eq!(game.flaps_this_run, 0);
+        game.set_game_status(GameStatus::Playing);
+        game.score = 5;
+        game.flaps_this_run = 2;
+        finish_run(&mut game, DeathCause::Manual, 0);
+        reset_run(&mut game);
+        assert_eq!((game.best_run_frames, game.best_run_flaps), (400, 31));
+    }
+
+    #[test]
+    fn challenge_beaten_only_by_a_higher_finished_score() {
+        let challenge = Challenge { score_to_beat: 10, ..Default::default() };
+        assert!(challenge.beaten_by(Some(11)));
+        assert!(!challenge.beaten_by(Some(10)));
+        assert!(!challenge.beaten_by(None));
+    }
+
+    #[test]
+    fn skin_registry_adds_removes_and_fills_up() {
+        let mut registry = SkinRegistry::default();
+        let mints: Vec<Pubkey> = (0..=MAX_SKIN_MINTS).map(|_| Pubkey::new_unique()).collect();
+        registry.add(3, mints[0]).unwrap();
+        registry.add(3, mints[0]).unwrap();
+        assert_eq!(registry.count, 1);
+        assert!(registry.unlocks(3, &mints[0]));
+        assert!(!registry.unlocks(4, &mints[0]));
+
+        for mint in &mints[1..MAX_SKIN_MINTS] {
+            registry.add(4, *mint).unwrap();
+        }
+        assert_eq!(registry.add(4, mints[MAX_SKIN_MINTS]), err!(FlappyError::SkinRegistryFull));
+        registry.remove(3, mints[0]);
+        assert!(!registry.unlocks(3, &mints[0]));
+        assert!(mints[1..MAX_SKIN_MINTS].iter().all(|mint| registry.unlocks(4, mint)));
+        registry.add(4, mints[MAX_SKIN_MINTS]).unwrap();
+        assert!(registry.unlocks(4, &mints[MAX_SKIN_MINTS]));
+    }
+
+    #[test]
+    fn nft_mint_read_only_from_a_held_single_token() {
+        let mint = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let token_account = |owner: Pubkey, amount: u64, state: u8| {
+            let mut data = vec![0u8; TOKEN_ACCOUNT_LEN];
+            data[0..32].copy_from_slice(mint.as_ref());
+            data[32..64].copy_from_slice(owner.as_ref());
+            data[64..72].copy_from_slice(&amount.to_le_bytes());
+            data[108] = state;
+            data
+        };
+        assert_eq!(held_nft_mint(&token_account(authority, 1, 1), &authority).unwrap(), mint);
+        let not_owned = err!(FlappyError::SkinNotOwned);
+        assert_eq!(held_nft_mint(&token_account(Pubkey::new_unique(), 1, 1), &authority), not_owned);
+        assert_eq!(held_nft_mint(&token_account(authority, 0, 1), &authority), not_owned);
+        assert_eq!(held_nft_mint(&token_account(authority, 2, 1), &authority), not_owned);
+        assert_eq!(held_nft_mint(&token_account(authority, 1, 0), &authority), not_owned);
+        assert_eq!(held_nft_mint(&token_account(authority, 1, 1)[..100], &authority), not_owned);
+    }
+
+    #[test]
+    fn skin_survives_resets_and_transfers() {
+        let mut game = GameState { bird_skin: 5, ..Default::default() };
+        reset_run(&mut game);
+        assert_eq!(game.bird_skin, 5);
+        assert_eq!(game.transferred(Pubkey::new_unique(), 1, 0).bird_skin, 5);
+    }
+
+    #[test]
+    fn milestone_claims_once_its_threshold_is_reached() {
+        let mut registry = MilestoneRegistry::default();
+        registry.thresholds[2] = 50;
+        assert_eq!(registry.threshold(2).unwrap(), 50);
+        assert_eq!(registry.threshold(1), err!(FlappyError::MilestoneNotConfigured));
+        assert_eq!(registry.threshold(MAX_MILESTONES as u8), err!(FlappyError::MilestoneNotConfigured));
+
+        let mut profile = PlayerProfile::default();
+        assert_eq!(profile.claim_milestone(2, 50, 49), err!(FlappyError::MilestoneNotReached));
+        assert_eq!(profile.milestones_claimed, 0);
+        profile.claim_milestone(2, 50, 50).unwrap();
+        assert_eq!(profile.milestones_claimed, 1 << 2);
+        assert_eq!(profile.claim_milestone(2, 50, 80), err!(FlappyError::MilestoneClaimed));
+        profile.claim_milestone(3, 80, 80).unwrap();
+        assert_eq!(profile.milestones_claimed, 1 << 2 | 1 << 3);
+    }
+
+    #[test]
+    fn weeks_roll_over_every_seven_days() {
+        assert_eq!(week_of(0), 0);
+        assert_eq!(week_of(SECONDS_PER_WEEK - 1), 0);
+        assert_eq!(week_of(SECONDS_PER_WEEK), 1);
+        // 2024-01-01 00:00 UTC, a Monday, is in the week that began Thu 2023-12-28
+        assert_eq!(week_of(1_704_067_200), 2817);
+        assert_eq!(week_of(1_703_721_600), 2817);
+        assert_eq!(week_of(1_703_721_599), 2816);
+    }
+
+    #[test]
+    fn ranked_flaps_wait_out_the_interval_to_the_frame() {
+        let clock = Clock { unix_timestamp: 1_000, ..Default::default() };
+        let mut game = GameState::default();
+        begin_run(&mut game, None, Difficulty::Normal, ScoringMode::PipesPassed, 1, &clock);
+        assert_eq!(game.min_flap_interval, MIN_FLAP_INTERVAL_FRAMES);
+        assert!(game.ranked());
+        assert!(game.flap_allowed());
+
+        // Queued after 10 frames, it applies on frame 11
+        game.frame_count = 10;
+        game.last_flap_frame = game.frame_count + 1;
+        for (frame, allowed) in [(10, false), (11, false), (12, false), (13, true), (14, true)] {
+            game.frame_count = frame;
+            assert_eq!(game.flap_allowed(), allowed, "frame {}", frame);
+        }
+
+        // Casual runs are never held back
+        game.frame_count = 11;
+        game.set_practice(true);
+        assert!(game.flap_allowed());
+
+        // A new run forgets the last flap and takes the configured interval
+        let config = GameConfig {
+            physics: PhysicsConfig::default(),
+            bump: 0,
+            ranked_cooldown_secs: 0,
+            min_flap_interval_frames: 1,
+            hitbox_inset_plus_one: 0,
+            grace_frames_plus_one: 0,
+            countdown_frames: 0,
+            flap_skips_countdown: false,
+            streak_threshold: 0,
+            ranked_pickups: 0,
+            revive_coin_cost: 0,
+            paused: false,
+            submit_window_secs: 0,
+            reserved: [0; 43],
+        };
+        begin_run(&mut game, Some(&config), Difficulty::Normal, ScoringMode::PipesPassed, 1, &clock);
+        assert_eq!((game.min_flap_interval, game.last_flap_frame), (1, 0));
+        game.last_flap_frame = 1;
+        assert!(!game.flap_allowed());
+        game.frame_count = 1;
+        assert!(game.flap_allowed());
+        assert_eq!(GameConfig { min_flap_interval_frames: 0, ..config }.min_flap_interval(), MIN_FLAP_INTERVAL_FRAMES);
+    }
+
+    /// Chi-squared statistic of 10,000 spawned gap positions against a
+    /// uniform spread over the valid range, one bucket per pixel
+    fn gap_chi_squared(rng_version: u8) -> f64 {
+        let low = PIPE_HEIGHT_MIN + PIPE_GAP / 2;
+        let high = GAME_HEIGHT - PIPE_HEIGHT_MIN - PIPE_GAP / 2;
+        let mut game = playing_at_slot(0);
+        game.rng_version = rng_version;
+        game.seed = 1_700_000_000;
+        let mut counts = vec![0u32; (high - low + 1) as usize];
+        for _ in 0..10_000 {
+            spawn_pipes(&mut game);
+            let pipe = game.pipes.iter_mut().find(|p| p.active()).unwrap();
+            assert!((low..=high).contains(&pipe.gap_y));
+            counts[(pipe.gap_y - low) as usize] += 1;
+            pipe.active = 0;
+            game.active_pipe_count = 0;
+        }
+        let expected = 10_000.0 / counts.len() as f64;
+        counts.iter().map(|&c| (c as f64 - expected).powi(2) / expected).sum()
+    }
+
+    #[test]
+    fn xorshift_gaps_are_spread_evenly() {
+        // 80 degrees of freedom: mean 80, standard deviation under 13
+        let chi_squared = gap_chi_squared(rng::RNG_XORSHIFT);
+        assert!(chi_squared < 140.0, "chi-squared {}", chi_squared);
+        // The clamped LCG piles most gaps onto the bottom edge
+        assert!(gap_chi_squared(rng::RNG_LCG) > 10_000.0);
+    }
+
+    #[test]
+    fn new_runs_draw_with_xorshift_and_replay_with_it() {
+        let clock = Clock::default();
+        let mut game = GameState::default();
+        begin_run(&mut game, None, Difficulty::Normal, ScoringMode::PipesPassed, 1_700_000_000, &clock);
+        assert_eq!(game.rng_version, rng::RNG_XORSHIFT);
+        let mut legacy = replay_start_state(&recorded_run(1_700_000_000));
+        assert_eq!(legacy.rng_version, rng::RNG_LCG);
+        spawn_pipes(&mut game);
+        spawn_pipes(&mut legacy);
+        assert_ne!(game.seed, legacy.seed);
+
+        let params = ReplayParams { rng_version: rng::RNG_XORSHIFT, ..recorded_run(1_700_000_000) };
+        let mut state = replay_start_state(&params);
+        while state.game_status() == GameStatus::Playing && state.frame_count < 600 {
+            if autopilot_flap(&state) {
+                record_flap(&mut state).unwrap();
+                state.set_pending_flap(true);
+            }
+            step_physics(&mut state);
+        }
+        let params = ReplayParams {
+            inputs: state.input_log,
+            frames: state.frame_count,
+            claimed_score: state.score,
+            ..params
+        };
+        assert!(params.claimed_score > 0);
+        let mut cursor = ReplayCursor::default();
+        assert_eq!(advance_replay(&mut cursor, &params, u32::MAX).unwrap(), ReplayOutcome::Verified);
+        // The same inputs don't score the same among the other generator's pipes
+        let other = ReplayParams { rng_version: rng::RNG_LCG, ..params };
+        let mut cursor = ReplayCursor::default();
+        assert_eq!(advance_replay(&mut cursor, &other, u32::MAX), err!(FlappyError::ReplayMismatch));
+    }
+
+    #[test]
+    fn pipes_never_spawn_closer_than_the_spawn_distance() {
+        for (speed, spawn_distance) in [(1_500, 250), (PIPE_SPEED, PIPE_SPAWN_DISTANCE), (9_000, 180), (25_000, 120)] {
+            let mut game = playing_at_slot(0);
+            game.seed = 0xF1A9;
+            game.physics.pipe_speed = speed;
+            game.physics.pipe_spawn_distance = spawn_distance;
+            reset_run(&mut game);
+            game.set_practice(true);
+            let mut spawned = 0;
+            for frame in 0..5_000u64 {
+                game.set_pending_flap(mix64(frame) % 9 == 0);
+                let before = game.pipes.iter().filter(|p| p.active()).count();
+                step_physics(&mut game);
+                spawned += game.pipes.iter().filter(|p| p.active()).count().saturating_sub(before);
+                let mut xs: Vec<i32> = game.pipes.iter().filter(|p| p.active()).map(|p| p.x).collect();
+                xs.sort_unstable();
+                for pair in xs.windows(2) {
+                    let gap = pair[1] - pair[0];
+                    assert!(gap > spawn_distance * 1000, "speed {speed}: {gap} apart at frame {frame}");
+                }
+            }
+            assert!(game.game_status() == GameStatus::Playing && spawned > 10, "speed {speed}");
+        }
+    }
+
+    #[test]
+    fn hitbox_inset_spares_the_sprite_corners_to_the_pixel() {
+        let clock = Clock { unix_timestamp: 1_000, ..Default::default() };
+        let mut game = GameState::default();
+        begin_run(&mut game, None, Difficulty::Normal, ScoringMode::PipesPassed, 1, &clock);
+        assert_eq!(game.hitbox_inset, HITBOX_INSET);
+        assert_eq!(hitbox(BIRD_SIZE, HITBOX_INSET), (4, BIRD_SIZE - 8));
+        // A tiny bird keeps a box at least a pixel across
+        assert_eq!(hitbox(BIRD_SIZE / 2, MAX_HITBOX_INSET), (7, 1));
+
+        // A still bird, alone or with one pipe, `inset` px in from the sprite
+        let still = |inset: u8, bird_y: i32, pipe: Option<Pipe>| {
+            let mut game = glide_course(0);
+            game.hitbox_inset = inset;
+            game.bird_y = bird_y * 1000;
+            game.pipes[0] = pipe.unwrap_or_default();
+            game.active_pipe_count = 0;
+            step_physics(&mut game).death
+        };
+
+        // Floor and ceiling: the sprite may sink `inset` px past either edge
+        assert_eq!(still(4, GAME_HEIGHT - BIRD_SIZE + 3, None), None);
+        assert_eq!(still(4, GAME_HEIGHT - BIRD_SIZE + 4, None), Some(DeathCause::Floor));
+        assert_eq!(still(0, GAME_HEIGHT - BIRD_SIZE, None), Some(DeathCause::Floor));
+        assert_eq!(still(4, -3, None), None);
+        assert_eq!(still(4, -4, None), Some(DeathCause::Ceiling));
+        assert_eq!(still(0, 0, None), Some(DeathCause::Ceiling));
+
+        // Pipe edges above and below: overlapping the pipe by `inset` px is fine
+        let gap_top = 200 - PIPE_GAP / 2;
+        let gap_bottom = 200 + PIPE_GAP / 2;
+        let beside = Some(Pipe { x: BIRD_X * 1000, gap_y: 200, active: 1, ..Default::default() });
+        assert_eq!(still(4, gap_top - 4, beside), None);
+        assert_eq!(still(4, gap_top - 5, beside), Some(DeathCause::Pipe));
+        assert_eq!(still(0, gap_top - 1, beside), Some(DeathCause::Pipe));
+        assert_eq!(still(4, gap_bottom - BIRD_SIZE + 4, beside), None);
+        assert_eq!(still(4, gap_bottom - BIRD_SIZE + 5, beside), Some(DeathCause::Pipe));
+
+        // Pipe front: a bird in the pipe's column dies only once the pipe
+        // reaches `inset` px into the sprite
+        let ahead = |overlap: i32| Some(Pipe { x: (BIRD_X + BIRD_SIZE - overlap) * 1000 + PIPE_SPEED, gap_y: 0, active: 1, ..Default::default() });
+        assert_eq!(still(4, 200, ahead(4)), None);
+        assert_eq!(still(4, 200, ahead(5)), Some(DeathCause::Pipe));
+        assert_eq!(still(0, 200, ahead(1)), Some(DeathCause::Pipe));
+    }
+
+    #[test]
+    fn hitbox_inset_does_not_delay_pipe_passes() {
+        let pass_frame = |inset: u8| {
+            let mut game = glide_course(1);
+            game.hitbox_inset = inset;
+            (0..).find(|_| step_physics(&mut game).pipes_passed > 0).unwrap()
+        };
+        assert_eq!(pass_frame(HITBOX_INSET), pass_frame(0));
+    }
+
+    #[test]
+    fn hitbox_inset_is_configured_and_snapshotted_per_run() {
+        let clock = Clock { unix_timestamp: 1_000, ..Default::default() };
+        let mut config = GameConfig {
+            physics: PhysicsConfig::default(),
+            bump: 0,
+            ranked_cooldown_secs: 0,
+            min_flap_interval_frames: 0,
+            hitbox_inset_plus_one: 0,
+            grace_frames_plus_one: 0,
+            countdown_frames: 0,
+            flap_skips_countdown: false,
+            streak_threshold: 0,
+            ranked_pickups: 0,
+            revive_coin_cost: 0,
+            paused: false,
+            submit_window_secs: 0,
+            reserved: [0; 43],
+        };
+        assert_eq!(config.hitbox_inset(), HITBOX_INSET);
+        config.hitbox_inset_plus_one = 1;
+        assert_eq!(config.hitbox_inset(), 0);
+        config.hitbox_inset_plus_one = 8;
+
+        let mut game = GameState::default();
+        begin_run(&mut game, Some(&config), Difficulty::Normal, ScoringMode::PipesPassed, 1, &clock);
+        assert_eq!(game.hitbox_inset, 7);
+        // Retuning the config leaves the run alone until the next one
+        config.hitbox_inset_plus_one = 0;
+        reset_run(&mut game);
+        assert_eq!(game.hitbox_inset, 7);
+        begin_run(&mut game, Some(&config), Difficulty::Normal, ScoringMode::PipesPassed, 1, &clock);
+        assert_eq!(game.hitbox_inset, HITBOX_INSET);
+
+        // Replays re-simulate with the run's inset
+        let params = ReplayParams { hitbox_inset: 7, ..recorded_run(1_700_000_000) };
+        assert_eq!(replay_start_state(&params).hitbox_inset, 7);
+    }
+
+    #[test]
+    fn pipes_pass_through_the_bird_in_the_grace_period() {
+        let clock = Clock { unix_timestamp: 1_000, ..Default::default() };
+        let mut config = GameConfig {
+            physics: PhysicsConfig::default(),
+            bump: 0,
+            ranked_cooldown_secs: 0,
+            min_flap_interval_frames: 0,
+            hitbox_inset_plus_one: 0,
+            grace_frames_plus_one: 0,
+            countdown_frames: 0,
+            flap_skips_countdown: false,
+            streak_threshold: 0,
+            ranked_pickups: 0,
+            revive_coin_cost: 0,
+            paused: false,
+            submit_window_secs: 0,
+            reserved: [0; 43],
+        };
+        let mut game = GameState::default();
+        begin_run(&mut game, Some(&config), Difficulty::Normal, ScoringMode::PipesPassed, 1, &clock);
+        assert_eq!(game.grace_frames, GRACE_FRAMES);
+        config.grace_frames_plus_one = 1;
+        assert_eq!(config.grace_frames(), 0);
+
+        // A still bird with a wall of a pipe (its only gap off screen) that
+        // reaches it mid-grace and is still on it after
+        let walled = |grace: u8| {
+            let mut game = glide_course(0);
+            game.grace_frames = grace;
+            game.pipes[0] = Pipe { x: BIRD_X * 1000 + (GRACE_FRAMES as i32 + 1) * PIPE_SPEED, gap_y: -500, active: 1, ..Default::default() };
+            game.active_pipe_count = 0;
+            game
+        };
+        let mut game = walled(GRACE_FRAMES);
+        for _ in 0..GRACE_FRAMES {
+            assert_eq!(step_physics(&mut game).death, None, "frame {}", game.frame_count);
+        }
+        assert!(game.pipes[0].x_pixels() < BIRD_X + BIRD_SIZE);
+        assert_eq!(step_physics(&mut game).death, Some(DeathCause::Pipe));
+        assert_eq!(game.frame_count, GRACE_FRAMES as u64 + 1);
+
+        // Without one the pipe kills as soon as it touches
+        let mut game = walled(0);
+        let death = (1..).find(|_| step_physics(&mut game).death.is_some()).unwrap();
+        assert!(death < GRACE_FRAMES as u64);
+
+        // The floor still kills
+        let mut game = walled(GRACE_FRAMES);
+        game.bird_y = GAME_HEIGHT * 1000;
+        assert_eq!(step_physics(&mut game).death, Some(DeathCause::Floor));
+
+        // A respawn after it gets only its own invulnerability
+        let mut game = walled(GRACE_FRAMES);
+        game.frame_count = GRACE_FRAMES as u64 + 50;
+        game.lives = 2;
+        assert!(respawn(&mut game, BIRD_SIZE));
+        assert!(!game.in_grace());
+    }
+
+    #[test]
+    fn first_pipe_waits_until_it_cannot_reach_the_bird_in_grace() {
+        let mut game = playing_at_slot(0);
+        game.physics.pipe_speed = 40_000;
+        game.grace_frames = GRACE_FRAMES;
+        game.set_difficulty(Difficulty::Normal);
+        reset_run(&mut game);
+        assert_eq!(game.pipe_speed, 40_000);
+        game.set_practice(true);
+        // 13.75 frames from GAME_WIDTH to BIRD_X at 40 px a frame: a pipe
+        // spawned on frame 6 would reach it on frame 20, one on frame 7 after
+        for frame in 1..=6 {
+            step_physics(&mut game);
+            assert_eq!((game.frame_count, game.pipes.iter().filter(|p| p.active()).count()), (frame, 0));
+        }
+        step_physics(&mut game);
+        let first = game.pipes.iter().find(|p| p.active()).expect("spawned on frame 7");
+        assert!(first.x - 13 * 40_000 > BIRD_X * 1000);
+
+        // At the default speed the first pipe is far enough away at once
+        let mut game = playing_at_slot(0);
+        game.grace_frames = GRACE_FRAMES;
+        step_physics(&mut game);
+        assert_eq!(game.pipes.iter().filter(|p| p.active()).count(), 1);
+
+        let params = ReplayParams { grace_frames: 9, ..recorded_run(1_700_000_000) };
+        assert_eq!(replay_start_state(&params).grace_frames, 9);
+    }
+
+    #[test]
+    fn countdown_holds_the_bird_until_it_runs_out() {
+        let clock = Clock { slot: 10, unix_timestamp: 1_000, ..Default::default() };
+        let config = GameConfig {
+            physics: PhysicsConfig::default(),
+            bump: 0,
+            ranked_cooldown_secs: 0,
+            min_flap_interval_frames: 0,
+            hitbox_inset_plus_one: 0,
+            grace_frames_plus_one: 0,
+            countdown_frames: 3,
+            flap_skips_countdown: false,
+            streak_threshold: 0,
+            ranked_pickups: 0,
+            revive_coin_cost: 0,
+            paused: false,
+            submit_window_secs: 0,
+            reserved: [0; 43],
+        };
+        let mut game = GameState::default();
+        begin_run(&mut game, Some(&config), Difficulty::Normal, ScoringMode::PipesPassed, 1, &clock);
+        assert!(game.game_status() == GameStatus::Countdown && game.game_status().in_run());
+        assert!(GameStatus::from_repr(GameStatus::Countdown as u8) == GameStatus::Countdown);
+        assert_eq!(game.countdown_frames_remaining, 3);
+
+        // Input waits, ticks count down; nothing moves and no frame passes
+        assert_eq!(require_playing(&game), err!(FlappyError::CountdownRunning));
+        assert_eq!(skip_countdown_by_flap(&mut game), err!(FlappyError::CountdownRunning));
+        assert!(require_ticking(&game).is_ok());
+        game.commit_interval = 1;
+        let (bird_y, pipes) = (game.bird_y, game.pipes);
+        for (slot, remaining) in [(11, 2), (12, 1)] {
+            assert_eq!(advance_at_slot(&mut game, slot, &mut ignore), TickOutcome::Advanced);
+            assert_eq!((game.countdown_frames_remaining, game.frame_count), (remaining, 0));
+            assert!(game.game_status() == GameStatus::Countdown && !commit_due(&game));
+        }
+        assert!(game.bird_y == bird_y && game.pipes == pipes);
+        assert_eq!(advance_at_slot(&mut game, 12, &mut ignore), TickOutcome::TooEarly);
+        assert_eq!(advance_frames(&mut game, 5, 14, &mut ignore), 2);
+        assert!(game.game_status() == GameStatus::Playing);
+        assert_eq!((game.countdown_frames_remaining, game.frame_count), (0, 1));
+        assert!(require_playing(&game).is_ok());
+
+        // A stale countdown catches up through to play as well
+        begin_run(&mut game, Some(&config), Difficulty::Normal, ScoringMode::PipesPassed, 1, &clock);
+        let frames = catch_up_frames(&mut game, 1_002, 30, &mut ignore);
+        assert!(frames > 3);
+        assert_eq!(game.frame_count, frames as u64 - 3);
+
+        // Where allowed, a flap goes live at once
+        let config = GameConfig { flap_skips_countdown: true, ..config };
+        begin_run(&mut game, Some(&config), Difficulty::Normal, ScoringMode::PipesPassed, 1, &clock);
+        assert!(skip_countdown_by_flap(&mut game).is_ok());
+        assert!(game.game_status() == GameStatus::Playing && game.countdown_frames_remaining == 0);
+
+        // No countdown without a config or with it set to 0
+        begin_run(&mut game, None, Difficulty::Normal, ScoringMode::PipesPassed, 1, &clock);
+        assert!(game.game_status() == GameStatus::Playing);
+        let config = GameConfig { countdown_frames: 0, ..config };
+        begin_run(&mut game, Some(&config), Difficulty::Normal, ScoringMode::PipesPassed, 1, &clock);
+        assert!(game.game_status() == GameStatus::Playing);
+        assert!(skip_countdown_by_flap(&mut game).is_ok());
+    }
+
+    #[test]
+    fn advance_matches_flapping_and_ticking_frame_by_frame() {
+        let clock = Clock { slot: 1_000, unix_timestamp: 1_000, ..Default::default() };
+        let mut batched = GameState::default();
+        begin_run(&mut batched, None, Difficulty::Normal, ScoringMode::PipesPassed, 7, &clock);
+        let mut stepped = batched;
+
+        // Three batches of 20 frames, a second apart. Bit 5 comes a frame
+        // after bit 4, too soon in a ranked run
+        let inputs: u64 = (1 << 4) | (1 << 5) | (1 << 20) | (1 << 59);
+        let mut flaps = 0;
+        for batch in 0..3 {
+            let slot = 1_100 + batch as u64 * 100;
+            let (frames, kept) = advance_with_inputs(&mut batched, inputs >> (batch * 20), 20, slot, 1_000 + batch as i64, &mut ignore).unwrap();
+            assert_eq!(frames, 20);
+            flaps += kept;
+        }
+        assert_eq!(flaps, 3);
+
+        let mut kept = 0;
+        for frame in 0..60 {
+            if inputs >> frame & 1 != 0 && queue_flap(&mut stepped).unwrap() {
+                kept += 1;
+            }
+            step_physics(&mut stepped);
+        }
+        assert_eq!(kept, 3);
+        assert_eq!(batched.frame_count, 60);
+        assert_eq!(
+            (batched.bird_y, batched.bird_velocity, batched.score, batched.flaps_this_run, batched.input_log),
+            (stepped.bird_y, stepped.bird_velocity, stepped.score, stepped.flaps_this_run, stepped.input_log)
+        );
+        assert_eq!((batched.last_tick_slot, batched.ticks_this_second), (1_300, 20));
+    }
 
-#[account]
-#[derive(InitSpace)]
-pub struct GameState {
-    /// Player who owns this game
-    pub authority: Pubkey,
-    /// Current score
-    pub score: u64,
-    /// Highest score achieved
-    pub high_score: u64,
-    /// Current game status
-    pub game_status: GameStatus,
-    /// Bird Y position (fixed-point, scaled by 1000)
-    pub bird_y: i32,
-    /// Bird velocity (fixed-point, scaled by 1000)
-    pub bird_velocity: i32,
-    /// Frame counter for timing
-    pub frame_count: u64,
-    /// Last update timestamp
-    pub last_update: i64,
-    /// Pipe data (up to 5 pipes on screen)
-    #[max_len(5)]
-    pub pipes: [Pipe; 5],
-    /// X position for next pipe spawn
-    pub next_pipe_spawn_x: i32,
-    /// Random seed for pipe generation
-    pub seed: u64,
-}
+    #[test]
+    fn advance_stays_within_the_budgets_and_stops_at_game_over() {
+        let clock = Clock { slot: 1_000, unix_timestamp: 1_000, ..Default::default() };
+        let mut game = GameState::default();
+        begin_run(&mut game, None, Difficulty::Normal, ScoringMode::PipesPassed, 7, &clock);
+        game.set_practice(true);
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Default)]
-pub struct Pipe {
-    /// X position of pipe
-    pub x: i32,
-    /// Y position of gap center
-    pub gap_y: i32,
-    /// Whether bird has passed this pipe
-    pub passed: bool,
-    /// Whether pipe is active
-    pub active: bool,
-}
+        // Five slots since the last frame allow five frames, however many are asked
+        assert_eq!(advance_with_inputs(&mut game, 0, 64, 1_005, 1_000, &mut ignore).unwrap(), (5, 0));
+        assert_eq!(advance_with_inputs(&mut game, u64::MAX, 64, 1_005, 1_000, &mut ignore).unwrap(), (0, 0));
+        assert_eq!(game.flaps_this_run, 0);
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Default)]
-pub enum GameStatus {
-    #[default]
-    NotStarted,
-    Playing,
-    GameOver,
-}
+        // The wall-clock budget spans the batch, so a full 64 never fit a second
+        assert_eq!(advance_with_inputs(&mut game, 0, 64, 2_000, 1_000, &mut ignore).unwrap().0, 20);
+        assert_eq!(advance_with_inputs(&mut game, 0, 64, 2_001, 1_000, &mut ignore).unwrap().0, 0);
+        let (frames, _) = advance_with_inputs(&mut game, 0, u8::MAX, 3_000, 1_001, &mut ignore).unwrap();
+        assert_eq!(frames as u16, MAX_FRAMES_PER_SECOND);
 
+        // A run that ends mid-batch stops it there
+        begin_run(&mut game, None, Difficulty::Normal, ScoringMode::PipesPassed, 7, &clock);
+        game.bird_y = (GAME_HEIGHT - BIRD_SIZE - 10) * 1000;
+        let (frames, _) = advance_with_inputs(&mut game, 0, 20, 2_000, 1_002, &mut ignore).unwrap();
+        assert!(frames < 20 && game.game_status() == GameStatus::GameOver);
+        assert_eq!(game.frame_count, frames as u64);
+        assert_eq!(game.end_cause(), Some(DeathCause::Floor));
+    }
 
-#[error_code]
-pub enum FlappyError {
-    #[msg("Game is not in playing state")]
-    GameNotPlaying,
-    #[msg("Game has already started")]
-    GameAlreadyStarted,
-}
+    #[test]
+    fn each_kind_of_death_is_recorded_with_where_the_bird_was() {
+        // A bird gliding at `bird_y` into a pipe in slot 2, or with no pipe
+        let crash = |bird_y: i32, velocity: i32, pipe: bool| {
+            let mut game = glide_course(0);
+            game.bird_y = bird_y * 1000;
+            game.bird_velocity = velocity;
+            if pipe {
+                game.pipes[2] = Pipe { x: BIRD_X * 1000, gap_y: 200, active: 1, ..Default::default() };
+                game.active_pipe_count = 0;
+            }
+            let report = step_physics(&mut game);
+            assert_eq!(report.death, game.end_cause());
+            game
+        };
+
+        let floor = crash(GAME_HEIGHT - BIRD_SIZE - 2, 3_000, false);
+        assert_eq!((floor.end_cause(), floor.end_bird_y, floor.end_pipe()), (Some(DeathCause::Floor), GAME_HEIGHT - BIRD_SIZE + 1, None));
+        let ceiling = crash(2, -3_000, false);
+        assert_eq!((ceiling.end_cause(), ceiling.end_bird_y, ceiling.end_pipe()), (Some(DeathCause::Ceiling), -1, None));
+
+        let top = crash(200 - PIPE_GAP / 2 - 10, 0, true);
+        assert_eq!(top.end_cause(), Some(DeathCause::Pipe));
+        assert_eq!(top.end_pipe(), Some(PipeHit { index: 2, half: PipeHalf::Top }));
+        assert_eq!(top.end_bird_y, 200 - PIPE_GAP / 2 - 10);
+        let bottom = crash(200 + PIPE_GAP / 2 - BIRD_SIZE + 10, 0, true);
+        assert_eq!(bottom.end_pipe(), Some(PipeHit { index: 2, half: PipeHalf::Bottom }));
+
+        // The event carries all of it
+        let event = top.game_over();
+        assert_eq!((event.cause, event.frame, event.bird_y), (DeathCause::Pipe, 1, top.end_bird_y));
+        assert_eq!(event.pipe, top.end_pipe());
+
+        // Ending by hand clears the pipe of an earlier crash
+        let mut game = top;
+        game.set_game_status(GameStatus::Playing);
+        finish_run(&mut game, DeathCause::Manual, 0);
+        assert_eq!((game.end_cause(), game.end_pipe()), (Some(DeathCause::Manual), None));
+        assert_eq!(game.game_over().pipe, None);
+
+        // Starting a run clears the last one's ending
+        let mut game = bottom;
+        let clock = Clock { unix_timestamp: 1_000, ..Default::default() };
+        begin_run(&mut game, None, Difficulty::Normal, ScoringMode::PipesPassed, 1, &clock);
+        assert_eq!((game.end_cause(), game.end_frame, game.end_bird_y, game.end_pipe()), (None, 0, 0, None));
+    }
+
+    #[test]
+    fn streaks_count_each_run_once() {
+        let clock = Clock::default();
+        let mut game = GameState::default();
+        let mut profile = PlayerProfile::default();
+        // A run scoring `score` that ends on the floor, then is ended again
+        // the way end_game_with_reward can
+        let run = |game: &mut GameState, profile: &mut PlayerProfile, score: u64| {
+            begin_run(game, None, Difficulty::Normal, ScoringMode::PipesPassed, 1, &clock);
+            game.score = score;
+            game.bird_y = (GAME_HEIGHT - BIRD_SIZE) * 1000;
+            game.bird_velocity = MAX_VELOCITY;
+            {
+                let mut hooks = frame_hooks(Some(&mut *profile), None, None, 0);
+                while game.game_status() != GameStatus::GameOver {
+                    step_frame(game, &mut hooks);
+                }
+            }
+            finish_run(game, DeathCause::Manual, 0);
+            profile.record_run_end(game, 0);
+        };
+
+        for streak in 1..=4 {
+            run(&mut game, &mut profile, STREAK_THRESHOLD as u64);
+            assert_eq!((profile.current_streak, profile.best_streak), (streak, streak));
+        }
+        assert_eq!(profile.achievements & 1 << ACHIEVEMENT_STREAK_5, 0);
+        run(&mut game, &mut profile, 30);
+        assert_eq!(profile.current_streak, 5);
+        assert_ne!(profile.achievements & 1 << ACHIEVEMENT_STREAK_5, 0);
+
+        // Falling short breaks the streak but keeps the best
+        run(&mut game, &mut profile, STREAK_THRESHOLD as u64 - 1);
+        assert_eq!((profile.current_streak, profile.best_streak), (0, 5));
+        run(&mut game, &mut profile, STREAK_THRESHOLD as u64);
+        assert_eq!((profile.current_streak, profile.best_streak), (1, 5));
+    }
+
+    #[test]
+    fn practice_runs_leave_the_streak_alone() {
+        let mut profile = PlayerProfile { current_streak: 3, best_streak: 3, ..Default::default() };
+        let mut game = GameState { score: 0, ..playing_at_slot(0) };
+        game.set_practice(true);
+        game.end_run(DeathCause::Manual);
+        profile.record_run_end(&mut game, 0);
+        assert_eq!((profile.current_streak, profile.best_streak), (3, 3));
+
+        // A short run ended by hand breaks it, once
+        let mut game = GameState { streak_threshold: 5, score: 4, ..playing_at_slot(0) };
+        game.end_run(DeathCause::Manual);
+        profile.record_run_end(&mut game, 0);
+        assert_eq!(profile.current_streak, 0);
+        game.score = 5;
+        profile.record_run_end(&mut game, 0);
+        assert_eq!(profile.current_streak, 0);
+    }
+
+    #[test]
+    fn total_distance_follows_the_ramped_speed_and_survives_resets() {
+        let mut game = glide_course(0);
+        game.set_practice(true);
+        let mut profile = PlayerProfile::default();
+        let mut hooks = |game: &mut GameState, report: &FrameReport| profile.record_frame(game, report, 0);
+        step_frame(&mut game, &mut hooks);
+        assert_eq!(game.total_distance, PIPE_SPEED as u64);
+
+        // A frame starting at 10 points scrolls a ramp step faster
+        game.score = RAMP_PIPES_PER_LEVEL;
+        step_frame(&mut game, &mut hooks);
+        assert_eq!(game.total_distance, (2 * PIPE_SPEED + RAMP_SPEED_STEP) as u64);
+        assert_eq!(profile.total_distance, game.total_distance);
+
+        reset_run(&mut game);
+        assert_eq!((game.distance, game.total_distance), (0, (2 * PIPE_SPEED + RAMP_SPEED_STEP) as u64));
+        assert_eq!(game.snapshot().total_distance, (2 * PIPE_SPEED + RAMP_SPEED_STEP) as u64 / 1000);
+
+        // A grinder caps instead of wrapping
+        let mut game = GameState { total_distance: u64::MAX - 1, ..glide_course(0) };
+        step_physics(&mut game);
+        assert_eq!(game.total_distance, u64::MAX);
+    }
+
+    #[test]
+    fn distance_achievements_unlock_on_lifetime_pixels() {
+        let game = playing_at_slot(0);
+        let earned = |pixels: u64| {
+            let profile = PlayerProfile { total_distance: pixels * 1000, ..Default::default() };
+            evaluate_achievements(&game, &profile) & (1 << ACHIEVEMENT_LONG_HAUL | 1 << ACHIEVEMENT_MARATHON)
+        };
+        assert_eq!(earned(LONG_HAUL_PIXELS - 1), 0);
+        assert_eq!(earned(LONG_HAUL_PIXELS), 1 << ACHIEVEMENT_LONG_HAUL);
+        assert_eq!(earned(MARATHON_PIXELS), 1 << ACHIEVEMENT_LONG_HAUL | 1 << ACHIEVEMENT_MARATHON);
+    }
+
+    #[test]
+    fn start_seed_uses_the_newest_slot_hash_when_there_is_one() {
+        let authority = Pubkey::new_unique();
+        let clock = Clock { slot: 11, unix_timestamp: 1_000, ..Default::default() };
+        let mut slot_hashes = vec![0; 8 + 2 * 40];
+        slot_hashes[..8].copy_from_slice(&2u64.to_le_bytes());
+        slot_hashes[16..48].copy_from_slice(&[1; 32]);
+        slot_hashes[56..].copy_from_slice(&[2; 32]);
+        assert_eq!(newest_slot_hash(&slot_hashes), Some(&[1; 32][..]));
+
+        let (seed, source) = start_seed(7, &authority, &clock, &slot_hashes);
+        assert_eq!(source, SEED_SOURCE_SLOT_HASH);
+        assert_eq!(start_seed(7, &authority, &clock, &slot_hashes), (seed, source));
+        // Two players starting in the same second get different layouts
+        assert_ne!(start_seed(7, &Pubkey::new_unique(), &clock, &slot_hashes).0, seed);
+        let later = Clock { unix_timestamp: 1_001, ..clock.clone() };
+        assert_ne!(start_seed(7, &authority, &later, &slot_hashes).0, seed);
+        let mut other_hash = slot_hashes.clone();
+        other_hash[16] = 9;
+        assert_ne!(start_seed(7, &authority, &clock, &other_hash).0, seed);
+
+        // No entries, or no sysvar data at all, falls back to the clock
+        slot_hashes[..8].copy_from_slice(&0u64.to_le_bytes());
+        for data in [&slot_hashes[..], &[][..], &[1, 0, 0, 0, 0, 0, 0, 0][..]] {
+            assert_eq!(newest_slot_hash(data), None);
+            let (fallback, source) = start_seed(7, &authority, &clock, data);
+            assert_eq!(source, SEED_SOURCE_CLOCK);
+            assert_ne!(fallback, seed);
+            assert_ne!(start_seed(7, &authority, &later, data).0, fallback);
+        }
+    }
+
+    #[test]
+    fn gravity_flip_turns_gravity_and_flaps_around_until_it_runs_out() {
+        let mut game = pipe_on_bird(GAME_HEIGHT / 2);
+        game.pipes[0].pickup = Powerup::GravityFlip.repr();
+        game.bird_y = (GAME_HEIGHT / 2 - BIRD_SIZE / 2) * 1000;
+        game.bird_velocity = -GRAVITY;
+        step_physics(&mut game);
+        assert_eq!(game.active_powerup(), Some(ActivePowerup { kind: Powerup::GravityFlip, frames_remaining: FLIP_FRAMES }));
+        assert_eq!(game.pipes[0].pickup, 0);
+
+        let mut game = holding(Powerup::GravityFlip, 2);
+        step_physics(&mut game);
+        assert_eq!(game.bird_velocity, -GRAVITY);
+        // A flap pushes the bird down
+        game.set_pending_flap(true);
+        step_physics(&mut game);
+        assert_eq!((game.bird_velocity, game.active_powerup()), (-JUMP_VELOCITY - GRAVITY, None));
+
+        // Expiring mid-air, gravity pulls down again from the same velocity
+        let y = game.bird_y;
+        step_physics(&mut game);
+        assert_eq!(game.bird_velocity, -JUMP_VELOCITY);
+        assert_eq!(game.bird_y, y + game.bird_velocity);
+    }
+
+    #[test]
+    fn gravity_flips_spawn_outside_ranked_runs_unless_the_config_allows_them() {
+        let spawn = |seed: u64, setup: fn(&mut GameState)| {
+            let mut game = GameState { pickups: PICKUP_KINDS, ..playing_at_slot(0) };
+            game.seed = seed;
+            setup(&mut game);
+            spawn_pipes(&mut game);
+            game.pipes[0]
+        };
+        let seeds = (0..1_000u64).map(|i| 1_700_000_000 + i * 7_919);
+        let flipped = |setup: fn(&mut GameState)| -> Vec<u64> {
+            seeds.clone().filter(|&seed| spawn(seed, setup).pickup == Powerup::GravityFlip.repr()).collect()
+        };
+        let unranked = flipped(|g| g.set_practice(true));
+        assert!(!unranked.is_empty() && unranked.len() < 100);
+        assert!(seeds.clone().all(|seed| {
+            let pipe = spawn(seed, |g| g.set_practice(true));
+            pipe.pickup == 0 || !(pipe.coin() || pipe.shield())
+        }));
+
+        assert!(flipped(|_| {}).is_empty());
+        assert!(flipped(|g| {
+            g.set_daily(true);
+            g.set_extra_lives(true);
+        })
+        .is_empty());
+        assert_eq!(flipped(|g| g.ranked_pickups = 1 << Powerup::GravityFlip.repr()), unranked);
+    }
+
+    #[test]
+    fn slow_motion_halves_the_world_for_its_window() {
+        let mut game = pipe_on_bird(GAME_HEIGHT / 2);
+        game.pipes[0].pickup = Powerup::SlowMotion.repr();
+        game.bird_y = (GAME_HEIGHT / 2 - BIRD_SIZE / 2) * 1000;
+        game.bird_velocity = -GRAVITY;
+        step_physics(&mut game);
+        assert_eq!(game.active_powerup().map(|p| p.frames_remaining), Some(SLOW_FRAMES));
+        assert_eq!(game.time_scale(), SLOW_TIME_SCALE);
+
+        let mut game = glide_course(1);
+        game.set_active_powerup(Some(ActivePowerup { kind: Powerup::SlowMotion, frames_remaining: 2 }));
+        game.physics.gravity = GRAVITY;
+        let x = game.pipes[0].x;
+        let y = game.bird_y;
+        let report = step_physics(&mut game);
+        assert_eq!((game.pipes[0].x, report.scrolled), (x - PIPE_SPEED / 2, PIPE_SPEED as u32 / 2));
+        assert_eq!((game.bird_velocity, game.bird_y), (GRAVITY / 2, y + GRAVITY / 4));
+        // The last slow frame is still slow, and full speed follows it
+        step_physics(&mut game);
+        assert_eq!((game.pipes[0].x, game.time_scale()), (x - PIPE_SPEED, TIME_SCALE_NORMAL));
+        step_physics(&mut game);
+        assert_eq!(game.pipes[0].x, x - 2 * PIPE_SPEED);
+
+        // Holding nothing, or anything else, runs at full speed
+        assert_eq!(GameState::default().time_scale(), TIME_SCALE_NORMAL);
+        assert_eq!(holding(Powerup::Magnet, 5).time_scale(), TIME_SCALE_NORMAL);
+    }
+
+    #[test]
+    fn slow_motion_keeps_passes_in_step_with_the_pipes() {
+        let mut game = playing_at_slot(0);
+        game.seed = 1_700_000_000;
+        game.set_practice(true);
+        game.set_scoring(ScoringMode::Distance);
+        let (mut passes, mut scrolled) = (0, 0);
+        for frame in 0..3_000u64 {
+            // Slow for 80 frames of every 200
+            if frame % 200 == 0 {
+                game.pick_up(Powerup::SlowMotion);
+            }
+            game.set_pending_flap(autopilot_flap(&game));
+            let before = game.pipes;
+            let report = step_physics(&mut game);
+            passes += report.pipes_passed as u64;
+            scrolled += report.scrolled as u64;
+            for (pipe, old) in game.pipes.iter().zip(&before).filter(|(p, _)| p.active()) {
+                // Every pipe moved by what the frame scrolled, and is passed
+                // exactly once it's behind the bird
+                if old.active() {
+                    assert_eq!(old.x - pipe.x, report.scrolled as i32, "frame {frame}");
+                }
+                assert_eq!(pipe.passed(), pipe.x_pixels() + PIPE_WIDTH < game.bird_x / 1000, "frame {frame}");
+            }
+        }
+        assert!(game.game_status() == GameStatus::Playing);
+        assert!(passes > 10);
+        assert_eq!((game.pipes_passed, game.distance), (passes, scrolled));
+    }
+
+    #[test]
+    fn shrink_halves_the_collision_box_about_its_center() {
+        assert_eq!(bird_hitbox(BIRD_SIZE, HITBOX_INSET, false), hitbox(BIRD_SIZE, HITBOX_INSET));
+        assert_eq!(bird_hitbox(BIRD_SIZE, HITBOX_INSET, true), (7 + 4, 15 - 8));
+        assert_eq!(bird_hitbox(BIRD_SIZE, 0, true), (7, 15));
+
+        // The full box would clip the top pipe by 3 px; the shrunk one clears it
+        let gap_top = GAME_HEIGHT / 2 - PIPE_GAP / 2;
+        let clipping = |shrunk: bool| {
+            let mut game = GameState { hitbox_inset: HITBOX_INSET, ..pipe_on_bird(GAME_HEIGHT / 2) };
+            if shrunk {
+                game.pick_up(Powerup::Shrink);
+            }
+            game.bird_y = (gap_top - HITBOX_INSET as i32 - 3) * 1000;
+            game.bird_velocity = -GRAVITY;
+            step_physics(&mut game).death
+        };
+        assert_eq!(clipping(false), Some(DeathCause::Pipe));
+        assert_eq!(clipping(true), None);
+    }
+
+    #[test]
+    fn shrink_lasts_until_regrowing_is_safe() {
+        let gap_top = GAME_HEIGHT / 2 - PIPE_GAP / 2;
+        let mut game = GameState { hitbox_inset: HITBOX_INSET, ..pipe_on_bird(GAME_HEIGHT / 2) };
+        game.set_active_powerup(Some(ActivePowerup { kind: Powerup::Shrink, frames_remaining: 1 }));
+        game.physics.gravity = 0;
+        game.bird_y = (gap_top - HITBOX_INSET as i32 - 3) * 1000;
+        let mut extended = 0;
+        while game.active_powerup().is_some() {
+            assert_eq!(step_physics(&mut game).death, None);
+            extended += 1;
+        }
+        // Kept small until the pipe was past, then full size without a hit
+        assert!(extended > 1);
+        assert!(game.pipes[0].x_pixels() + PIPE_WIDTH <= BIRD_X + HITBOX_INSET as i32);
+        assert_eq!(step_physics(&mut game).death, None);
+
+        // Clear of everything, it regrows on time
+        let mut game = holding(Powerup::Shrink, 2);
+        step_physics(&mut game);
+        step_physics(&mut game);
+        assert_eq!(game.active_powerup(), None);
+    }
+
+    #[test]
+    fn every_pickup_kind_spawns_from_the_seed() {
+        let mut seen = [false; Powerup::TIMED.len() + 2];
+        for i in 0..2_000u64 {
+            let mut game = GameState { pickups: PICKUP_KINDS, ..playing_at_slot(0) };
+            game.set_practice(true);
+            game.seed = 1_700_000_000 + i * 7_919;
+            spawn_pipes(&mut game);
+            seen[game.pipes[0].pickup as usize] = true;
+        }
+        // Everything but a shield, which spawns on its own
+        seen[Powerup::Shield.repr() as usize] = true;
+        assert!(seen.iter().all(|&s| s));
+    }
+
+    /// A playing state holding `kind` with `frames_remaining` left
+    fn holding(kind: Powerup, frames_remaining: u8) -> GameState {
+        let mut game = playing_at_slot(0);
+        game.set_active_powerup(Some(ActivePowerup { kind, frames_remaining }));
+        game
+    }
+
+    #[test]
+    fn a_new_powerup_replaces_the_one_held() {
+        let mut game = pipe_on_bird(GAME_HEIGHT / 2);
+        game.pick_up(Powerup::Shield);
+        game.pipes[0].pickup = Powerup::SlowMotion.repr();
+        game.bird_y = (GAME_HEIGHT / 2 - BIRD_SIZE / 2) * 1000;
+        game.bird_velocity = -GRAVITY;
+        step_physics(&mut game);
+        assert!(!game.has_shield());
+        assert_eq!(game.time_scale(), SLOW_TIME_SCALE);
+
+        // The replacement starts in full, and nothing of slow motion is left
+        game.pick_up(Powerup::GravityFlip);
+        assert_eq!(game.active_powerup(), Some(ActivePowerup { kind: Powerup::GravityFlip, frames_remaining: FLIP_FRAMES }));
+        assert_eq!(game.time_scale(), TIME_SCALE_NORMAL);
+        game.pick_up(Powerup::Shield);
+        assert_eq!(game.active_powerup(), Some(ActivePowerup { kind: Powerup::Shield, frames_remaining: 0 }));
+    }
+
+    #[test]
+    fn timed_powerups_expire_and_shields_do_not() {
+        for kind in Powerup::TIMED {
+            let mut game = glide_course(0);
+            game.pick_up(kind);
+            for _ in 1..kind.frames() {
+                step_physics(&mut game);
+            }
+            assert_eq!(game.active_powerup().map(|p| (p.kind, p.frames_remaining)), Some((kind, 1)), "{kind:?}");
+            step_physics(&mut game);
+            assert_eq!(game.active_powerup(), None, "{kind:?}");
+        }
+
+        let mut game = glide_course(0);
+        game.pick_up(Powerup::Shield);
+        for _ in 0..500 {
+            step_physics(&mut game);
+        }
+        assert!(game.has_shield());
+
+        // A shield from before power-ups, stored as a flag of 1, still reads
+        // as one; starting over drops whatever is held
+        let mut game = GameState { powerup: 1, ..playing_at_slot(0) };
+        assert!(game.has_shield());
+        game.pick_up(Powerup::Magnet);
+        reset_run(&mut game);
+        assert_eq!((game.powerup, game.powerup_frames), (0, 0));
+    }
 
+    #[test]
+    fn magnet_collects_coins_within_its_radius() {
+        // Bird centered on (15, 15); the coin sits centered in the gap
+        let coin_at = |x: i32, y: i32| Pipe { x: (x - PIPE_WIDTH / 2) * 1000, gap_y: y, ..Default::default() };
+        assert!(within_magnet(0, 0, BIRD_SIZE, &coin_at(15 + MAGNET_RADIUS, 15)));
+        assert!(!within_magnet(0, 0, BIRD_SIZE, &coin_at(16 + MAGNET_RADIUS, 15)));
+        assert!(within_magnet(0, 0, BIRD_SIZE, &coin_at(15 + 36, 15 - 48)));
+        assert!(!within_magnet(0, 0, BIRD_SIZE, &coin_at(15 + 37, 15 - 48)));
+
+        // A coin 40 px ahead is out of reach, unless a magnet pulls it in
+        let collected = |magnet: bool| {
+            let mut game = glide_course(0);
+            if magnet {
+                game.pick_up(Powerup::Magnet);
+            }
+            let center_x = game.bird_x / 1000 + BIRD_SIZE / 2;
+            let mut pipe = coin_at(center_x + 40, game.bird_y / 1000 + BIRD_SIZE / 2);
+            pipe.x += PIPE_SPEED;
+            pipe.set_active(true);
+            pipe.set_coin(true);
+            game.pipes[0] = pipe;
+            let report = step_physics(&mut game);
+            assert_eq!(report.death, None);
+            report.coins_collected
+        };
+        assert_eq!(collected(false), 0);
+        assert_eq!(collected(true), 1);
+    }
+
+    #[test]
+    fn coin_revive_spends_the_balance_once_per_run() {
+        let clock = Clock { slot: 9_000, unix_timestamp: 1_234, ..Default::default() };
+        let mut game = playing_at_slot(0);
+        game.coins = REVIVE_COIN_COST + 10;
+        assert_eq!(revive_for_coins(&mut game, REVIVE_COIN_COST, &clock), err!(FlappyError::GameNotOver));
+        run_until_over(&mut game, |_| false);
+
+        // Short of the cost, nothing changes
+        let before = encode(&game);
+        assert_eq!(revive_for_coins(&mut game, REVIVE_COIN_COST + 11, &clock), err!(FlappyError::InsufficientCoins));
+        assert_eq!(encode(&game), before);
+
+        revive_for_coins(&mut game, REVIVE_COIN_COST, &clock).unwrap();
+        assert!(game.game_status() == GameStatus::Playing && game.revived());
+        assert_eq!(game.coins, 10);
+        assert_eq!(game.last_tick_slot, 9_000);
+
+        // One revive a run, whichever way it's paid for
+        run_until_over(&mut game, |_| false);
+        assert_eq!(revive_for_coins(&mut game, 0, &clock), err!(FlappyError::AlreadyRevived));
+        assert_eq!(check_revivable(&game), err!(FlappyError::AlreadyRevived));
+        assert_eq!(game.coins, 10);
+        reset_run(&mut game);
+        run_until_over(&mut game, |_| false);
+        revive_run(&mut game, &clock);
+        run_until_over(&mut game, |_| false);
+        assert_eq!(revive_for_coins(&mut game, 0, &clock), err!(FlappyError::AlreadyRevived));
+    }
+
+    #[test]
+    fn coin_revived_runs_are_not_ranked() {
+        let clock = Clock::default();
+        let mut game = playing_at_slot(0);
+        game.coins = REVIVE_COIN_COST;
+        game.set_daily(true);
+        run_until_over(&mut game, |_| false);
+        assert!(game.ranked());
+        revive_for_coins(&mut game, REVIVE_COIN_COST, &clock).unwrap();
+        run_until_over(&mut game, |_| false);
+        assert!(!game.ranked());
+        assert_eq!(game.claim_reward(), 0);
+        game.daily_day = day_of(game.last_update);
+        assert_eq!(daily_board_day(&game), err!(FlappyError::RevivedRun));
+
+        // The cost falls back to the default until the config sets one
+        let config = GameConfig {
+            physics: PhysicsConfig::default(),
+            bump: 0,
+            ranked_cooldown_secs: 0,
+            min_flap_interval_frames: 0,
+            hitbox_inset_plus_one: 0,
+            grace_frames_plus_one: 0,
+            countdown_frames: 0,
+            flap_skips_countdown: false,
+            streak_threshold: 0,
+            ranked_pickups: 0,
+            revive_coin_cost: 0,
+            paused: false,
+            submit_window_secs: 0,
+            reserved: [0; 43],
+        };
+        assert_eq!(config.revive_coin_cost(), REVIVE_COIN_COST);
+        assert_eq!(GameConfig { revive_coin_cost: 5, ..config }.revive_coin_cost(), 5);
+    }
+
+    #[test]
+    fn missions_rotate_by_day_and_week() {
+        let now = 19_676 * SECONDS_PER_DAY + 600;
+        let missions: Vec<Mission> = (0..MISSION_SLOTS).map(|slot| rotation_mission(slot, now)).collect();
+        // One of each kind a day, and two different kinds a week
+        let mut daily: Vec<usize> = missions[..DAILY_MISSION_SLOTS].iter().map(|m| m.kind as usize).collect();
+        daily.sort();
+        assert_eq!(daily, vec![0, 1, 2, 3]);
+        assert_ne!(missions[4].kind, missions[5].kind);
+        for (slot, mission) in missions.iter().enumerate() {
+            let weekly = slot >= DAILY_MISSION_SLOTS;
+            let end = if weekly { (week_of(now) as i64 + 1) * SECONDS_PER_WEEK } else { 19_677 * SECONDS_PER_DAY };
+            assert_eq!(mission.expires_at, end);
+            assert!((0..MISSION_TIERS).any(|tier| mission.target == mission.kind.target(weekly, tier)));
+            assert_eq!((mission.progress, mission.claimed), (0, false));
+            // The same all day
+            assert_eq!(rotation_mission(slot, 19_677 * SECONDS_PER_DAY - 1), *mission);
+        }
+        assert_ne!(rotation_mission(0, now + SECONDS_PER_DAY).kind, missions[0].kind);
+    }
+
+    #[test]
+    fn gameplay_advances_unexpired_missions() {
+        let now = 19_676 * SECONDS_PER_DAY;
+        let mut missions = Missions::default();
+        assert_eq!(missions.refresh(now), MISSION_SLOTS as u8);
+        let slot_of = |missions: &Missions, kind| missions.missions[..DAILY_MISSION_SLOTS].iter().position(|m| m.kind == kind).unwrap();
+        let pipes = slot_of(&missions, MissionKind::PassNPipes);
+        let score = slot_of(&missions, MissionKind::ScoreAtLeastX);
+        missions.missions[pipes].target = 1_000;
+        missions.missions[score].target = 1_000;
+
+        let mut game = playing_at_slot(0);
+        game.seed = 1_700_000_000;
+        let mut passed = 0;
+        {
+            let mut hooks = frame_hooks(None, Some(&mut missions), None, now);
+            let mut slot = game.last_tick_slot;
+            while game.game_status() == GameStatus::Playing && game.frame_count < 5_000 {
+                game.set_pending_flap(autopilot_flap(&game));
+                slot += 1;
+                advance_at_slot(&mut game, slot, &mut |game, report| {
+                    passed += report.pipes_passed as u32;
+                    hooks(game, report)
+                });
+            }
+        }
+        assert!(passed > 0);
+        assert_eq!(missions.missions[pipes].progress, passed);
+        assert_eq!(missions.missions[score].progress as u64, game.score);
+
+        // Progress stops at the target, and counts only until expiry
+        let progress = missions.missions[pipes].progress;
+        missions.missions[pipes].target = progress + 1;
+        let report = FrameReport { pipes_passed: 3, ..Default::default() };
+        missions.record_frame(&game, &report, now);
+        assert!(missions.missions[pipes].complete());
+        assert_eq!(missions.missions[pipes].progress, progress + 1);
+        let games = slot_of(&missions, MissionKind::PlayNGames);
+        missions.record_game_started(now);
+        assert_eq!(missions.missions[games].progress, 1);
+        missions.record_game_started(missions.missions[games].expires_at);
+        assert_eq!(missions.missions[games].progress, 1);
+
+        // Practice doesn't count
+        let before = missions.missions;
+        let mut game = playing_at_slot(0);
+        game.seed = 1_700_000_000;
+        game.set_practice(true);
+        game.score = 5;
+        missions.record_frame(&game, &report, now);
+        missions.record_run_end(&game, now);
+        assert_eq!(missions.missions, before);
+    }
+
+    #[test]
+    fn mission_rewards_are_claimed_once_and_expired_missions_refresh() {
+        let now = 19_676 * SECONDS_PER_DAY;
+        let mut missions = Missions::default();
+        missions.refresh(now);
+        let slot = (0..MISSION_SLOTS).find(|&slot| missions.missions[slot].kind == MissionKind::PlayNGames).unwrap();
+        assert_eq!(missions.claim(slot as u8), err!(FlappyError::MissionNotComplete));
+        assert_eq!(missions.claim(MISSION_SLOTS as u8), err!(FlappyError::InvalidMissionSlot));
+        for _ in 0..missions.missions[slot].target {
+            missions.record_game_started(now);
+        }
+        assert_eq!(missions.claim(slot as u8), Ok(missions.missions[slot].reward_coins));
+        assert_eq!(missions.claim(slot as u8), err!(FlappyError::MissionAlreadyClaimed));
+
+        // Nothing is replaced before it expires; the next day, only the
+        // daily missions are
+        assert_eq!(missions.refresh(now + 60), 0);
+        let weekly = missions.missions[DAILY_MISSION_SLOTS..].to_vec();
+        let tomorrow = now + SECONDS_PER_DAY;
+        let replaced = if week_of(tomorrow) == week_of(now) { DAILY_MISSION_SLOTS } else { MISSION_SLOTS };
+        assert_eq!(missions.refresh(tomorrow) as usize, replaced);
+        assert!(missions.missions.iter().all(|m| !m.claimed && m.progress == 0 && m.expires_at > tomorrow));
+        if replaced == DAILY_MISSION_SLOTS {
+            assert_eq!(&missions.missions[DAILY_MISSION_SLOTS..], &weekly[..]);
+        }
+    }
+
+    #[test]
+    fn levels_start_at_each_square_threshold() {
+        assert_eq!(level_for_xp(0), 0);
+        assert_eq!(level_for_xp(LEVEL_XP_BASE - 1), 0);
+        assert_eq!(level_for_xp(LEVEL_XP_BASE), 1);
+        assert_eq!(level_for_xp(4 * LEVEL_XP_BASE - 1), 1);
+        assert_eq!(level_for_xp(4 * LEVEL_XP_BASE), 2);
+        assert_eq!(level_for_xp(9 * LEVEL_XP_BASE - 1), 2);
+        assert_eq!(level_for_xp(9 * LEVEL_XP_BASE), 3);
+        assert_eq!(level_for_xp(10_000 * LEVEL_XP_BASE), 100);
+        let top = u16::MAX as u64;
+        assert_eq!(level_for_xp(top * top * LEVEL_XP_BASE - 1), u16::MAX - 1);
+        assert_eq!(level_for_xp(top * top * LEVEL_XP_BASE), u16::MAX);
+        assert_eq!(level_for_xp(u64::MAX), u16::MAX);
+    }
+
+    #[test]
+    fn xp_is_granted_once_per_run_and_not_for_practice() {
+        let mut game = GameState { score: 12, frame_count: 419, ..playing_at_slot(0) };
+        assert_eq!(run_xp(&game), 12 * 10 + 20);
+        game.end_run(DeathCause::Pipe);
+        let mut profile = PlayerProfile::default();
+        profile.record_run_end(&mut game, 7);
+        assert_eq!((profile.xp, profile.level), (140, 1));
+        // Crossing 100 and 400 XP at once goes straight to level 2
+        (profile.xp, profile.level) = (300, 0);
+        game.run_end_recorded = 0;
+        profile.record_run_end(&mut game, 7);
+        assert_eq!((profile.xp, profile.level), (440, 2));
+
+        // end_game after the collision already ended the run adds nothing
+        finish_run(&mut game, DeathCause::Manual, 8);
+        profile.record_run_end(&mut game, 8);
+        assert_eq!((profile.xp, profile.level), (440, 2));
+
+        let mut practice = GameState { score: 50, frame_count: 1_000, ..playing_at_slot(0) };
+        practice.set_practice(true);
+        finish_run(&mut practice, DeathCause::Manual, 9);
+        profile.record_run_end(&mut practice, 9);
+        assert_eq!(profile.xp, 440);
+    }
+
+    #[test]
+    fn global_stats_count_runs_pipes_and_flaps_but_not_practice() {
+        let mut stats = GlobalStats::default();
+        let mut game = playing_at_slot(0);
+        game.seed = 1_700_000_000;
+        stats.record_game_started(&game);
+        let mut passed = 0;
+        {
+            let mut hooks = frame_hooks(None, None, Some(&mut stats), 0);
+            let mut slot = game.last_tick_slot;
+            while game.game_status() == GameStatus::Playing && game.frame_count < 5_000 {
+                game.set_pending_flap(autopilot_flap(&game));
+                slot += 1;
+                advance_at_slot(&mut game, slot, &mut |game, report| {
+                    passed += report.pipes_passed as u64;
+                    hooks(game, report)
+                });
+            }
+        }
+        stats.record_flaps(&game, 3);
+        assert!(passed > 0);
+        assert!(game.game_status() == GameStatus::GameOver);
+        assert_eq!(
+            (stats.total_games_started, stats.total_games_finished, stats.total_pipes_passed, stats.total_flaps),
+            (1, 1, passed, 3)
+        );
+
+        let before = stats.clone();
+        let mut practice = playing_at_slot(0);
+        practice.set_practice(true);
+        stats.record_game_started(&practice);
+        stats.record_frame(&practice, &FrameReport { pipes_passed: 2, ..Default::default() });
+        stats.record_flaps(&practice, 1);
+        stats.record_game_finished(&practice);
+        assert_eq!(stats.total_flaps, before.total_flaps);
+        assert_eq!(stats.total_pipes_passed, before.total_pipes_passed);
+        assert_eq!((stats.total_games_started, stats.total_games_finished), (1, 1));
+
+        // Counters saturate
+        stats.total_flaps = u64::MAX - 1;
+        stats.record_flaps(&game, 5);
+        assert_eq!(stats.total_flaps, u64::MAX);
+    }
+
+    #[test]
+    fn global_stats_update_only_on_the_games_layer() {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = vec![0u8; 8 + GlobalStats::INIT_SPACE];
+        GlobalStats { total_flaps: 4, ..Default::default() }.try_serialize(&mut &mut data[..]).unwrap();
+        let mut game = playing_at_slot(0);
+
+        // Not passed, or passed read-only: skipped
+        assert!(load_global_stats(None, &game).unwrap().is_none());
+        let owner = crate::ID;
+        let read_only = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        assert!(load_global_stats(Some(&read_only), &game).unwrap().is_none());
+
+        // Delegated away from this layer, so no longer ours: skipped
+        let mut lamports = 0;
+        let delegation = Pubkey::new_unique();
+        let away = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &delegation, false, 0);
+        assert!(load_global_stats(Some(&away), &game).unwrap().is_none());
+
+        let mut lamports = 0;
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+        let mut stats = load_global_stats(Some(&info), &game).unwrap().unwrap();
+        stats.record_flaps(&game, 1);
+        store_global_stats(Some(&info), Some(stats)).unwrap();
+        let stored = GlobalStats::try_deserialize(&mut &info.try_borrow_data().unwrap()[..]).unwrap();
+        assert_eq!(stored.total_flaps, 5);
+
+        // A delegated game on the ER skips an undelegated GlobalStats
+        game.set_is_delegated(true);
+        assert!(load_global_stats(Some(&info), &game).unwrap().is_none());
+    }
+
+    #[test]
+    fn only_a_paused_config_stops_play() {
+        assert_eq!(require_not_paused(None), Ok(()));
+        let mut config = GameConfig {
+            physics: PhysicsConfig::default(),
+            bump: 0,
+            ranked_cooldown_secs: 0,
+            min_flap_interval_frames: 0,
+            hitbox_inset_plus_one: 0,
+            grace_frames_plus_one: 0,
+            countdown_frames: 0,
+            flap_skips_countdown: false,
+            streak_threshold: 0,
+            ranked_pickups: 0,
+            revive_coin_cost: 0,
+            paused: false,
+            submit_window_secs: 0,
+            reserved: [0; 43],
+        };
+        assert_eq!(require_not_paused(Some(&config)), Ok(()));
+        config.paused = true;
+        assert_eq!(require_not_paused(Some(&config)), err!(FlappyError::ProgramPaused));
+
+        // A GameConfig that doesn't exist yet, or wasn't passed, loads as none
+        let key = Pubkey::new_unique();
+        let (mut lamports, mut data) = (0, vec![]);
+        let system = anchor_lang::system_program::ID;
+        let missing = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &system, false, 0);
+        assert!(load_optional_config(Some(&missing)).unwrap().is_none());
+        assert!(load_optional_config(None).unwrap().is_none());
+
+        let mut lamports = 0;
+        let mut data = vec![0u8; 8 + GameConfig::INIT_SPACE];
+        config.try_serialize(&mut &mut data[..]).unwrap();
+        let owner = crate::ID;
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        let loaded = load_optional_config(Some(&info)).unwrap();
+        assert_eq!(require_not_paused(loaded.as_ref()), err!(FlappyError::ProgramPaused));
+    }
+
+    #[test]
+    fn initializing_an_existing_game_keeps_its_high_score() {
+        let (key, authority) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let owner = crate::ID;
+        let mut lamports = 0;
+        let mut data = vec![0u8; 8 + std::mem::size_of::<GameState>()];
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+        let loader = AccountLoader::<GameState>::try_from_unchecked(&owner, &info).unwrap();
+        assert_eq!(init_game(&loader, authority, 254, 0, None, 1_000), Ok(true));
+        // As at the end of the instruction, which writes the discriminator
+        loader.exit(&owner).unwrap();
+        {
+            let game = &mut *loader.load_mut().unwrap();
+            assert_eq!((game.authority, game.bump, game.seed), (authority, 254, 1_000));
+            game.high_score = 42;
+            game.high_scores = [42, 7, 0];
+            game.coins = 9;
+            game.score = 5;
+            game.set_game_status(GameStatus::Playing);
+        }
+
+        // Called again, as on every page load: the run resets, nothing else
+        for now in [2_000, 3_000] {
+            assert_eq!(init_game(&loader, authority, 254, 0, None, now), Ok(false));
+            let game = &*loader.load().unwrap();
+            assert_eq!((game.high_score, game.high_scores, game.coins, game.seed), (42, [42, 7, 0], 9, 1_000));
+            assert_eq!(game.score, 0);
+            assert!(game.game_status() == GameStatus::NotStarted);
+        }
+
+        // initialize_with_params replaces the playfield; plain initialize keeps it
+        assert_eq!((loader.load().unwrap().width(), loader.load().unwrap().height()), (GAME_WIDTH, GAME_HEIGHT));
+        init_game(&loader, authority, 254, 0, Some((400, 600)), 4_000).unwrap();
+        init_game(&loader, authority, 254, 0, None, 5_000).unwrap();
+        let game = &*loader.load().unwrap();
+        assert_eq!((game.width(), game.height(), game.bird_y), (400, 600, 300 * 1000));
+        assert_eq!(game.high_score, 42);
+    }
+
+    #[test]
+    fn tick_n_stops_before_the_compute_runs_out() {
+        let clock = Clock { slot: 1_000, unix_timestamp: 1_000, ..Default::default() };
+        let mut game = GameState::default();
+        begin_run(&mut game, None, Difficulty::Normal, ScoringMode::PipesPassed, 7, &clock);
+        game.set_practice(true);
+        game.bird_y = 100 * 1000;
+
+        // Each frame spends a frame's worth of the meter; room for three more
+        // frames plus the finish allows exactly three
+        let left = std::cell::Cell::new(3 * TICK_N_FRAME_CU + TICK_N_FINISH_CU);
+        let metered = || {
+            let now = left.get();
+            left.set(now.saturating_sub(TICK_N_FRAME_CU));
+            now
+        };
+        assert_eq!(advance_frames_compute_bounded(&mut game, 20, 1_020, 1_000, metered, &mut ignore), 3);
+        assert_eq!((game.frame_count, game.last_tick_slot, game.ticks_this_second), (3, 1_020, 3));
+        assert_eq!(advance_frames_compute_bounded(&mut game, 20, 1_040, 1_000, || TICK_N_FRAME_CU, &mut ignore), 0);
+        assert_eq!(game.last_tick_slot, 1_020);
+
+        // With compute to spare, max_frames, the cadence and the wall-clock
+        // budget still bound it
+        assert_eq!(advance_frames_compute_bounded(&mut game, 2, 1_040, 1_000, || u64::MAX, &mut ignore), 2);
+        assert_eq!(advance_frames_compute_bounded(&mut game, 20, 1_045, 1_000, || u64::MAX, &mut ignore), 5);
+        let frames = advance_frames_compute_bounded(&mut game, u16::MAX, 5_000, 1_000, || u64::MAX, &mut ignore);
+        assert_eq!(frames + 10, MAX_FRAMES_PER_SECOND);
+
+        // A run that ends mid-batch stops it there
+        begin_run(&mut game, None, Difficulty::Normal, ScoringMode::PipesPassed, 7, &clock);
+        game.bird_y = (GAME_HEIGHT - BIRD_SIZE - 10) * 1000;
+        let frames = advance_frames_compute_bounded(&mut game, 200, 2_000, 1_002, || u64::MAX, &mut ignore);
+        assert!(frames < 20 && game.game_status() == GameStatus::GameOver);
+        assert_eq!(game.frame_count, frames as u64);
+    }
+
+    #[test]
+    fn an_unchanged_game_is_committed_once() {
+        let mut game = playing_at_slot(0);
+        assert!(mark_committed(&mut game));
+        assert!(!mark_committed(&mut game));
+        assert_eq!(game.committed_digest, state_digest(&game));
+
+        // A frame is a change, and so is one that leaves frame_count alone
+        advance_frames(&mut game, 1, 1, &mut ignore);
+        assert!(mark_committed(&mut game));
+        assert!(!mark_committed(&mut game));
+        game.set_spectator_mode(true);
+        assert!(mark_committed(&mut game));
+        assert!(!mark_committed(&mut game));
+    }
+
+    #[test]
+    fn the_high_score_record_keeps_the_best_unassisted_run() {
+        let mut record = HighScoreRecord::default();
+        let mut game = playing_at_slot(0);
+        assert!(!record.sync(&game));
+
+        // Even a scoreless first run gives the record something to submit
+        finish_run(&mut game, DeathCause::Manual, 1_000);
+        assert!(record.sync(&game));
+        assert_eq!((record.high_score, record.high_score_timestamp), (0, 1_000));
+        assert!(!record.sync(&game));
+
+        reset_run(&mut game);
+        game.set_game_status(GameStatus::Playing);
+        game.run_seed = 77;
+        game.score = 12;
+        game.frame_count = 300;
+        game.flaps_this_run = 9;
+        finish_run(&mut game, DeathCause::Manual, 2_000);
+        assert!(record.sync(&game));
+        assert_eq!(
+            (record.high_score, record.high_score_timestamp, record.high_score_frame, record.high_score_seed),
+            (12, 2_000, 300, 77)
+        );
+        let entry = LeaderboardEntry::for_record(&record, 2_005);
+        assert_eq!((entry.score, entry.ended_at, entry.frame, entry.seed, entry.flaps), (12, 2_000, 300, 77, 9));
+
+        // A higher score with extra lives stays on the game only
+        reset_run(&mut game);
+        game.set_game_status(GameStatus::Playing);
+        game.set_extra_lives(true);
+        game.score = 20;
+        finish_run(&mut game, DeathCause::Manual, 3_000);
+        assert!(game.high_score == 20 && game.high_score_assisted());
+        assert!(!record.sync(&game));
+        assert_eq!(record.high_score, 12);
+
+        // Another slot's lower score doesn't replace it
+        let mut other = playing_at_slot(0);
+        other.game_slot = 1;
+        other.score = 5;
+        finish_run(&mut other, DeathCause::Manual, 4_000);
+        assert!(!record.sync(&other));
+        assert_eq!((record.high_score, record.game_slot), (12, 0));
+    }
+
+    #[test]
+    fn only_a_settled_recent_run_can_be_submitted() {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = vec![0u8; 8 + std::mem::size_of::<GameState>()];
+        data[..8].copy_from_slice(GameState::DISCRIMINATOR);
+        let game: &mut GameState = bytemuck::from_bytes_mut(&mut data[8..]);
+        game.set_game_status(GameStatus::Playing);
+
+        let delegation = Pubkey::new_unique();
+        let delegated = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &delegation, false, 0);
+        assert_eq!(require_settled(&delegated), err!(FlappyError::GameDelegated));
+        let owner = crate::ID;
+        let mut lamports = 0;
+        let mut playing = data.clone();
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut playing, &owner, false, 0);
+        assert_eq!(require_settled(&info), err!(FlappyError::GameNotOver));
+        let mut lamports = 0;
+        let mut over = data.clone();
+        bytemuck::from_bytes_mut::<GameState>(&mut over[8..]).set_game_status(GameStatus::GameOver);
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut over, &owner, false, 0);
+        assert!(require_settled(&info).is_ok());
+
+        let record = HighScoreRecord { high_score_timestamp: 1_000, ..Default::default() };
+        let window = SUBMIT_WINDOW_SECS as i64;
+        assert!(require_fresh(&record, None, 1_000 + window).is_ok());
+        assert_eq!(require_fresh(&record, None, 1_001 + window), err!(FlappyError::RunTooOld));
+        let mut config = GameConfig {
+            physics: PhysicsConfig::default(),
+            bump: 0,
+            ranked_cooldown_secs: 0,
+            min_flap_interval_frames: 0,
+            hitbox_inset_plus_one: 0,
+            grace_frames_plus_one: 0,
+            countdown_frames: 0,
+            flap_skips_countdown: false,
+            streak_threshold: 0,
+            ranked_pickups: 0,
+            revive_coin_cost: 0,
+            paused: false,
+            submit_window_secs: 60,
+            reserved: [0; 43],
+        };
+        assert!(require_fresh(&record, Some(&config), 1_060).is_ok());
+        assert_eq!(require_fresh(&record, Some(&config), 1_061), err!(FlappyError::RunTooOld));
+        config.submit_window_secs = 0;
+        assert!(require_fresh(&record, Some(&config), 1_000 + window).is_ok());
+    }
+
+    #[test]
+    fn only_a_stale_run_can_be_cranked() {
+        let clock = Clock { slot: 1_000, unix_timestamp: 1_000, ..Default::default() };
+        let mut game = GameState::default();
+        begin_run(&mut game, None, Difficulty::Normal, ScoringMode::PipesPassed, 7, &clock);
+        game.bird_y = 100 * 1000;
+        let stale = 1_000 + CRANK_STALE_SECS;
+        assert_eq!(crank_frames(&mut game, 10, stale, 1_200, &mut ignore), err!(FlappyError::GameNotStale));
+        assert_eq!(game.frame_count, 0);
+
+        // Past the window it runs, capped, and the run counts as updated again
+        assert_eq!(crank_frames(&mut game, 5, stale + 1, 1_220, &mut ignore), Ok(5));
+        assert_eq!((game.frame_count, game.last_update, game.last_tick_slot), (5, stale + 1, 1_220));
+        assert_eq!(crank_frames(&mut game, 5, stale + 2, 1_240, &mut ignore), err!(FlappyError::GameNotStale));
+
+        // With no flaps, enough cranks put an abandoned bird on the floor
+        let mut now = stale + 1;
+        while game.game_status() == GameStatus::Playing {
+            now += CRANK_STALE_SECS + 1;
+            let frames = crank_frames(&mut game, u16::MAX, now, 0, &mut ignore).unwrap();
+            assert!(frames as u16 <= MAX_CRANK_FRAMES);
+        }
+        assert_eq!(game.end_cause(), Some(DeathCause::Floor));
+        let later = now + CRANK_STALE_SECS + 1;
+        assert_eq!(crank_frames(&mut game, 10, later, 0, &mut ignore), err!(FlappyError::GameNotPlaying));
+
+        // The tip never dips into the rent
+        assert_eq!(crank_tip(10_000 + CRANK_TIP_LAMPORTS, 10_000), CRANK_TIP_LAMPORTS);
+        assert_eq!(crank_tip(10_000 + CRANK_TIP_LAMPORTS - 1, 10_000), 0);
+        assert_eq!(crank_tip(5_000, 10_000), 0);
+    }
+
+    #[test]
+    fn only_a_long_abandoned_run_is_finalized() {
+        let clock = Clock { slot: 1_000, unix_timestamp: 1_000, ..Default::default() };
+        let mut game = GameState::default();
+        assert_eq!(finalize_abandoned(&mut game, 1_000_000), err!(FlappyError::GameNotPlaying));
+        begin_run(&mut game, None, Difficulty::Normal, ScoringMode::PipesPassed, 7, &clock);
+        let stale = 1_000 + CRANK_FINALIZE_SECS;
+        assert_eq!(finalize_abandoned(&mut game, stale), err!(FlappyError::GameNotStale));
+        assert!(game.game_status() == GameStatus::Playing);
+
+        assert_eq!(finalize_abandoned(&mut game, stale + 1), Ok(()));
+        assert!(game.game_status() == GameStatus::GameOver);
+        assert_eq!((game.end_cause(), game.last_update), (Some(DeathCause::Abandoned), stale + 1));
+        assert_eq!(finalize_abandoned(&mut game, stale + 2), err!(FlappyError::GameNotPlaying));
+
+        // A paused run is still in play, and ends the same way
+        let mut game = GameState::default();
+        begin_run(&mut game, None, Difficulty::Normal, ScoringMode::PipesPassed, 7, &clock);
+        pause_run(&mut game, 1_000).unwrap();
+        assert_eq!(finalize_abandoned(&mut game, stale + 1), Ok(()));
+        assert_eq!(game.end_cause(), Some(DeathCause::Abandoned));
+        assert_eq!(DeathCause::from_repr(DeathCause::Abandoned as u8), Some(DeathCause::Abandoned));
+    }
+
+    fn coop_game() -> GameState {
+        let mut game = playing_at_slot(0);
+        game.authority = Pubkey::new_unique();
+        game.begin_coop(Pubkey::new_unique());
+        game
+    }
+
+    #[test]
+    fn a_coop_run_ends_when_either_bird_crashes() {
+        for crashed in 0..2 {
+            let mut game = coop_game();
+            let floor = (GAME_HEIGHT - 5) * 1000;
+            match crashed {
+                0 => game.bird_y = floor,
+                _ => game.coop.bird_y = floor,
+            }
+            assert_eq!(step_physics(&mut game).death, Some(DeathCause::Floor));
+            assert!(game.game_status() == GameStatus::GameOver);
+            assert_eq!((game.coop.alive(0), game.coop.alive(1)), (crashed == 1, crashed == 0));
+        }
+
+        // Each bird flaps on its own input
+        let mut game = coop_game();
+        queue_coop_flap(&mut game, 1).unwrap();
+        step_physics(&mut game);
+        assert!(!game.coop.pending_flap());
+        assert!(game.coop.bird_velocity < 0 && game.bird_velocity > 0);
+        assert_eq!(game.input_log, InputLog::default());
+        queue_coop_flap(&mut game, 0).unwrap();
+        assert!(game.pending_flap() && game.input_log.flapped_after(game.frame_count));
+    }
+
+    #[test]
+    fn both_coop_birds_meet_the_pipes_and_each_pipe_scores_once() {
+        let passing = |partner_y: i32| {
+            let mut game = coop_game();
+            game.physics.gravity = 0;
+            game.bird_y = (200 - BIRD_SIZE / 2) * 1000;
+            game.coop.bird_y = partner_y * 1000;
+            game.pipes[0] = Pipe { x: (BIRD_X - PIPE_WIDTH + 2) * 1000, gap_y: 200, active: 1, ..Default::default() };
+            game
+        };
+        let mut game = passing(200 - BIRD_SIZE / 2);
+        let report = step_physics(&mut game);
+        assert_eq!((game.score, report.pipes_passed, report.death), (1, 1, None));
+
+        // The partner clipping the pipe ends the run for both
+        let mut game = passing(200 - PIPE_GAP / 2 - 1);
+        assert_eq!(step_physics(&mut game).death, Some(DeathCause::Pipe));
+        assert_eq!((game.coop.alive(0), game.coop.alive(1)), (true, false));
+        assert_eq!(game.end_pipe().map(|hit| hit.index), Some(0));
+
+        // ... unless the shared shield takes the hit, putting it in the gap
+        let mut game = passing(200 - PIPE_GAP / 2 - 1);
+        game.pick_up(Powerup::Shield);
+        assert_eq!(step_physics(&mut game).death, None);
+        assert!(!game.has_shield());
+        assert_eq!(game.coop.bird_y, (200 - BIRD_SIZE / 2) * 1000);
+        assert_eq!(game.score, 1);
+    }
+
+    #[test]
+    fn solo_and_coop_runs_keep_to_their_own_instructions() {
+        let solo = playing_at_slot(0);
+        assert_eq!(solo.coop_player(0), err!(FlappyError::NotCoopGame));
+        assert_eq!(require_solo(&solo), Ok(()));
+
+        let mut game = coop_game();
+        assert_eq!(game.coop_player(0), Ok(game.authority));
+        assert_eq!(game.coop_player(1), Ok(game.coop.partner));
+        assert_eq!(game.coop_player(2), err!(FlappyError::InvalidPlayerIndex));
+        assert_eq!(require_solo(&game), err!(FlappyError::CoopGame));
+        assert!(game.snapshot().partner_bird.is_some() && solo.snapshot().partner_bird.is_none());
+
+        // A co-op run isn't ranked, recorded or revivable, and a reset makes
+        // the game solo again
+        assert!(!game.ranked());
+        game.score = 5;
+        finish_run(&mut game, DeathCause::Manual, 1_000);
+        assert_eq!((game.high_score, game.high_score_timestamp), (0, 0));
+        assert_eq!(check_revivable(&game), err!(FlappyError::CoopGame));
+        reset_run(&mut game);
+        assert!(!game.coop() && game.coop == CoopState::default());
+    }
+
+    #[test]
+    fn a_session_key_flaps_only_for_the_player_who_issued_it() {
+        let (player, key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let token = SessionToken { authority: player, target_program: crate::ID, session_signer: key, valid_until: 100 };
+        let address = session_token_address(&player, &key);
+        assert!(acts_for(&player, &player, None, 0));
+        assert!(!acts_for(&player, &key, None, 0));
+        assert!(acts_for(&player, &key, Some((&address, &token)), 99));
+        assert!(!acts_for(&player, &key, Some((&address, &token)), 100));
+
+        // Someone else's token, for another program, or at another address
+        let other = Pubkey::new_unique();
+        assert!(!acts_for(&other, &key, Some((&address, &token)), 0));
+        let elsewhere = SessionToken { target_program: other, ..token };
+        assert!(!acts_for(&player, &key, Some((&address, &elsewhere)), 0));
+        assert!(!acts_for(&player, &key, Some((&other, &token)), 0));
+    }
+
+    #[test]
+    fn end_once_ends_a_run_in_play_and_leaves_a_finished_one() {
+        let clock = Clock { slot: 1_000, unix_timestamp: 1_000, ..Default::default() };
+        let mut game = GameState::default();
+        assert_eq!(end_once(&mut game, 1_000), err!(FlappyError::GameNotPlaying));
+        assert!(game.game_status() == GameStatus::NotStarted);
+
+        begin_run(&mut game, None, Difficulty::Normal, ScoringMode::PipesPassed, 7, &clock);
+        game.frame_count = 40;
+        game.score = 3;
+        assert_eq!(end_once(&mut game, 1_100), Ok(true));
+        assert!(game.game_status() == GameStatus::GameOver);
+        assert_eq!(game.claim_reward(), 3);
+        let ended = game;
+
+        // Calling it again changes nothing: not how or when the run ended,
+        // nor the ranked cooldown, and the reward is already paid
+        game.bird_y += 5_000;
+        assert_eq!(end_once(&mut game, 5_000), Ok(false));
+        assert_eq!(
+            (game.end_cause(), game.end_frame, game.end_bird_y, game.last_update, game.last_game_over_at),
+            (ended.end_cause(), 40, ended.end_bird_y, 1_100, 1_100)
+        );
+        assert_eq!(game.claim_reward(), 0);
+
+        // A paused run is still in play
+        begin_run(&mut game, None, Difficulty::Normal, ScoringMode::PipesPassed, 7, &clock);
+        pause_run(&mut game, 1_000).unwrap();
+        assert_eq!(end_once(&mut game, 1_200), Ok(true));
+        assert_eq!(game.end_cause(), Some(DeathCause::Manual));
+    }
+
+    #[test]
+    fn a_game_stored_with_a_wrong_bump_fails_the_seeds_constraint() {
+        let authority = Pubkey::new_unique();
+        let (key, canonical) =
+            Pubkey::find_program_address(&[GAME_SEED, authority.as_ref(), &[0]], &crate::ID);
+        let owner = crate::ID;
+        let accounts_with_bump = |bump: u8| {
+            let mut data = vec![0u8; 8 + std::mem::size_of::<GameState>()];
+            data[..8].copy_from_slice(GameState::DISCRIMINATOR);
+            let game: &mut GameState = bytemuck::from_bytes_mut(&mut data[8..]);
+            game.authority = authority;
+            game.bump = bump;
+            let mut lamports = 1;
+            let mut signer_lamports = 1;
+            let mut signer_data = vec![];
+            let system = Pubkey::default();
+            // The optional accounts are left out by passing the program id
+            let mut none = [(0u64, vec![]), (0, vec![]), (0, vec![]), (0, vec![])];
+            let mut infos = vec![
+                AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0),
+                AccountInfo::new(&authority, true, false, &mut signer_lamports, &mut signer_data, &system, false, 0),
+            ];
+            for (lamports, data) in none.iter_mut() {
+                infos.push(AccountInfo::new(&crate::ID, false, false, lamports, data, &system, false, 0));
+            }
+            let mut infos = &infos[..];
+            let mut bumps = SimpleGameActionBumps::default();
+            SimpleGameAction::try_accounts(&crate::ID, &mut infos, &[], &mut bumps, &mut std::collections::BTreeSet::new()).err()
+        };
+
+        assert!(accounts_with_bump(canonical).is_none());
+        // Whatever the stored bump derives to, it isn't this account
+        for forged in [canonical.wrapping_sub(1), canonical.wrapping_add(1), 0] {
+            assert_eq!(accounts_with_bump(forged), Some(ErrorCode::ConstraintSeeds.into()));
+        }
+    }
+
+    #[test]
+    fn an_external_bonus_stays_out_of_the_score() {
+        let mut game = playing_at_slot(0);
+        game.score = 4;
+        game.award_bonus(u16::MAX).unwrap();
+        game.award_bonus(6).unwrap();
+        assert_eq!((game.score, game.bonus_score), (4, u16::MAX as u64 + 6));
+
+        finish_run(&mut game, DeathCause::Manual, 1_000);
+        assert_eq!(game.high_score, 4);
+        assert_eq!(game.award_bonus(1), err!(FlappyError::GameNotPlaying));
+        let mut record = HighScoreRecord::default();
+        assert!(record.sync(&game));
+        assert_eq!(record.high_score, 4);
+        assert_eq!(game.claim_reward(), 4);
+
+        reset_run(&mut game);
+        assert_eq!(game.bonus_score, 0);
+    }
+
+    #[test]
+    fn catch_up_leaves_a_run_being_played_alone() {
+        let mut game = playing_at_slot(0);
+        game.last_update = 1_000;
+        assert_eq!(advance_at_slot(&mut game, 1, &mut ignore), TickOutcome::Advanced);
+        let ticked = (game.frame_count, game.last_tick_slot, game.last_update);
+        for now in [1_000, 1_000 + CATCH_UP_STALE_SECS] {
+            assert_eq!(catch_up_frames(&mut game, now, 2, &mut ignore), 0);
+            assert_eq!((game.frame_count, game.last_tick_slot, game.last_update), ticked);
+        }
+        // So the player's own tick in that slot still goes through
+        assert_eq!(advance_at_slot(&mut game, 2, &mut ignore), TickOutcome::Advanced);
+    }
+
+    #[test]
+    fn the_ranked_cooldown_follows_the_player_across_slots_and_closed_games() {
+        let authority = Pubkey::new_unique();
+        let mut record = HighScoreRecord::default();
+        record.open_slot(0);
+        let mut game = playing_at_slot(0);
+        game.authority = authority;
+        finish_run(&mut game, DeathCause::Manual, 1_000);
+
+        // Closing the slot and initializing it again starts from a fresh game,
+        // but the record kept when the run ended
+        record.close_slot(&game);
+        assert_eq!((record.game_slots, record.last_game_over_at), (0, 1_000));
+        record.open_slot(0);
+        let mut fresh = playing_at_slot(0);
+        fresh.authority = authority;
+        assert_eq!(fresh.last_game_over_at, 0);
+        let ended = last_ranked_game_over(&fresh, Some(&record), &[]).unwrap();
+        assert_eq!(check_ranked_cooldown(&fresh, ended, 60, 1_030), err!(FlappyError::CooldownActive));
+
+        // A run ending in another slot holds back a ranked start in this one,
+        // which has to be shown that slot's game
+        let (key, bump) = Pubkey::find_program_address(&[GAME_SEED, authority.as_ref(), &[2]], &crate::ID);
+        let mut data = vec![0u8; 8 + std::mem::size_of::<GameState>()];
+        data[..8].copy_from_slice(GameState::DISCRIMINATOR);
+        let other: &mut GameState = bytemuck::from_bytes_mut(&mut data[8..]);
+        other.bump = bump;
+        other.last_game_over_at = 2_000;
+        record.open_slot(2);
+        assert_eq!(last_ranked_game_over(&fresh, Some(&record), &[]), err!(FlappyError::MissingGameSlots));
+        let owner = crate::ID;
+        let mut lamports = 0;
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        assert_eq!(last_ranked_game_over(&fresh, Some(&record), std::slice::from_ref(&info)), Ok(2_000));
+        // ...and not some other game in its place
+        let stranger = Pubkey::new_unique();
+        let mut moved = info.clone();
+        moved.key = &stranger;
+        assert_eq!(last_ranked_game_over(&fresh, Some(&record), &[moved]), err!(FlappyError::MissingGameSlots));
+    }
+
+    #[test]
+    fn a_player_is_new_only_when_their_record_is_created() {
+        let authority = Pubkey::new_unique();
+        let mut record = HighScoreRecord::default();
+        // The first game can be in any slot
+        assert!(record.claim(authority, 7));
+        record.open_slot(2);
+        assert!(!record.claim(authority, 7));
+        record.open_slot(0);
+
+        // Closing a game and initializing the slot again is the same player
+        let mut game = playing_at_slot(0);
+        game.authority = authority;
+        finish_run(&mut game, DeathCause::Manual, 1_000);
+        record.close_slot(&game);
+        assert!(!record.claim(authority, 7));
+        assert_eq!((record.authority, record.bump, record.game_slots), (authority, 7, 1 << 2));
+    }
+}
@@ -0,0 +1,87 @@
+//! Pipe layout randomness. Each run records the generator it started with
+//! in `GameState::rng_version`, so replaying an older run reproduces its
+//! pipes even after the generator changes.
+
+/// The original LCG (`seed * 1103515245 + 12345`), whose gaps lean low;
+/// runs from before `rng_version` existed read as this
+pub const RNG_LCG: u8 = 0;
+/// xorshift64*, with gaps uniform over the valid range
+pub const RNG_XORSHIFT: u8 = 1;
+/// Generator new runs start with
+pub const RNG_VERSION: u8 = RNG_XORSHIFT;
+
+/// Stands in for a zero xorshift state, which would never leave zero
+const ZERO_STATE: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// One step of the original LCG; the new state is also the output
+pub fn lcg(state: &mut u64) -> u64 {
+    *state = state.wrapping_mul(1103515245).wrapping_add(12345);
+    *state
+}
+
+/// One step of xorshift64* (Vigna): shifts 12/25/27, then a multiply that
+/// mixes the weak low bits into every output bit
+pub fn xorshift64_star(state: &mut u64) -> u64 {
+    let mut x = if *state == 0 { ZERO_STATE } else { *state };
+    x ^= x >> 12;
+    x ^= x << 25;
+    x ^= x >> 27;
+    *state = x;
+    x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+}
+
+/// `x` mapped onto `low..=high` by multiply-high: no modulo and no
+/// rejection loop, and off uniform by at most span / 2^64. `high` below
+/// `low` yields `low`.
+pub fn uniform_in(x: u64, low: i32, high: i32) -> i32 {
+    let span = (high as i64 - low as i64).max(0) as u128 + 1;
+    (low as i64 + ((x as u128 * span) >> 64) as i64) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xorshift_matches_the_reference_sequence() {
+        let mut state = 1;
+        let outputs: Vec<u64> = (0..3).map(|_| xorshift64_star(&mut state)).collect();
+        assert_eq!(outputs, vec![0x47e4_ce4b_896c_dd1d, 0xabcf_a6a8_e079_651d, 0xb9d1_0d8f_eb73_1f57]);
+        assert_eq!(state, 0xd_0040_0320_2803);
+    }
+
+    #[test]
+    fn xorshift_leaves_a_zero_state() {
+        let mut state = 0;
+        let first = xorshift64_star(&mut state);
+        assert_ne!(state, 0);
+        assert_ne!(first, 0);
+        let mut replaced = ZERO_STATE;
+        assert_eq!(xorshift64_star(&mut replaced), first);
+    }
+
+    #[test]
+    fn lcg_is_the_original_step() {
+        let mut state = 7;
+        assert_eq!(lcg(&mut state), 7 * 1103515245 + 12345);
+        assert_eq!(state, 7 * 1103515245 + 12345);
+    }
+
+    #[test]
+    fn uniform_covers_the_range_inclusively() {
+        assert_eq!(uniform_in(0, 160, 240), 160);
+        assert_eq!(uniform_in(u64::MAX, 160, 240), 240);
+        assert_eq!(uniform_in(u64::MAX / 2, -10, 10), 0);
+        assert_eq!(uniform_in(u64::MAX, 5, 5), 5);
+        assert_eq!(uniform_in(u64::MAX, 5, 1), 5);
+        assert_eq!(uniform_in(u64::MAX, i32::MIN, i32::MAX), i32::MAX);
+
+        let mut state = 42;
+        let mut seen = [false; 11];
+        for _ in 0..1_000 {
+            let y = uniform_in(xorshift64_star(&mut state), 0, 10);
+            seen[y as usize] = true;
+        }
+        assert!(seen.iter().all(|&s| s));
+    }
+}
@@ -0,0 +1,140 @@
+//! Example of another program composing flappy_bird over CPI: the player
+//! creates and starts their game through it, and it awards quest bonuses,
+//! which flappy_bird takes only from programs on its CPI allowlist.
+
+use anchor_lang::prelude::*;
+use flappy_bird::cpi::accounts::{AwardExternalBonus, Initialize, SimpleGameAction, StartGame};
+use flappy_bird::program::FlappyBird;
+use flappy_bird::StartOptions;
+
+declare_id!("8nZQYmdekkqFBqFbqSvzoPr4vKm2V5njaLTiNsEgPKDZ");
+
+#[program]
+pub mod quest_example {
+    use super::*;
+
+    /// Create the player's game in `slot`, via `flappy_bird::initialize`
+    pub fn join(ctx: Context<Join>, slot: u8) -> Result<()> {
+        let accounts = Initialize {
+            game: ctx.accounts.game.to_account_info(),
+            authority: ctx.accounts.player.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            global_stats: None,
+            high_score_record: ctx.accounts.high_score_record.to_account_info(),
+        };
+        flappy_bird::cpi::initialize(CpiContext::new(ctx.accounts.flappy_bird.to_account_info(), accounts), slot)
+    }
+
+    /// Commit to the next quest run's secret, via `flappy_bird::request_game`
+    pub fn request_run(ctx: Context<QuestGame>, commitment: [u8; 32]) -> Result<()> {
+        let accounts = SimpleGameAction {
+            game: ctx.accounts.game.to_account_info(),
+            signer: ctx.accounts.player.to_account_info(),
+            profile: None,
+            missions: None,
+            global_stats: None,
+            config: None,
+        };
+        flappy_bird::cpi::request_game(CpiContext::new(ctx.accounts.flappy_bird.to_account_info(), accounts), commitment)
+    }
+
+    /// Start the quest run, via `flappy_bird::start_game`
+    pub fn start_run(ctx: Context<StartRun>, options: StartOptions) -> Result<()> {
+        let accounts = StartGame {
+            game: ctx.accounts.game.to_account_info(),
+            signer: ctx.accounts.player.to_account_info(),
+            profile: None,
+            missions: None,
+            global_stats: None,
+            slot_hashes: ctx.accounts.slot_hashes.to_account_info(),
+            config: ctx.accounts.config.to_account_info(),
+            high_score_record: ctx.accounts.high_score_record.to_account_info(),
+        };
+        flappy_bird::cpi::start_game(CpiContext::new(ctx.accounts.flappy_bird.to_account_info(), accounts), options)
+    }
+
+    /// Add `points` to the bonus of the run in progress, via
+    /// `flappy_bird::award_external_bonus`; the player signs. Any player can
+    /// claim any bonus in this example; a real quest program checks the
+    /// quest was done first, as flappy_bird trusts whatever an allowlisted
+    /// program awards.
+    pub fn complete_quest(ctx: Context<CompleteQuest>, points: u16) -> Result<()> {
+        let accounts = AwardExternalBonus {
+            game: ctx.accounts.game.to_account_info(),
+            signer: ctx.accounts.player.to_account_info(),
+            session_token: None,
+            allowlist: ctx.accounts.allowlist.to_account_info(),
+            instructions: ctx.accounts.instructions.to_account_info(),
+        };
+        flappy_bird::cpi::award_external_bonus(CpiContext::new(ctx.accounts.flappy_bird.to_account_info(), accounts), points)
+    }
+}
+
+// flappy_bird checks its own accounts, so they pass through unchecked here
+
+#[derive(Accounts)]
+pub struct Join<'info> {
+    /// CHECK: created and checked by flappy_bird
+    #[account(mut)]
+    pub game: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    /// CHECK: flappy_bird's HighScoreRecord for the player, created and
+    /// checked by flappy_bird
+    #[account(mut)]
+    pub high_score_record: UncheckedAccount<'info>,
+
+    pub flappy_bird: Program<'info, FlappyBird>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct QuestGame<'info> {
+    /// CHECK: checked by flappy_bird
+    #[account(mut)]
+    pub game: UncheckedAccount<'info>,
+
+    pub player: Signer<'info>,
+
+    pub flappy_bird: Program<'info, FlappyBird>,
+}
+
+#[derive(Accounts)]
+pub struct StartRun<'info> {
+    /// CHECK: checked by flappy_bird
+    #[account(mut)]
+    pub game: UncheckedAccount<'info>,
+
+    pub player: Signer<'info>,
+
+    /// CHECK: checked by flappy_bird
+    pub slot_hashes: UncheckedAccount<'info>,
+
+    /// CHECK: flappy_bird's GameConfig PDA, checked by flappy_bird
+    pub config: UncheckedAccount<'info>,
+
+    /// CHECK: flappy_bird's HighScoreRecord for the player, checked by
+    /// flappy_bird
+    pub high_score_record: UncheckedAccount<'info>,
+
+    pub flappy_bird: Program<'info, FlappyBird>,
+}
+
+#[derive(Accounts)]
+pub struct CompleteQuest<'info> {
+    /// CHECK: checked by flappy_bird
+    #[account(mut)]
+    pub game: UncheckedAccount<'info>,
+
+    pub player: Signer<'info>,
+
+    /// CHECK: flappy_bird's CPI allowlist, checked by flappy_bird
+    pub allowlist: UncheckedAccount<'info>,
+
+    /// CHECK: the instructions sysvar, checked by flappy_bird
+    pub instructions: UncheckedAccount<'info>,
+
+    pub flappy_bird: Program<'info, FlappyBird>,
+}
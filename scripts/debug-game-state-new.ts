@@ -35,7 +35,7 @@ async function main() {
             const p = gameAccount.pipes[i];
             if (p.active) {
                 activePipes++;
-                console.log(`  Pipe ${i}: x=${p.x}, gapY=${p.gapY}, passed=${p.passed}`);
+                console.log(`  Pipe ${i}: x=${p.x / 1000}, gapY=${p.gapY}, passed=${p.passed}`);
             }
         }
         console.log("Active Pipes:", activePipes);
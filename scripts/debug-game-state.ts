@@ -16,14 +16,14 @@ const IDL = JSON.parse(readFileSync("./app/src/idl/flappy_bird.json", "utf-8"));
 const ER_ENDPOINT = "https://devnet.magicblock.app";
 const ER_WS_ENDPOINT = "wss://devnet.magicblock.app";
 const PROGRAM_ID = new PublicKey("DfJtsSqWNSetyRr3Fj4ZxM44oSBuZzAh4MTFMHQJSWvj");
-const GAME_SEED = Buffer.from("game_v2");
+const GAME_SEED = Buffer.from("game_v7");
 
 // Player wallet - the one you're testing with
 const PLAYER_WALLET = new PublicKey("anshxnbjGiUpsZpnx3c6LrK2vt8zt54vLMvY3C7Locm");
 
 // Derive PDA
 const [gamePDA] = PublicKey.findProgramAddressSync(
-    [GAME_SEED, PLAYER_WALLET.toBuffer()],
+    [GAME_SEED, PLAYER_WALLET.toBuffer(), Buffer.from([0])],
     PROGRAM_ID
 );
 
@@ -82,7 +82,7 @@ async function main() {
         for (let i = 0; i < pipes.length; i++) {
             const pipe = pipes[i];
             console.log(`  Pipe ${i}:`);
-            console.log(`    X: ${pipe.x} (pixels)`);
+            console.log(`    X: ${pipe.x / 1000} (pixels)`);
             console.log(`    Gap Y: ${pipe.gapY} (pixels)`);
             console.log(`    Passed: ${pipe.passed}`);
             console.log(`    Active: ${pipe.active}`);
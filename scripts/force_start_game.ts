@@ -11,14 +11,14 @@ const IDL = JSON.parse(readFileSync("./app/src/idl/flappy_bird.json", "utf-8"));
 const ER_ENDPOINT = "https://devnet.magicblock.app"; // Using generic endpoint -- generic endpoint routes based on account usually
 const ER_WS_ENDPOINT = "wss://devnet.magicblock.app";
 const PROGRAM_ID = new PublicKey("DfJtsSqWNSetyRr3Fj4ZxM44oSBuZzAh4MTFMHQJSWvj");
-const GAME_SEED = Buffer.from("game_v2");
+const GAME_SEED = Buffer.from("game_v7");
 
 // Player Wallet (test wallet)
 const TEST_WALLET = Keypair.generate();
 
 // Derive PDA
 const [gamePDA] = PublicKey.findProgramAddressSync(
-    [GAME_SEED, TEST_WALLET.publicKey.toBuffer()],
+    [GAME_SEED, TEST_WALLET.publicKey.toBuffer(), Buffer.from([0])],
     PROGRAM_ID
 );
 
@@ -46,7 +46,7 @@ async function main() {
         // Note: Without base layer delegation, this creates an account ONLY on this ER node
         try {
             const tx = await program.methods
-                .initialize()
+                .initialize(0)
                 .accounts({
                     authority: TEST_WALLET.publicKey,
                 })
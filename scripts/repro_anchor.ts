@@ -9,7 +9,7 @@ const ER_ENDPOINT = "https://devnet-as.magicblock.app";
 const ER_VALIDATOR = new PublicKey("MAS1Dt9qreoRMQ14YQuhg8UTZMMzDdKhmkZMECCzk57"); // From use-flappy-bird-program.ts
 
 const IDL = JSON.parse(readFileSync("./app/src/idl/flappy_bird.json", "utf-8"));
-const GAME_SEED = Buffer.from("game_v2");
+const GAME_SEED = Buffer.from("game_v7");
 
 // Wallet
 const keypairData = JSON.parse(readFileSync(process.env.HOME + "/.config/solana/id.json", "utf-8"));
@@ -33,7 +33,7 @@ async function main() {
     const erProgram = new Program(IDL, erProvider);
 
     const [gamePDA] = PublicKey.findProgramAddressSync(
-        [GAME_SEED, wallet.publicKey.toBuffer()],
+        [GAME_SEED, wallet.publicKey.toBuffer(), Buffer.from([0])],
         devProgram.programId
     );
     console.log("Game PDA:", gamePDA.toString());
@@ -43,11 +43,11 @@ async function main() {
         console.log("\n1. [DEVNET] Initialize...");
         try {
             const tx = await devProgram.methods
-                .initialize()
+                .initialize(0)
                 .accounts({
                     authority: wallet.publicKey,
                     // game: derived automatically by Anchor? Need to check IDL.
-                    // IDL says seeds = [GAME_SEED, authority]. Anchor resolves this.
+                    // IDL says seeds = [GAME_SEED, authority, slot]. Anchor resolves this.
                 })
                 .rpc();
             console.log("   -> Init Tx:", tx);
@@ -71,7 +71,7 @@ async function main() {
             console.log("   -> Already Delegated.");
         } else {
             const tx = await devProgram.methods
-                .delegate()
+                .delegate(0)
                 .accounts({
                     payer: wallet.publicKey,
                 })
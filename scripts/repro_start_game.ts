@@ -12,14 +12,14 @@ const IDL = JSON.parse(readFileSync("./app/src/idl/flappy_bird.json", "utf-8"));
 // Use the endpoint from user logs
 const ER_ENDPOINT = "https://devnet-as.magicblock.app";
 const PROGRAM_ID = new PublicKey("DfJtsSqWNSetyRr3Fj4ZxM44oSBuZzAh4MTFMHQJSWvj");
-const GAME_SEED = Buffer.from("game_v2");
+const GAME_SEED = Buffer.from("game_v7");
 
 // Generate a random wallet to ensure clean state
 const TEST_WALLET = Keypair.generate();
 
 // Derive PDA
 const [gamePDA] = PublicKey.findProgramAddressSync(
-    [GAME_SEED, TEST_WALLET.publicKey.toBuffer()],
+    [GAME_SEED, TEST_WALLET.publicKey.toBuffer(), Buffer.from([0])],
     PROGRAM_ID
 );
 
@@ -44,7 +44,7 @@ async function main() {
 
         try {
             const tx = await program.methods
-                .initialize()
+                .initialize(0)
                 .accounts({
                     game: gamePDA,
                     authority: TEST_WALLET.publicKey,
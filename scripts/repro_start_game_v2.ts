@@ -8,7 +8,7 @@ import * as borsh from "@coral-xyz/borsh";
 // Config
 const ER_ENDPOINT = "https://devnet-as.magicblock.app";
 const PROGRAM_ID = new PublicKey("DfJtsSqWNSetyRr3Fj4ZxM44oSBuZzAh4MTFMHQJSWvj");
-const GAME_SEED = Buffer.from("game_v2");
+const GAME_SEED = Buffer.from("game_v7");
 
 // const TEST_WALLET = Keypair.generate();
 // Load from ~/.config/solana/id.json
@@ -16,7 +16,7 @@ const keypairData = JSON.parse(readFileSync(process.env.HOME + "/.config/solana/
 const TEST_WALLET = Keypair.fromSecretKey(Uint8Array.from(keypairData));
 
 const [gamePDA] = PublicKey.findProgramAddressSync(
-    [GAME_SEED, TEST_WALLET.publicKey.toBuffer()],
+    [GAME_SEED, TEST_WALLET.publicKey.toBuffer(), Buffer.from([0])],
     PROGRAM_ID
 );
 
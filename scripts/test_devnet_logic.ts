@@ -16,7 +16,7 @@ const ER_WS_URL = "wss://devnet-as.magicblock.app";
 const ER_VALIDATOR = new PublicKey("MAS1Dt9qreoRMQ14YQuhg8UTZMMzDdKhmkZMECCzk57");
 
 // Game seed - must match program
-const GAME_SEED = Buffer.from("game_v2");
+const GAME_SEED = Buffer.from("game_v7");
 
 const IDL = JSON.parse(readFileSync("./app/src/idl/flappy_bird.json", "utf-8"));
 
@@ -48,7 +48,7 @@ async function main() {
 
     // Derive game PDA
     const [gamePDA] = PublicKey.findProgramAddressSync(
-        [GAME_SEED, wallet.publicKey.toBuffer()],
+        [GAME_SEED, wallet.publicKey.toBuffer(), Buffer.from([0])],
         PROGRAM_ID
     );
     console.log("Game PDA:", gamePDA.toString());
@@ -81,7 +81,7 @@ async function main() {
     } else {
         try {
             const tx = await program.methods
-                .initialize()
+                .initialize(0)
                 .accounts({
                     authority: wallet.publicKey,
                 } as any)
@@ -106,7 +106,7 @@ async function main() {
     } else {
         try {
             const tx = await program.methods
-                .delegate()
+                .delegate(0)
                 .accounts({
                     payer: wallet.publicKey,
                 } as any)
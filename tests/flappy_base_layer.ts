@@ -1,10 +1,15 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { expect } from "chai";
-import { Keypair, PublicKey } from "@solana/web3.js";
+import { Keypair, PublicKey, SYSVAR_SLOT_HASHES_PUBKEY, SystemProgram, Transaction } from "@solana/web3.js";
 import type { FlappyBird } from "../target/types/flappy_bird";
+import { DeathCause, Difficulty, GameStatus, newRunSecret, startAccounts, startRun, waitForNextSlot } from "./run_request";
 
-const GAME_SEED = Buffer.from("game_v2");
+const GAME_SEED = Buffer.from("game_v10");
+const CONFIG_ADMIN = new PublicKey("anshxnbjGiUpsZpnx3c6LrK2vt8zt54vLMvY3C7Locm");
+const REWARD_MINT_SEED = Buffer.from("reward_mint");
+const TOKEN_PROGRAM_ID = new PublicKey("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+const ASSOCIATED_TOKEN_PROGRAM_ID = new PublicKey("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
 
 describe("Flappy Bird - Base Layer", () => {
   const provider = anchor.AnchorProvider.env();
@@ -14,13 +19,13 @@ describe("Flappy Bird - Base Layer", () => {
   const wallet = provider.wallet as anchor.Wallet;
 
   const [gamePda] = PublicKey.findProgramAddressSync(
-    [GAME_SEED, wallet.publicKey.toBuffer()],
+    [GAME_SEED, wallet.publicKey.toBuffer(), Buffer.from([0])],
     program.programId
   );
 
   it("initializes (idempotent) and sets defaults", async () => {
     await program.methods
-      .initialize()
+      .initialize(0)
       .accounts({
         authority: wallet.publicKey,
       } as any)
@@ -30,24 +35,91 @@ describe("Flappy Bird - Base Layer", () => {
     expect(game.authority.toBase58()).to.equal(wallet.publicKey.toBase58());
     expect(game.score.toNumber()).to.equal(0);
     expect(game.highScore.toNumber()).to.equal(0);
-    expect(game.gameStatus).to.have.property("notStarted");
+    expect(game.gameStatus).to.equal(GameStatus.notStarted);
     expect(game.birdVelocity).to.equal(0);
   });
 
-  it("startGame transitions to playing and resets state", async () => {
+  it("caches the canonical bump and rejects a game PDA derived for another authority", async () => {
+    const [, canonicalBump] = PublicKey.findProgramAddressSync(
+      [GAME_SEED, wallet.publicKey.toBuffer(), Buffer.from([0])],
+      program.programId
+    );
+    const game = await program.account.gameState.fetch(gamePda);
+    expect(game.bump).to.equal(canonicalBump);
+
+    // The seeds constraint checks the stored bump with one create_program_address
+    const accounts = { game: gamePda, signer: wallet.publicKey } as any;
+    await startRun(program, accounts);
+    await waitForNextSlot(provider.connection);
+    const sig = await program.methods.tick().accounts(accounts).rpc({ commitment: "confirmed" });
+    const tx = await provider.connection.getTransaction(sig, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+    console.log(`      tick: ${tx!.meta!.computeUnitsConsumed} CU with the bump cached`);
+    await program.methods.resetGame().accounts(accounts).rpc();
+
+    // An address built from our seeds with any other bump is not the account
+    // stored at gamePda, so it is turned away before `bump = game.bump` is
+    // checked; the constraint itself is covered by the program's unit tests.
+    for (let bump = canonicalBump - 1; bump >= 0; bump--) {
+      let forged: PublicKey;
+      try {
+        forged = PublicKey.createProgramAddressSync(
+          [GAME_SEED, wallet.publicKey.toBuffer(), Buffer.from([0]), Buffer.from([bump])],
+          program.programId
+        );
+      } catch {
+        continue;
+      }
+      expect(forged.toBase58()).to.not.equal(gamePda.toBase58());
+      try {
+        await program.methods
+          .tick()
+          .accounts({
+            game: forged,
+            signer: wallet.publicKey,
+          } as any)
+          .rpc();
+        expect.fail("expected a non-canonical PDA to be rejected");
+      } catch (e) {
+        expect(String(e)).to.match(/AccountNotInitialized|ConstraintSeeds|owned by a different program/i);
+      }
+      break;
+    }
+  });
+
+  it("startGame rejects a reveal that doesn't match the request", async () => {
+    const { commitment } = newRunSecret();
     await program.methods
-      .startGame()
-      .accounts({
-        game: gamePda,
-        signer: wallet.publicKey,
-        sessionToken: null,
-      } as any)
+      .requestGame(commitment)
+      .accounts({ game: gamePda, signer: wallet.publicKey } as any)
       .rpc();
+    await waitForNextSlot(provider.connection);
+
+    const start = await startAccounts(program, { game: gamePda, signer: wallet.publicKey });
+    try {
+      await program.methods
+        .startGame({ difficulty: { normal: {} }, reveal: newRunSecret().reveal, scoring: { pipesPassed: {} }, horizontalControl: false, wind: null, mode: null, practice: false, lives: 1 })
+        .accounts(start.accounts)
+        .remainingAccounts(start.remainingAccounts)
+        .rpc();
+      expect.fail("expected InvalidReveal");
+    } catch (e) {
+      expect(String(e)).to.match(/InvalidReveal|does not match/i);
+    }
+  });
+
+  it("startGame transitions to playing and resets state", async () => {
+    await startRun(program, {
+      game: gamePda,
+      signer: wallet.publicKey,
+      sessionToken: null,
+    });
 
     const game = await program.account.gameState.fetch(gamePda);
-    expect(game.gameStatus).to.have.property("playing");
+    expect(game.gameStatus).to.equal(GameStatus.playing);
     expect(game.score.toNumber()).to.equal(0);
     expect(game.birdVelocity).to.equal(0);
+    // The local validator keeps slot hashes
+    expect(game.seedSource).to.equal(0);
   });
 
   it("tick advances frameCount and updates bird position", async () => {
@@ -69,7 +141,7 @@ describe("Flappy Bird - Base Layer", () => {
 
   it("flap sets upward velocity (negative) and advances one tick", async () => {
     await program.methods
-      .flap()
+      .flap(true)
       .accounts({
         game: gamePda,
         signer: wallet.publicKey,
@@ -82,15 +154,87 @@ describe("Flappy Bird - Base Layer", () => {
     expect(game.frameCount.toNumber()).to.be.greaterThan(0);
   });
 
+  it("flap(false) only queues the input; the next tick applies it", async () => {
+    // Ranked flaps must be MIN_FLAP_INTERVAL_FRAMES apart: clear the last one
+    for (let i = 0; i < 2; i++) {
+      await waitForNextSlot(provider.connection);
+      await program.methods.tick().accounts({ game: gamePda, signer: wallet.publicKey } as any).rpc();
+    }
+    const before = await program.account.gameState.fetch(gamePda);
+
+    await program.methods
+      .flap(false)
+      .accounts({
+        game: gamePda,
+        signer: wallet.publicKey,
+      } as any)
+      .rpc();
+
+    const queued = await program.account.gameState.fetch(gamePda);
+    expect(queued.pendingFlap).to.equal(1);
+    expect(queued.frameCount.toNumber()).to.equal(before.frameCount.toNumber());
+    expect(queued.birdY).to.equal(before.birdY);
+    // Logged against the frame it was queued after
+    const frame = before.frameCount.toNumber();
+    expect(queued.inputLog.bits[Math.floor(frame / 64)].testn(frame % 64)).to.be.true;
+
+    await program.methods
+      .tick()
+      .accounts({
+        game: gamePda,
+        signer: wallet.publicKey,
+      } as any)
+      .rpc();
+
+    const after = await program.account.gameState.fetch(gamePda);
+    expect(after.pendingFlap).to.equal(0);
+    expect(after.birdVelocity).to.be.lessThan(0);
+    expect(after.frameCount.toNumber()).to.equal(before.frameCount.toNumber() + 1);
+  });
+
+  it("a ranked flap too soon after the last one is dropped", async () => {
+    const before = await program.account.gameState.fetch(gamePda);
+    let dropped: any = null;
+    const listener = program.addEventListener("flapTooSoon", (event) => (dropped = event));
+    try {
+      await program.methods.flap(false).accounts({ game: gamePda, signer: wallet.publicKey } as any).rpc();
+      const after = await program.account.gameState.fetch(gamePda);
+      expect(after.pendingFlap).to.equal(0);
+      expect(after.flapsThisRun).to.equal(before.flapsThisRun);
+      expect(after.lastFlapFrame.toNumber()).to.equal(before.lastFlapFrame.toNumber());
+      await new Promise((resolve) => setTimeout(resolve, 1000));
+      expect(dropped).to.not.be.null;
+      expect(dropped.nextFrame.toNumber()).to.equal(before.lastFlapFrame.toNumber() - 1 + before.minFlapInterval);
+    } finally {
+      await program.removeEventListener(listener);
+    }
+  });
+
+  it("getState returns a snapshot of the game via return data", async () => {
+    const snapshot = await program.methods
+      .getState()
+      .accounts({ game: gamePda } as any)
+      .view();
+    const game = await program.account.gameState.fetch(gamePda);
+    expect(snapshot.version).to.equal(6);
+    expect(snapshot.partnerBird).to.equal(null);
+    expect(snapshot.status).to.have.property("playing");
+    expect(snapshot.frameCount.toNumber()).to.equal(game.frameCount.toNumber());
+    expect(snapshot.bird.y).to.equal(Math.trunc(game.birdY / 1000));
+    expect(snapshot.birdVelocity).to.equal(game.birdVelocity);
+    expect(snapshot.bestRunFlaps).to.equal(game.bestRunFlaps);
+    expect(snapshot.totalDistance.toNumber()).to.equal(Math.trunc(game.totalDistance.toNumber() / 1000));
+    expect(snapshot.powerup === null).to.equal(game.powerup === 0);
+    expect([snapshot.width, snapshot.height]).to.deep.equal([600, 400]);
+  });
+
   it("startGame fails when already playing", async () => {
+    const start = await startAccounts(program, { game: gamePda, signer: wallet.publicKey });
     try {
       await program.methods
-        .startGame()
-        .accounts({
-          game: gamePda,
-          signer: wallet.publicKey,
-          sessionToken: null,
-        } as any)
+        .startGame({ difficulty: { normal: {} }, reveal: new anchor.BN(0), scoring: { pipesPassed: {} }, horizontalControl: false, wind: null, mode: null, practice: false, lives: 1 })
+        .accounts(start.accounts)
+        .remainingAccounts(start.remainingAccounts)
         .rpc();
       expect.fail("expected startGame to fail when already playing");
     } catch (e) {
@@ -138,7 +282,7 @@ describe("Flappy Bird - Base Layer", () => {
       .rpc();
 
     const game = await program.account.gameState.fetch(gamePda);
-    expect(game.gameStatus).to.have.property("notStarted");
+    expect(game.gameStatus).to.equal(GameStatus.notStarted);
 
     try {
       await program.methods
@@ -155,4 +299,606 @@ describe("Flappy Bird - Base Layer", () => {
       expect(msg).to.match(/GameNotPlaying|not in playing/i);
     }
   });
+
+  it("startGame with Hard difficulty loads the Hard physics", async () => {
+    await startRun(program, { game: gamePda, signer: wallet.publicKey }, { hard: {} });
+
+    const game = await program.account.gameState.fetch(gamePda);
+    expect(game.difficulty).to.equal(Difficulty.hard);
+    expect(game.pipeGap).to.be.lessThan(160);
+    expect(game.pipeSpeed).to.be.greaterThan(4000);
+
+    await program.methods
+      .resetGame()
+      .accounts({ game: gamePda, signer: wallet.publicKey } as any)
+      .rpc();
+  });
+
+  it("initialize with another slot creates an independent game", async () => {
+    const [slotOnePda] = PublicKey.findProgramAddressSync(
+      [GAME_SEED, wallet.publicKey.toBuffer(), Buffer.from([1])],
+      program.programId
+    );
+    await program.methods
+      .initialize(1)
+      .accounts({ authority: wallet.publicKey } as any)
+      .rpc();
+
+    const slotOne = await program.account.gameState.fetch(slotOnePda);
+    expect(slotOne.gameSlot).to.equal(1);
+    expect(slotOne.gameStatus).to.equal(GameStatus.notStarted);
+    const slotZero = await program.account.gameState.fetch(gamePda);
+    expect(slotZero.gameSlot).to.equal(0);
+
+    try {
+      await program.methods
+        .initialize(4)
+        .accounts({ authority: wallet.publicKey } as any)
+        .rpc();
+      expect.fail("expected InvalidGameSlot");
+    } catch (e) {
+      expect(String(e)).to.match(/InvalidGameSlot|out of range/i);
+    }
+  });
+
+  it("initializeWithParams sets the game's playfield within bounds", async () => {
+    const [slotTwoPda] = PublicKey.findProgramAddressSync(
+      [GAME_SEED, wallet.publicKey.toBuffer(), Buffer.from([2])],
+      program.programId
+    );
+    try {
+      await program.methods
+        .initializeWithParams(2, 299, 600)
+        .accounts({ authority: wallet.publicKey } as any)
+        .rpc();
+      expect.fail("expected InvalidPlayfield");
+    } catch (e) {
+      expect(String(e)).to.match(/InvalidPlayfield|Playfield size/i);
+    }
+    await program.methods
+      .initializeWithParams(2, 400, 600)
+      .accounts({ authority: wallet.publicKey } as any)
+      .rpc();
+    const game = await program.account.gameState.fetch(slotTwoPda);
+    expect([game.width, game.height]).to.deep.equal([400, 600]);
+    expect(game.birdY).to.equal(300 * 1000);
+
+    // Starting a run keeps it
+    const accounts = { game: slotTwoPda, signer: wallet.publicKey } as any;
+    await startRun(program, accounts);
+    const started = await program.account.gameState.fetch(slotTwoPda);
+    expect([started.width, started.height]).to.deep.equal([400, 600]);
+    await program.methods.endGame().accounts(accounts).rpc();
+  });
+
+  it("initializeConfig is admin-only", async () => {
+    try {
+      await program.methods
+        .initializeConfig({
+          gravity: 400,
+          jumpVelocity: -6000,
+          pipeSpeed: 4000,
+          pipeGap: 160,
+          pipeSpawnDistance: 250,
+          nearMissThreshold: 10,
+        })
+        .accounts({ admin: wallet.publicKey } as any)
+        .rpc();
+      expect.fail("expected Unauthorized");
+    } catch (e) {
+      expect(String(e)).to.match(/Unauthorized|config admin/i);
+    }
+  });
+
+  it("a ranked restart waits out the ranked cooldown, a casual one doesn't", async function () {
+    // Only the config admin can set the cooldown
+    if (!wallet.publicKey.equals(CONFIG_ADMIN)) {
+      this.skip();
+    }
+    const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("config_v2")], program.programId);
+    if (!(await provider.connection.getAccountInfo(configPda))) {
+      await program.methods
+        .initializeConfig({
+          gravity: 400,
+          jumpVelocity: -6000,
+          pipeSpeed: 4000,
+          pipeGap: 160,
+          pipeSpawnDistance: 250,
+          nearMissThreshold: 10,
+        })
+        .accounts({ admin: wallet.publicKey } as any)
+        .rpc();
+    }
+    await program.methods.setRankedCooldown(3600).accounts({ admin: wallet.publicKey } as any).rpc();
+    const accounts = { game: gamePda, signer: wallet.publicKey } as any;
+    try {
+      await startRun(program, accounts);
+      await program.methods.endGame().accounts(accounts).rpc();
+      try {
+        await startRun(program, accounts);
+        expect.fail("expected CooldownActive");
+      } catch (e) {
+        expect(String(e)).to.match(/CooldownActive|cooldown/i);
+      }
+
+      const { reveal, commitment } = newRunSecret();
+      await program.methods.requestGame(commitment).accounts(accounts).rpc();
+      await waitForNextSlot(provider.connection);
+      await program.methods
+        .startGame({ difficulty: { normal: {} }, reveal, scoring: { pipesPassed: {} }, horizontalControl: false, wind: null, mode: null, practice: true, lives: 1 })
+        .accounts((await startAccounts(program, accounts)).accounts)
+        .rpc();
+      expect((await program.account.gameState.fetch(gamePda)).gameStatus).to.equal(GameStatus.playing);
+      await program.methods.endGame().accounts(accounts).rpc();
+    } finally {
+      await program.methods.setRankedCooldown(0).accounts({ admin: wallet.publicKey } as any).rpc();
+    }
+  });
+
+  it("a countdown holds the bird until it ticks down", async function () {
+    // Only the config admin can set the countdown, on the config made above
+    const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("config_v2")], program.programId);
+    if (!wallet.publicKey.equals(CONFIG_ADMIN) || !(await provider.connection.getAccountInfo(configPda))) {
+      this.skip();
+    }
+    await program.methods.setCountdown(2, false).accounts({ admin: wallet.publicKey } as any).rpc();
+    const accounts = { game: gamePda, signer: wallet.publicKey } as any;
+    try {
+      await startRun(program, accounts);
+      const started = await program.account.gameState.fetch(gamePda);
+      expect(started.gameStatus).to.equal(GameStatus.countdown);
+      expect(started.countdownFramesRemaining).to.equal(2);
+      try {
+        await program.methods.flap(false).accounts(accounts).rpc();
+        expect.fail("expected CountdownRunning");
+      } catch (e) {
+        expect(String(e)).to.match(/CountdownRunning|counting down/i);
+      }
+
+      for (let i = 0; i < 2; i++) {
+        await waitForNextSlot(provider.connection);
+        await program.methods.tick().accounts(accounts).rpc();
+      }
+      const live = await program.account.gameState.fetch(gamePda);
+      expect(live.gameStatus).to.equal(GameStatus.playing);
+      expect(live.frameCount.toNumber()).to.equal(0);
+      expect(live.birdY).to.equal(started.birdY);
+      await program.methods.endGame().accounts(accounts).rpc();
+    } finally {
+      await program.methods.setCountdown(0, false).accounts({ admin: wallet.publicKey } as any).rpc();
+    }
+  });
+
+  it("an emergency pause stops play and new runs but lets the run end", async function () {
+    // Only the config admin can pause, on the config made above
+    const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("config_v2")], program.programId);
+    if (!wallet.publicKey.equals(CONFIG_ADMIN) || !(await provider.connection.getAccountInfo(configPda))) {
+      this.skip();
+    }
+    const accounts = { game: gamePda, signer: wallet.publicKey, config: configPda } as any;
+
+    // A match and a challenge to start runs from while paused
+    const id = new anchor.BN(Date.now());
+    await program.methods
+      .createMatch(id, Keypair.generate().publicKey, { normal: {} })
+      .accounts({ challenger: wallet.publicKey, slotHashes: SYSVAR_SLOT_HASHES_PUBKEY } as any)
+      .rpc();
+    const [matchPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("match_v2"), wallet.publicKey.toBuffer(), id.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const challenger = Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(challenger.publicKey, 100_000_000);
+    await provider.connection.confirmTransaction(sig, "confirmed");
+    await program.methods
+      .createChallenge(wallet.publicKey, new anchor.BN(5), id, new anchor.BN(Math.floor(Date.now() / 1000) + 3600))
+      .accounts({ challenger: challenger.publicKey } as any)
+      .signers([challenger])
+      .rpc();
+    const [challengePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("challenge"), challenger.publicKey.toBuffer(), wallet.publicKey.toBuffer(), id.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const starts = { game: gamePda, authority: wallet.publicKey, config: configPda };
+
+    await startRun(program, accounts);
+    await program.methods.setPaused(true).accounts({ admin: wallet.publicKey } as any).rpc();
+    try {
+      const paused = [
+        program.methods.flap(true).accounts(accounts),
+        program.methods.tick().accounts(accounts),
+        program.methods.moveHorizontal(1).accounts(accounts),
+        program.methods.tickAndMaybeCommit().accounts({ ...accounts, payer: wallet.publicKey }),
+        program.methods.startMatchRun().accounts({ ...starts, gameMatch: matchPda } as any),
+        program.methods.acceptChallenge().accounts({ ...starts, challenge: challengePda } as any),
+      ];
+      for (const call of paused) {
+        try {
+          await call.rpc();
+          expect.fail("expected ProgramPaused");
+        } catch (e) {
+          expect(String(e)).to.match(/ProgramPaused|paused by the admin/i);
+        }
+      }
+      await program.methods.endGame().accounts(accounts).rpc();
+      expect((await program.account.gameState.fetch(gamePda)).gameStatus).to.equal(GameStatus.gameOver);
+      expect(await program.methods.getState().accounts({ game: gamePda } as any).view()).to.not.be.null;
+    } finally {
+      await program.methods.setPaused(false).accounts({ admin: wallet.publicKey } as any).rpc();
+    }
+  });
+
+  it("setSpectatorMode toggles the FrameState events", async () => {
+    const accounts = { game: gamePda, signer: wallet.publicKey } as any;
+    await program.methods.setSpectatorMode(true).accounts(accounts).rpc();
+    expect((await program.account.gameState.fetch(gamePda)).spectatorMode).to.equal(1);
+    await program.methods.setSpectatorMode(false).accounts(accounts).rpc();
+    expect((await program.account.gameState.fetch(gamePda)).spectatorMode).to.equal(0);
+  });
+
+  it("verbose logging trades compute for a binary frame record", async () => {
+    const accounts = { game: gamePda, signer: wallet.publicKey } as any;
+    const tickMeta = async () => {
+      await waitForNextSlot(provider.connection);
+      const sig = await program.methods.tick().accounts(accounts).rpc({ commitment: "confirmed" });
+      const tx = await provider.connection.getTransaction(sig, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+      return tx!.meta!;
+    };
+    await startRun(program, accounts);
+    try {
+      const quiet = await tickMeta();
+      expect(quiet.logMessages!.some((line) => line.startsWith("Program data:"))).to.be.false;
+
+      await program.methods.setVerboseLogging(true).accounts(accounts).rpc();
+      const verbose = await tickMeta();
+      expect(verbose.logMessages!.some((line) => line.startsWith("Program data:"))).to.be.true;
+      console.log(
+        `      tick: ${quiet.computeUnitsConsumed} CU quiet, ${verbose.computeUnitsConsumed} CU verbose ` +
+          `(+${verbose.computeUnitsConsumed! - quiet.computeUnitsConsumed!})`
+      );
+    } finally {
+      await program.methods.setVerboseLogging(false).accounts(accounts).rpc();
+      await program.methods.endGame().accounts(accounts).rpc();
+    }
+  });
+
+  it("advance runs a batch of frames with their flaps", async () => {
+    const accounts = { game: gamePda, signer: wallet.publicKey } as any;
+    await startRun(program, accounts);
+    const before = await program.account.gameState.fetch(gamePda);
+    for (let i = 0; i < 3; i++) {
+      await waitForNextSlot(provider.connection);
+    }
+    // Flap before the first frame only; the cadence may allow fewer than 3
+    const sig = await program.methods.advance(new anchor.BN(0b001), 3).accounts(accounts).rpc({ commitment: "confirmed" });
+    const after = await program.account.gameState.fetch(gamePda);
+    const frames = after.frameCount.toNumber() - before.frameCount.toNumber();
+    expect(frames).to.be.within(1, 3);
+    expect(after.flapsThisRun).to.equal(1);
+    expect(after.birdY).to.be.lessThan(before.birdY);
+
+    const tx = await provider.connection.getTransaction(sig, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+    console.log(`      advance: ${frames} frames in ${tx!.meta!.computeUnitsConsumed} CU`);
+    await program.methods.resetGame().accounts(accounts).rpc();
+  });
+
+  it("crankTick advances only a stale run, tipping the cranker from the game", async function () {
+    this.timeout(60_000);
+    const accounts = { game: gamePda, signer: wallet.publicKey } as any;
+    const cranker = Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(cranker.publicKey, 100_000_000);
+    await provider.connection.confirmTransaction(sig, "confirmed");
+    const crank = () =>
+      program.methods
+        .crankTick(10)
+        .accounts({ game: gamePda, cranker: cranker.publicKey } as any)
+        .signers([cranker])
+        .rpc({ commitment: "confirmed" });
+
+    await startRun(program, accounts);
+    try {
+      try {
+        await crank();
+        expect.fail("expected GameNotStale");
+      } catch (e) {
+        expect(String(e)).to.match(/GameNotStale|too recently/i);
+      }
+
+      // Fund a tip, then leave the run for longer than CRANK_STALE_SECS
+      await provider.sendAndConfirm(
+        new Transaction().add(
+          SystemProgram.transfer({ fromPubkey: wallet.publicKey, toPubkey: gamePda, lamports: 5_000 })
+        )
+      );
+      await new Promise((resolve) => setTimeout(resolve, 11_000));
+      const before = await program.account.gameState.fetch(gamePda);
+      const lamports = await provider.connection.getBalance(gamePda, "confirmed");
+      await crank();
+      const after = await program.account.gameState.fetch(gamePda);
+      expect(after.frameCount.toNumber()).to.be.greaterThan(before.frameCount.toNumber());
+      expect(await provider.connection.getBalance(gamePda, "confirmed")).to.equal(lamports - 5_000);
+    } finally {
+      await program.methods.resetGame().accounts(accounts).rpc();
+    }
+  });
+
+  it("startCoop gives each player a bird, flapped only by them with flapAs", async () => {
+    const accounts = { game: gamePda, signer: wallet.publicKey } as any;
+    const partner = Keypair.generate();
+    const { reveal, commitment } = newRunSecret();
+    await program.methods.requestGame(commitment).accounts(accounts).rpc();
+    await waitForNextSlot(provider.connection);
+    await program.methods.startCoop(partner.publicKey, { normal: {} }, reveal).accounts((await startAccounts(program, accounts)).accounts).rpc();
+    try {
+      const game = await program.account.gameState.fetch(gamePda);
+      expect(game.coop.partner.toBase58()).to.equal(partner.publicKey.toBase58());
+      expect(game.coop.alive).to.deep.equal([1, 1]);
+
+      await program.methods.flapAs(1).accounts({ game: gamePda, signer: partner.publicKey } as any).signers([partner]).rpc();
+      expect((await program.account.gameState.fetch(gamePda)).coop.pendingFlap).to.equal(1);
+      await program.methods.flapAs(0).accounts(accounts).rpc();
+
+      for (const [name, call] of [
+        ["the partner's bird", () => program.methods.flapAs(1).accounts(accounts).rpc()],
+        ["a solo flap", () => program.methods.flap(false).accounts(accounts).rpc()],
+      ] as const) {
+        try {
+          await call();
+          expect.fail(`expected ${name} to be rejected`);
+        } catch (e) {
+          expect(String(e)).to.match(/NotCoopPlayer|CoopGame|session key|use flap_as/i);
+        }
+      }
+    } finally {
+      await program.methods.endGame().accounts(accounts).rpc();
+      await program.methods.resetGame().accounts(accounts).rpc();
+    }
+    expect((await program.account.gameState.fetch(gamePda)).coop.active).to.equal(0);
+  });
+
+  it("opens a keeper ledger, with nothing to claim until a crank accrues", async () => {
+    const keeper = Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(keeper.publicKey, 100_000_000);
+    await provider.connection.confirmTransaction(sig, "confirmed");
+    const [ledgerPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("keeper_ledger"), keeper.publicKey.toBuffer()],
+      program.programId
+    );
+    await program.methods.openKeeperLedger().accounts({ keeper: keeper.publicKey } as any).signers([keeper]).rpc();
+    const ledger = await program.account.keeperLedger.fetch(ledgerPda);
+    expect(ledger.keeper.toBase58()).to.equal(keeper.publicKey.toBase58());
+    expect(ledger.accrued.toNumber()).to.equal(0);
+
+    const [vaultPda] = PublicKey.findProgramAddressSync([Buffer.from("keeper_vault")], program.programId);
+    if (!(await provider.connection.getAccountInfo(vaultPda))) {
+      if (!wallet.publicKey.equals(CONFIG_ADMIN)) {
+        return;
+      }
+      await program.methods.initializeKeeperVault().accounts({ admin: wallet.publicKey } as any).rpc();
+    }
+    try {
+      await program.methods.claimKeeperTips().accounts({ keeper: keeper.publicKey } as any).signers([keeper]).rpc();
+      expect.fail("expected NoTipsAccrued");
+    } catch (e) {
+      expect(String(e)).to.match(/NoTipsAccrued|No keeper tips/i);
+    }
+  });
+
+  it("tickN runs what the cadence allows, each frame within TICK_N_FRAME_CU", async () => {
+    const accounts = { game: gamePda, signer: wallet.publicKey } as any;
+    const consumed = async (sig: string) => {
+      const tx = await provider.connection.getTransaction(sig, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+      return tx!.meta!.computeUnitsConsumed!;
+    };
+    await startRun(program, accounts);
+    try {
+      await waitForNextSlot(provider.connection);
+      const single = await consumed(await program.methods.tick().accounts(accounts).rpc({ commitment: "confirmed" }));
+
+      for (let i = 0; i < 5; i++) {
+        await waitForNextSlot(provider.connection);
+      }
+      const outcome = await program.methods.tickN(5).accounts(accounts).view();
+      expect(outcome.frames).to.be.within(0, 5);
+      expect(outcome.status).to.deep.equal({ playing: {} });
+
+      const before = await program.account.gameState.fetch(gamePda);
+      const sig = await program.methods.tickN(5).accounts(accounts).rpc({ commitment: "confirmed" });
+      const after = await program.account.gameState.fetch(gamePda);
+      const frames = after.frameCount.toNumber() - before.frameCount.toNumber();
+      expect(frames).to.be.within(1, 5);
+
+      // The frames past the first cost what TICK_N_FRAME_CU budgets for, at most
+      const batch = await consumed(sig);
+      if (frames > 1) {
+        const perFrame = Math.ceil((batch - single) / (frames - 1));
+        expect(perFrame).to.be.at.most(12_000);
+        console.log(`      tickN: ${frames} frames in ${batch} CU, ~${perFrame} CU per extra frame`);
+      }
+    } finally {
+      await program.methods.resetGame().accounts(accounts).rpc();
+    }
+  });
+
+    it("pauseGame rejects ticks until resumeGame", async () => {
+    const accounts = { game: gamePda, signer: wallet.publicKey } as any;
+    await startRun(program, accounts);
+    await program.methods.pauseGame().accounts(accounts).rpc();
+
+    const paused = await program.account.gameState.fetch(gamePda);
+    expect(paused.gameStatus).to.equal(GameStatus.paused);
+    try {
+      await program.methods.tick().accounts(accounts).rpc();
+      expect.fail("expected GamePaused");
+    } catch (e) {
+      expect(String(e)).to.match(/GamePaused|is paused/i);
+    }
+
+    await program.methods.resumeGame().accounts(accounts).rpc();
+    const resumed = await program.account.gameState.fetch(gamePda);
+    expect(resumed.gameStatus).to.equal(GameStatus.playing);
+    expect(resumed.frameCount.toNumber()).to.equal(paused.frameCount.toNumber());
+
+    await program.methods.resetGame().accounts(accounts).rpc();
+  });
+
+  it("a session key can play but not end, reset, pause, resume or transfer the game", async () => {
+    const sessionKey = Keypair.generate();
+    const asSession = { game: gamePda, signer: sessionKey.publicKey } as any;
+    await startRun(program, { game: gamePda, signer: wallet.publicKey });
+
+    await program.methods.flap(false).accounts(asSession).signers([sessionKey]).rpc();
+    await program.methods.tick().accounts(asSession).signers([sessionKey]).rpc();
+    await program.methods.tickMultiple(2).accounts(asSession).signers([sessionKey]).rpc();
+    await program.methods.catchUp().accounts(asSession).signers([sessionKey]).rpc();
+
+    for (const method of ["endGame", "resetGame", "pauseGame"]) {
+      try {
+        await (program.methods as any)[method]().accounts(asSession).signers([sessionKey]).rpc();
+        expect.fail(`expected ${method} to reject a session key`);
+      } catch (e) {
+        expect(String(e)).to.match(/NotGameAuthority|game's authority/i);
+      }
+    }
+    await program.methods.pauseGame().accounts({ game: gamePda, signer: wallet.publicKey } as any).rpc();
+    try {
+      await program.methods.resumeGame().accounts(asSession).signers([sessionKey]).rpc();
+      expect.fail("expected resumeGame to reject a session key");
+    } catch (e) {
+      expect(String(e)).to.match(/NotGameAuthority|game's authority/i);
+    }
+    await program.methods.resumeGame().accounts({ game: gamePda, signer: wallet.publicKey } as any).rpc();
+    try {
+      await program.methods
+        .transferAuthority()
+        .accounts({ game: gamePda, authority: sessionKey.publicKey, newAuthority: wallet.publicKey } as any)
+        .signers([sessionKey])
+        .rpc();
+      expect.fail("expected transferAuthority to reject a session key");
+    } catch (e) {
+      expect(String(e)).to.match(/ConstraintSeeds|ConstraintHasOne|seeds constraint|has one/i);
+    }
+
+    const game = await program.account.gameState.fetch(gamePda);
+    expect(game.gameStatus).to.equal(GameStatus.playing);
+    await program.methods.resetGame().accounts({ game: gamePda, signer: wallet.publicKey } as any).rpc();
+  });
+
+  it("endGameWithReward is authority-only", async () => {
+    const other = Keypair.generate();
+    const [rewardMint] = PublicKey.findProgramAddressSync([REWARD_MINT_SEED], program.programId);
+    const [playerTokenAccount] = PublicKey.findProgramAddressSync(
+      [wallet.publicKey.toBuffer(), TOKEN_PROGRAM_ID.toBuffer(), rewardMint.toBuffer()],
+      ASSOCIATED_TOKEN_PROGRAM_ID
+    );
+    await startRun(program, { game: gamePda, signer: wallet.publicKey });
+
+    try {
+      await program.methods
+        .endGameWithReward()
+        .accounts({ game: gamePda, signer: other.publicKey, playerTokenAccount } as any)
+        .signers([other])
+        .rpc();
+      expect.fail("expected endGameWithReward to reject a non-authority signer");
+    } catch (e) {
+      expect(String(e)).to.match(/NotGameAuthority|game's authority/i);
+    }
+
+    const game = await program.account.gameState.fetch(gamePda);
+    expect(game.gameStatus).to.equal(GameStatus.playing);
+    await program.methods.resetGame().accounts({ game: gamePda, signer: wallet.publicKey } as any).rpc();
+  });
+
+  it("setCommitInterval stores the interval for the game's authority only", async () => {
+    await program.methods
+      .setCommitInterval(30)
+      .accounts({ game: gamePda, authority: wallet.publicKey } as any)
+      .rpc();
+    const game = await program.account.gameState.fetch(gamePda);
+    expect(game.commitInterval).to.equal(30);
+
+    const other = Keypair.generate();
+    try {
+      await program.methods
+        .setCommitInterval(0)
+        .accounts({ game: gamePda, authority: other.publicKey } as any)
+        .signers([other])
+        .rpc();
+      expect.fail("expected another wallet to be rejected");
+    } catch (e) {
+      expect(String(e)).to.match(/ConstraintSeeds|ConstraintHasOne|seeds constraint|has one/i);
+    }
+
+    await program.methods
+      .setCommitInterval(0)
+      .accounts({ game: gamePda, authority: wallet.publicKey } as any)
+      .rpc();
+  });
+
+  it("endGame and endGameWithReward only end a run that is playing, and record how it ended", async () => {
+    const accounts = { game: gamePda, signer: wallet.publicKey } as any;
+    const expectEndRejected = async (pattern: RegExp) => {
+      try {
+        await program.methods.endGame().accounts(accounts).rpc();
+        expect.fail("expected endGame to be rejected");
+      } catch (e) {
+        expect(String(e)).to.match(pattern);
+      }
+    };
+
+    const notStarted = await program.account.gameState.fetch(gamePda);
+    expect(notStarted.gameStatus).to.equal(GameStatus.notStarted);
+    await expectEndRejected(/GameNotPlaying|not in playing/i);
+
+    await startRun(program, accounts);
+    await program.methods.pauseGame().accounts(accounts).rpc();
+    await expectEndRejected(/GamePaused|is paused/i);
+    await program.methods.resumeGame().accounts(accounts).rpc();
+
+    await program.methods.tick().accounts(accounts).rpc();
+    await program.methods.endGame().accounts(accounts).rpc();
+    const ended = await program.account.gameState.fetch(gamePda);
+    expect(ended.gameStatus).to.equal(GameStatus.gameOver);
+    expect(ended.endFrame.toNumber()).to.equal(ended.frameCount.toNumber());
+    expect(ended.endCause).to.equal(DeathCause.manual + 1);
+    expect(ended.endBirdY).to.equal(Math.trunc(ended.birdY / 1000));
+    expect(ended.endPipe).to.equal(0);
+
+    await expectEndRejected(/GameNotPlaying|not in playing/i);
+    await program.methods.resetGame().accounts(accounts).rpc();
+
+    // endGameWithReward ends a run in play the same way, and on a finished
+    // run only claims the (already paid) reward, leaving the ending as it was
+    const [rewardMint] = PublicKey.findProgramAddressSync([REWARD_MINT_SEED], program.programId);
+    const [playerTokenAccount] = PublicKey.findProgramAddressSync(
+      [wallet.publicKey.toBuffer(), TOKEN_PROGRAM_ID.toBuffer(), rewardMint.toBuffer()],
+      ASSOCIATED_TOKEN_PROGRAM_ID
+    );
+    const endWithReward = () =>
+      program.methods.endGameWithReward().accounts({ ...accounts, playerTokenAccount }).rpc();
+    try {
+      await endWithReward();
+      expect.fail("expected endGameWithReward to reject a game that isn't started");
+    } catch (e) {
+      expect(String(e)).to.match(/GameNotPlaying|not in playing/i);
+    }
+
+    await startRun(program, accounts);
+    await program.methods.tick().accounts(accounts).rpc();
+    await endWithReward();
+    const rewarded = await program.account.gameState.fetch(gamePda);
+    expect(rewarded.gameStatus).to.equal(GameStatus.gameOver);
+    expect(rewarded.endFrame.toNumber()).to.equal(rewarded.frameCount.toNumber());
+    expect(rewarded.endCause).to.equal(DeathCause.manual + 1);
+
+    await waitForNextSlot(provider.connection);
+    await endWithReward();
+    const again = await program.account.gameState.fetch(gamePda);
+    expect(again.gameStatus).to.equal(GameStatus.gameOver);
+    expect(again.endFrame.toNumber()).to.equal(rewarded.endFrame.toNumber());
+    expect(again.endCause).to.equal(rewarded.endCause);
+    expect(again.endBirdY).to.equal(rewarded.endBirdY);
+    expect(again.lastUpdate.toNumber()).to.equal(rewarded.lastUpdate.toNumber());
+    await program.methods.resetGame().accounts(accounts).rpc();
+  });
 });
@@ -0,0 +1,97 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import type { FlappyBird } from "../target/types/flappy_bird";
+import { GameStatus } from "./run_request";
+
+const GAME_SEED = Buffer.from("game_v10");
+const CHALLENGE_SEED = Buffer.from("challenge");
+
+describe("Flappy Bird - Challenges", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = anchor.workspace.FlappyBird as Program<FlappyBird>;
+  const challenger = provider.wallet.publicKey;
+
+  const target = Keypair.generate();
+  const targetProgram = new Program<FlappyBird>(
+    program.idl as any,
+    new anchor.AnchorProvider(provider.connection, new anchor.Wallet(target), provider.opts)
+  );
+  const [targetGame] = PublicKey.findProgramAddressSync(
+    [GAME_SEED, target.publicKey.toBuffer(), Buffer.from([0])],
+    program.programId
+  );
+  const challengePda = (seed: anchor.BN) =>
+    PublicKey.findProgramAddressSync(
+      [CHALLENGE_SEED, challenger.toBuffer(), target.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+  const expiry = () => new anchor.BN(Math.floor(Date.now() / 1000) + 3600);
+
+  before(async () => {
+    const sig = await provider.connection.requestAirdrop(target.publicKey, 1_000_000_000);
+    await provider.connection.confirmTransaction(sig, "confirmed");
+    await targetProgram.methods.initialize(0).accounts({ authority: target.publicKey } as any).rpc();
+  });
+
+  it("lets the challenger withdraw a challenge before it's accepted", async () => {
+    const seed = new anchor.BN(Date.now());
+    await program.methods
+      .createChallenge(target.publicKey, new anchor.BN(5), seed, expiry())
+      .accounts({ challenger } as any)
+      .rpc();
+    const challenge = await program.account.challenge.fetch(challengePda(seed));
+    expect(challenge.target.equals(target.publicKey)).to.be.true;
+    expect(challenge.accepted).to.be.false;
+
+    await program.methods.cancelChallenge().accounts({ challenge: challengePda(seed), challenger } as any).rpc();
+    expect(await provider.connection.getAccountInfo(challengePda(seed))).to.be.null;
+  });
+
+  it("starts the target's run on the challenge seed, once", async () => {
+    const seed = new anchor.BN(Date.now() + 1);
+    await program.methods
+      .createChallenge(target.publicKey, new anchor.BN(5), seed, expiry())
+      .accounts({ challenger } as any)
+      .rpc();
+
+    const accounts = { challenge: challengePda(seed), game: targetGame, authority: target.publicKey } as any;
+    await targetProgram.methods.acceptChallenge().accounts(accounts).rpc();
+    const game = await program.account.gameState.fetch(targetGame);
+    expect(game.gameStatus).to.equal(GameStatus.playing);
+    expect(game.runSeed.toString()).to.equal(seed.toString());
+
+    try {
+      await targetProgram.methods.acceptChallenge().accounts(accounts).rpc();
+      expect.fail("expected ChallengeAccepted");
+    } catch (e) {
+      expect(String(e)).to.match(/ChallengeAccepted|already accepted/i);
+    }
+    try {
+      await program.methods.cancelChallenge().accounts({ challenge: challengePda(seed), challenger } as any).rpc();
+      expect.fail("expected ChallengeAccepted");
+    } catch (e) {
+      expect(String(e)).to.match(/ChallengeAccepted|already accepted/i);
+    }
+    try {
+      await program.methods.resolveChallenge().accounts({ challenge: challengePda(seed), game: targetGame } as any).rpc();
+      expect.fail("expected ChallengeNotFinished");
+    } catch (e) {
+      expect(String(e)).to.match(/ChallengeNotFinished|not over/i);
+    }
+  });
+
+  it("rejects challenging yourself", async () => {
+    try {
+      await program.methods
+        .createChallenge(challenger, new anchor.BN(5), new anchor.BN(Date.now()), expiry())
+        .accounts({ challenger } as any)
+        .rpc();
+      expect.fail("expected InvalidChallenge");
+    } catch (e) {
+      expect(String(e)).to.match(/InvalidChallenge|another player/i);
+    }
+  });
+});
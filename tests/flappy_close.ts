@@ -0,0 +1,129 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import type { FlappyBird } from "../target/types/flappy_bird";
+import { GameStatus, startRun } from "./run_request";
+
+const GAME_SEED = Buffer.from("game_v10");
+const HIGH_SCORE_SEED = Buffer.from("highscore");
+const GLOBAL_STATS_SEED = Buffer.from("global_stats");
+const CONFIG_ADMIN = new PublicKey("anshxnbjGiUpsZpnx3c6LrK2vt8zt54vLMvY3C7Locm");
+
+describe("Flappy Bird - Close Game", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = anchor.workspace.FlappyBird as Program<FlappyBird>;
+
+  const player = Keypair.generate();
+  const playerProgram = new Program<FlappyBird>(
+    program.idl as any,
+    new anchor.AnchorProvider(provider.connection, new anchor.Wallet(player), provider.opts)
+  );
+  const [gamePda] = PublicKey.findProgramAddressSync(
+    [GAME_SEED, player.publicKey.toBuffer(), Buffer.from([0])],
+    program.programId
+  );
+  const [recordPda] = PublicKey.findProgramAddressSync(
+    [HIGH_SCORE_SEED, player.publicKey.toBuffer()],
+    program.programId
+  );
+
+  before(async () => {
+    const sig = await provider.connection.requestAirdrop(player.publicKey, 1_000_000_000);
+    await provider.connection.confirmTransaction(sig, "confirmed");
+    await playerProgram.methods
+      .initialize(0)
+      .accounts({ authority: player.publicKey } as any)
+      .rpc();
+  });
+
+  it("refuses to close a game in progress", async () => {
+    await startRun(playerProgram, { game: gamePda, signer: player.publicKey });
+
+    try {
+      await playerProgram.methods
+        .closeGame()
+        .accounts({ game: gamePda, authority: player.publicKey } as any)
+        .rpc();
+      expect.fail("expected GameInProgress");
+    } catch (e) {
+      expect(String(e)).to.match(/GameInProgress|in progress/i);
+    }
+  });
+
+  it("closes a finished game, refunds rent, and initialize starts fresh but keeps the ranked cooldown", async () => {
+    await playerProgram.methods
+      .endGame()
+      .accounts({ game: gamePda, signer: player.publicKey } as any)
+      .rpc();
+    const ended = await program.account.gameState.fetch(gamePda);
+    expect(ended.lastGameOverAt.toNumber()).to.be.greaterThan(0);
+
+    const rent = (await provider.connection.getAccountInfo(gamePda))!.lamports;
+    const balanceBefore = await provider.connection.getBalance(player.publicKey);
+    await playerProgram.methods
+      .closeGame()
+      .accounts({ game: gamePda, authority: player.publicKey } as any)
+      .rpc();
+
+    expect(await provider.connection.getAccountInfo(gamePda)).to.be.null;
+    const balanceAfter = await provider.connection.getBalance(player.publicKey);
+    // Refund minus the transaction fee
+    expect(balanceAfter).to.be.greaterThan(balanceBefore + rent - 10_000);
+
+    await playerProgram.methods
+      .initialize(0)
+      .accounts({ authority: player.publicKey } as any)
+      .rpc();
+    const game = await program.account.gameState.fetch(gamePda);
+    expect(game.gameStatus).to.equal(GameStatus.notStarted);
+    expect(game.highScore.toNumber()).to.equal(0);
+    expect(game.lastGameOverAt.toNumber()).to.equal(0);
+    // The record kept when the closed game's ranked run ended, for the next
+    // ranked start's cooldown
+    const record = await program.account.highScoreRecord.fetch(recordPda);
+    expect(record.lastGameOverAt.toNumber()).to.equal(ended.lastGameOverAt.toNumber());
+    expect(record.gameSlots).to.equal(1);
+  });
+
+  it("counts a player once, whichever slot comes first and however often it's re-created", async function () {
+    const [globalStatsPda] = PublicKey.findProgramAddressSync([GLOBAL_STATS_SEED], program.programId);
+    if (!(await provider.connection.getAccountInfo(globalStatsPda))) {
+      // Only the config admin can create the GlobalStats
+      if (!provider.wallet.publicKey.equals(CONFIG_ADMIN)) {
+        this.skip();
+      }
+      await program.methods
+        .initializeGlobalStats()
+        .accounts({ admin: provider.wallet.publicKey } as any)
+        .rpc();
+    }
+    const uniquePlayers = async () =>
+      (await program.account.globalStats.fetch(globalStatsPda)).uniquePlayers.toNumber();
+    const before = await uniquePlayers();
+
+    await playerProgram.methods
+      .closeGame()
+      .accounts({ game: gamePda, authority: player.publicKey } as any)
+      .rpc();
+    for (const slot of [0, 1]) {
+      await playerProgram.methods
+        .initialize(slot)
+        .accounts({ authority: player.publicKey, globalStats: globalStatsPda } as any)
+        .rpc();
+    }
+    expect(await uniquePlayers()).to.equal(before);
+
+    // A new player whose first game isn't in slot 0
+    const newcomer = Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(newcomer.publicKey, 100_000_000);
+    await provider.connection.confirmTransaction(sig, "confirmed");
+    await program.methods
+      .initialize(2)
+      .accounts({ authority: newcomer.publicKey, globalStats: globalStatsPda } as any)
+      .signers([newcomer])
+      .rpc();
+    expect(await uniquePlayers()).to.equal(before + 1);
+  });
+});
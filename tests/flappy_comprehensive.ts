@@ -20,6 +20,7 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { Connection, Keypair, PublicKey, Transaction, SystemProgram } from "@solana/web3.js";
 import { FlappyBird } from "../target/types/flappy_bird";
+import { DEFAULT_DELEGATE_PARAMS, GameStatus, newRunSecret, startAccounts, waitForNextSlot } from "./run_request";
 import { expect } from "chai";
 
 // ========================================
@@ -33,7 +34,7 @@ const ER_WS_URL = process.env.EPHEMERAL_WS_ENDPOINT || "ws://localhost:7800";
 const LOCAL_ER_VALIDATOR = new PublicKey("mAGicPQYBMvcYveUZA5F5UNNwyHvfYh5xkLS2Fr1mev");
 
 // Game seed - must match program
-const GAME_SEED = Buffer.from("game_v2");
+const GAME_SEED = Buffer.from("game_v10");
 
 // Game constants (must match program)
 const GAME_WIDTH = 600;
@@ -70,7 +71,7 @@ describe("Flappy Bird - Comprehensive Test Suite", () => {
 
     // Derive game PDA
     const [gamePDA] = PublicKey.findProgramAddressSync(
-        [GAME_SEED, wallet.publicKey.toBuffer()],
+        [GAME_SEED, wallet.publicKey.toBuffer(), Buffer.from([0])],
         program.programId
     );
 
@@ -121,6 +122,18 @@ describe("Flappy Bird - Comprehensive Test Suite", () => {
         return txHash;
     };
 
+    // request_game on the ER; returns the reveal for the start_game that follows
+    const requestRunOnER = async (): Promise<anchor.BN> => {
+        const { reveal, commitment } = newRunSecret();
+        const tx = await erProgram.methods
+            .requestGame(commitment)
+            .accounts({ game: gamePDA, signer: wallet.publicKey } as any)
+            .transaction();
+        await sendToER(tx);
+        await waitForNextSlot(erConnection);
+        return reveal;
+    };
+
     // Helper to send transaction with session key
     const sendToERWithSession = async (tx: Transaction, sessionSigner: Keypair): Promise<string> => {
         await new Promise(resolve => setTimeout(resolve, 5 + Math.random() * 10));
@@ -169,7 +182,7 @@ describe("Flappy Bird - Comprehensive Test Suite", () => {
             
             try {
                 const tx = await program.methods
-                    .initialize()
+                    .initialize(0)
                     .accounts({
                         authority: wallet.publicKey,
                     })
@@ -203,9 +216,10 @@ describe("Flappy Bird - Comprehensive Test Suite", () => {
             }
 
             const tx = await program.methods
-                .delegate()
+                .delegate(0, DEFAULT_DELEGATE_PARAMS)
                 .accountsPartial({
                     payer: wallet.publicKey,
+                    authority: wallet.publicKey,
                     pda: gamePDA,
                 })
                 .remainingAccounts([
@@ -239,7 +253,7 @@ describe("Flappy Bird - Comprehensive Test Suite", () => {
         it("should be able to read game from ER", async () => {
             const gameState = await erProgram.account.gameState.fetch(gamePDA);
             expect(gameState).to.not.be.null;
-            console.log("   ✅ Game readable from ER, status:", Object.keys(gameState.gameStatus)[0]);
+            console.log("   ✅ Game readable from ER, status:", gameState.gameStatus);
         });
     });
 
@@ -248,20 +262,18 @@ describe("Flappy Bird - Comprehensive Test Suite", () => {
     // ========================================
     describe("3. Start Game on ER", () => {
         it("should start game and set status to Playing", async () => {
+            const start = await startAccounts(erProgram, { game: gamePDA, signer: wallet.publicKey });
             const tx = await erProgram.methods
-                .startGame()
-                .accounts({
-                    game: gamePDA,
-                    signer: wallet.publicKey,
-                    sessionToken: null,
-                } as any)
+                .startGame({ difficulty: { normal: {} }, reveal: await requestRunOnER(), scoring: { pipesPassed: {} }, horizontalControl: false, wind: null, mode: null, practice: false, lives: 1 })
+                .accounts(start.accounts)
+                .remainingAccounts(start.remainingAccounts)
                 .transaction();
 
             const txHash = await sendToER(tx);
             console.log("   ✅ Start game tx:", txHash.slice(0, 20) + "...");
 
             const gameState = await getGameState();
-            expect(Object.keys(gameState.gameStatus)[0]).to.equal("playing");
+            expect(gameState.gameStatus).to.equal(GameStatus.playing);
             expect(gameState.birdY).to.equal(GAME_HEIGHT / 2 * 1000); // Center position
             console.log("   ✅ Game status: Playing, Bird at center (Y:", gameState.birdY / 1000, ")");
         });
@@ -276,7 +288,7 @@ describe("Flappy Bird - Comprehensive Test Suite", () => {
             const beforeY = beforeState.birdY;
 
             const tx = await erProgram.methods
-                .flap()
+                .flap(true)
                 .accounts({
                     game: gamePDA,
                     signer: wallet.publicKey,
@@ -303,7 +315,7 @@ describe("Flappy Bird - Comprehensive Test Suite", () => {
                 const start = Date.now();
 
                 const tx = await erProgram.methods
-                    .flap()
+                    .flap(true)
                     .accounts({
                         game: gamePDA,
                         signer: wallet.publicKey,
@@ -337,14 +349,12 @@ describe("Flappy Bird - Comprehensive Test Suite", () => {
             let state = await getGameState();
             
             // If not playing, start the game
-            if (state.gameStatus.notStarted || state.gameStatus.gameOver) {
+            if (state.gameStatus === GameStatus.notStarted || state.gameStatus === GameStatus.gameOver) {
+                const start = await startAccounts(erProgram, { game: gamePDA, signer: wallet.publicKey });
                 const startTx = await erProgram.methods
-                    .startGame()
-                    .accounts({
-                        game: gamePDA,
-                        signer: wallet.publicKey,
-                        sessionToken: null,
-                    } as any)
+                    .startGame({ difficulty: { normal: {} }, reveal: await requestRunOnER(), scoring: { pipesPassed: {} }, horizontalControl: false, wind: null, mode: null, practice: false, lives: 1 })
+                    .accounts(start.accounts)
+                    .remainingAccounts(start.remainingAccounts)
                     .transaction();
                 await sendToER(startTx);
                 state = await getGameState();
@@ -384,7 +394,7 @@ describe("Flappy Bird - Comprehensive Test Suite", () => {
             let state = await getGameState();
             
             // If game is over or not started, we need to reset and start
-            if (state.gameStatus.gameOver) {
+            if (state.gameStatus === GameStatus.gameOver) {
                 const resetTx = await erProgram.methods
                     .resetGame()
                     .accounts({
@@ -397,14 +407,12 @@ describe("Flappy Bird - Comprehensive Test Suite", () => {
             }
             
             state = await getGameState();
-            if (state.gameStatus.notStarted) {
+            if (state.gameStatus === GameStatus.notStarted) {
+                const start = await startAccounts(erProgram, { game: gamePDA, signer: wallet.publicKey });
                 const startTx = await erProgram.methods
-                    .startGame()
-                    .accounts({
-                        game: gamePDA,
-                        signer: wallet.publicKey,
-                        sessionToken: null,
-                    } as any)
+                    .startGame({ difficulty: { normal: {} }, reveal: await requestRunOnER(), scoring: { pipesPassed: {} }, horizontalControl: false, wind: null, mode: null, practice: false, lives: 1 })
+                    .accounts(start.accounts)
+                    .remainingAccounts(start.remainingAccounts)
                     .transaction();
                 await sendToER(startTx);
             }
@@ -427,7 +435,7 @@ describe("Flappy Bird - Comprehensive Test Suite", () => {
                     tickCount++;
 
                     const state = await getGameState();
-                    if (Object.keys(state.gameStatus)[0] === "gameOver") {
+                    if (state.gameStatus === GameStatus.gameOver) {
                         gameOver = true;
                         console.log("   ✅ Floor collision detected at tick", tickCount);
                         console.log("   ✅ Bird Y at collision:", state.birdY / 1000);
@@ -443,14 +451,14 @@ describe("Flappy Bird - Comprehensive Test Suite", () => {
             }
 
             const finalState = await getGameState();
-            expect(Object.keys(finalState.gameStatus)[0]).to.equal("gameOver");
+            expect(finalState.gameStatus).to.equal(GameStatus.gameOver);
         });
 
         it("should detect ceiling collision (bird jumps too high)", async () => {
             // Check current state and reset if needed
             let state = await getGameState();
             
-            if (state.gameStatus.gameOver) {
+            if (state.gameStatus === GameStatus.gameOver) {
                 const resetTx = await erProgram.methods
                     .resetGame()
                     .accounts({
@@ -463,14 +471,12 @@ describe("Flappy Bird - Comprehensive Test Suite", () => {
             }
             
             state = await getGameState();
-            if (state.gameStatus.notStarted) {
+            if (state.gameStatus === GameStatus.notStarted) {
+                const start = await startAccounts(erProgram, { game: gamePDA, signer: wallet.publicKey });
                 const startTx = await erProgram.methods
-                    .startGame()
-                    .accounts({
-                        game: gamePDA,
-                        signer: wallet.publicKey,
-                        sessionToken: null,
-                    } as any)
+                    .startGame({ difficulty: { normal: {} }, reveal: await requestRunOnER(), scoring: { pipesPassed: {} }, horizontalControl: false, wind: null, mode: null, practice: false, lives: 1 })
+                    .accounts(start.accounts)
+                    .remainingAccounts(start.remainingAccounts)
                     .transaction();
                 await sendToER(startTx);
             }
@@ -482,7 +488,7 @@ describe("Flappy Bird - Comprehensive Test Suite", () => {
             while (!gameOver && flapCount < 50) {
                 try {
                     const flapTx = await erProgram.methods
-                        .flap()
+                        .flap(true)
                         .accounts({
                             game: gamePDA,
                             signer: wallet.publicKey,
@@ -493,7 +499,7 @@ describe("Flappy Bird - Comprehensive Test Suite", () => {
                     flapCount++;
 
                     const state = await getGameState();
-                    if (Object.keys(state.gameStatus)[0] === "gameOver") {
+                    if (state.gameStatus === GameStatus.gameOver) {
                         gameOver = true;
                         console.log("   ✅ Ceiling collision detected at flap", flapCount);
                         console.log("   ✅ Bird Y at collision:", state.birdY / 1000);
@@ -509,7 +515,7 @@ describe("Flappy Bird - Comprehensive Test Suite", () => {
             }
 
             const finalState = await getGameState();
-            expect(Object.keys(finalState.gameStatus)[0]).to.equal("gameOver");
+            expect(finalState.gameStatus).to.equal(GameStatus.gameOver);
         });
     });
 
@@ -546,13 +552,11 @@ describe("Flappy Bird - Comprehensive Test Suite", () => {
                 .transaction();
             await sendToER(resetTx);
 
+            const start = await startAccounts(erProgram, { game: gamePDA, signer: wallet.publicKey });
             const startTx = await erProgram.methods
-                .startGame()
-                .accounts({
-                    game: gamePDA,
-                    signer: wallet.publicKey,
-                    sessionToken: null,
-                } as any)
+                .startGame({ difficulty: { normal: {} }, reveal: await requestRunOnER(), scoring: { pipesPassed: {} }, horizontalControl: false, wind: null, mode: null, practice: false, lives: 1 })
+                .accounts(start.accounts)
+                .remainingAccounts(start.remainingAccounts)
                 .transaction();
             await sendToER(startTx);
 
@@ -565,7 +569,7 @@ describe("Flappy Bird - Comprehensive Test Suite", () => {
 
                 try {
                     const tx = await erProgram.methods
-                        .flap()
+                        .flap(true)
                         .accounts({
                             game: gamePDA,
                             signer: wallet.publicKey,
@@ -607,9 +611,10 @@ describe("Flappy Bird - Comprehensive Test Suite", () => {
         it("should commit game state to base layer", async () => {
             try {
                 const tx = await erProgram.methods
-                    .commit()
+                    .commit(0)
                     .accounts({
                         payer: wallet.publicKey,
+                        authority: wallet.publicKey,
                     } as any)
                     .transaction();
 
@@ -623,9 +628,10 @@ describe("Flappy Bird - Comprehensive Test Suite", () => {
         it("should undelegate and return to base layer", async () => {
             try {
                 const tx = await erProgram.methods
-                    .undelegate()
+                    .undelegate(0, true)
                     .accounts({
                         payer: wallet.publicKey,
+                        authority: wallet.publicKey,
                     } as any)
                     .transaction();
 
@@ -0,0 +1,147 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Keypair, PublicKey, SYSVAR_INSTRUCTIONS_PUBKEY, SYSVAR_SLOT_HASHES_PUBKEY } from "@solana/web3.js";
+import type { FlappyBird } from "../target/types/flappy_bird";
+import type { QuestExample } from "../target/types/quest_example";
+import { GameStatus, newRunSecret, waitForNextSlot } from "./run_request";
+
+const GAME_SEED = Buffer.from("game_v10");
+const CONFIG_SEED = Buffer.from("config_v2");
+const CPI_ALLOWLIST_SEED = Buffer.from("cpi_allowlist");
+const HIGH_SCORE_SEED = Buffer.from("highscore");
+const CONFIG_ADMIN = new PublicKey("anshxnbjGiUpsZpnx3c6LrK2vt8zt54vLMvY3C7Locm");
+
+describe("Flappy Bird - CPI", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = anchor.workspace.FlappyBird as Program<FlappyBird>;
+  const quest = anchor.workspace.QuestExample as Program<QuestExample>;
+  const wallet = provider.wallet as anchor.Wallet;
+
+  const [allowlistPda] = PublicKey.findProgramAddressSync([CPI_ALLOWLIST_SEED], program.programId);
+  const [configPda] = PublicKey.findProgramAddressSync([CONFIG_SEED], program.programId);
+
+  const player = Keypair.generate();
+  const questAsPlayer = new Program<QuestExample>(
+    quest.idl as any,
+    new anchor.AnchorProvider(provider.connection, new anchor.Wallet(player), provider.opts)
+  );
+  const [gamePda] = PublicKey.findProgramAddressSync(
+    [GAME_SEED, player.publicKey.toBuffer(), Buffer.from([0])],
+    program.programId
+  );
+  const [highScoreRecord] = PublicKey.findProgramAddressSync(
+    [HIGH_SCORE_SEED, player.publicKey.toBuffer()],
+    program.programId
+  );
+  const questAccounts = {
+    game: gamePda,
+    player: player.publicKey,
+    allowlist: allowlistPda,
+    instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+    flappyBird: program.programId,
+  } as any;
+
+  before(async () => {
+    if (!(await provider.connection.getAccountInfo(allowlistPda))) {
+      await program.methods
+        .initializeCpiAllowlist()
+        .accounts({ payer: wallet.publicKey } as any)
+        .rpc();
+    }
+    const sig = await provider.connection.requestAirdrop(player.publicKey, 1_000_000_000);
+    await provider.connection.confirmTransaction(sig, "confirmed");
+  });
+
+  it("creates and starts a game through another program", async () => {
+    await questAsPlayer.methods
+      .join(0)
+      .accounts({ game: gamePda, player: player.publicKey, highScoreRecord, flappyBird: program.programId } as any)
+      .rpc();
+
+    const { reveal, commitment } = newRunSecret();
+    await questAsPlayer.methods
+      .requestRun(commitment)
+      .accounts({ game: gamePda, player: player.publicKey, flappyBird: program.programId } as any)
+      .rpc();
+    await waitForNextSlot(provider.connection);
+    await questAsPlayer.methods
+      .startRun({ difficulty: { normal: {} }, reveal, scoring: { pipesPassed: {} }, horizontalControl: false, wind: null, mode: null, practice: false, lives: 1 })
+      .accounts({
+        game: gamePda,
+        player: player.publicKey,
+        slotHashes: SYSVAR_SLOT_HASHES_PUBKEY,
+        config: configPda,
+        highScoreRecord,
+        flappyBird: program.programId,
+      } as any)
+      .rpc();
+
+    const game = await program.account.gameState.fetch(gamePda);
+    expect(game.authority.equals(player.publicKey)).to.be.true;
+    expect(game.gameStatus).to.equal(GameStatus.playing);
+  });
+
+  it("rejects awardExternalBonus called directly", async () => {
+    try {
+      await program.methods
+        .awardExternalBonus(5)
+        .accounts({ game: gamePda, signer: player.publicKey, allowlist: allowlistPda, instructions: SYSVAR_INSTRUCTIONS_PUBKEY } as any)
+        .signers([player])
+        .rpc();
+      expect.fail("expected CallerNotAllowed");
+    } catch (e) {
+      expect(String(e)).to.match(/CallerNotAllowed|not on the CPI allowlist/i);
+    }
+  });
+
+  it("rejects a bonus from a program that is not on the allowlist", async () => {
+    if (wallet.publicKey.equals(CONFIG_ADMIN)) {
+      await program.methods
+        .setCpiCaller(quest.programId, false)
+        .accounts({ allowlist: allowlistPda, admin: wallet.publicKey } as any)
+        .rpc();
+    }
+    try {
+      await questAsPlayer.methods.completeQuest(5).accounts(questAccounts).rpc();
+      expect.fail("expected CallerNotAllowed");
+    } catch (e) {
+      expect(String(e)).to.match(/CallerNotAllowed|not on the CPI allowlist/i);
+    }
+  });
+
+  it("awards the bonus once the admin allows the program, apart from the score and only to the player", async function () {
+    // Only the config admin can list programs
+    if (!wallet.publicKey.equals(CONFIG_ADMIN)) {
+      this.skip();
+    }
+    await program.methods
+      .setCpiCaller(quest.programId, true)
+      .accounts({ allowlist: allowlistPda, admin: wallet.publicKey } as any)
+      .rpc();
+
+    const before = await program.account.gameState.fetch(gamePda);
+    await questAsPlayer.methods.completeQuest(5).accounts(questAccounts).rpc();
+    const after = await program.account.gameState.fetch(gamePda);
+    expect(after.bonusScore.toNumber()).to.equal(before.bonusScore.toNumber() + 5);
+    expect(after.score.toNumber()).to.equal(before.score.toNumber());
+
+    // Someone else can't award the player's run a bonus
+    try {
+      await quest.methods.completeQuest(5).accounts({ ...questAccounts, player: wallet.publicKey }).rpc();
+      expect.fail("expected NotGameAuthority");
+    } catch (e) {
+      expect(String(e)).to.match(/NotGameAuthority|game's authority/i);
+    }
+
+    // Ending the run leaves the bonus out of the high score
+    await program.methods
+      .endGame()
+      .accounts({ game: gamePda, signer: player.publicKey } as any)
+      .signers([player])
+      .rpc();
+    const ended = await program.account.gameState.fetch(gamePda);
+    expect(ended.highScore.toNumber()).to.equal(ended.score.toNumber());
+  });
+});
@@ -0,0 +1,445 @@
+/**
+ * Delegation lifecycle tests
+ *
+ * The base-layer half runs under plain `anchor test`: Anchor.toml clones the
+ * delegation program into the local validator, so delegate is exercised for
+ * real. Commit/undelegate are executed by the ER validator and need a local ER
+ * (same opt-in as flappy_comprehensive.ts):
+ *
+ * RUN_ER_TESTS=1 \
+ * EPHEMERAL_PROVIDER_ENDPOINT="http://localhost:7799" \
+ * EPHEMERAL_WS_ENDPOINT="ws://localhost:7800" \
+ * anchor test --provider.cluster localnet --skip-local-validator --skip-build --skip-deploy
+ *
+ * recover_stale_game is not covered: the instruction does not exist yet.
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Connection, Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import type { FlappyBird } from "../target/types/flappy_bird";
+import { DEFAULT_DELEGATE_PARAMS, GameStatus, startRun } from "./run_request";
+
+const GAME_SEED = Buffer.from("game_v10");
+
+const DELEGATION_PROGRAM_ID = new PublicKey("DELeGGvXpWV2fqJUhqcF5ZSYMS4JTLjteaAMARRSaeSh");
+const LOCAL_ER_VALIDATOR = new PublicKey("mAGicPQYBMvcYveUZA5F5UNNwyHvfYh5xkLS2Fr1mev");
+const ER_URL = process.env.EPHEMERAL_PROVIDER_ENDPOINT || "http://localhost:7799";
+const ER_WS_URL = process.env.EPHEMERAL_WS_ENDPOINT || "ws://localhost:7800";
+
+describe("Flappy Bird - Delegation Lifecycle", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = anchor.workspace.FlappyBird as Program<FlappyBird>;
+
+  // Each suite plays with its own fresh wallet so it never disturbs the
+  // provider wallet's game used by the other test files.
+  const newPlayer = async () => {
+    const player = Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(player.publicKey, 2_000_000_000);
+    await provider.connection.confirmTransaction(sig, "confirmed");
+    const playerProvider = new anchor.AnchorProvider(
+      provider.connection,
+      new anchor.Wallet(player),
+      provider.opts
+    );
+    const playerProgram = new Program<FlappyBird>(program.idl as any, playerProvider);
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [GAME_SEED, player.publicKey.toBuffer(), Buffer.from([0])],
+      program.programId
+    );
+    await playerProgram.methods
+      .initialize(0)
+      .accounts({ authority: player.publicKey } as any)
+      .rpc();
+    return { player, playerProgram, gamePda };
+  };
+
+  const delegationPdas = (gamePda: PublicKey) => {
+    const [buffer] = PublicKey.findProgramAddressSync(
+      [Buffer.from("buffer"), gamePda.toBuffer()],
+      program.programId
+    );
+    const [record] = PublicKey.findProgramAddressSync(
+      [Buffer.from("delegation"), gamePda.toBuffer()],
+      DELEGATION_PROGRAM_ID
+    );
+    const [metadata] = PublicKey.findProgramAddressSync(
+      [Buffer.from("delegation-metadata"), gamePda.toBuffer()],
+      DELEGATION_PROGRAM_ID
+    );
+    return { buffer, record, metadata };
+  };
+
+  const delegateIx = (playerProgram: Program<FlappyBird>, player: Keypair, gamePda: PublicKey) =>
+    playerProgram.methods
+      .delegate(0, DEFAULT_DELEGATE_PARAMS)
+      .accountsPartial({
+        payer: player.publicKey,
+        authority: player.publicKey,
+        pda: gamePda,
+      })
+      .remainingAccounts([
+        { pubkey: LOCAL_ER_VALIDATOR, isSigner: false, isWritable: false },
+      ]);
+
+  describe("base layer", () => {
+    it("delegate builds the expected CPI accounts and data", async () => {
+      const { player, playerProgram, gamePda } = await newPlayer();
+      const ix = await delegateIx(playerProgram, player, gamePda).instruction();
+      const { buffer, record, metadata } = delegationPdas(gamePda);
+
+      const discriminator = (program.idl as any).instructions.find(
+        (i: any) => i.name === "delegate"
+      ).discriminator;
+      // Followed by the slot argument and the params, all unset
+      expect([...ix.data]).to.deep.equal([...discriminator, 0, 0, 0, 0]);
+
+      const metas = ix.keys.map((k) => ({
+        key: k.pubkey.toBase58(),
+        signer: k.isSigner,
+        writable: k.isWritable,
+      }));
+      expect(metas).to.deep.equal([
+        { key: player.publicKey.toBase58(), signer: true, writable: false },
+        { key: player.publicKey.toBase58(), signer: true, writable: false },
+        { key: buffer.toBase58(), signer: false, writable: true },
+        { key: record.toBase58(), signer: false, writable: true },
+        { key: metadata.toBase58(), signer: false, writable: true },
+        { key: gamePda.toBase58(), signer: false, writable: true },
+        { key: program.programId.toBase58(), signer: false, writable: false },
+        { key: DELEGATION_PROGRAM_ID.toBase58(), signer: false, writable: false },
+        { key: SystemProgram.programId.toBase58(), signer: false, writable: false },
+        { key: LOCAL_ER_VALIDATOR.toBase58(), signer: false, writable: false },
+      ]);
+    });
+
+    it("initialize -> delegate hands the account to the delegation program", async () => {
+      const { player, playerProgram, gamePda } = await newPlayer();
+      const before = await provider.connection.getAccountInfo(gamePda);
+      expect(before?.owner.toBase58()).to.equal(program.programId.toBase58());
+
+      await delegateIx(playerProgram, player, gamePda).rpc();
+
+      const after = await provider.connection.getAccountInfo(gamePda);
+      expect(after?.owner.toBase58()).to.equal(DELEGATION_PROGRAM_ID.toBase58());
+      // Game data travels with the account, with only the delegation recorded
+      const { isDelegated: wasDelegated, delegation: _, ...kept } = program.coder.accounts.decode("gameState", before!.data);
+      const { isDelegated, delegation, ...carried } = program.coder.accounts.decode("gameState", after!.data);
+      expect(JSON.stringify(carried)).to.equal(JSON.stringify(kept));
+      expect([wasDelegated, isDelegated]).to.deep.equal([0, 1]);
+      expect(delegation.validator.toBase58()).to.equal(LOCAL_ER_VALIDATOR.toBase58());
+
+      // Delegation record names the requested validator
+      const { record } = delegationPdas(gamePda);
+      const recordInfo = await provider.connection.getAccountInfo(record);
+      expect(recordInfo?.owner.toBase58()).to.equal(DELEGATION_PROGRAM_ID.toBase58());
+      expect(new PublicKey(recordInfo!.data.subarray(8, 40)).toBase58()).to.equal(
+        LOCAL_ER_VALIDATOR.toBase58()
+      );
+    });
+
+    it("another wallet can pay for delegating the player's game", async () => {
+      const { player, gamePda } = await newPlayer();
+      const balance = await provider.connection.getBalance(player.publicKey);
+      await program.methods
+        .delegate(0, DEFAULT_DELEGATE_PARAMS)
+        .accountsPartial({ payer: provider.wallet.publicKey, authority: player.publicKey, pda: gamePda })
+        .remainingAccounts([{ pubkey: LOCAL_ER_VALIDATOR, isSigner: false, isWritable: false }])
+        .signers([player])
+        .rpc();
+
+      const info = await provider.connection.getAccountInfo(gamePda);
+      expect(info?.owner.toBase58()).to.equal(DELEGATION_PROGRAM_ID.toBase58());
+      expect(await provider.connection.getBalance(player.publicKey)).to.equal(balance);
+    });
+
+    it("rejects delegating an already delegated game", async () => {
+      const { player, playerProgram, gamePda } = await newPlayer();
+      await delegateIx(playerProgram, player, gamePda).rpc();
+
+      let rejected = false;
+      try {
+        await delegateIx(playerProgram, player, gamePda).rpc();
+      } catch {
+        rejected = true;
+      }
+      expect(rejected, "second delegate must fail").to.be.true;
+      const info = await provider.connection.getAccountInfo(gamePda);
+      expect(info?.owner.toBase58()).to.equal(DELEGATION_PROGRAM_ID.toBase58());
+    });
+
+    it("close_game refuses a delegated game", async () => {
+      const { player, playerProgram, gamePda } = await newPlayer();
+      await delegateIx(playerProgram, player, gamePda).rpc();
+
+      try {
+        await playerProgram.methods
+          .closeGame()
+          .accounts({ game: gamePda, authority: player.publicKey } as any)
+          .rpc();
+        expect.fail("expected close to fail while delegated");
+      } catch (e) {
+        expect(String(e)).to.match(/AccountOwnedByWrongProgram|owned by a different program/i);
+      }
+      const info = await provider.connection.getAccountInfo(gamePda);
+      expect(info?.owner.toBase58()).to.equal(DELEGATION_PROGRAM_ID.toBase58());
+    });
+
+    it("rejects commit on a game that is not delegated", async () => {
+      const { player, playerProgram, gamePda } = await newPlayer();
+      const before = await program.account.gameState.fetch(gamePda);
+
+      let rejected = false;
+      try {
+        await playerProgram.methods
+          .commit(0)
+          .accounts({ payer: player.publicKey, authority: player.publicKey } as any)
+          .rpc();
+      } catch {
+        rejected = true;
+      }
+      expect(rejected, "commit outside the ER must fail").to.be.true;
+
+      const info = await provider.connection.getAccountInfo(gamePda);
+      expect(info?.owner.toBase58()).to.equal(program.programId.toBase58());
+      const after = await program.account.gameState.fetch(gamePda);
+      expect(after.frameCount.toNumber()).to.equal(before.frameCount.toNumber());
+    });
+  });
+
+  describe("ephemeral rollup", () => {
+    // Opt-in: requires a local ER setup and MagicBlock components.
+    if (process.env.RUN_ER_TESTS !== "1") {
+      console.log("\n[skip] ER delegation tests: set RUN_ER_TESTS=1 to run them\n");
+      return;
+    }
+
+    const erConnection = new Connection(ER_URL, { wsEndpoint: ER_WS_URL, commitment: "confirmed" });
+
+    const onEr = (player: Keypair) =>
+      new Program<FlappyBird>(
+        program.idl as any,
+        new anchor.AnchorProvider(erConnection, new anchor.Wallet(player), {
+          commitment: "confirmed",
+          skipPreflight: true,
+        })
+      );
+
+    const waitFor = async (check: () => Promise<boolean>, what: string) => {
+      for (let i = 0; i < 30; i++) {
+        if (await check()) return;
+        await new Promise((resolve) => setTimeout(resolve, 500));
+      }
+      expect.fail(`timed out waiting for ${what}`);
+    };
+
+    it("commit while delegated, then undelegate carries the final state home", async () => {
+      const { player, playerProgram, gamePda } = await newPlayer();
+      await delegateIx(playerProgram, player, gamePda).rpc();
+      const erProgram = onEr(player);
+      await waitFor(
+        async () => (await erConnection.getAccountInfo(gamePda)) !== null,
+        "the ER to clone the game"
+      );
+
+      await startRun(erProgram, { game: gamePda, signer: player.publicKey });
+      for (let i = 0; i < 3; i++) {
+        await erProgram.methods
+          .tick()
+          .accounts({ game: gamePda, signer: player.publicKey } as any)
+          .rpc();
+      }
+      const played = await erProgram.account.gameState.fetch(gamePda);
+      expect(played.frameCount.toNumber()).to.be.greaterThan(0);
+
+      await erProgram.methods
+        .commit(0)
+        .accounts({ payer: player.publicKey, authority: player.publicKey } as any)
+        .rpc();
+      await waitFor(async () => {
+        const base = await program.account.gameState.fetch(gamePda);
+        return base.frameCount.eq(played.frameCount);
+      }, "the commit to reach the base layer");
+      // Still delegated after a plain commit, and the committed state says so
+      const info = await provider.connection.getAccountInfo(gamePda);
+      expect(info?.owner.toBase58()).to.equal(DELEGATION_PROGRAM_ID.toBase58());
+      expect(program.coder.accounts.decode("gameState", info!.data).isDelegated).to.equal(1);
+
+      await erProgram.methods
+        .endGame()
+        .accounts({ game: gamePda, signer: player.publicKey } as any)
+        .rpc();
+      const final = await erProgram.account.gameState.fetch(gamePda);
+
+      await erProgram.methods
+        .undelegate(0, false)
+        .accounts({ payer: player.publicKey, authority: player.publicKey } as any)
+        .rpc();
+      await waitFor(async () => {
+        const acc = await provider.connection.getAccountInfo(gamePda);
+        return acc?.owner.equals(program.programId) ?? false;
+      }, "undelegation");
+
+      const base = await program.account.gameState.fetch(gamePda);
+      expect(base.gameStatus).to.equal(GameStatus.gameOver);
+      expect(base.frameCount.toNumber()).to.equal(final.frameCount.toNumber());
+      expect(base.highScore.toNumber()).to.equal(final.highScore.toNumber());
+      expect(base.isDelegated).to.equal(0);
+    });
+
+    it("delegate params are stored on the game and kept after undelegating", async () => {
+      const { player, playerProgram, gamePda } = await newPlayer();
+      await playerProgram.methods
+        .delegate(0, { validator: LOCAL_ER_VALIDATOR, commitFrequencyMs: 10_000, timeLimit: new anchor.BN(3_600) })
+        .accountsPartial({ payer: player.publicKey, authority: player.publicKey, pda: gamePda })
+        .rpc();
+      const expectParams = (data: Buffer) => {
+        const { delegation } = program.coder.accounts.decode("gameState", data);
+        expect(delegation.validator.toBase58()).to.equal(LOCAL_ER_VALIDATOR.toBase58());
+        expect(delegation.commitFrequencyMs).to.equal(10_000);
+        expect(delegation.timeLimit.toNumber()).to.equal(3_600);
+      };
+      expectParams((await provider.connection.getAccountInfo(gamePda))!.data);
+
+      await waitFor(
+        async () => (await erConnection.getAccountInfo(gamePda)) !== null,
+        "the ER to clone the game"
+      );
+      await onEr(player).methods
+        .undelegate(0, false)
+        .accounts({ payer: player.publicKey, authority: player.publicKey } as any)
+        .rpc();
+      await waitFor(async () => {
+        const acc = await provider.connection.getAccountInfo(gamePda);
+        return acc?.owner.equals(program.programId) ?? false;
+      }, "undelegation");
+      const base = await provider.connection.getAccountInfo(gamePda);
+      expectParams(base!.data);
+      expect(program.coder.accounts.decode("gameState", base!.data).isDelegated).to.equal(0);
+    });
+
+    it("undelegate refuses a run in progress unless forced", async () => {
+      const { player, playerProgram, gamePda } = await newPlayer();
+      await delegateIx(playerProgram, player, gamePda).rpc();
+      const erProgram = onEr(player);
+      await waitFor(
+        async () => (await erConnection.getAccountInfo(gamePda)) !== null,
+        "the ER to clone the game"
+      );
+      await startRun(erProgram, { game: gamePda, signer: player.publicKey });
+
+      try {
+        await erProgram.methods
+          .undelegate(0, false)
+          .accounts({ payer: player.publicKey, authority: player.publicKey } as any)
+          .rpc();
+        expect.fail("expected GameInProgress");
+      } catch (e) {
+        expect(String(e)).to.match(/GameInProgress|in progress/i);
+      }
+
+      await erProgram.methods
+        .undelegate(0, true)
+        .accounts({ payer: player.publicKey, authority: player.publicKey } as any)
+        .rpc();
+      await waitFor(async () => {
+        const acc = await provider.connection.getAccountInfo(gamePda);
+        return acc?.owner.equals(program.programId) ?? false;
+      }, "undelegation");
+      const base = await program.account.gameState.fetch(gamePda);
+      expect(base.gameStatus).to.equal(GameStatus.gameOver);
+    });
+
+    it("end_and_commit finishes the run and commits it in one transaction", async () => {
+      const { player, playerProgram, gamePda } = await newPlayer();
+      await delegateIx(playerProgram, player, gamePda).rpc();
+      const erProgram = onEr(player);
+      await waitFor(
+        async () => (await erConnection.getAccountInfo(gamePda)) !== null,
+        "the ER to clone the game"
+      );
+
+      try {
+        await erProgram.methods
+          .endAndCommit(0)
+          .accounts({ payer: player.publicKey, authority: player.publicKey } as any)
+          .rpc();
+        expect.fail("expected end_and_commit to reject a game that isn't started");
+      } catch (e) {
+        expect(String(e)).to.match(/GameNotPlaying|not in playing/i);
+      }
+
+      await startRun(erProgram, { game: gamePda, signer: player.publicKey });
+      await erProgram.methods
+        .tick()
+        .accounts({ game: gamePda, signer: player.publicKey } as any)
+        .rpc();
+      await erProgram.methods
+        .endAndCommit(0)
+        .accounts({ payer: player.publicKey, authority: player.publicKey } as any)
+        .rpc();
+      const final = await erProgram.account.gameState.fetch(gamePda);
+      expect(final.gameStatus).to.equal(GameStatus.gameOver);
+
+      await waitFor(async () => {
+        const base = await program.account.gameState.fetch(gamePda);
+        return base.gameStatus === GameStatus.gameOver && base.frameCount.eq(final.frameCount);
+      }, "the committed game over to reach the base layer");
+
+      // Calling it again changes nothing and skips the commit, but still succeeds
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const sig = await erProgram.methods
+        .endAndCommit(0)
+        .accounts({ payer: player.publicKey, authority: player.publicKey } as any)
+        .rpc();
+      let tx = null;
+      for (let i = 0; i < 30 && !tx; i++) {
+        tx = await erConnection.getTransaction(sig, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+        if (!tx) await new Promise((resolve) => setTimeout(resolve, 500));
+      }
+      const events = [...parser.parseLogs(tx!.meta!.logMessages!)].map((event) => event.name);
+      expect(events).to.include("nothingToCommit");
+      expect(events).to.not.include("committed");
+      const again = await erProgram.account.gameState.fetch(gamePda);
+      expect(again.highScore.toNumber()).to.equal(final.highScore.toNumber());
+    });
+
+    it("a second commit with nothing changed skips the commit", async () => {
+      const { player, playerProgram, gamePda } = await newPlayer();
+      await delegateIx(playerProgram, player, gamePda).rpc();
+      const erProgram = onEr(player);
+      await waitFor(
+        async () => (await erConnection.getAccountInfo(gamePda)) !== null,
+        "the ER to clone the game"
+      );
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const commitEvents = async () => {
+        const sig = await erProgram.methods
+          .commit(0)
+          .accounts({ payer: player.publicKey, authority: player.publicKey } as any)
+          .rpc();
+        let tx = null;
+        for (let i = 0; i < 30 && !tx; i++) {
+          tx = await erConnection.getTransaction(sig, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+          if (!tx) await new Promise((resolve) => setTimeout(resolve, 500));
+        }
+        return [...parser.parseLogs(tx!.meta!.logMessages!)].map((event) => event.name);
+      };
+
+      await startRun(erProgram, { game: gamePda, signer: player.publicKey });
+      expect(await commitEvents()).to.include("committed");
+      const second = await commitEvents();
+      expect(second).to.include("nothingToCommit");
+      expect(second).to.not.include("committed");
+
+      // A tick is a change, so the next commit goes through again
+      await erProgram.methods
+        .tick()
+        .accounts({ game: gamePda, signer: player.publicKey } as any)
+        .rpc();
+      expect(await commitEvents()).to.include("committed");
+    });
+  });
+});
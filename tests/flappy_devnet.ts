@@ -3,6 +3,7 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { Connection, Keypair, PublicKey, Transaction, SystemProgram } from "@solana/web3.js";
 import { FlappyBird } from "../target/types/flappy_bird";
+import { DEFAULT_DELEGATE_PARAMS, GameStatus, newRunSecret, startAccounts, waitForNextSlot } from "./run_request";
 import { expect } from "chai";
 import { readFileSync } from "fs";
 
@@ -17,7 +18,7 @@ const ER_WS_URL = "wss://devnet-as.magicblock.app";
 const ER_VALIDATOR = new PublicKey("MAS1Dt9qreoRMQ14YQuhg8UTZMMzDdKhmkZMECCzk57");
 
 // Game seed - must match program
-const GAME_SEED = Buffer.from("game_v2");
+const GAME_SEED = Buffer.from("game_v10");
 
 const IDL = JSON.parse(readFileSync("./app/src/idl/flappy_bird.json", "utf-8"));
 
@@ -63,7 +64,7 @@ describe("Flappy Bird - DEVNET Verification", () => {
 
     // Derive game PDA
     const [gamePDA] = PublicKey.findProgramAddressSync(
-        [GAME_SEED, wallet.publicKey.toBuffer()],
+        [GAME_SEED, wallet.publicKey.toBuffer(), Buffer.from([0])],
         PROGRAM_ID
     );
 
@@ -91,6 +92,18 @@ describe("Flappy Bird - DEVNET Verification", () => {
         return sig;
     };
 
+    // request_game on the ER; returns the reveal for the start_game that follows
+    const requestRunOnER = async (): Promise<anchor.BN> => {
+        const { reveal, commitment } = newRunSecret();
+        const tx = await erProgram.methods
+            .requestGame(commitment)
+            .accounts({ game: gamePDA, signer: wallet.publicKey } as any)
+            .transaction();
+        await sendToER(tx);
+        await waitForNextSlot(erConnection);
+        return reveal;
+    };
+
     it("1. Initialize (Base Layer)", async () => {
         // Check if exists
         const info = await baseConnection.getAccountInfo(gamePDA);
@@ -99,7 +112,7 @@ describe("Flappy Bird - DEVNET Verification", () => {
         } else {
             console.log("   ℹ️ Initializing...");
             const tx = await program.methods
-                .initialize()
+                .initialize(0)
                 .accounts({
                     authority: wallet.publicKey,
                 } as any)
@@ -118,9 +131,10 @@ describe("Flappy Bird - DEVNET Verification", () => {
         } else {
             console.log("   ℹ️ Delegating...");
             const tx = await program.methods
-                .delegate()
+                .delegate(0, DEFAULT_DELEGATE_PARAMS)
                 .accounts({
                     payer: wallet.publicKey,
+                    authority: wallet.publicKey,
                 } as any)
                 .remainingAccounts([{ pubkey: ER_VALIDATOR, isSigner: false, isWritable: false }])
                 .rpc();
@@ -143,13 +157,11 @@ describe("Flappy Bird - DEVNET Verification", () => {
         if (!synced) throw new Error("Account not synced to ER");
 
         console.log("   ℹ️ Starting Game...");
+        const start = await startAccounts(erProgram, { game: gamePDA, signer: wallet.publicKey });
         const tx = await erProgram.methods
-            .startGame()
-            .accounts({
-                game: gamePDA,
-                signer: wallet.publicKey,
-                sessionToken: null
-            } as any)
+            .startGame({ difficulty: { normal: {} }, reveal: await requestRunOnER(), scoring: { pipesPassed: {} }, horizontalControl: false, wind: null, mode: null, practice: false, lives: 1 })
+            .accounts(start.accounts)
+            .remainingAccounts(start.remainingAccounts)
             .transaction();
 
         const sig = await sendToER(tx);
@@ -158,13 +170,13 @@ describe("Flappy Bird - DEVNET Verification", () => {
         const state = await erProgram.account.gameState.fetch(gamePDA);
         // GameStatus::Playing is enum variant 1 (NotStarted=0, Playing=1, GameOver=2)
         // Anchor enums form: { playing: {} }
-        expect(state.gameStatus).to.have.property("playing");
+        expect(state.gameStatus).to.equal(GameStatus.playing);
     });
 
     it("4. Flap (ER) - Main Wallet", async () => {
         console.log("   ℹ️ Flapping...");
         const tx = await erProgram.methods
-            .flap()
+            .flap(true)
             .accounts({
                 game: gamePDA,
                 signer: wallet.publicKey,
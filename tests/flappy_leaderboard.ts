@@ -0,0 +1,212 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import type { FlappyBird } from "../target/types/flappy_bird";
+import { DEFAULT_DELEGATE_PARAMS, startRun } from "./run_request";
+
+const GAME_SEED = Buffer.from("game_v10");
+const LEADERBOARD_SEED = Buffer.from("leaderboard_v3");
+const WEEKLY_SEED = Buffer.from("weekly");
+const HIGH_SCORE_SEED = Buffer.from("highscore");
+
+describe("Flappy Bird - Leaderboard", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = anchor.workspace.FlappyBird as Program<FlappyBird>;
+
+  const [leaderboardPda] = PublicKey.findProgramAddressSync(
+    [LEADERBOARD_SEED],
+    program.programId
+  );
+
+  const weeklyPda = (week: number) =>
+    PublicKey.findProgramAddressSync(
+      [WEEKLY_SEED, new anchor.BN(week).toArrayLike(Buffer, "le", 4)],
+      program.programId
+    )[0];
+  const currentWeek = async () => (await program.methods.currentWeek().view()) as number;
+  const recordPda = (player: PublicKey) =>
+    PublicKey.findProgramAddressSync([HIGH_SCORE_SEED, player.toBuffer()], program.programId)[0];
+  const syncHighScore = (playerProgram: Program<FlappyBird>, player: Keypair) =>
+    playerProgram.methods
+      .syncHighScore(0)
+      .accounts({ authority: player.publicKey } as any)
+      .rpc();
+
+  const newPlayer = async () => {
+    const player = Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(player.publicKey, 1_000_000_000);
+    await provider.connection.confirmTransaction(sig, "confirmed");
+    const playerProgram = new Program<FlappyBird>(
+      program.idl as any,
+      new anchor.AnchorProvider(provider.connection, new anchor.Wallet(player), provider.opts)
+    );
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [GAME_SEED, player.publicKey.toBuffer(), Buffer.from([0])],
+      program.programId
+    );
+    await playerProgram.methods
+      .initialize(0)
+      .accounts({ authority: player.publicKey } as any)
+      .rpc();
+    return { player, playerProgram, gamePda };
+  };
+
+  before(async () => {
+    const existing = await provider.connection.getAccountInfo(leaderboardPda);
+    if (!existing) {
+      await program.methods
+        .initializeLeaderboard()
+        .accounts({ payer: provider.wallet.publicKey } as any)
+        .rpc();
+    }
+  });
+
+  it("rejects a player whose record has no finished run", async () => {
+    const { player, playerProgram, gamePda } = await newPlayer();
+    await startRun(playerProgram, { game: gamePda, signer: player.publicKey });
+    await syncHighScore(playerProgram, player);
+
+    try {
+      await playerProgram.methods
+        .submitScore(0, await currentWeek())
+        .accounts({ game: gamePda, authority: player.publicKey, seasonLeaderboard: null, distanceLeaderboard: null } as any)
+        .rpc();
+      expect.fail("expected NoHighScore");
+    } catch (e) {
+      expect(String(e)).to.match(/NoHighScore|No high score/i);
+    }
+  });
+
+  it("rejects a run that has not ended", async () => {
+    const { player, playerProgram, gamePda } = await newPlayer();
+    await startRun(playerProgram, { game: gamePda, signer: player.publicKey });
+    await playerProgram.methods
+      .endGame()
+      .accounts({ game: gamePda, signer: player.publicKey } as any)
+      .rpc();
+    await syncHighScore(playerProgram, player);
+    await startRun(playerProgram, { game: gamePda, signer: player.publicKey });
+
+    try {
+      await playerProgram.methods
+        .submitScore(0, await currentWeek())
+        .accounts({ game: gamePda, authority: player.publicKey, seasonLeaderboard: null, distanceLeaderboard: null } as any)
+        .rpc();
+      expect.fail("expected GameNotOver");
+    } catch (e) {
+      expect(String(e)).to.match(/GameNotOver|not over/i);
+    }
+  });
+
+  it("rejects a score while its game is delegated", async () => {
+    const { player, playerProgram, gamePda } = await newPlayer();
+    await startRun(playerProgram, { game: gamePda, signer: player.publicKey });
+    await playerProgram.methods
+      .endGame()
+      .accounts({ game: gamePda, signer: player.publicKey } as any)
+      .rpc();
+    await syncHighScore(playerProgram, player);
+    await playerProgram.methods
+      .delegate(0, DEFAULT_DELEGATE_PARAMS)
+      .accountsPartial({ payer: player.publicKey, authority: player.publicKey, pda: gamePda })
+      .rpc();
+
+    try {
+      await playerProgram.methods
+        .submitScore(0, await currentWeek())
+        .accounts({ game: gamePda, authority: player.publicKey, seasonLeaderboard: null, distanceLeaderboard: null } as any)
+        .rpc();
+      expect.fail("expected GameDelegated");
+    } catch (e) {
+      expect(String(e)).to.match(/GameDelegated|undelegate it first/i);
+    }
+  });
+
+  it("syncHighScore copies the game's high score into the record", async () => {
+    const { player, playerProgram, gamePda } = await newPlayer();
+    await startRun(playerProgram, { game: gamePda, signer: player.publicKey });
+    await playerProgram.methods
+      .endGame()
+      .accounts({ game: gamePda, signer: player.publicKey } as any)
+      .rpc();
+    await syncHighScore(playerProgram, player);
+
+    const game = await program.account.gameState.fetch(gamePda);
+    const record = await program.account.highScoreRecord.fetch(recordPda(player.publicKey));
+    expect(record.authority.equals(player.publicKey)).to.be.true;
+    expect(record.highScore.toNumber()).to.equal(game.highScore.toNumber());
+    expect(record.highScoreTimestamp.toNumber()).to.equal(game.highScoreTimestamp.toNumber());
+    expect(record.highScoreSeed.toString()).to.equal(game.highScoreSeed.toString());
+  });
+
+  it("records a finished run once per player", async () => {
+    const { player, playerProgram, gamePda } = await newPlayer();
+    await startRun(playerProgram, { game: gamePda, signer: player.publicKey });
+    await playerProgram.methods
+      .endGame()
+      .accounts({ game: gamePda, signer: player.publicKey } as any)
+      .rpc();
+    await syncHighScore(playerProgram, player);
+
+    const before = await program.account.leaderboard.fetch(leaderboardPda);
+    await playerProgram.methods
+      .submitScore(0, await currentWeek())
+      .accounts({ game: gamePda, authority: player.publicKey, seasonLeaderboard: null, distanceLeaderboard: null } as any)
+      .rpc();
+
+    const after = await program.account.leaderboard.fetch(leaderboardPda);
+    const count = after.count.toNumber();
+    expect(count).to.equal(Math.min(before.count.toNumber() + 1, 100));
+    const mine = after.entries
+      .slice(0, count)
+      .filter((e: any) => e.player.equals(player.publicKey));
+    expect(mine).to.have.length(1);
+
+    // Same score again is not an improvement
+    try {
+      await playerProgram.methods
+        .submitScore(0, await currentWeek())
+        .accounts({ game: gamePda, authority: player.publicKey, seasonLeaderboard: null, distanceLeaderboard: null } as any)
+        .rpc();
+      expect.fail("expected ScoreNotImproved");
+    } catch (e) {
+      expect(String(e)).to.match(/ScoreNotImproved|equal or better/i);
+    }
+  });
+
+  it("posts to this week's board, created on first use, and only this week's", async () => {
+    const { player, playerProgram, gamePda } = await newPlayer();
+    await startRun(playerProgram, { game: gamePda, signer: player.publicKey });
+    await playerProgram.methods
+      .endGame()
+      .accounts({ game: gamePda, signer: player.publicKey } as any)
+      .rpc();
+    await syncHighScore(playerProgram, player);
+
+    const week = await currentWeek();
+    expect(week).to.equal(Math.floor(Date.now() / 1000 / 604800));
+    try {
+      await playerProgram.methods
+        .submitScore(0, week - 1)
+        .accounts({ game: gamePda, authority: player.publicKey, seasonLeaderboard: null, distanceLeaderboard: null } as any)
+        .rpc();
+      expect.fail("expected WrongWeek");
+    } catch (e) {
+      expect(String(e)).to.match(/WrongWeek|current week/i);
+    }
+
+    await playerProgram.methods
+      .submitScore(0, week)
+      .accounts({ game: gamePda, authority: player.publicKey, seasonLeaderboard: null, distanceLeaderboard: null } as any)
+      .rpc();
+    const board = await program.account.weeklyLeaderboard.fetch(weeklyPda(week));
+    expect(board.week.toNumber()).to.equal(week);
+    expect(board.entries.length).to.equal(25);
+    const mine = board.entries
+      .slice(0, board.count.toNumber())
+      .filter((e: any) => e.player.equals(player.publicKey));
+    expect(mine).to.have.length(1);
+  });
+});
@@ -0,0 +1,168 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import type { FlappyBird } from "../target/types/flappy_bird";
+import { startRun } from "./run_request";
+
+const GAME_SEED = Buffer.from("game_v10");
+const PROFILE_SEED = Buffer.from("profile");
+const MISSIONS_SEED = Buffer.from("missions");
+const GLOBAL_STATS_SEED = Buffer.from("global_stats");
+const CONFIG_ADMIN = new PublicKey("anshxnbjGiUpsZpnx3c6LrK2vt8zt54vLMvY3C7Locm");
+const MILESTONE_REGISTRY_SEED = Buffer.from("milestone_registry");
+const MILESTONE_MINT_SEED = Buffer.from("milestone_mint");
+const TOKEN_PROGRAM_ID = new PublicKey("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+const ASSOCIATED_TOKEN_PROGRAM_ID = new PublicKey("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+describe("Flappy Bird - Player Profile", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = anchor.workspace.FlappyBird as Program<FlappyBird>;
+
+  const player = Keypair.generate();
+  const playerProgram = new Program<FlappyBird>(
+    program.idl as any,
+    new anchor.AnchorProvider(provider.connection, new anchor.Wallet(player), provider.opts)
+  );
+  const [gamePda] = PublicKey.findProgramAddressSync(
+    [GAME_SEED, player.publicKey.toBuffer(), Buffer.from([0])],
+    program.programId
+  );
+  const [profilePda] = PublicKey.findProgramAddressSync(
+    [PROFILE_SEED, player.publicKey.toBuffer()],
+    program.programId
+  );
+
+  const [missionsPda] = PublicKey.findProgramAddressSync(
+    [MISSIONS_SEED, player.publicKey.toBuffer()],
+    program.programId
+  );
+
+  const [globalStatsPda] = PublicKey.findProgramAddressSync([GLOBAL_STATS_SEED], program.programId);
+
+  before(async () => {
+    const sig = await provider.connection.requestAirdrop(player.publicKey, 1_000_000_000);
+    await provider.connection.confirmTransaction(sig, "confirmed");
+    await playerProgram.methods
+      .initialize(0)
+      .accounts({ authority: player.publicKey } as any)
+      .rpc();
+    await playerProgram.methods
+      .initializeProfile()
+      .accounts({ authority: player.publicKey } as any)
+      .rpc();
+  });
+
+  const action = () => ({ game: gamePda, signer: player.publicKey, profile: profilePda } as any);
+
+  it("counts games, flaps and frames when the profile is passed", async () => {
+    await startRun(playerProgram, action());
+    await playerProgram.methods.flap(true).accounts(action()).rpc();
+    await playerProgram.methods.tick().accounts(action()).rpc();
+    await playerProgram.methods.endGame().accounts(action()).rpc();
+
+    const game = await program.account.gameState.fetch(gamePda);
+    const profile = await program.account.playerProfile.fetch(profilePda);
+    expect(profile.authority.toBase58()).to.equal(player.publicKey.toBase58());
+    expect(profile.gamesPlayed.toNumber()).to.equal(1);
+    expect(profile.flaps.toNumber()).to.equal(1);
+    expect(profile.framesSurvived.toNumber()).to.equal(game.frameCount.toNumber());
+  });
+
+  it("survives reset_game and keeps working without the profile", async () => {
+    await playerProgram.methods.resetGame().accounts(action()).rpc();
+    await startRun(playerProgram, { game: gamePda, signer: player.publicKey, profile: null });
+
+    const profile = await program.account.playerProfile.fetch(profilePda);
+    expect(profile.gamesPlayed.toNumber()).to.equal(1);
+  });
+
+  it("counts started games towards the player's missions", async () => {
+    await playerProgram.methods
+      .initializeMissions()
+      .accounts({ authority: player.publicKey } as any)
+      .rpc();
+    const before = await program.account.missions.fetch(missionsPda);
+    const slot = before.missions.findIndex((m: any) => "playNGames" in m.kind);
+    expect(slot).to.be.within(0, 3);
+    try {
+      await playerProgram.methods
+        .claimMissionReward(slot)
+        .accounts({ authority: player.publicKey, game: gamePda } as any)
+        .rpc();
+      expect.fail("expected MissionNotComplete");
+    } catch (e) {
+      expect(String(e)).to.match(/MissionNotComplete/);
+    }
+
+    await playerProgram.methods.endGame().accounts(action()).rpc();
+    await playerProgram.methods.resetGame().accounts(action()).rpc();
+    await startRun(playerProgram, { ...action(), missions: missionsPda });
+    const after = await program.account.missions.fetch(missionsPda);
+    expect(after.missions[slot].progress).to.equal(before.missions[slot].progress + 1);
+  });
+
+  it("adds to the global stats when passed, and plays on without them", async function () {
+    if (!(await provider.connection.getAccountInfo(globalStatsPda))) {
+      // Only the config admin can create the GlobalStats
+      if (!provider.wallet.publicKey.equals(CONFIG_ADMIN)) {
+        this.skip();
+      }
+      await program.methods
+        .initializeGlobalStats()
+        .accounts({ admin: provider.wallet.publicKey } as any)
+        .rpc();
+    }
+    const withStats = () => ({ ...action(), globalStats: globalStatsPda });
+    const before = await program.account.globalStats.fetch(globalStatsPda);
+    await playerProgram.methods.endGame().accounts(action()).rpc();
+    await playerProgram.methods.resetGame().accounts(action()).rpc();
+    await startRun(playerProgram, withStats());
+    await playerProgram.methods.flap(true).accounts(withStats()).rpc();
+    await playerProgram.methods.endGame().accounts(withStats()).rpc();
+
+    const after = await program.account.globalStats.fetch(globalStatsPda);
+    expect(after.totalGamesStarted.toNumber()).to.equal(before.totalGamesStarted.toNumber() + 1);
+    expect(after.totalGamesFinished.toNumber()).to.equal(before.totalGamesFinished.toNumber() + 1);
+    expect(after.totalFlaps.toNumber()).to.equal(before.totalFlaps.toNumber() + 1);
+
+    // Clients that don't pass it are unaffected
+    await playerProgram.methods.resetGame().accounts(action()).rpc();
+    await startRun(playerProgram, action());
+    await playerProgram.methods.endGame().accounts(action()).rpc();
+    const unchanged = await program.account.globalStats.fetch(globalStatsPda);
+    expect(unchanged.totalGamesStarted.toNumber()).to.equal(after.totalGamesStarted.toNumber());
+  });
+
+  it("won't mint a milestone NFT the high score hasn't earned", async () => {
+    const [registryPda] = PublicKey.findProgramAddressSync([MILESTONE_REGISTRY_SEED], program.programId);
+    if (!(await provider.connection.getAccountInfo(registryPda))) {
+      await program.methods
+        .initializeMilestoneRegistry()
+        .accounts({ payer: provider.wallet.publicKey } as any)
+        .rpc();
+    }
+    const milestone = 0;
+    const [milestoneMint] = PublicKey.findProgramAddressSync(
+      [MILESTONE_MINT_SEED, Buffer.from([milestone])],
+      program.programId
+    );
+    const [playerTokenAccount] = PublicKey.findProgramAddressSync(
+      [player.publicKey.toBuffer(), TOKEN_PROGRAM_ID.toBuffer(), milestoneMint.toBuffer()],
+      ASSOCIATED_TOKEN_PROGRAM_ID
+    );
+    try {
+      await playerProgram.methods
+        .claimMilestone(milestone)
+        .accounts({ authority: player.publicKey, game: gamePda, playerTokenAccount } as any)
+        .rpc();
+      expect.fail("expected the claim to fail");
+    } catch (e) {
+      // Not set up on a fresh validator; out of reach of this player's 0 high score otherwise
+      expect(String(e)).to.match(/MilestoneNotConfigured|MilestoneNotReached/);
+    }
+    const profile = await program.account.playerProfile.fetch(profilePda);
+    expect(profile.milestonesClaimed.toNumber()).to.equal(0);
+  });
+});
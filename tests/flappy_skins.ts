@@ -0,0 +1,94 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import type { FlappyBird } from "../target/types/flappy_bird";
+
+const GAME_SEED = Buffer.from("game_v10");
+const SKIN_REGISTRY_SEED = Buffer.from("skin_registry");
+const CONFIG_ADMIN = new PublicKey("anshxnbjGiUpsZpnx3c6LrK2vt8zt54vLMvY3C7Locm");
+
+describe("Flappy Bird - Skins", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = anchor.workspace.FlappyBird as Program<FlappyBird>;
+  const wallet = provider.wallet as anchor.Wallet;
+
+  const [registryPda] = PublicKey.findProgramAddressSync([SKIN_REGISTRY_SEED], program.programId);
+  const [gamePda] = PublicKey.findProgramAddressSync(
+    [GAME_SEED, wallet.publicKey.toBuffer(), Buffer.from([0])],
+    program.programId
+  );
+  const setSkin = (skinId: number, token?: PublicKey) =>
+    program.methods
+      .setSkin(skinId)
+      .accounts({ game: gamePda, signer: wallet.publicKey, registry: registryPda } as any)
+      .remainingAccounts(token ? [{ pubkey: token, isSigner: false, isWritable: false }] : [])
+      .rpc();
+
+  before(async () => {
+    if (!(await provider.connection.getAccountInfo(registryPda))) {
+      await program.methods
+        .initializeSkinRegistry()
+        .accounts({ payer: wallet.publicKey } as any)
+        .rpc();
+    }
+    if (!(await provider.connection.getAccountInfo(gamePda))) {
+      await program.methods.initialize(0).accounts({ authority: wallet.publicKey } as any).rpc();
+    }
+  });
+
+  it("wears a free skin without an NFT, and keeps it through a reset", async () => {
+    await setSkin(2);
+    await program.methods.resetGame().accounts({ game: gamePda, signer: wallet.publicKey } as any).rpc();
+    const game = await program.account.gameState.fetch(gamePda);
+    expect(game.birdSkin).to.equal(2);
+  });
+
+  it("refuses a paid skin without a token account holding its NFT", async () => {
+    try {
+      await setSkin(3);
+      expect.fail("expected SkinNotOwned");
+    } catch (e) {
+      expect(String(e)).to.match(/SkinNotOwned|No NFT unlocking/i);
+    }
+    // Not an SPL token account at all
+    try {
+      await setSkin(3, gamePda);
+      expect.fail("expected SkinNotOwned");
+    } catch (e) {
+      expect(String(e)).to.match(/SkinNotOwned|No NFT unlocking/i);
+    }
+    const game = await program.account.gameState.fetch(gamePda);
+    expect(game.birdSkin).to.equal(2);
+  });
+
+  it("lets only the admin register skin mints", async function () {
+    const mint = Keypair.generate().publicKey;
+    if (!wallet.publicKey.equals(CONFIG_ADMIN)) {
+      try {
+        await program.methods
+          .addSkin(3, mint)
+          .accounts({ registry: registryPda, admin: wallet.publicKey } as any)
+          .rpc();
+        expect.fail("expected Unauthorized");
+      } catch (e) {
+        expect(String(e)).to.match(/Unauthorized|ConstraintAddress/i);
+      }
+      return;
+    }
+    try {
+      await program.methods.addSkin(1, mint).accounts({ registry: registryPda, admin: wallet.publicKey } as any).rpc();
+      expect.fail("expected FreeSkin");
+    } catch (e) {
+      expect(String(e)).to.match(/FreeSkin|are free/i);
+    }
+    await program.methods.addSkin(3, mint).accounts({ registry: registryPda, admin: wallet.publicKey } as any).rpc();
+    let registry = await program.account.skinRegistry.fetch(registryPda);
+    expect(registry.skins.slice(0, registry.count).some((s: any) => s.skinId === 3 && s.mint.equals(mint))).to.be.true;
+
+    await program.methods.removeSkin(3, mint).accounts({ registry: registryPda, admin: wallet.publicKey } as any).rpc();
+    registry = await program.account.skinRegistry.fetch(registryPda);
+    expect(registry.skins.slice(0, registry.count).some((s: any) => s.mint.equals(mint))).to.be.false;
+  });
+});
@@ -0,0 +1,119 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Keypair, LAMPORTS_PER_SOL, PublicKey } from "@solana/web3.js";
+import type { FlappyBird } from "../target/types/flappy_bird";
+import { startRun } from "./run_request";
+
+const GAME_SEED = Buffer.from("game_v10");
+const TOURNAMENT_SEED = Buffer.from("tournament");
+
+describe("Flappy Bird - Tournament", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = anchor.workspace.FlappyBird as Program<FlappyBird>;
+  const organizer = provider.wallet.publicKey;
+
+  const id = new anchor.BN(Date.now());
+  const entryFee = new anchor.BN(LAMPORTS_PER_SOL / 100);
+  const [tournamentPda] = PublicKey.findProgramAddressSync(
+    [TOURNAMENT_SEED, organizer.toBuffer(), id.toArrayLike(Buffer, "le", 8)],
+    program.programId
+  );
+
+  const player = Keypair.generate();
+  const playerProgram = new Program<FlappyBird>(
+    program.idl as any,
+    new anchor.AnchorProvider(provider.connection, new anchor.Wallet(player), provider.opts)
+  );
+  const [gamePda] = PublicKey.findProgramAddressSync(
+    [GAME_SEED, player.publicKey.toBuffer(), Buffer.from([0])],
+    program.programId
+  );
+
+  before(async () => {
+    const sig = await provider.connection.requestAirdrop(player.publicKey, 1_000_000_000);
+    await provider.connection.confirmTransaction(sig, "confirmed");
+    await playerProgram.methods
+      .initialize(0)
+      .accounts({ authority: player.publicKey } as any)
+      .rpc();
+
+    const now = Math.floor(Date.now() / 1000);
+    await program.methods
+      .createTournament(id, entryFee, new anchor.BN(now - 60), new anchor.BN(now + 3600), { normal: {} })
+      .accounts({ organizer } as any)
+      .rpc();
+  });
+
+  it("takes the entry fee into the prize pool, once per player", async () => {
+    const before = await provider.connection.getBalance(tournamentPda);
+    await playerProgram.methods
+      .joinTournament()
+      .accounts({ tournament: tournamentPda, player: player.publicKey } as any)
+      .rpc();
+
+    const tournament = await program.account.tournament.fetch(tournamentPda);
+    expect(tournament.prizePool.toNumber()).to.equal(entryFee.toNumber());
+    expect(tournament.entrantCount).to.equal(1);
+    expect(await provider.connection.getBalance(tournamentPda)).to.equal(
+      before + entryFee.toNumber()
+    );
+
+    try {
+      await playerProgram.methods
+        .joinTournament()
+        .accounts({ tournament: tournamentPda, player: player.publicKey } as any)
+        .rpc();
+      expect.fail("expected AlreadyJoined");
+    } catch (e) {
+      expect(String(e)).to.match(/AlreadyJoined|Already joined/i);
+    }
+  });
+
+  it("turns away a run on another difficulty", async () => {
+    const accounts = { game: gamePda, signer: player.publicKey } as any;
+    await startRun(playerProgram, accounts, { easy: {} });
+    await playerProgram.methods.endGame().accounts(accounts).rpc();
+    try {
+      await playerProgram.methods
+        .submitTournamentScore()
+        .accounts({ tournament: tournamentPda, game: gamePda, authority: player.publicKey } as any)
+        .rpc();
+      expect.fail("expected WrongDifficulty");
+    } catch (e) {
+      expect(String(e)).to.match(/WrongDifficulty|different difficulty/i);
+    }
+  });
+
+  it("records a finished run as the player's score", async () => {
+    await startRun(playerProgram, { game: gamePda, signer: player.publicKey });
+    await playerProgram.methods.tick().accounts({ game: gamePda, signer: player.publicKey } as any).rpc();
+    await playerProgram.methods.endGame().accounts({ game: gamePda, signer: player.publicKey } as any).rpc();
+
+    await playerProgram.methods
+      .submitTournamentScore()
+      .accounts({ tournament: tournamentPda, game: gamePda, authority: player.publicKey } as any)
+      .rpc();
+
+    const game = await program.account.gameState.fetch(gamePda);
+    const tournament = await program.account.tournament.fetch(tournamentPda);
+    const entry = tournament.entrants[0];
+    expect(entry.player.equals(player.publicKey)).to.be.true;
+    expect(entry.score.toNumber()).to.equal(game.score.toNumber());
+    expect(entry.submission.toNumber()).to.equal(1);
+  });
+
+  it("refuses to settle before the end time", async () => {
+    try {
+      await program.methods
+        .settleTournament()
+        .accounts({ tournament: tournamentPda, organizer } as any)
+        .remainingAccounts([{ pubkey: player.publicKey, isSigner: false, isWritable: true }])
+        .rpc();
+      expect.fail("expected TournamentNotOver");
+    } catch (e) {
+      expect(String(e)).to.match(/TournamentNotOver|not ended/i);
+    }
+  });
+});
@@ -0,0 +1,87 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import type { FlappyBird } from "../target/types/flappy_bird";
+import { GameStatus, startRun } from "./run_request";
+
+const GAME_SEED = Buffer.from("game_v10");
+const PROFILE_SEED = Buffer.from("profile");
+
+describe("Flappy Bird - Transfer Authority", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = anchor.workspace.FlappyBird as Program<FlappyBird>;
+
+  const oldWallet = Keypair.generate();
+  const newWallet = Keypair.generate();
+  const oldProgram = new Program<FlappyBird>(
+    program.idl as any,
+    new anchor.AnchorProvider(provider.connection, new anchor.Wallet(oldWallet), provider.opts)
+  );
+  const pda = (seeds: Buffer[]) => PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+  const gamePda = (owner: PublicKey) => pda([GAME_SEED, owner.toBuffer(), Buffer.from([0])]);
+  const profilePda = (owner: PublicKey) => pda([PROFILE_SEED, owner.toBuffer()]);
+  const oldGame = gamePda(oldWallet.publicKey);
+  const newGame = gamePda(newWallet.publicKey);
+
+  before(async () => {
+    for (const w of [oldWallet, newWallet]) {
+      const sig = await provider.connection.requestAirdrop(w.publicKey, 1_000_000_000);
+      await provider.connection.confirmTransaction(sig, "confirmed");
+    }
+    await oldProgram.methods
+      .initialize(0)
+      .accounts({ authority: oldWallet.publicKey } as any)
+      .rpc();
+    await oldProgram.methods
+      .initializeProfile()
+      .accounts({ authority: oldWallet.publicKey } as any)
+      .rpc();
+  });
+
+  const transfer = () =>
+    oldProgram.methods
+      .transferAuthority()
+      .accounts({
+        game: oldGame,
+        authority: oldWallet.publicKey,
+        newAuthority: newWallet.publicKey,
+        profile: profilePda(oldWallet.publicKey),
+        newProfile: profilePda(newWallet.publicKey),
+      } as any)
+      .signers([newWallet]);
+
+  it("refuses to move a game in progress", async () => {
+    await startRun(oldProgram, { game: oldGame, signer: oldWallet.publicKey });
+    try {
+      await transfer().rpc();
+      expect.fail("expected GameInProgress");
+    } catch (e) {
+      expect(String(e)).to.match(/GameInProgress|in progress/i);
+    }
+    await oldProgram.methods
+      .endGame()
+      .accounts({ game: oldGame, signer: oldWallet.publicKey } as any)
+      .rpc();
+  });
+
+  it("moves the game and profile to the new wallet and closes the old ones", async () => {
+    const before = await program.account.gameState.fetch(oldGame);
+    const profileBefore = await program.account.playerProfile.fetch(profilePda(oldWallet.publicKey));
+    await transfer().rpc();
+
+    expect(await provider.connection.getAccountInfo(oldGame)).to.be.null;
+    expect(await provider.connection.getAccountInfo(profilePda(oldWallet.publicKey))).to.be.null;
+
+    const game = await program.account.gameState.fetch(newGame);
+    expect(game.authority.toBase58()).to.equal(newWallet.publicKey.toBase58());
+    expect(game.gameStatus).to.equal(GameStatus.notStarted);
+    expect(game.highScore.toNumber()).to.equal(before.highScore.toNumber());
+    expect(game.pipes.every((p: any) => !p.active)).to.be.true;
+
+    const profile = await program.account.playerProfile.fetch(profilePda(newWallet.publicKey));
+    expect(profile.authority.toBase58()).to.equal(newWallet.publicKey.toBase58());
+    expect(profile.gamesPlayed.toNumber()).to.equal(profileBefore.gamesPlayed.toNumber());
+  });
+});
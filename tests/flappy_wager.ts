@@ -0,0 +1,99 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { Keypair, LAMPORTS_PER_SOL, PublicKey, SYSVAR_SLOT_HASHES_PUBKEY } from "@solana/web3.js";
+import type { FlappyBird } from "../target/types/flappy_bird";
+
+const GAME_SEED = Buffer.from("game_v10");
+const MATCH_SEED = Buffer.from("match_v2");
+
+describe("Flappy Bird - Wager Matches", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = anchor.workspace.FlappyBird as Program<FlappyBird>;
+  const challenger = provider.wallet.publicKey;
+
+  const id = new anchor.BN(Date.now());
+  const stake = new anchor.BN(LAMPORTS_PER_SOL / 100);
+  const [matchPda] = PublicKey.findProgramAddressSync(
+    [MATCH_SEED, challenger.toBuffer(), id.toArrayLike(Buffer, "le", 8)],
+    program.programId
+  );
+
+  const opponent = Keypair.generate();
+  const opponentProgram = new Program<FlappyBird>(
+    program.idl as any,
+    new anchor.AnchorProvider(provider.connection, new anchor.Wallet(opponent), provider.opts)
+  );
+  const [challengerGame] = PublicKey.findProgramAddressSync(
+    [GAME_SEED, challenger.toBuffer(), Buffer.from([0])],
+    program.programId
+  );
+
+  before(async () => {
+    const sig = await provider.connection.requestAirdrop(opponent.publicKey, 1_000_000_000);
+    await provider.connection.confirmTransaction(sig, "confirmed");
+    if (!(await provider.connection.getAccountInfo(challengerGame))) {
+      await program.methods.initialize(0).accounts({ authority: challenger } as any).rpc();
+    }
+  });
+
+  it("escrows the challenger's stake and holds the runs until it's accepted", async () => {
+    await program.methods
+      .createWagerMatch(id, opponent.publicKey, { normal: {} }, stake)
+      .accounts({ challenger, slotHashes: SYSVAR_SLOT_HASHES_PUBKEY } as any)
+      .rpc();
+
+    const created = await program.account.match.fetch(matchPda);
+    expect(created.stake.toNumber()).to.equal(stake.toNumber());
+    expect(created.pot.toNumber()).to.equal(stake.toNumber());
+    const rent = await provider.connection.getMinimumBalanceForRentExemption(
+      (await provider.connection.getAccountInfo(matchPda))!.data.length
+    );
+    expect(await provider.connection.getBalance(matchPda)).to.equal(rent + stake.toNumber());
+
+    try {
+      await program.methods
+        .startMatchRun()
+        .accounts({ gameMatch: matchPda, game: challengerGame, authority: challenger } as any)
+        .rpc();
+      expect.fail("expected WagerNotAccepted");
+    } catch (e) {
+      expect(String(e)).to.match(/WagerNotAccepted|not been accepted/i);
+    }
+    try {
+      await program.methods.cancelAndRefund().accounts({ gameMatch: matchPda, challenger } as any).rpc();
+      expect.fail("expected AcceptWindowOpen");
+    } catch (e) {
+      expect(String(e)).to.match(/AcceptWindowOpen|can still accept/i);
+    }
+  });
+
+  it("takes the opponent's equal stake, once", async () => {
+    const accounts = { gameMatch: matchPda, opponent: opponent.publicKey } as any;
+    await opponentProgram.methods.acceptWager().accounts(accounts).rpc();
+
+    const accepted = await program.account.match.fetch(matchPda);
+    expect(accepted.pot.toNumber()).to.equal(2 * stake.toNumber());
+    expect(accepted.acceptedAt.toNumber()).to.be.greaterThan(0);
+
+    try {
+      await opponentProgram.methods.acceptWager().accounts(accounts).rpc();
+      expect.fail("expected WagerAccepted");
+    } catch (e) {
+      expect(String(e)).to.match(/WagerAccepted|already accepted/i);
+    }
+  });
+
+  it("pays nothing before the match is settled", async () => {
+    try {
+      await program.methods
+        .claimWinnings()
+        .accounts({ gameMatch: matchPda, challenger, opponent: opponent.publicKey } as any)
+        .rpc();
+      expect.fail("expected MatchNotSettled");
+    } catch (e) {
+      expect(String(e)).to.match(/MatchNotSettled|not been settled/i);
+    }
+  });
+});
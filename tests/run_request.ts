@@ -0,0 +1,81 @@
+/**
+ * Helpers for the two-phase run start: request_game commits to a secret,
+ * start_game reveals it in a later slot.
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { AccountMeta, Connection, PublicKey } from "@solana/web3.js";
+import { createHash, randomBytes } from "crypto";
+import type { FlappyBird } from "../target/types/flappy_bird";
+
+const GAME_SEED = Buffer.from("game_v10");
+const HIGH_SCORE_SEED = Buffer.from("highscore");
+const MAX_GAME_SLOTS = 4;
+
+/** GameState stores its enums as their variant index */
+export const GameStatus = { notStarted: 0, playing: 1, gameOver: 2, paused: 3, countdown: 4 } as const;
+export const Difficulty = { easy: 0, normal: 1, hard: 2 } as const;
+export const DeathCause = { floor: 0, ceiling: 1, pipe: 2, manual: 3, pauseExpired: 4, undelegated: 5, abandoned: 6 } as const;
+
+/** `delegate` params that keep the ER defaults */
+export const DEFAULT_DELEGATE_PARAMS = { validator: null, commitFrequencyMs: null, timeLimit: null };
+
+/** Fresh reveal and its commitment: sha256 of the reveal's 8 little-endian bytes */
+export const newRunSecret = () => {
+  const bytes = randomBytes(8);
+  return {
+    reveal: new anchor.BN(bytes, "le"),
+    commitment: [...createHash("sha256").update(bytes).digest()],
+  };
+};
+
+/** start_game rejects a reveal in the same slot as the request */
+export const waitForNextSlot = async (connection: Connection) => {
+  const slot = await connection.getSlot("confirmed");
+  while ((await connection.getSlot("confirmed")) <= slot) {
+    await new Promise((resolve) => setTimeout(resolve, 50));
+  }
+};
+
+/**
+ * `accounts` plus what a start reads for the ranked cooldown: the player's
+ * HighScoreRecord, and their other games in its `gameSlots` as remaining
+ * accounts, in slot order
+ */
+export const startAccounts = async (program: Program<FlappyBird>, accounts: any) => {
+  const { authority, gameSlot } = await program.account.gameState.fetch(accounts.game);
+  const [highScoreRecord] = PublicKey.findProgramAddressSync(
+    [HIGH_SCORE_SEED, authority.toBuffer()],
+    program.programId
+  );
+  const record = await program.account.highScoreRecord.fetchNullable(highScoreRecord);
+  const remainingAccounts: AccountMeta[] = [];
+  for (let slot = 0; slot < MAX_GAME_SLOTS; slot++) {
+    if (slot !== gameSlot && record && (record.gameSlots >> slot) & 1) {
+      const [pubkey] = PublicKey.findProgramAddressSync(
+        [GAME_SEED, authority.toBuffer(), Buffer.from([slot])],
+        program.programId
+      );
+      remainingAccounts.push({ pubkey, isSigner: false, isWritable: false });
+    }
+  }
+  return { accounts: { ...accounts, highScoreRecord }, remainingAccounts };
+};
+
+/** request_game + start_game for rpc-style callers */
+export const startRun = async (
+  program: Program<FlappyBird>,
+  accounts: any,
+  difficulty: any = { normal: {} }
+) => {
+  const { reveal, commitment } = newRunSecret();
+  await program.methods.requestGame(commitment).accounts(accounts).rpc();
+  await waitForNextSlot(program.provider.connection);
+  const start = await startAccounts(program, accounts);
+  return program.methods
+    .startGame({ difficulty, reveal, scoring: { pipesPassed: {} }, horizontalControl: false, wind: null, mode: null, practice: false, lives: 1 })
+    .accounts(start.accounts)
+    .remainingAccounts(start.remainingAccounts)
+    .rpc();
+};